@@ -3506,8 +3506,8 @@ If it is nil, then the handler is \"byte-compile-SYMBOL.\""
 (byte-defop-compiler set-marker	2-3)
 (byte-defop-compiler match-beginning	1)
 (byte-defop-compiler match-end	1)
-(byte-defop-compiler upcase		1)
-(byte-defop-compiler downcase		1)
+(byte-defop-compiler upcase		byte-compile-one-arg-or-call)
+(byte-defop-compiler downcase		byte-compile-one-arg-or-call)
 (byte-defop-compiler string=		2)
 (byte-defop-compiler string<		2)
 (byte-defop-compiler (string-equal byte-string=) 2)
@@ -3600,6 +3600,14 @@ These implicitly `and' together a bunch of two-arg bytecodes."
 	  ((= len 4) (byte-compile-three-args form))
 	  (t (byte-compile-subr-wrong-args form "2-3")))))
 
+(defun byte-compile-one-arg-or-call (form)
+  "Compile a call to a function whose opcode takes just its first argument.
+Calls passing the optional arguments as well are compiled as normal calls."
+  (let ((len (length form)))
+    (cond ((= len 2) (byte-compile-one-arg form))
+	  ((> len 2) (byte-compile-normal-call form))
+	  (t (byte-compile-subr-wrong-args form 1)))))
+
 (defun byte-compile-noop (_form)
   (byte-compile-constant nil))
 
@@ -5146,12 +5154,12 @@ and corresponding effects."
 (provide 'bytecomp)
 
 
-;;; report metering (see the hacks in bytecode.c)
+;;; report metering (see `byte-metering-on' and `byte-code-meter-report')
 
 (defvar byte-code-meter)
 (defun byte-compile-report-ops ()
-  (or (boundp 'byte-metering-on)
-      (error "You must build Emacs with -DBYTE_CODE_METER to use this"))
+  (or (vectorp byte-code-meter)
+      (error "No byte-code has been metered; set `byte-metering-on' first"))
   (with-output-to-temp-buffer "*Meter*"
     (set-buffer "*Meter*")
     (let ((i 0) n op off)
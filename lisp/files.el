@@ -806,6 +806,12 @@ order, and files from each directory are sorted in alphabetical order.
 Each file name appears in the returned list in its absolute form.
 Optional argument INCLUDE-DIRECTORIES non-nil means also include in the
 output directories whose names match REGEXP."
+  (if (not (find-file-name-handler (expand-file-name dir) 'directory-files))
+      (directory-walk dir regexp include-directories)
+    (directory-files-recursively--lisp dir regexp include-directories)))
+
+(defun directory-files-recursively--lisp (dir regexp include-directories)
+  "Implement `directory-files-recursively' for handled file names."
   (let ((result nil)
 	(files nil)
 	;; When DIR is "/", remote file names like "/method:" could
@@ -820,7 +826,7 @@ output directories whose names match REGEXP."
 	      ;; Don't follow symlinks to other directories.
 	      (unless (file-symlink-p full-file)
 		(setq result
-		      (nconc result (directory-files-recursively
+		      (nconc result (directory-files-recursively--lisp
 				     full-file regexp include-directories))))
 	      (when (and include-directories
 			 (string-match regexp leaf))
@@ -761,8 +761,41 @@ fn run_bindgen() {
     }
 }
 
+/// With the `lisp-tests` feature, link the C side of Emacs into the
+/// test binary.  REMACS_LISP_TEST_LIBS holds the linker flags, as set by
+/// `make check-lisp-tests` in src; only `-L` and `-l` flags are passed on.
+fn link_lisp_test_libs() {
+    if env::var_os("CARGO_FEATURE_LISP_TESTS").is_none() {
+        return;
+    }
+    let libs = env::var("REMACS_LISP_TEST_LIBS").unwrap_or_else(|_| {
+        eprintln!("The lisp-tests feature needs REMACS_LISP_TEST_LIBS;");
+        eprintln!("run `make check-lisp-tests` in src instead.");
+        process::exit(1);
+    });
+    let mut flags = libs.split_whitespace();
+    while let Some(flag) = flags.next() {
+        if flag == "-L" || flag == "-l" {
+            // The argument is in the next word.
+            if let Some(arg) = flags.next() {
+                emit_link_flag(flag, arg);
+            }
+        } else if flag.starts_with("-L") || flag.starts_with("-l") {
+            emit_link_flag(&flag[..2], &flag[2..]);
+        }
+    }
+}
+
+fn emit_link_flag(flag: &str, arg: &str) {
+    if flag == "-L" {
+        println!("cargo:rustc-link-search=native={}", arg);
+    } else {
+        println!("cargo:rustc-link-lib={}", arg);
+    }
+}
+
 fn main() {
-    for varname in ["EMACS_CFLAGS", "SRC_HASH"].iter() {
+    for varname in ["EMACS_CFLAGS", "SRC_HASH", "REMACS_LISP_TEST_LIBS"].iter() {
         println!("cargo:rerun-if-env-changed={}", varname);
     }
 
@@ -780,4 +813,5 @@ fn main() {
     generate_definitions();
     run_bindgen();
     generate_globals();
+    link_lisp_test_libs();
 }
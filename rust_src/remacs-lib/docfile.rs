@@ -5,13 +5,15 @@
 use libc::{c_char, c_int};
 use regex::Regex;
 
+use std::collections::HashMap;
 use std::ffi::{CStr, CString};
 use std::fs::File;
 use std::io::{stdout, BufRead, BufReader, Write};
 use std::mem;
+use std::process;
 use std::ptr;
 
-use remacs_util::parse_lisp_fn;
+use remacs_util::{parse_lisp_fn, LispFnArgs};
 
 #[allow(dead_code)]
 const INVALID: c_int = 0;
@@ -28,6 +30,393 @@ const FUNCTION: c_int = 5;
 
 type AddGlobalFn = fn(c_int, *const c_char, c_int, *const c_char) -> *const ();
 
+/// Report an error in FILENAME at LINENO and exit, failing the build.
+fn fatal(filename: &str, lineno: usize, msg: &str) -> ! {
+    eprintln!("{}:{}: {}", filename, lineno, msg);
+    process::exit(1);
+}
+
+/// Whether the parentheses and brackets in TEXT, outside of string
+/// literals, are balanced.
+fn is_balanced(text: &str) -> bool {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in text.chars() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else {
+            match c {
+                '"' => in_string = true,
+                '(' | '[' => depth += 1,
+                ')' | ']' => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+    depth <= 0
+}
+
+/// Split TEXT at the commas that are not nested inside brackets.
+fn split_toplevel_commas(text: &str) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut prev = ' ';
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' | '[' | '<' => depth += 1,
+            // `->` in a function type is not a closing bracket.
+            '>' if prev == '-' => {}
+            ')' | ']' | '>' => depth -= 1,
+            ',' if depth == 0 => {
+                pieces.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        prev = c;
+    }
+    pieces.push(&text[start..]);
+    pieces.into_iter().filter(|p| !p.trim().is_empty()).collect()
+}
+
+/// NAME, a Rust parameter or field name, as it is written in a usage line.
+fn lisp_name(name: &str) -> String {
+    name.trim_left_matches('_').to_uppercase().replace("_", "-")
+}
+
+/// A parameter of a `#[lisp_fn]` function.
+struct Param {
+    name: String,
+    ty: String,
+}
+
+impl Param {
+    /// The parameter name as it is written in a usage line.
+    fn lisp_name(&self) -> String {
+        lisp_name(&self.name)
+    }
+
+    /// Whether this is the `&[LispObject]` parameter of a MANY function.
+    fn is_slice(&self) -> bool {
+        self.ty.starts_with('&') && self.ty.contains('[')
+    }
+}
+
+/// Return the parameters of the function whose signature is SIG.
+/// `None` means that the parameter list is not complete yet.
+fn parse_params(sig: &str) -> Option<Vec<Param>> {
+    let open = sig.find('(')?;
+    let mut depth = 0;
+    let mut close = None;
+    for (i, c) in sig[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(open + i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let params = split_toplevel_commas(&sig[open + 1..close?])
+        .into_iter()
+        .map(|param| {
+            let mut split = param.splitn(2, ':');
+            let name = split.next().unwrap().trim();
+            Param {
+                name: name.trim_left_matches("mut ").trim().to_string(),
+                ty: split.next().unwrap_or("").trim().to_string(),
+            }
+        })
+        .collect();
+    Some(params)
+}
+
+/// The argument list of a Lisp function, as shown in its usage line.
+struct Arglist {
+    required: Vec<String>,
+    optional: Vec<String>,
+    /// Everything after `&rest` or `&key`, if the function takes an
+    /// unbounded number of arguments.
+    rest: Option<String>,
+    /// Whether `rest` follows `&key` rather than `&rest`.
+    keys: bool,
+}
+
+impl Arglist {
+    /// Derive the argument list of a `#[lisp_fn]` from its parameters.
+    /// KEYWORDS maps the structs deriving `LispKeywords` to the names of
+    /// their keywords.  Return `None` for a MANY function, whose slice
+    /// parameter says nothing about how its arguments are used, and for
+    /// a function whose keyword argument type is not in KEYWORDS.
+    fn from_params(
+        params: &[Param],
+        attr: &LispFnArgs,
+        keywords: &HashMap<String, Vec<String>>,
+    ) -> Option<Self> {
+        let (positional, tail) = if attr.keys.is_some() || attr.rest.is_some() {
+            let (tail, positional) = params.split_last()?;
+            (positional, Some(tail))
+        } else if params.iter().any(Param::is_slice) {
+            return None;
+        } else {
+            (params, None)
+        };
+        let rest = match tail {
+            Some(tail) if attr.keys.is_some() => {
+                let ty = tail.ty.rsplit("::").next().unwrap();
+                Some(keywords.get(ty)?.join(" "))
+            }
+            tail => tail.map(Param::lisp_name),
+        };
+        let min = (attr.min.max(0) as usize).min(positional.len());
+        Some(Arglist {
+            required: positional[..min].iter().map(Param::lisp_name).collect(),
+            optional: positional[min..].iter().map(Param::lisp_name).collect(),
+            rest,
+            keys: attr.keys.is_some(),
+        })
+    }
+
+    /// Parse USAGE, of the form `(NAME ARGS...)`, into the function name
+    /// and its argument list.
+    fn parse_usage(usage: &str) -> Result<(String, Self), String> {
+        let usage = usage.trim();
+        if !usage.starts_with('(') || !usage.ends_with(')') {
+            return Err(format!("malformed usage line `{}`", usage));
+        }
+        let mut words = Vec::new();
+        let mut word = String::new();
+        let mut depth = 0;
+        for c in usage[1..usage.len() - 1].chars() {
+            match c {
+                '[' | '(' => depth += 1,
+                ']' | ')' => depth -= 1,
+                _ => {}
+            }
+            if c.is_whitespace() && depth == 0 {
+                if !word.is_empty() {
+                    words.push(word.clone());
+                    word.clear();
+                }
+            } else {
+                word.push(c);
+            }
+        }
+        if !word.is_empty() {
+            words.push(word);
+        }
+        if words.is_empty() {
+            return Err(format!("malformed usage line `{}`", usage));
+        }
+
+        let name = words.remove(0);
+        let mut arglist = Arglist {
+            required: Vec::new(),
+            optional: Vec::new(),
+            rest: None,
+            keys: false,
+        };
+        let mut optional = false;
+        for (i, word) in words.iter().enumerate() {
+            if word == "&rest" || word == "&key" {
+                arglist.rest = Some(words[i + 1..].join(" "));
+                arglist.keys = word == "&key";
+                break;
+            } else if word == "&optional" {
+                optional = true;
+            } else if word.ends_with("...") {
+                // `BODY...' is the special form spelling of `&rest BODY'.
+                arglist.rest = Some(words[i..].join(" "));
+                break;
+            } else if optional {
+                arglist.optional.push(word.clone());
+            } else {
+                arglist.required.push(word.clone());
+            }
+        }
+        Ok((name, arglist))
+    }
+
+    fn usage(&self) -> String {
+        let mut usage = String::from("(fn");
+        for arg in &self.required {
+            usage.push(' ');
+            usage.push_str(arg);
+        }
+        if !self.optional.is_empty() {
+            usage.push_str(" &optional");
+            for arg in &self.optional {
+                usage.push(' ');
+                usage.push_str(arg);
+            }
+        }
+        if let Some(ref rest) = self.rest {
+            usage.push_str(if self.keys { " &key " } else { " &rest " });
+            usage.push_str(rest);
+        }
+        usage.push(')');
+        usage
+    }
+}
+
+/// Check the usage line of the `#[lisp_fn]` with attributes ATTR and
+/// parameters PARAMS, returning the usage to put in the DOC file.
+/// KEYWORDS is as for `Arglist::from_params`.
+fn check_usage(
+    usage: Option<&str>,
+    params: &[Param],
+    attr: &LispFnArgs,
+    keywords: &HashMap<String, Vec<String>>,
+) -> Result<String, String> {
+    let derived = Arglist::from_params(params, attr, keywords);
+    let usage = match usage {
+        Some(usage) => usage,
+        None => {
+            return match derived {
+                Some(ref arglist) if !attr.unevalled => Ok(arglist.usage()),
+                _ if attr.unevalled => Err("special forms need a `usage:` line".to_string()),
+                _ if attr.keys.is_some() => Err(
+                    "functions whose keywords are not derived in this file need a `usage:` line"
+                        .to_string(),
+                ),
+                _ => Err("MANY functions need a `usage:` line".to_string()),
+            };
+        }
+    };
+
+    let (name, arglist) = Arglist::parse_usage(usage)?;
+    if name != attr.name && name != "fn" {
+        return Err(format!("usage line is for `{}`, not `{}`", name, attr.name));
+    }
+    // The arguments of a special form are whatever its body makes of them.
+    if !attr.unevalled {
+        let min = attr.min.max(0) as usize;
+        if arglist.required.len() != min {
+            return Err(format!(
+                "usage line has {} required arguments, but `min` is {}",
+                arglist.required.len(),
+                min
+            ));
+        }
+        if attr.keys.is_some() && !arglist.keys {
+            return Err("usage line lacks the `&key` arguments".to_string());
+        }
+        if attr.rest.is_some() && arglist.keys {
+            return Err("usage line has `&key` arguments, but the function has none".to_string());
+        }
+        match derived {
+            Some(ref derived) => {
+                // A usage line may hide trailing optional arguments that
+                // are only there for compatibility, but not invent any.
+                if arglist.optional.len() > derived.optional.len() {
+                    return Err(format!(
+                        "usage line has {} optional arguments, but the function takes {}",
+                        arglist.optional.len(),
+                        derived.optional.len()
+                    ));
+                }
+                if arglist.rest.is_some() != derived.rest.is_some() {
+                    return Err(if derived.rest.is_some() {
+                        "usage line lacks the `&rest` or `&key` arguments".to_string()
+                    } else {
+                        "usage line has `&rest` arguments, but the function has none".to_string()
+                    });
+                }
+                if derived.keys && !same_words(&arglist.rest, &derived.rest) {
+                    return Err(format!(
+                        "usage line has the keywords `{}`, but the function takes `{}`",
+                        arglist.rest.unwrap_or_default(),
+                        derived.rest.as_ref().unwrap()
+                    ));
+                }
+            }
+            None => {
+                if arglist.rest.is_none() {
+                    return Err("usage line of a MANY function lacks `&rest` arguments".to_string());
+                }
+            }
+        }
+    }
+    // Like make-docfile, replace the function name with `fn'.
+    Ok(format!("(fn{}", &usage.trim()[1 + name.len()..]))
+}
+
+/// Whether A and B hold the same words, in any order.
+fn same_words(a: &Option<String>, b: &Option<String>) -> bool {
+    let words = |s: &Option<String>| {
+        let mut words: Vec<String> = s
+            .as_ref()
+            .map_or(vec![], |s| s.split_whitespace().map(str::to_string).collect());
+        words.sort();
+        words
+    };
+    words(a) == words(b)
+}
+
+/// Map each struct in LINES that derives `LispKeywords` to the usage
+/// names of its keywords.
+fn keyword_structs(lines: &[String]) -> HashMap<String, Vec<String>> {
+    lazy_static! {
+        static ref STRUCT_RE: Regex =
+            Regex::new(r"^(?:pub(?:\([^)]*\))?\s+)?struct\s+(\w+)\s*\{").unwrap();
+        static ref FIELD_RE: Regex = Regex::new(r"^(?:pub(?:\([^)]*\))?\s+)?(\w+)\s*:").unwrap();
+    }
+
+    let mut structs = HashMap::new();
+    let mut derives = false;
+    let mut lines = lines.iter().map(|line| line.trim());
+    while let Some(line) = lines.next() {
+        if line.starts_with("#[derive(") && line.contains("LispKeywords") {
+            derives = true;
+            continue;
+        }
+        // Doc comments and other attributes may sit between the derive
+        // and the struct.
+        if line.starts_with("//") || line.starts_with("#[") {
+            continue;
+        }
+        if derives {
+            if let Some(caps) = STRUCT_RE.captures(line) {
+                let fields = lines
+                    .by_ref()
+                    .take_while(|line| !line.starts_with('}'))
+                    .filter_map(|line| FIELD_RE.captures(line))
+                    .map(|caps| lisp_name(&caps[1]))
+                    .collect();
+                structs.insert(caps[1].to_string(), fields);
+            }
+        }
+        derives = false;
+    }
+    structs
+}
+
+/// Return the name of the function that LINE starts to define, if any.
+/// The name of a function defined by a macro starts with `$`.
+fn fn_name(line: &str) -> Option<&str> {
+    lazy_static! {
+        static ref FN_RE: Regex = Regex::new(concat!(
+            r"^(?:pub(?:\([^)]*\))?\s+)?",
+            r#"(?:(?:const|unsafe|extern(?:\s+"[^"]*")?)\s+)*"#,
+            r"fn\s+(\$?\w+)"
+        ))
+        .unwrap();
+    }
+    FN_RE.captures(line).map(|caps| caps.get(1).unwrap().as_str())
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn scan_rust_file(
     filename: *const c_char,
@@ -36,121 +425,119 @@ pub unsafe extern "C" fn scan_rust_file(
 ) {
     let filename = CStr::from_ptr(filename).to_str().unwrap();
     let fp = BufReader::new(File::open(&*filename).unwrap());
+    let lines: Vec<String> = fp.lines().map(Result::unwrap).collect();
+    let keywords = keyword_structs(&lines);
 
     let mut in_docstring = false;
     let mut docstring = String::new();
-    let mut docstring_usage = String::new();
+    // The usage line and the line number where it starts.
+    let mut docstring_usage: Option<(String, usize)> = None;
+    let mut in_usage = false;
     let mut attribute = String::new();
+    let mut in_attribute = false;
 
-    let mut line_iter = fp.lines();
+    let mut line_iter = lines.iter().enumerate().map(|(i, line)| (i + 1, line));
 
-    let mut in_lisp_fn = false;
-
-    while let Some(line) = line_iter.next() {
-        let line = line.unwrap();
+    while let Some((lineno, line)) = line_iter.next() {
         let line = line.trim();
 
         // Collect a whole docstring
         if line.starts_with("///") {
             if !in_docstring {
                 attribute.clear();
-                docstring_usage.clear();
+                docstring_usage = None;
                 docstring.clear();
             }
             in_docstring = true;
-            if line.starts_with("/// usage: (") {
-                docstring_usage.push_str(&line[11..]);
+            let text = line[3..].trim();
+            if in_usage {
+                let usage = &mut docstring_usage.as_mut().unwrap().0;
+                usage.push(' ');
+                usage.push_str(text);
+                in_usage = !is_balanced(usage);
+            } else if text.starts_with("usage:") {
+                let usage = text["usage:".len()..].trim().to_string();
+                in_usage = !is_balanced(&usage);
+                docstring_usage = Some((usage, lineno));
             } else {
                 docstring.push_str(line[3..].trim_left());
                 docstring.push('\n');
             }
-        } else {
-            in_docstring = false;
-        }
-
-        if line == "#[lisp_fn(" {
-            attribute = line.to_owned();
-            in_lisp_fn = true;
-            continue;
-        } else if line.starts_with("#[lisp_fn") {
-            attribute = line.to_owned();
             continue;
         }
+        in_docstring = false;
+        in_usage = false;
 
-        if in_lisp_fn {
-            if line == ")]" {
-                attribute += line;
-                in_lisp_fn = false;
-                continue;
-            } else {
-                attribute += line;
-                continue;
+        // Collect a whole attribute, which may span several lines
+        if in_attribute || line.starts_with("#[lisp_fn") {
+            if !in_attribute {
+                attribute.clear();
             }
+            attribute.push_str(line);
+            attribute.push(' ');
+            in_attribute = !is_balanced(&attribute);
+            continue;
         }
 
-        if line.starts_with("pub fn ") || line.starts_with("fn ") {
+        if let Some(name) = fn_name(line) {
+            // Whatever this function is, a pending attribute was its own.
+            let attribute = mem::replace(&mut attribute, String::new());
             if attribute.is_empty() {
                 // Not a #[lisp_fn]
                 continue;
             }
-            let attribute = mem::replace(&mut attribute, String::new());
-            let mut split = line.split('(');
-            let name = split.next().unwrap().split_whitespace().last().unwrap();
-
+            let name = name.to_string();
             if name.starts_with('$') {
                 // Macro; do not use it
                 continue;
             }
 
             // Read lines until the closing paren
-            let mut sig = split.next().unwrap().to_string();
-            while !sig.contains(')') {
-                sig.extend(line_iter.next().unwrap());
-            }
-            let sig = sig.split(')').next().unwrap();
-            let has_many_args = sig.contains("&mut") || sig.contains("&[");
-
-            // Split arg names and types
-            let splitters = [':', ','];
-            let args = sig.split_terminator(&splitters[..]).collect::<Vec<_>>();
+            let mut sig = line[line.find("fn ").unwrap()..].to_string();
+            let params = loop {
+                if let Some(params) = parse_params(&sig) {
+                    break params;
+                }
+                match line_iter.next() {
+                    Some((_, next)) => {
+                        sig.push(' ');
+                        sig.push_str(next.trim());
+                    }
+                    None => fatal(filename, lineno, "unexpected end of file in signature"),
+                }
+            };
 
-            let nargs = args.len() / 2;
+            let has_many_args = params.iter().any(Param::is_slice);
+            let nargs = params.len();
             let def_min_args = if has_many_args { 0 } else { nargs as i16 };
-            let attr_props = parse_lisp_fn(&attribute, name, def_min_args)
-                .unwrap_or_else(|e| panic!("Invalid #[lisp_fn] macro ({}): {}", attribute, e));
+            let attr_props = parse_lisp_fn(&attribute, &name, def_min_args).unwrap_or_else(|e| {
+                fatal(
+                    filename,
+                    lineno,
+                    &format!("Invalid #[lisp_fn] macro ({}): {}", attribute, e),
+                )
+            });
 
             if generate_globals != 0 {
                 let c_name_str = CString::new(format!("F{}", attr_props.c_name)).unwrap();
                 // -1 is MANY
                 // -2 is UNEVALLED
-                let maxargs = if has_many_args { -1 } else { nargs as c_int };
+                let takes_many =
+                    has_many_args || attr_props.keys.is_some() || attr_props.rest.is_some();
+                let maxargs = if takes_many { -1 } else { nargs as c_int };
                 add_global(FUNCTION, c_name_str.as_ptr(), maxargs, ptr::null());
             } else {
-                // Create usage line (fn ARG1 ...) from signature if necessary
-                if docstring_usage.is_empty() {
-                    docstring_usage.push_str("(fn ");
-                    for (i, chunk) in args.chunks(2).enumerate() {
-                        if chunk[1].contains("&mut") || chunk[1].contains("&[") {
-                            docstring_usage.push_str("&rest ");
-                        } else if i == attr_props.min as usize {
-                            docstring_usage.push_str("&optional ");
-                        }
-                        let argname = chunk[0]
-                            .trim()
-                            .trim_left_matches("mut ")
-                            .trim()
-                            .to_uppercase()
-                            .replace("_", "-");
-                        docstring_usage.push_str(&argname);
-                        docstring_usage.push(' ');
-                    }
-                    docstring_usage.pop();
-                    docstring_usage.push(')');
-                }
+                let usage = docstring_usage.as_ref().map(|&(ref usage, _)| usage.as_str());
+                let usage = check_usage(usage, &params, &attr_props, &keywords).unwrap_or_else(|e| {
+                    let lineno = docstring_usage.as_ref().map_or(lineno, |&(_, l)| l);
+                    fatal(filename, lineno, &format!("`{}`: {}", attr_props.name, e))
+                });
                 // Print contents for docfile to stdout
                 print!(
-                    "\x1fF{}\n{}\n{}",
-                    attr_props.name, docstring, docstring_usage
+                    "\x1fF{}\n{}\n\n{}",
+                    attr_props.name,
+                    docstring.trim_right_matches('\n'),
+                    usage
                 );
             }
         } else if line.starts_with("def_lisp_sym!(") {
@@ -196,3 +583,111 @@ pub unsafe extern "C" fn scan_rust_file(
     }
     stdout().flush().unwrap();
 }
+
+#[cfg(test)]
+fn test_attr(min: i16, keys: Option<&str>, rest: Option<&str>) -> LispFnArgs {
+    LispFnArgs {
+        name: "frob".to_string(),
+        c_name: "frob".to_string(),
+        min,
+        intspec: None,
+        unevalled: false,
+        keys: keys.map(str::to_string),
+        rest: rest.map(str::to_string),
+    }
+}
+
+#[test]
+fn test_parse_params() {
+    let params = parse_params("fn frob(mut a: Option<EmacsInt>, b_c: HashMap<K, V>) -> X").unwrap();
+    let names: Vec<_> = params.iter().map(Param::lisp_name).collect();
+    assert_eq!(names, ["A", "B-C"]);
+    assert_eq!(params[1].ty, "HashMap<K, V>");
+    assert!(parse_params("fn frob(a: LispObject,").is_none());
+}
+
+#[test]
+fn test_keyword_structs() {
+    let source = "
+        #[derive(LispKeywords)]
+        /// Options.
+        pub struct FrobOptions {
+            count: Option<EmacsInt>,
+            /// Whether to frob in place.
+            pub in_place: bool,
+        }
+
+        #[derive(Clone)]
+        struct Other {
+            field: bool,
+        }
+    ";
+    let lines: Vec<String> = source.lines().map(str::to_string).collect();
+    let structs = keyword_structs(&lines);
+    assert_eq!(structs.len(), 1);
+    assert_eq!(structs["FrobOptions"], ["COUNT", "IN-PLACE"]);
+}
+
+#[test]
+fn test_derived_usage() {
+    let none = HashMap::new();
+    let params = parse_params("fn frob(a: LispObject, b: LispObject, c: Vec<Buf>)").unwrap();
+    let attr = test_attr(2, None, None);
+    assert_eq!(check_usage(None, &params, &attr, &none).unwrap(), "(fn A B &optional C)");
+    let attr = test_attr(1, None, Some("c"));
+    assert_eq!(
+        check_usage(None, &params, &attr, &none).unwrap(),
+        "(fn A &optional B &rest C)"
+    );
+
+    let params = parse_params("fn frob(object: LispObject, options: FrobOptions)").unwrap();
+    let attr = test_attr(1, Some("options"), None);
+    assert!(check_usage(None, &params, &attr, &none).is_err());
+    let mut keywords = HashMap::new();
+    keywords.insert("FrobOptions".to_string(), vec!["COUNT".to_string(), "IN-PLACE".to_string()]);
+    assert_eq!(
+        check_usage(None, &params, &attr, &keywords).unwrap(),
+        "(fn OBJECT &key COUNT IN-PLACE)"
+    );
+}
+
+#[test]
+fn test_checked_usage() {
+    let none = HashMap::new();
+    let params = parse_params("fn frob(object: LispObject, options: FrobOptions)").unwrap();
+    let attr = test_attr(1, Some("options"), None);
+    assert_eq!(
+        check_usage(Some("(frob OBJECT &key COUNT IN-PLACE)"), &params, &attr, &none).unwrap(),
+        "(fn OBJECT &key COUNT IN-PLACE)"
+    );
+    assert!(check_usage(Some("(frob OBJECT)"), &params, &attr, &none).is_err());
+    assert!(check_usage(Some("(frob &key COUNT)"), &params, &attr, &none).is_err());
+    assert!(check_usage(Some("(frab OBJECT &rest ARGS)"), &params, &attr, &none).is_err());
+    assert!(check_usage(Some("(frob OBJECT &rest ARGS)"), &params, &attr, &none).is_err());
+
+    // With the keywords known, the usage line must list exactly them.
+    let mut keywords = HashMap::new();
+    keywords.insert("FrobOptions".to_string(), vec!["COUNT".to_string(), "IN-PLACE".to_string()]);
+    let usage = Some("(frob OBJECT &key IN-PLACE COUNT)");
+    assert!(check_usage(usage, &params, &attr, &keywords).is_ok());
+    let usage = Some("(frob OBJECT &key COUNT)");
+    assert!(check_usage(usage, &params, &attr, &keywords).is_err());
+
+    let params = parse_params("fn frob(object: LispObject, rest: Vec<Buf>)").unwrap();
+    let attr = test_attr(1, None, Some("rest"));
+    assert!(check_usage(Some("(frob OBJECT &key COUNT)"), &params, &attr, &none).is_err());
+
+    let params = parse_params("fn frob(args: &[LispObject])").unwrap();
+    let attr = test_attr(1, None, None);
+    assert!(check_usage(None, &params, &attr, &none).is_err());
+    assert!(check_usage(Some("(frob A &optional B)"), &params, &attr, &none).is_err());
+    assert!(check_usage(Some("(frob A &optional B &rest C)"), &params, &attr, &none).is_ok());
+}
+
+#[test]
+fn test_fn_name() {
+    assert_eq!(fn_name("pub fn frob(a: LispObject) -> LispObject {"), Some("frob"));
+    assert_eq!(fn_name("pub(crate) unsafe extern \"C\" fn frob() {"), Some("frob"));
+    assert_eq!(fn_name("fn $float_func(arg: LispObject) -> LispObject {"), Some("$float_func"));
+    assert_eq!(fn_name("let f = frob();"), None);
+}
@@ -4,16 +4,27 @@ use rand::{Rng, StdRng};
 use std::sync::Mutex;
 
 use std::ffi::{CStr, CString};
-use std::io;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
 use libc::{self, c_char, c_int, EEXIST, EINVAL};
 
 #[cfg(unix)]
 use libc::{open, O_CLOEXEC, O_CREAT, O_EXCL, O_RDWR};
+#[cfg(unix)]
+use std::ffi::OsStr;
+#[cfg(unix)]
+use std::os::unix::{
+    ffi::OsStrExt,
+    fs::{MetadataExt, PermissionsExt},
+    io::{AsRawFd, FromRawFd},
+};
 
 #[cfg(windows)]
 extern "C" {
     fn sys_open(filename: *const c_char, flags: c_int, mode: c_int) -> c_int;
+    fn sys_close(fd: c_int) -> c_int;
 }
 
 #[cfg(test)]
@@ -21,12 +32,16 @@ use std::env;
 
 const NUM_RETRIES: usize = 50;
 
+// The most symbolic links followed to find the file to replace, as
+// with the MAXSYMLINKS of most systems.
+const MAX_SYMLINKS: usize = 40;
+
 #[no_mangle]
 pub unsafe extern "C" fn rust_make_temp(template: *mut c_char, flags: c_int) -> c_int {
     let save_errno = errno::errno();
-    let template_string = CStr::from_ptr(template).to_string_lossy().into_owned();
+    let template_bytes = CStr::from_ptr(template).to_bytes().to_vec();
 
-    match make_temporary_file(template_string, flags) {
+    match make_temporary_file(template_bytes, flags) {
         Ok(result) => {
             errno::set_errno(save_errno);
             let name = CString::new(result.1).unwrap();
@@ -41,14 +56,16 @@ pub unsafe extern "C" fn rust_make_temp(template: *mut c_char, flags: c_int) ->
     }
 }
 
-pub fn make_temporary_file(template: String, flags: i32) -> Result<(i32, String), i32> {
+pub fn make_temporary_file(template: Vec<u8>, flags: i32) -> Result<(i32, Vec<u8>), i32> {
     let mut validated_template = validate_template(template)?;
     for _ in 0..NUM_RETRIES {
         generate_temporary_filename(&mut validated_template);
-        let attempt = CString::new(validated_template.clone()).map_err(|_| EEXIST)?;
+        let attempt = CString::new(validated_template.clone()).map_err(|_| EINVAL)?;
         let file_handle = match open_temporary_file(&attempt, flags) {
             Ok(file) => file,
-            Err(_) => continue,
+            // Only a name clash is worth another try.
+            Err(ref e) if e.raw_os_error() == Some(EEXIST) => continue,
+            Err(e) => return Err(e.raw_os_error().unwrap_or(EINVAL)),
         };
 
         return Ok((file_handle, validated_template));
@@ -57,15 +74,15 @@ pub fn make_temporary_file(template: String, flags: i32) -> Result<(i32, String)
     Err(EEXIST)
 }
 
-fn validate_template(template: String) -> Result<String, i32> {
-    if !template.ends_with("XXXXXX") {
+fn validate_template(template: Vec<u8>) -> Result<Vec<u8>, i32> {
+    if !template.ends_with(b"XXXXXX") {
         Err(EINVAL)
     } else {
         Ok(template)
     }
 }
 
-fn generate_temporary_filename(name: &mut String) {
+fn generate_temporary_filename(name: &mut [u8]) {
     // Note: rand::thread_rng causes a segfault on mac when used here,
     //  as of nightly-09-15
     lazy_static! {
@@ -74,9 +91,8 @@ fn generate_temporary_filename(name: &mut String) {
 
     let len = name.len();
     assert!(len >= 6);
-    let name_vec = unsafe { &mut name.as_mut_vec() };
 
-    let bytes = &mut name_vec[len - 6..len];
+    let bytes = &mut name[len - 6..len];
     {
         let mut rng = shared_rng.lock().unwrap();
         rng.fill_bytes(bytes);
@@ -118,25 +134,171 @@ fn open_temporary_file(name: &CString, flags: c_int) -> io::Result<c_int> {
     }
 }
 
+/// Replace the contents of the file at PATH with CONTENTS, such that a
+/// crash at any point leaves either the old or the new contents behind.
+///
+/// The data is written to a temporary file in the same directory, which
+/// is synced to disk, given the mode and ownership of the file it
+/// replaces, and finally renamed over PATH.  If PATH is a symbolic link,
+/// the file it points to is replaced and the link is kept.
+pub fn write_file_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let path = &resolve_symlinks(path)?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::from_raw_os_error(libc::EISDIR))?;
+    let mut template = path_to_bytes(dir);
+    template.extend_from_slice(b"/.");
+    template.extend_from_slice(&path_to_bytes(Path::new(name)));
+    template.extend_from_slice(b"-XXXXXX");
+
+    let (fd, temp_name) =
+        make_temporary_file(template, 0).map_err(io::Error::from_raw_os_error)?;
+    let temp_name = bytes_to_path(temp_name);
+    let mut file = file_from_fd(fd, &temp_name)?;
+
+    let result = file
+        .write_all(contents)
+        .and_then(|_| copy_permissions(path, &file))
+        .and_then(|_| file.sync_all());
+    drop(file);
+
+    let result = result.and_then(|_| fs::rename(&temp_name, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_name);
+        return result;
+    }
+
+    sync_directory(dir)
+}
+
+/// Follow PATH while it is a symbolic link, returning the name of the
+/// file it finally refers to, which need not exist.
+fn resolve_symlinks(path: &Path) -> io::Result<PathBuf> {
+    let mut path = path.to_path_buf();
+    for _ in 0..MAX_SYMLINKS {
+        match fs::symlink_metadata(&path) {
+            Ok(ref metadata) if metadata.file_type().is_symlink() => {
+                let target = fs::read_link(&path)?;
+                path = match path.parent() {
+                    Some(dir) => dir.join(target),
+                    None => target,
+                };
+            }
+            _ => return Ok(path),
+        }
+    }
+    Err(io::Error::from_raw_os_error(libc::ELOOP))
+}
+
+#[cfg(unix)]
+fn path_to_bytes(path: &Path) -> Vec<u8> {
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(unix)]
+fn bytes_to_path(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(OsStr::from_bytes(&bytes))
+}
+
+// File names are UTF-8 on the Emacs side of the MS-Windows port.
+#[cfg(windows)]
+fn path_to_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(windows)]
+fn bytes_to_path(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[cfg(unix)]
+fn file_from_fd(fd: c_int, _name: &Path) -> io::Result<File> {
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+#[cfg(windows)]
+fn file_from_fd(fd: c_int, name: &Path) -> io::Result<File> {
+    unsafe { sys_close(fd) };
+    fs::OpenOptions::new().write(true).open(name)
+}
+
+/// Give FILE the mode and ownership of the file at PATH.  If there is
+/// no such file yet, use the default mode for new files instead of the
+/// private mode temporary files are created with.
+#[cfg(unix)]
+fn copy_permissions(path: &Path, file: &File) -> io::Result<()> {
+    match fs::metadata(path) {
+        Ok(metadata) => {
+            file.set_permissions(metadata.permissions())?;
+            let temp_metadata = file.metadata()?;
+            if (temp_metadata.uid(), temp_metadata.gid()) != (metadata.uid(), metadata.gid()) {
+                // Only root may give a file away; others at least keep
+                // the group when they belong to it, and otherwise end up
+                // owning the file, just as with a plain save.
+                let fd = file.as_raw_fd();
+                unsafe {
+                    if libc::fchown(fd, metadata.uid(), metadata.gid()) != 0 {
+                        libc::fchown(fd, !0, metadata.gid());
+                    }
+                }
+            }
+            Ok(())
+        }
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+            let umask = unsafe {
+                let mask = libc::umask(0o022);
+                libc::umask(mask);
+                mask
+            };
+            file.set_permissions(fs::Permissions::from_mode(0o666 & !u32::from(umask)))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(windows)]
+fn copy_permissions(path: &Path, file: &File) -> io::Result<()> {
+    match fs::metadata(path) {
+        Ok(metadata) => file.set_permissions(metadata.permissions()),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Make the rename of a file in DIR durable.
+#[cfg(unix)]
+fn sync_directory(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(windows)]
+fn sync_directory(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
 #[test]
 #[should_panic]
 fn test_generate_bad_temporary_filename() {
-    let mut bad_name = String::from("1234");
+    let mut bad_name = b"1234".to_vec();
     generate_temporary_filename(&mut bad_name);
 }
 
 #[test]
 #[should_panic]
 fn test_bad_temporary_filename_validation() {
-    let bad_name = String::from("123");
+    let bad_name = b"123".to_vec();
     validate_template(bad_name).unwrap();
 }
 
 #[test]
 fn test_good_temporary_filename_validation() {
-    let good_name_1 = String::from("XXXXXX");
-    let good_name_2 = String::from(".emacsXXXXXX");
-    let good_name_3 = String::from("âââââ薔薔薔薔薔XXXXXX");
+    let good_name_1 = b"XXXXXX".to_vec();
+    let good_name_2 = b".emacsXXXXXX".to_vec();
+    let good_name_3 = "âââââ薔薔薔薔薔XXXXXX".as_bytes().to_vec();
     validate_template(good_name_1).unwrap();
     validate_template(good_name_2).unwrap();
     validate_template(good_name_3).unwrap();
@@ -144,7 +306,7 @@ fn test_good_temporary_filename_validation() {
 
 #[test]
 fn test_generate_temporary_filename() {
-    let mut name = String::from(".emacs-XXXXXX");
+    let mut name = b".emacs-XXXXXX".to_vec();
     let name_copy = name.clone();
     generate_temporary_filename(&mut name);
     assert!(
@@ -155,9 +317,9 @@ fn test_generate_temporary_filename() {
 
 #[test]
 fn test_generate_temporary_filename_change() {
-    let mut name = String::from(".emacs-XXXXXX");
+    let mut name = b".emacs-XXXXXX".to_vec();
     generate_temporary_filename(&mut name);
-    assert!(!name.ends_with("XXXXXX"));
+    assert!(!name.ends_with(b"XXXXXX"));
 }
 
 #[test]
@@ -187,3 +349,82 @@ fn test_rust_make_temp_error_inval() {
     let error = errno::errno();
     assert!(file_handle == -1 && error == errno::Errno(EINVAL));
 }
+
+#[test]
+fn test_write_file_atomically() {
+    let mut dir = env::temp_dir();
+    dir.push(format!(".emacs-atomic-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config");
+
+    write_file_atomically(&path, b"first").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"first");
+
+    write_file_atomically(&path, b"second").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"second");
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_write_file_atomically_keeps_mode() {
+    let mut dir = env::temp_dir();
+    dir.push(format!(".emacs-atomic-mode-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("secret");
+
+    fs::write(&path, b"old").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+    write_file_atomically(&path, b"new").unwrap();
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o640);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_make_temporary_file_error() {
+    let mut dir = env::temp_dir();
+    dir.push(format!(".emacs-missing-{}", std::process::id()));
+    dir.push(".emacs-XXXXXX");
+    let template = dir.to_string_lossy().into_owned().into_bytes();
+    assert_eq!(make_temporary_file(template, 0), Err(libc::ENOENT));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_write_file_atomically_through_symlink() {
+    let mut dir = env::temp_dir();
+    dir.push(format!(".emacs-atomic-link-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let target = dir.join("target");
+    let link = dir.join("link");
+
+    fs::write(&target, b"old").unwrap();
+    std::os::unix::fs::symlink("target", &link).unwrap();
+    write_file_atomically(&link, b"new").unwrap();
+    assert!(fs::symlink_metadata(&link)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(fs::read(&target).unwrap(), b"new");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_write_file_atomically_raw_name() {
+    let mut dir = env::temp_dir();
+    dir.push(format!(".emacs-atomic-raw-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(OsStr::from_bytes(b"caf\xe9"));
+
+    write_file_atomically(&path, b"latin-1").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"latin-1");
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
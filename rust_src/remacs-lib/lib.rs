@@ -22,6 +22,8 @@ pub use crate::{
     docfile::scan_rust_file,
     // Used for creating temporary files in emacs
     files::rust_make_temp,
+    // Used by write-region-atomically
+    files::write_file_atomically,
 
     math::{rust_count_one_bits, rust_count_trailing_zero_bits},
 
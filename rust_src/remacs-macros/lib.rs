@@ -39,8 +39,75 @@ pub fn lisp_fn(attr_ts: TokenStream, fn_ts: TokenStream) -> TokenStream {
         quote!{ std::ptr::null() }
     };
 
-    match function.fntype {
-        function::LispFnType::Normal(_) => {
+    let tail = match (&lisp_fn_args.keys, &lisp_fn_args.rest) {
+        (Some(name), _) => Some((name.clone(), true)),
+        (_, Some(name)) => Some((name.clone(), false)),
+        (None, None) => None,
+    };
+
+    if let Some((ref name, _)) = tail {
+        if lisp_fn_args.unevalled {
+            panic!("Invalid lisp_fn attribute: unevalled functions cannot use keys or rest");
+        }
+        match function.fntype {
+            function::LispFnType::Normal(_) => {}
+            function::LispFnType::Many => {
+                panic!("Invalid lisp_fn attribute: `[LispObject]` cannot be used with keys or rest")
+            }
+        }
+        if function.args.last().map_or(true, |ident| ident.to_string() != *name) {
+            panic!("Invalid lisp_fn attribute: `{}` is not the last argument", name);
+        }
+    }
+
+    // Keyword and rest arguments are collected on the C side as MANY.
+    let takes_many = match function.fntype {
+        function::LispFnType::Normal(_) => tail.is_some(),
+        function::LispFnType::Many => true,
+    };
+
+    match (&function.fntype, tail) {
+        (&function::LispFnType::Normal(_), Some((_, is_keys))) => {
+            let args = quote! {
+                nargs: libc::ptrdiff_t,
+                args: *mut crate::lisp::LispObject,
+            };
+            cargs.append_all(args);
+
+            let b = quote! {
+                let args = unsafe {
+                    std::slice::from_raw_parts::<crate::lisp::LispObject>(args, nargs as usize)
+                };
+            };
+            body.append_all(b);
+
+            // Positional arguments that were not passed are nil, as for
+            // the optional arguments of a normal function.
+            let npositional = function.args.len() - 1;
+            for i in 0..npositional {
+                let arg = quote! {
+                    args.get(#i).map_or(crate::remacs_sys::Qnil, |&arg| arg).into(),
+                };
+                rargs.append_all(arg);
+            }
+
+            let arg = if is_keys {
+                quote! {
+                    crate::lisp::LispKeywords::from_keyword_args(
+                        &args[std::cmp::min(#npositional, args.len())..]
+                    )
+                }
+            } else {
+                quote! {
+                    args[std::cmp::min(#npositional, args.len())..]
+                        .iter()
+                        .map(|&arg| arg.into())
+                        .collect()
+                }
+            };
+            rargs.append_all(arg);
+        }
+        (&function::LispFnType::Normal(_), None) => {
             for ident in function.args {
                 let arg = quote! { #ident: crate::lisp::LispObject, };
                 cargs.append_all(arg);
@@ -49,7 +116,7 @@ pub fn lisp_fn(attr_ts: TokenStream, fn_ts: TokenStream) -> TokenStream {
                 rargs.append_all(arg);
             }
         }
-        function::LispFnType::Many => {
+        (&function::LispFnType::Many, _) => {
             let args = quote! {
                 nargs: libc::ptrdiff_t,
                 args: *mut crate::lisp::LispObject,
@@ -78,31 +145,29 @@ pub fn lisp_fn(attr_ts: TokenStream, fn_ts: TokenStream) -> TokenStream {
 
     let functype = if lisp_fn_args.unevalled {
         quote! { aUNEVALLED }
+    } else if takes_many {
+        quote! { aMANY }
     } else {
-        match function.fntype {
-            function::LispFnType::Normal(_) => match max_args {
-                0 => quote! { a0 },
-                1 => quote! { a1 },
-                2 => quote! { a2 },
-                3 => quote! { a3 },
-                4 => quote! { a4 },
-                5 => quote! { a5 },
-                6 => quote! { a6 },
-                7 => quote! { a7 },
-                8 => quote! { a8 },
-                _ => panic!("max_args too high"),
-            },
-            function::LispFnType::Many => quote! { aMANY },
+        match max_args {
+            0 => quote! { a0 },
+            1 => quote! { a1 },
+            2 => quote! { a2 },
+            3 => quote! { a3 },
+            4 => quote! { a4 },
+            5 => quote! { a5 },
+            6 => quote! { a6 },
+            7 => quote! { a7 },
+            8 => quote! { a8 },
+            _ => panic!("max_args too high"),
         }
     };
 
     let max_args = if lisp_fn_args.unevalled {
         quote! { -1 }
+    } else if takes_many {
+        quote! { crate::lisp::MANY }
     } else {
-        match function.fntype {
-            function::LispFnType::Normal(_) => quote! { #max_args },
-            function::LispFnType::Many => quote! { crate::lisp::MANY  },
-        }
+        quote! { #max_args }
     };
     let symbol_name = CByteLiteral(&lisp_fn_args.name);
 
@@ -164,6 +229,88 @@ pub fn lisp_fn(attr_ts: TokenStream, fn_ts: TokenStream) -> TokenStream {
     tokens.into_iter().chain(fn_ts.into_iter()).collect()
 }
 
+/// Turn a function into a test that runs inside a live Lisp runtime,
+/// in a fresh temporary buffer.  The test is only built with the
+/// `lisp-tests` feature, which links in the C side of Emacs.
+#[proc_macro_attribute]
+pub fn lisp_test(attr_ts: TokenStream, fn_ts: TokenStream) -> TokenStream {
+    if !attr_ts.is_empty() {
+        panic!("lisp_test takes no arguments");
+    }
+    let (attrs, ident, block) = match syn::parse(fn_ts).unwrap() {
+        syn::Item::Fn(syn::ItemFn {
+            attrs,
+            ident,
+            decl,
+            block,
+            ..
+        }) => {
+            if !decl.inputs.is_empty() || !decl.generics.params.is_empty() {
+                panic!("lisp tests cannot take arguments");
+            }
+            match decl.output {
+                syn::ReturnType::Default => {}
+                _ => panic!("lisp tests cannot return a value"),
+            }
+            (attrs, ident, block)
+        }
+        _ => panic!("`lisp_test` attribute can only be used on functions"),
+    };
+
+    let tokens = quote! {
+        #[cfg(feature = "lisp-tests")]
+        #[test]
+        #(#attrs)*
+        fn #ident() {
+            fn body() #block
+            crate::lisp_test::run(stringify!(#ident), body);
+        }
+    };
+    tokens.into()
+}
+
+/// Implement `LispKeywords` for a struct with named fields.  Each field
+/// `foo_bar` receives the value of the keyword `:foo-bar`.
+#[proc_macro_derive(LispKeywords)]
+pub fn lisp_keywords(ts: TokenStream) -> TokenStream {
+    let input: syn::DeriveInput = syn::parse(ts).unwrap();
+    if !input.generics.params.is_empty() {
+        panic!("LispKeywords cannot be derived for generic structs");
+    }
+    let fields = match input.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(ref fields),
+            ..
+        }) => &fields.named,
+        _ => panic!("LispKeywords can only be derived for structs with named fields"),
+    };
+
+    let name = input.ident;
+    let idents: Vec<syn::Ident> = fields.iter().map(|f| f.ident.clone().unwrap()).collect();
+    let keywords: Vec<syn::Ident> = idents
+        .iter()
+        .map(|ident| concat_idents("QC", &ident.to_string()))
+        .collect();
+    let nfields = idents.len();
+    let indices = 0..nfields;
+
+    let tokens = quote! {
+        impl crate::lisp::LispKeywords for #name {
+            const KEYWORDS: &'static [crate::lisp::LispObject] =
+                &[#(crate::remacs_sys::#keywords),*];
+
+            fn from_keyword_args(args: &[crate::lisp::LispObject]) -> Self {
+                let mut values = [crate::remacs_sys::Qnil; #nfields];
+                crate::lisp::parse_keyword_args(args, Self::KEYWORDS, &mut values);
+                #name {
+                    #(#idents: values[#indices].into()),*
+                }
+            }
+        }
+    };
+    tokens.into()
+}
+
 struct CByteLiteral<'a>(&'a str);
 
 impl<'a> quote::ToTokens for CByteLiteral<'a> {
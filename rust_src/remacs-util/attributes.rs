@@ -18,7 +18,8 @@ struct LispFnArgsRaw {
     c_name: Option<String>,
     /// Minimum number of required arguments.
     /// If not given, all arguments are required for normal functions,
-    /// and no arguments are required for MANY functions.
+    /// and no arguments are required for MANY functions.  A `keys` or
+    /// `rest` parameter does not count as a required argument.
     #[darling(default)]
     min: Option<String>,
     /// The interactive specification. This may be a normal prompt
@@ -29,6 +30,14 @@ struct LispFnArgsRaw {
     /// Whether unevalled or not.
     #[darling(default)]
     unevalled: Option<String>,
+    /// Name of the trailing parameter that receives the keyword
+    /// arguments.  Its type must implement `LispKeywords`.
+    #[darling(default)]
+    keys: Option<String>,
+    /// Name of the trailing parameter that receives the `&rest`
+    /// arguments, converted element by element into a `Vec`.
+    #[darling(default)]
+    rest: Option<String>,
 }
 
 impl LispFnArgsRaw {
@@ -36,6 +45,14 @@ impl LispFnArgsRaw {
     where
         D: Display + ?Sized,
     {
+        if self.keys.is_some() && self.rest.is_some() {
+            return Err("\"keys\" and \"rest\" cannot be used together".to_string());
+        }
+        let def_min_args = if self.keys.is_some() || self.rest.is_some() {
+            def_min_args - 1
+        } else {
+            def_min_args
+        };
         Ok(LispFnArgs {
             name: self
                 .name
@@ -53,6 +70,8 @@ impl LispFnArgsRaw {
             } else {
                 false
             },
+            keys: self.keys,
+            rest: self.rest,
         })
     }
 }
@@ -63,6 +82,8 @@ pub struct LispFnArgs {
     pub min: i16,
     pub intspec: Option<String>,
     pub unevalled: bool,
+    pub keys: Option<String>,
+    pub rest: Option<String>,
 }
 
 pub fn parse_lisp_fn<D>(src: &str, def_name: &D, def_min_args: i16) -> Result<LispFnArgs, String>
@@ -1,11 +1,15 @@
 //! Storage allocation and gc
 
+use std::mem::size_of;
+
 use remacs_macros::lisp_fn;
 
 use crate::{
     lisp::{defsubr, LispObject},
     remacs_sys::bounded_number,
-    remacs_sys::globals,
+    remacs_sys::{
+        globals, interval, EmacsInt, Lisp_Cons, Lisp_Float, Lisp_Misc, Lisp_String, Lisp_Symbol,
+    },
 };
 
 /// Return a list of counters that measure how much consing there has been.
@@ -35,4 +39,20 @@ pub fn memory_use_counts() -> Vec<LispObject> {
     }
 }
 
+/// Return roughly how many bytes have been consed, from the counters
+/// that `memory-use-counts' returns.  Like them, this wraps around.
+pub fn bytes_consed() -> EmacsInt {
+    let bytes = |count: EmacsInt, size: usize| count.wrapping_mul(size as EmacsInt);
+    unsafe {
+        bytes(globals.cons_cells_consed, size_of::<Lisp_Cons>())
+            .wrapping_add(bytes(globals.floats_consed, size_of::<Lisp_Float>()))
+            .wrapping_add(bytes(globals.vector_cells_consed, size_of::<LispObject>()))
+            .wrapping_add(bytes(globals.symbols_consed, size_of::<Lisp_Symbol>()))
+            .wrapping_add(globals.string_chars_consed)
+            .wrapping_add(bytes(globals.misc_objects_consed, size_of::<Lisp_Misc>()))
+            .wrapping_add(bytes(globals.intervals_consed, size_of::<interval>()))
+            .wrapping_add(bytes(globals.strings_consed, size_of::<Lisp_String>()))
+    }
+}
+
 include!(concat!(env!("OUT_DIR"), "/alloc_exports.rs"));
@@ -174,6 +174,10 @@ impl LispBufferRef {
         self.case_fold_search_
     }
 
+    pub fn case_canon_table(self) -> LispObject {
+        self.case_canon_table_
+    }
+
     // Check if buffer is live
     pub fn is_live(self) -> bool {
         self.name_.is_not_nil()
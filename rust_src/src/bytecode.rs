@@ -1,41 +1,1846 @@
 //! Execution of byte code produced by bytecomp.el.
 
+use std::cmp;
+
+use libc::{c_char, ptrdiff_t};
+
 use remacs_macros::lisp_fn;
 
 use crate::{
+    buffers::{current_buffer, set_buffer},
+    casefiddle::{downcase, upcase},
+    cmds::{end_of_line, forward_char, forward_line},
+    data::{aref, aset, set},
+    editfns::{
+        bobp, bolp, char_after, eobp, eolp, following_char, goto_char, point, point_max,
+        point_min, preceding_char,
+    },
+    eval::{funcall, progn},
+    fns::concat,
+    hashtable::{gethash, puthash, HashLookupResult},
+    indent::current_column,
     lisp::{defsubr, LispObject},
-    remacs_sys::exec_byte_code as c_exec_byte_code,
-    remacs_sys::Qnil,
+    lists::{
+        assq, car, car_safe, cdr, cdr_safe, get, list, member, memq, nth, nthcdr, setcar, setcdr,
+    },
+    marker::set_marker,
+    math::{add1, arithcompare, max, min, minus, plus, quo, rem, sub1, times, ArithComparison},
+    multibyte::LispStringRef,
+    numbers::MOST_POSITIVE_FIXNUM,
+    obarray::{intern, LispObarrayRef},
+    remacs_sys::{
+        globals, internal_catch, internal_condition_case_1, internal_lisp_condition_case,
+        maybe_gc, maybe_quit, prog_ignore, record_unwind_current_buffer, record_unwind_protect,
+        restore_window_configuration, save_excursion_restore, save_excursion_save,
+        save_restriction_restore, save_restriction_save, specbind, temp_output_buffer_setup,
+        temp_output_buffer_show, unbind_to,
+    },
+    remacs_sys::{eval_sub, EmacsInt, Lisp_Compiled},
+    remacs_sys::{
+        Fbuffer_substring, Fchar_syntax, Fcurrent_window_configuration, Fdelete_region,
+        Ffetch_bytecode, Fforward_word, Ffset, Findent_to, Finsert, Fmake_hash_table,
+        Fmake_vector, Fnarrow_to_region, Fnconc, Fnreverse, Fskip_chars_backward,
+        Fskip_chars_forward, Fstring_as_unibyte, Fsubstring, Fwiden,
+    },
+    remacs_sys::{QCtest, QCweakness, Qeq, Qkey, Qnil, Qt, Qwrong_number_of_arguments},
+    search::{match_beginning, match_end},
+    strings::{string_equal, string_lessp},
+    symbols::{symbol_function, symbol_value},
+    threads::{c_specpdl_index, ThreadState},
+    vectors::{elt, length, LispVectorRef},
 };
 
-// Temporary Rust wrapper for C's exec_byte_code
-fn rust_exec_byte_code(
+/// Byte codes, as emitted by bytecomp.el.  The names follow the C
+/// interpreter (and `byte-code-vector'), with the `B' prefix dropped.
+pub mod op {
+    /// `STACK_REF + 0' is not implemented: the compiler uses `DUP'.
+    pub const STACK_REF: u8 = 0;
+    pub const STACK_REF1: u8 = 1;
+    pub const STACK_REF5: u8 = 5;
+    pub const STACK_REF6: u8 = 6;
+    pub const STACK_REF7: u8 = 7;
+    pub const VARREF: u8 = 0o10;
+    pub const VARREF7: u8 = 0o17;
+    pub const VARSET: u8 = 0o20;
+    pub const VARSET7: u8 = 0o27;
+    pub const VARBIND: u8 = 0o30;
+    pub const VARBIND7: u8 = 0o37;
+    pub const CALL: u8 = 0o40;
+    pub const CALL7: u8 = 0o47;
+    pub const UNBIND: u8 = 0o50;
+    pub const UNBIND7: u8 = 0o57;
+
+    pub const POPHANDLER: u8 = 0o60;
+    pub const PUSHCONDITIONCASE: u8 = 0o61;
+    pub const PUSHCATCH: u8 = 0o62;
+
+    pub const NTH: u8 = 0o70;
+    pub const SYMBOLP: u8 = 0o71;
+    pub const CONSP: u8 = 0o72;
+    pub const STRINGP: u8 = 0o73;
+    pub const LISTP: u8 = 0o74;
+    pub const EQ: u8 = 0o75;
+    pub const MEMQ: u8 = 0o76;
+    pub const NOT: u8 = 0o77;
+    pub const CAR: u8 = 0o100;
+    pub const CDR: u8 = 0o101;
+    pub const CONS: u8 = 0o102;
+    pub const LIST1: u8 = 0o103;
+    pub const LIST2: u8 = 0o104;
+    pub const LIST3: u8 = 0o105;
+    pub const LIST4: u8 = 0o106;
+    pub const LENGTH: u8 = 0o107;
+    pub const AREF: u8 = 0o110;
+    pub const ASET: u8 = 0o111;
+    pub const SYMBOL_VALUE: u8 = 0o112;
+    pub const SYMBOL_FUNCTION: u8 = 0o113;
+    pub const SET: u8 = 0o114;
+    pub const FSET: u8 = 0o115;
+    pub const GET: u8 = 0o116;
+    pub const SUBSTRING: u8 = 0o117;
+    pub const CONCAT2: u8 = 0o120;
+    pub const CONCAT3: u8 = 0o121;
+    pub const CONCAT4: u8 = 0o122;
+    pub const SUB1: u8 = 0o123;
+    pub const ADD1: u8 = 0o124;
+    pub const EQLSIGN: u8 = 0o125;
+    pub const GTR: u8 = 0o126;
+    pub const LSS: u8 = 0o127;
+    pub const LEQ: u8 = 0o130;
+    pub const GEQ: u8 = 0o131;
+    pub const DIFF: u8 = 0o132;
+    pub const NEGATE: u8 = 0o133;
+    pub const PLUS: u8 = 0o134;
+    pub const MAX: u8 = 0o135;
+    pub const MIN: u8 = 0o136;
+    pub const MULT: u8 = 0o137;
+
+    pub const POINT: u8 = 0o140;
+    /// Obsolete; was `Bmark' in v17.
+    pub const SAVE_CURRENT_BUFFER: u8 = 0o141;
+    pub const GOTO_CHAR: u8 = 0o142;
+    pub const INSERT: u8 = 0o143;
+    pub const POINT_MAX: u8 = 0o144;
+    pub const POINT_MIN: u8 = 0o145;
+    pub const CHAR_AFTER: u8 = 0o146;
+    pub const FOLLOWING_CHAR: u8 = 0o147;
+    pub const PRECEDING_CHAR: u8 = 0o150;
+    pub const CURRENT_COLUMN: u8 = 0o151;
+    pub const INDENT_TO: u8 = 0o152;
+    pub const EOLP: u8 = 0o154;
+    pub const EOBP: u8 = 0o155;
+    pub const BOLP: u8 = 0o156;
+    pub const BOBP: u8 = 0o157;
+    pub const CURRENT_BUFFER: u8 = 0o160;
+    pub const SET_BUFFER: u8 = 0o161;
+    pub const SAVE_CURRENT_BUFFER_1: u8 = 0o162;
+    /// Obsolete since 24.1.
+    pub const INTERACTIVE_P: u8 = 0o164;
+
+    pub const FORWARD_CHAR: u8 = 0o165;
+    pub const FORWARD_WORD: u8 = 0o166;
+    pub const SKIP_CHARS_FORWARD: u8 = 0o167;
+    pub const SKIP_CHARS_BACKWARD: u8 = 0o170;
+    pub const FORWARD_LINE: u8 = 0o171;
+    pub const CHAR_SYNTAX: u8 = 0o172;
+    pub const BUFFER_SUBSTRING: u8 = 0o173;
+    pub const DELETE_REGION: u8 = 0o174;
+    pub const NARROW_TO_REGION: u8 = 0o175;
+    pub const WIDEN: u8 = 0o176;
+    pub const END_OF_LINE: u8 = 0o177;
+
+    pub const CONSTANT2: u8 = 0o201;
+    pub const GOTO: u8 = 0o202;
+    pub const GOTOIFNIL: u8 = 0o203;
+    pub const GOTOIFNONNIL: u8 = 0o204;
+    pub const GOTOIFNILELSEPOP: u8 = 0o205;
+    pub const GOTOIFNONNILELSEPOP: u8 = 0o206;
+    pub const RETURN: u8 = 0o207;
+    pub const DISCARD: u8 = 0o210;
+    pub const DUP: u8 = 0o211;
+
+    pub const SAVE_EXCURSION: u8 = 0o212;
+    /// Obsolete since 24.1.
+    pub const SAVE_WINDOW_EXCURSION: u8 = 0o213;
+    pub const SAVE_RESTRICTION: u8 = 0o214;
+    /// Obsolete since 24.4.
+    pub const CATCH: u8 = 0o215;
+
+    pub const UNWIND_PROTECT: u8 = 0o216;
+    /// Obsolete since 24.4.
+    pub const CONDITION_CASE: u8 = 0o217;
+    /// Obsolete since 24.1.
+    pub const TEMP_OUTPUT_BUFFER_SETUP: u8 = 0o220;
+    /// Obsolete since 24.1.
+    pub const TEMP_OUTPUT_BUFFER_SHOW: u8 = 0o221;
+
+    /// Obsolete.  Never used.
+    pub const UNBIND_ALL: u8 = 0o222;
+
+    pub const SET_MARKER: u8 = 0o223;
+    pub const MATCH_BEGINNING: u8 = 0o224;
+    pub const MATCH_END: u8 = 0o225;
+    pub const UPCASE: u8 = 0o226;
+    pub const DOWNCASE: u8 = 0o227;
+
+    pub const STRINGEQLSIGN: u8 = 0o230;
+    pub const STRINGLSS: u8 = 0o231;
+    pub const EQUAL: u8 = 0o232;
+    pub const NTHCDR: u8 = 0o233;
+    pub const ELT: u8 = 0o234;
+    pub const MEMBER: u8 = 0o235;
+    pub const ASSQ: u8 = 0o236;
+    pub const NREVERSE: u8 = 0o237;
+    pub const SETCAR: u8 = 0o240;
+    pub const SETCDR: u8 = 0o241;
+    pub const CAR_SAFE: u8 = 0o242;
+    pub const CDR_SAFE: u8 = 0o243;
+    pub const NCONC: u8 = 0o244;
+    pub const QUO: u8 = 0o245;
+    pub const REM: u8 = 0o246;
+    pub const NUMBERP: u8 = 0o247;
+    pub const INTEGERP: u8 = 0o250;
+
+    pub const RGOTO: u8 = 0o252;
+    pub const RGOTOIFNIL: u8 = 0o253;
+    pub const RGOTOIFNONNIL: u8 = 0o254;
+    pub const RGOTOIFNILELSEPOP: u8 = 0o255;
+    pub const RGOTOIFNONNILELSEPOP: u8 = 0o256;
+
+    pub const LISTN: u8 = 0o257;
+    pub const CONCATN: u8 = 0o260;
+    pub const INSERTN: u8 = 0o261;
+
+    pub const STACK_SET: u8 = 0o262;
+    pub const STACK_SET2: u8 = 0o263;
+    pub const DISCARDN: u8 = 0o266;
+
+    pub const SWITCH: u8 = 0o267;
+
+    pub const CONSTANT: u8 = 0o300;
+}
+
+/// Why a run of the interpreter loop stopped.
+enum Exit {
+    /// `POPHANDLER' closed the handler region being run.
+    PopHandler,
+    /// `RETURN' was executed; the value is the function's result.
+    Return(LispObject),
+}
+
+/// The state of one activation of a byte-code function.
+///
+/// Nothing in here owns Rust heap memory: a `throw' or `signal' may
+/// longjmp straight over the frame.  The stack is a region of the
+/// thread's byte-code stack, a Lisp vector, so that the garbage
+/// collector marks the values on it; see `reserve_stack'.  The code is
+/// read out of the byte-code string by index, since compacting the
+/// string data may move it while Lisp code runs.
+struct ByteCodeFrame {
+    bytestr: LispStringRef,
+    constants: LispVectorRef,
+    /// The index of the first slot of this frame's region.
+    base: usize,
+    /// The size of the region, MAXDEPTH.
+    depth: usize,
+    /// The number of values on the stack.
+    top: usize,
+    pc: usize,
+    /// The specpdl index on entry, to check that bindings are balanced.
+    count: ptrdiff_t,
+    quitcounter: u8,
+    /// Set by `run_handler_body' when a handler region finishes
+    /// without a non-local exit.
+    handler_exit: Option<Exit>,
+    /// The opcode counts of this function, if `byte-metering-on' was
+    /// non-nil when it was called.
+    meter: Option<LispVectorRef>,
+    /// The previously executed opcode, for the pair counts in
+    /// `byte-code-meter'.
+    last_opcode: u8,
+}
+
+impl ByteCodeFrame {
+    fn fetch(&mut self) -> u8 {
+        let code = self.bytestr.as_slice();
+        if self.pc >= code.len() {
+            error!("Byte-code ends unexpectedly at pc {}", self.pc);
+        }
+        let byte = code[self.pc];
+        self.pc += 1;
+        byte
+    }
+
+    /// Fetch two bytes from the code and make a 16-bit number out of
+    /// them.
+    fn fetch2(&mut self) -> usize {
+        let lo = self.fetch() as usize;
+        let hi = self.fetch() as usize;
+        lo + (hi << 8)
+    }
+
+    fn constant(&self, idx: usize) -> LispObject {
+        if idx >= self.constants.len() {
+            error!("Invalid byte-code constant index {}", idx);
+        }
+        self.constants.get(idx)
+    }
+
+    fn push(&mut self, value: LispObject) {
+        if self.top >= self.depth {
+            error!("Byte-code stack overflow (maximum depth {})", self.depth);
+        }
+        let idx = self.top;
+        self.set(idx, value);
+        self.top += 1;
+    }
+
+    /// Return the value in slot IDX of this frame's region.  The
+    /// thread's stack vector is looked up afresh on every access, since
+    /// a call may have replaced it with a bigger one.
+    fn get(&self, idx: usize) -> LispObject {
+        thread_stack().get(self.base + idx)
+    }
+
+    fn set(&mut self, idx: usize, value: LispObject) {
+        thread_stack().set(self.base + idx, value);
+    }
+
+    fn pop(&mut self) -> LispObject {
+        let value = self.top_value();
+        self.top -= 1;
+        value
+    }
+
+    fn top_value(&self) -> LispObject {
+        self.peek(0)
+    }
+
+    fn set_top(&mut self, value: LispObject) {
+        let idx = self.stack_index(0);
+        self.set(idx, value);
+    }
+
+    /// Return the stack index of the value N places below the top.
+    fn stack_index(&self, n: usize) -> usize {
+        if n >= self.top {
+            error!("Byte-code stack underflow at pc {}", self.pc);
+        }
+        self.top - 1 - n
+    }
+
+    fn peek(&self, n: usize) -> LispObject {
+        self.get(self.stack_index(n))
+    }
+
+    fn discard(&mut self, n: usize) {
+        if n > self.top {
+            error!("Byte-code stack underflow at pc {}", self.pc);
+        }
+        self.top -= n;
+    }
+
+    /// Remove the top N values from the stack and return them, deepest
+    /// first.  They stay in the stack vector, so they remain protected
+    /// from GC while the caller uses them.  Should a call replace the
+    /// vector, the old one lives on as long as the slice does, as the
+    /// collector scans the C stack for pointers into objects.
+    fn pop_n(&mut self, n: usize) -> &mut [LispObject] {
+        self.discard(n);
+        let start = self.base + self.top;
+        let slots = &mut thread_stack().as_mut_slice()[start..start + n];
+        unsafe { std::slice::from_raw_parts_mut(slots.as_mut_ptr(), n) }
+    }
+
+    fn goto(&mut self, dest: usize) {
+        if dest >= self.bytestr.len_bytes() as usize {
+            error!("Byte-code jump to {} is out of range", dest);
+        }
+        self.jump_to(dest);
+    }
+
+    fn relative_goto(&mut self, offset: u8) {
+        let dest = self.pc as isize + offset as isize - 128;
+        if dest < 0 {
+            error!("Byte-code jump to {} is out of range", dest);
+        }
+        self.goto(dest as usize);
+    }
+
+    fn jump_to(&mut self, dest: usize) {
+        if dest < self.pc {
+            self.quitcounter = self.quitcounter.wrapping_add(1);
+            if self.quitcounter == 0 {
+                self.quitcounter = 1;
+                unsafe {
+                    maybe_gc();
+                    maybe_quit();
+                }
+            }
+        }
+        self.pc = dest;
+    }
+
+    /// Pop an argument, apply F to it and the new top of stack, and
+    /// replace the top of stack with the result.
+    fn binary<F: FnOnce(LispObject, LispObject) -> LispObject>(&mut self, f: F) {
+        let v1 = self.pop();
+        let v2 = self.top_value();
+        self.set_top(f(v2, v1));
+    }
+
+    fn unary<F: FnOnce(LispObject) -> LispObject>(&mut self, f: F) {
+        let v1 = self.top_value();
+        self.set_top(f(v1));
+    }
+
+    /// Replace the top N values with the result of F applied to them.
+    fn nary<F: FnOnce(&mut [LispObject]) -> LispObject>(&mut self, n: usize, f: F) {
+        let result = f(self.pop_n(n));
+        self.push(result);
+    }
+
+    /// Run the body of a `PUSHCATCH' or `PUSHCONDITIONCASE' handler
+    /// region, then continue after the handler has been popped.
+    fn push_handler(&mut self, catch: bool) -> Option<Exit> {
+        let tag = self.pop();
+        let dest = self.fetch2();
+        let saved_top = self.top;
+        self.handler_exit = None;
+
+        let frame = LispObject::from_raw_ptr(self as *mut ByteCodeFrame);
+        let value = unsafe {
+            if catch {
+                internal_catch(tag, Some(run_handler_body), frame)
+            } else {
+                internal_condition_case_1(Some(run_handler_body), frame, tag, Some(handler_value))
+            }
+        };
+
+        match self.handler_exit.take() {
+            Some(Exit::PopHandler) => None,
+            exit @ Some(Exit::Return(_)) => exit,
+            None => {
+                // A non-local exit landed here.
+                self.top = saved_top;
+                self.push(value);
+                self.goto(dest);
+                None
+            }
+        }
+    }
+
+    /// Count the execution of OPCODE, if this function is metered.
+    fn meter_opcode(&mut self, opcode: u8) {
+        if let Some(meter) = self.meter {
+            bump_meter(meter.as_lisp_obj(), opcode);
+            let global = global_meter();
+            bump_meter(global.get(0), opcode);
+            if self.last_opcode != 0 {
+                bump_meter(global.get(self.last_opcode as usize), opcode);
+            }
+            self.last_opcode = opcode;
+        }
+    }
+
+    fn run(&mut self) -> Exit {
+        loop {
+            let opcode = self.fetch();
+            self.meter_opcode(opcode);
+            match opcode {
+                op::STACK_REF1..=op::STACK_REF5 => {
+                    let v1 = self.peek((opcode - op::STACK_REF) as usize);
+                    self.push(v1);
+                }
+                op::STACK_REF6 => {
+                    let n = self.fetch() as usize;
+                    let v1 = self.peek(n);
+                    self.push(v1);
+                }
+                op::STACK_REF7 => {
+                    let n = self.fetch2();
+                    let v1 = self.peek(n);
+                    self.push(v1);
+                }
+
+                op::VARREF..=op::VARREF7 => {
+                    let idx = self.operand(opcode, op::VARREF);
+                    let v1 = symbol_value(self.constant(idx));
+                    self.push(v1);
+                }
+                op::VARSET..=op::VARSET7 => {
+                    let idx = self.operand(opcode, op::VARSET);
+                    let sym = self.constant(idx);
+                    let val = self.pop();
+                    set(sym.into(), val);
+                }
+                op::VARBIND..=op::VARBIND7 => {
+                    let idx = self.operand(opcode, op::VARBIND);
+                    let sym = self.constant(idx);
+                    let val = self.pop();
+                    unsafe { specbind(sym, val) };
+                }
+                op::CALL..=op::CALL7 => {
+                    let nargs = self.operand(opcode, op::CALL);
+                    self.nary(nargs + 1, funcall);
+                }
+                op::UNBIND..=op::UNBIND7 => {
+                    let n = self.operand(opcode, op::UNBIND);
+                    unsafe { unbind_to(c_specpdl_index() - n as ptrdiff_t, Qnil) };
+                }
+                op::UNBIND_ALL => unsafe {
+                    unbind_to(self.count, Qnil);
+                },
+
+                op::POPHANDLER => return Exit::PopHandler,
+                op::PUSHCATCH | op::PUSHCONDITIONCASE => {
+                    if let Some(exit) = self.push_handler(opcode == op::PUSHCATCH) {
+                        return exit;
+                    }
+                }
+
+                op::GOTO => {
+                    let dest = self.fetch2();
+                    self.goto(dest);
+                }
+                op::GOTOIFNIL => {
+                    let dest = self.fetch2();
+                    if self.pop().is_nil() {
+                        self.goto(dest);
+                    }
+                }
+                op::GOTOIFNONNIL => {
+                    let dest = self.fetch2();
+                    if self.pop().is_not_nil() {
+                        self.goto(dest);
+                    }
+                }
+                op::GOTOIFNILELSEPOP => {
+                    let dest = self.fetch2();
+                    if self.top_value().is_nil() {
+                        self.goto(dest);
+                    } else {
+                        self.discard(1);
+                    }
+                }
+                op::GOTOIFNONNILELSEPOP => {
+                    let dest = self.fetch2();
+                    if self.top_value().is_not_nil() {
+                        self.goto(dest);
+                    } else {
+                        self.discard(1);
+                    }
+                }
+                op::RGOTO => {
+                    let offset = self.fetch();
+                    self.relative_goto(offset);
+                }
+                op::RGOTOIFNIL => {
+                    let offset = self.fetch();
+                    if self.pop().is_nil() {
+                        self.relative_goto(offset);
+                    }
+                }
+                op::RGOTOIFNONNIL => {
+                    let offset = self.fetch();
+                    if self.pop().is_not_nil() {
+                        self.relative_goto(offset);
+                    }
+                }
+                op::RGOTOIFNILELSEPOP => {
+                    let offset = self.fetch();
+                    if self.top_value().is_nil() {
+                        self.relative_goto(offset);
+                    } else {
+                        self.discard(1);
+                    }
+                }
+                op::RGOTOIFNONNILELSEPOP => {
+                    let offset = self.fetch();
+                    if self.top_value().is_not_nil() {
+                        self.relative_goto(offset);
+                    } else {
+                        self.discard(1);
+                    }
+                }
+                op::SWITCH => {
+                    let jmp_table = self.pop().as_hash_table_or_error();
+                    let v1 = self.pop();
+                    if let HashLookupResult::Found(idx) = jmp_table.lookup(v1) {
+                        let dest = jmp_table.get_hash_value(idx);
+                        match dest.as_fixnum() {
+                            Some(dest) if dest >= 0 => self.goto(dest as usize),
+                            _ => error!("Invalid byte-code switch target"),
+                        }
+                    }
+                }
+
+                op::RETURN => return Exit::Return(self.top_value()),
+                op::DISCARD => self.discard(1),
+                op::DISCARDN => {
+                    let mut n = self.fetch() as usize;
+                    if n & 0x80 != 0 {
+                        n &= 0x7F;
+                        let v1 = self.top_value();
+                        let idx = self.stack_index(n);
+                        self.set(idx, v1);
+                    }
+                    self.discard(n);
+                }
+                op::DUP => {
+                    let v1 = self.top_value();
+                    self.push(v1);
+                }
+                op::STACK_SET | op::STACK_SET2 => {
+                    let n = if opcode == op::STACK_SET {
+                        self.fetch() as usize
+                    } else {
+                        self.fetch2()
+                    };
+                    let idx = self.stack_index(n);
+                    let v1 = self.pop();
+                    self.set(idx, v1);
+                }
+                op::CONSTANT2 => {
+                    let idx = self.fetch2();
+                    let v1 = self.constant(idx);
+                    self.push(v1);
+                }
+
+                op::SAVE_EXCURSION => unsafe {
+                    record_unwind_protect(Some(save_excursion_restore), save_excursion_save());
+                },
+                op::SAVE_CURRENT_BUFFER | op::SAVE_CURRENT_BUFFER_1 => unsafe {
+                    record_unwind_current_buffer();
+                },
+                op::SAVE_WINDOW_EXCURSION => {
+                    let count = c_specpdl_index();
+                    unsafe {
+                        record_unwind_protect(
+                            Some(restore_window_configuration),
+                            Fcurrent_window_configuration(Qnil),
+                        )
+                    };
+                    let value = progn(self.top_value());
+                    self.set_top(unsafe { unbind_to(count, value) });
+                }
+                op::SAVE_RESTRICTION => unsafe {
+                    record_unwind_protect(Some(save_restriction_restore), save_restriction_save());
+                },
+                op::CATCH => self.binary(|tag, body| unsafe {
+                    internal_catch(tag, Some(eval_sub), body)
+                }),
+                op::UNWIND_PROTECT => {
+                    let handler = self.pop();
+                    // Support for a function here is new in 24.4.
+                    let unwind: unsafe extern "C" fn(LispObject) = if handler.is_function() {
+                        bcall0
+                    } else {
+                        prog_ignore
+                    };
+                    unsafe { record_unwind_protect(Some(unwind), handler) };
+                }
+                op::CONDITION_CASE => {
+                    let handlers = self.pop();
+                    let body = self.pop();
+                    let var = self.top_value();
+                    self.set_top(unsafe { internal_lisp_condition_case(var, body, handlers) });
+                }
+                op::TEMP_OUTPUT_BUFFER_SETUP => {
+                    let name = self.top_value().as_string_or_error();
+                    unsafe { temp_output_buffer_setup(name.const_data_ptr() as *const c_char) };
+                    self.set_top(unsafe { globals.Vstandard_output });
+                }
+                op::TEMP_OUTPUT_BUFFER_SHOW => {
+                    let v1 = self.pop();
+                    unsafe { temp_output_buffer_show(self.top_value()) };
+                    self.set_top(v1);
+                    // Pop the binding of standard-output.
+                    unsafe { unbind_to(c_specpdl_index() - 1, Qnil) };
+                }
+
+                op::NTH => self.binary(|n, list| nth(n.into(), list)),
+                op::SYMBOLP => self.unary(|v1| v1.is_symbol().into()),
+                op::CONSP => self.unary(|v1| v1.is_cons().into()),
+                op::STRINGP => self.unary(|v1| v1.is_string().into()),
+                op::LISTP => self.unary(|v1| v1.is_list().into()),
+                op::EQ => self.binary(|v1, v2| v1.eq(v2).into()),
+                op::MEMQ => self.binary(memq),
+                op::NOT => self.unary(|v1| v1.is_nil().into()),
+                op::CAR => self.unary(car),
+                op::CDR => self.unary(cdr),
+                op::CONS => self.binary(LispObject::cons),
+                op::LIST1 => self.nary(1, |args| list(args)),
+                op::LIST2 => self.nary(2, |args| list(args)),
+                op::LIST3 => self.nary(3, |args| list(args)),
+                op::LIST4 => self.nary(4, |args| list(args)),
+                op::LISTN => {
+                    let n = self.fetch() as usize;
+                    self.nary(n, |args| list(args));
+                }
+                op::LENGTH => self.unary(length),
+                op::AREF => self.binary(|array, idx| aref(array, idx.into())),
+                op::ASET => {
+                    let newelt = self.pop();
+                    self.binary(|array, idx| aset(array, idx.into(), newelt));
+                }
+                op::SYMBOL_VALUE => self.unary(symbol_value),
+                op::SYMBOL_FUNCTION => self.unary(|v1| symbol_function(v1.into())),
+                op::SET => self.binary(|sym, val| set(sym.into(), val)),
+                op::FSET => self.binary(|sym, def| unsafe { Ffset(sym, def) }),
+                op::GET => self.binary(|sym, prop| get(sym.into(), prop)),
+                op::SUBSTRING => {
+                    let to = self.pop();
+                    self.binary(|string, from| unsafe { Fsubstring(string, from, to) });
+                }
+                op::CONCAT2 => self.nary(2, concat),
+                op::CONCAT3 => self.nary(3, concat),
+                op::CONCAT4 => self.nary(4, concat),
+                op::CONCATN => {
+                    let n = self.fetch() as usize;
+                    self.nary(n, concat);
+                }
+                op::SUB1 => self.unary(|v1| sub1(v1.into()).into()),
+                op::ADD1 => self.unary(|v1| add1(v1.into()).into()),
+                op::EQLSIGN => self.compare(ArithComparison::Equal),
+                op::GTR => self.compare(ArithComparison::Grtr),
+                op::LSS => self.compare(ArithComparison::Less),
+                op::LEQ => self.compare(ArithComparison::LessOrEqual),
+                op::GEQ => self.compare(ArithComparison::GrtrOrEqual),
+                op::DIFF => self.nary(2, |args| minus(args)),
+                op::NEGATE => self.nary(1, |args| minus(args)),
+                op::PLUS => self.nary(2, |args| plus(args)),
+                op::MAX => self.nary(2, |args| max(args)),
+                op::MIN => self.nary(2, |args| min(args)),
+                op::MULT => self.nary(2, |args| times(args)),
+                op::QUO => self.nary(2, |args| quo(args)),
+                op::REM => self.binary(|x, y| rem(x.into(), y.into()).into()),
+
+                op::POINT => self.push(point().into()),
+                op::GOTO_CHAR => self.unary(goto_char),
+                op::INSERT => self.nary(1, insert),
+                op::INSERTN => {
+                    let n = self.fetch() as usize;
+                    self.nary(n, insert);
+                }
+                op::POINT_MAX => self.push(point_max().into()),
+                op::POINT_MIN => self.push(point_min().into()),
+                op::CHAR_AFTER => self.unary(|pos| char_after(pos).into()),
+                op::FOLLOWING_CHAR => self.push(following_char().into()),
+                op::PRECEDING_CHAR => self.push(preceding_char().into()),
+                op::CURRENT_COLUMN => self.push(current_column().into()),
+                op::INDENT_TO => self.unary(|column| unsafe { Findent_to(column, Qnil) }),
+                op::EOLP => self.push(eolp().into()),
+                op::EOBP => self.push(eobp().into()),
+                op::BOLP => self.push(bolp().into()),
+                op::BOBP => self.push(bobp().into()),
+                op::CURRENT_BUFFER => self.push(current_buffer()),
+                op::SET_BUFFER => self.unary(|buffer| set_buffer(buffer.into()).into()),
+                op::INTERACTIVE_P => {
+                    let v1 = funcall(&mut [intern("interactive-p")]);
+                    self.push(v1);
+                }
+
+                op::FORWARD_CHAR => self.unary(|n| forward_char(n).into()),
+                op::FORWARD_WORD => self.unary(|n| unsafe { Fforward_word(n) }),
+                op::SKIP_CHARS_FORWARD => {
+                    self.binary(|string, lim| unsafe { Fskip_chars_forward(string, lim) })
+                }
+                op::SKIP_CHARS_BACKWARD => {
+                    self.binary(|string, lim| unsafe { Fskip_chars_backward(string, lim) })
+                }
+                op::FORWARD_LINE => self.unary(|n| forward_line(n.into()).into()),
+                op::CHAR_SYNTAX => self.unary(|c| unsafe { Fchar_syntax(c) }),
+                op::BUFFER_SUBSTRING => {
+                    self.binary(|start, end| unsafe { Fbuffer_substring(start, end) })
+                }
+                op::DELETE_REGION => {
+                    self.binary(|start, end| unsafe { Fdelete_region(start, end) })
+                }
+                op::NARROW_TO_REGION => {
+                    self.binary(|start, end| unsafe { Fnarrow_to_region(start, end) })
+                }
+                op::WIDEN => self.push(unsafe { Fwiden() }),
+                op::END_OF_LINE => self.unary(|n| end_of_line(n.into()).into()),
+
+                op::SET_MARKER => {
+                    let buffer = self.pop();
+                    self.binary(|marker, position| set_marker(marker, position, buffer));
+                }
+                op::MATCH_BEGINNING => self.unary(match_beginning),
+                op::MATCH_END => self.unary(match_end),
+                op::UPCASE => self.unary(|obj| upcase(obj, Qnil)),
+                op::DOWNCASE => self.unary(|obj| downcase(obj, Qnil)),
+
+                op::STRINGEQLSIGN => self.binary(|s1, s2| string_equal(s1, s2).into()),
+                op::STRINGLSS => self.binary(|s1, s2| string_lessp(s1, s2).into()),
+                op::EQUAL => self.binary(|v1, v2| v1.equal(v2).into()),
+                op::NTHCDR => self.binary(|n, list| nthcdr(n.into(), list)),
+                op::ELT => self.binary(|seq, n| elt(seq, n.into())),
+                op::MEMBER => self.binary(member),
+                op::ASSQ => self.binary(assq),
+                op::NREVERSE => self.unary(|seq| unsafe { Fnreverse(seq) }),
+                op::SETCAR => self.binary(|cell, newcar| setcar(cell.into(), newcar)),
+                op::SETCDR => self.binary(|cell, newcdr| setcdr(cell.into(), newcdr)),
+                op::CAR_SAFE => self.unary(car_safe),
+                op::CDR_SAFE => self.unary(cdr_safe),
+                op::NCONC => self.nary(2, |args| unsafe {
+                    Fnconc(args.len() as ptrdiff_t, args.as_mut_ptr())
+                }),
+                op::NUMBERP => self.unary(|v1| v1.is_number().into()),
+                op::INTEGERP => self.unary(|v1| v1.is_integer().into()),
+
+                op::CONSTANT..=0xFF => {
+                    let v1 = self.constant((opcode - op::CONSTANT) as usize);
+                    self.push(v1);
+                }
+
+                _ => error!(
+                    "Invalid byte opcode: op={}, ptr={}",
+                    opcode,
+                    self.pc - 1
+                ),
+            }
+        }
+    }
+
+    /// Return the operand of the eight-opcode group starting at BASE:
+    /// the low three bits for the first six, then a one- or two-byte
+    /// operand.
+    fn operand(&mut self, opcode: u8, base: u8) -> usize {
+        match opcode - base {
+            6 => self.fetch() as usize,
+            7 => self.fetch2(),
+            n => n as usize,
+        }
+    }
+
+    fn compare(&mut self, comparison: ArithComparison) {
+        self.binary(|v1, v2| arithcompare(v1, v2, comparison).into());
+    }
+}
+
+fn insert(args: &mut [LispObject]) -> LispObject {
+    unsafe { Finsert(args.len() as ptrdiff_t, args.as_mut_ptr()) }
+}
+
+extern "C" fn bcall0(f: LispObject) {
+    funcall(&mut [f]);
+}
+
+extern "C" fn run_handler_body(frame: LispObject) -> LispObject {
+    let frame = unsafe { &mut *frame.to_raw_ptr::<ByteCodeFrame>() };
+    let exit = frame.run();
+    frame.handler_exit = Some(exit);
+    Qnil
+}
+
+extern "C" fn handler_value(err: LispObject) -> LispObject {
+    err
+}
+
+/// Execute the byte-code in BYTESTR.  VECTOR is the constant vector,
+/// and MAXDEPTH is the maximum stack depth used.  If ARGS_TEMPLATE is
+/// non-nil, it should be a lisp argument list (including &rest,
+/// &optional, etc.), and ARGS, of size NARGS, should be a vector of the
+/// actual arguments.  The arguments in ARGS are pushed on the stack
+/// according to ARGS_TEMPLATE before executing BYTESTR.
+///
+/// A MAXDEPTH that is too small, or a malformed BYTESTR, signals an
+/// error instead of corrupting memory.
+#[no_mangle]
+pub extern "C" fn exec_byte_code(
     bytestr: LispObject,
     vector: LispObject,
     maxdepth: LispObject,
     args_template: LispObject,
-    arg: &mut [LispObject],
+    nargs: ptrdiff_t,
+    args: *mut LispObject,
 ) -> LispObject {
-    unsafe {
-        c_exec_byte_code(
-            bytestr,
-            vector,
-            maxdepth,
-            args_template,
-            arg.len() as isize,
-            arg.as_mut_ptr() as *mut LispObject,
-        )
+    let meter = if unsafe { globals.byte_metering_on } {
+        Some(function_meter(bytestr))
+    } else {
+        None
+    };
+    let bytestr = unibyte_code(bytestr);
+    let constants = vector.as_vector_or_error();
+    let depth = maxdepth.as_natnum_or_error() as usize;
+
+    let mut frame = ByteCodeFrame {
+        bytestr,
+        constants,
+        base: reserve_stack(depth),
+        depth,
+        top: 0,
+        pc: 0,
+        count: c_specpdl_index(),
+        quitcounter: 1,
+        handler_exit: None,
+        meter,
+        last_opcode: 0,
+    };
+
+    if args_template.is_not_nil() {
+        let args = if nargs == 0 {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(args, nargs as usize) }
+        };
+        push_args(&mut frame, args_template.as_fixnum_or_error(), args);
+    }
+
+    let result = match frame.run() {
+        Exit::Return(value) => value,
+        Exit::PopHandler => error!("Byte-code pops a handler it did not push"),
+    };
+
+    // Binds and unbinds are supposed to be compiled balanced.
+    let count = c_specpdl_index();
+    if count != frame.count {
+        if count > frame.count {
+            unsafe { unbind_to(frame.count, Qnil) };
+        }
+        error!("binding stack not balanced (serious byte compiler bug)");
+    }
+
+    release_stack(frame.base, frame.depth);
+    result
+}
+
+/// The size the byte-code stack of a thread starts with.
+const INITIAL_STACK_SIZE: usize = 1024;
+
+/// Return the byte-code stack of the current thread.
+fn thread_stack() -> LispVectorRef {
+    ThreadState::current_thread().m_byte_code_stack.as_vector_or_error()
+}
+
+/// Reserve DEPTH slots of the current thread's byte-code stack for a
+/// call and return the index of the first one.
+///
+/// The active calls of a thread use the slots below its
+/// `m_byte_code_stack_top', which the handlers of `catch' and
+/// `condition-case' reset when they unwind calls.  If the vector is too
+/// small it is replaced by a bigger copy.
+fn reserve_stack(depth: usize) -> usize {
+    let mut thread = ThreadState::current_thread();
+    let base = thread.m_byte_code_stack_top as usize;
+    let size = thread.m_byte_code_stack.as_vector().map_or(0, |v| v.len());
+    if base + depth > size {
+        let new_size = cmp::max(base + depth, cmp::max(2 * size, INITIAL_STACK_SIZE));
+        let mut stack =
+            unsafe { Fmake_vector(LispObject::from(new_size), Qnil) }.as_vector_or_error();
+        if let Some(old) = thread.m_byte_code_stack.as_vector() {
+            stack.as_mut_slice()[..base].copy_from_slice(&old.as_slice()[..base]);
+        }
+        thread.m_byte_code_stack = stack.as_lisp_obj();
+    }
+    thread.m_byte_code_stack_top = (base + depth) as ptrdiff_t;
+    base
+}
+
+/// Give back the DEPTH slots at BASE reserved by `reserve_stack'.  They
+/// are cleared so that they don't keep their last values alive.
+fn release_stack(base: usize, depth: usize) {
+    for slot in &mut thread_stack().as_mut_slice()[base..base + depth] {
+        *slot = Qnil;
+    }
+    ThreadState::current_thread().m_byte_code_stack_top = base as ptrdiff_t;
+}
+
+/// Return CODE, a byte-code string, in unibyte form.
+fn unibyte_code(code: LispObject) -> LispStringRef {
+    let code = code.as_string_or_error();
+    if code.is_multibyte() {
+        // CODE must have been produced by Emacs 20.2 or earlier because
+        // they produced a raw 8-bit string for byte-code and now such a
+        // byte-code string is loaded as multibyte while raw 8-bit
+        // characters converted to multibyte form.  Thus, now we must
+        // convert them back to the originally intended unibyte form.
+        unsafe { Fstring_as_unibyte(code.into()) }.as_string_or_error()
+    } else {
+        code
+    }
+}
+
+/// Push ARGS on the stack of FRAME as described by the integer
+/// ARGS_TEMPLATE: the low seven bits hold the number of mandatory
+/// arguments, bit 7 says whether there is a &rest argument, and the
+/// remaining bits hold the number of non-&rest arguments.
+fn push_args(frame: &mut ByteCodeFrame, args_template: EmacsInt, args: &[LispObject]) {
+    let rest = (args_template & 128) != 0;
+    let mandatory = (args_template & 127) as usize;
+    let nonrest = (args_template >> 8) as usize;
+    let nargs = args.len();
+
+    if nargs < mandatory || (!rest && nargs > nonrest) {
+        xsignal!(
+            Qwrong_number_of_arguments,
+            LispObject::cons(LispObject::from(mandatory), LispObject::from(nonrest)),
+            LispObject::from(nargs)
+        );
+    }
+
+    for &arg in args.iter().take(nonrest) {
+        frame.push(arg);
+    }
+    if nonrest < nargs {
+        frame.push(list(&args[nonrest..]));
+    } else {
+        for _ in nargs..nonrest + rest as usize {
+            frame.push(Qnil);
+        }
+    }
+}
+
+/// How the operand of an instruction is encoded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operand {
+    Absent,
+    /// Encoded in the opcode itself.
+    Implicit(usize),
+    /// The following byte.
+    Byte,
+    /// The following two bytes, low byte first.
+    Word,
+    /// A jump whose target is the following byte, minus 128, relative
+    /// to the next instruction.
+    Relative,
+}
+
+/// Return the name bytecomp.el uses for OPCODE, or None if the
+/// interpreter does not implement it.
+pub fn opcode_name(opcode: u8) -> Option<&'static str> {
+    let name = match opcode {
+        op::STACK_REF1..=op::STACK_REF7 => "byte-stack-ref",
+        op::VARREF..=op::VARREF7 => "byte-varref",
+        op::VARSET..=op::VARSET7 => "byte-varset",
+        op::VARBIND..=op::VARBIND7 => "byte-varbind",
+        op::CALL..=op::CALL7 => "byte-call",
+        op::UNBIND..=op::UNBIND7 => "byte-unbind",
+        op::POPHANDLER => "byte-pophandler",
+        op::PUSHCONDITIONCASE => "byte-pushconditioncase",
+        op::PUSHCATCH => "byte-pushcatch",
+        op::NTH => "byte-nth",
+        op::SYMBOLP => "byte-symbolp",
+        op::CONSP => "byte-consp",
+        op::STRINGP => "byte-stringp",
+        op::LISTP => "byte-listp",
+        op::EQ => "byte-eq",
+        op::MEMQ => "byte-memq",
+        op::NOT => "byte-not",
+        op::CAR => "byte-car",
+        op::CDR => "byte-cdr",
+        op::CONS => "byte-cons",
+        op::LIST1 => "byte-list1",
+        op::LIST2 => "byte-list2",
+        op::LIST3 => "byte-list3",
+        op::LIST4 => "byte-list4",
+        op::LENGTH => "byte-length",
+        op::AREF => "byte-aref",
+        op::ASET => "byte-aset",
+        op::SYMBOL_VALUE => "byte-symbol-value",
+        op::SYMBOL_FUNCTION => "byte-symbol-function",
+        op::SET => "byte-set",
+        op::FSET => "byte-fset",
+        op::GET => "byte-get",
+        op::SUBSTRING => "byte-substring",
+        op::CONCAT2 => "byte-concat2",
+        op::CONCAT3 => "byte-concat3",
+        op::CONCAT4 => "byte-concat4",
+        op::SUB1 => "byte-sub1",
+        op::ADD1 => "byte-add1",
+        op::EQLSIGN => "byte-eqlsign",
+        op::GTR => "byte-gtr",
+        op::LSS => "byte-lss",
+        op::LEQ => "byte-leq",
+        op::GEQ => "byte-geq",
+        op::DIFF => "byte-diff",
+        op::NEGATE => "byte-negate",
+        op::PLUS => "byte-plus",
+        op::MAX => "byte-max",
+        op::MIN => "byte-min",
+        op::MULT => "byte-mult",
+        op::POINT => "byte-point",
+        op::SAVE_CURRENT_BUFFER => "byte-save-current-buffer-OBSOLETE",
+        op::GOTO_CHAR => "byte-goto-char",
+        op::INSERT => "byte-insert",
+        op::POINT_MAX => "byte-point-max",
+        op::POINT_MIN => "byte-point-min",
+        op::CHAR_AFTER => "byte-char-after",
+        op::FOLLOWING_CHAR => "byte-following-char",
+        op::PRECEDING_CHAR => "byte-preceding-char",
+        op::CURRENT_COLUMN => "byte-current-column",
+        op::INDENT_TO => "byte-indent-to",
+        op::EOLP => "byte-eolp",
+        op::EOBP => "byte-eobp",
+        op::BOLP => "byte-bolp",
+        op::BOBP => "byte-bobp",
+        op::CURRENT_BUFFER => "byte-current-buffer",
+        op::SET_BUFFER => "byte-set-buffer",
+        op::SAVE_CURRENT_BUFFER_1 => "byte-save-current-buffer",
+        op::INTERACTIVE_P => "byte-interactive-p-OBSOLETE",
+        op::FORWARD_CHAR => "byte-forward-char",
+        op::FORWARD_WORD => "byte-forward-word",
+        op::SKIP_CHARS_FORWARD => "byte-skip-chars-forward",
+        op::SKIP_CHARS_BACKWARD => "byte-skip-chars-backward",
+        op::FORWARD_LINE => "byte-forward-line",
+        op::CHAR_SYNTAX => "byte-char-syntax",
+        op::BUFFER_SUBSTRING => "byte-buffer-substring",
+        op::DELETE_REGION => "byte-delete-region",
+        op::NARROW_TO_REGION => "byte-narrow-to-region",
+        op::WIDEN => "byte-widen",
+        op::END_OF_LINE => "byte-end-of-line",
+        op::CONSTANT2 => "byte-constant2",
+        op::GOTO => "byte-goto",
+        op::GOTOIFNIL => "byte-goto-if-nil",
+        op::GOTOIFNONNIL => "byte-goto-if-not-nil",
+        op::GOTOIFNILELSEPOP => "byte-goto-if-nil-else-pop",
+        op::GOTOIFNONNILELSEPOP => "byte-goto-if-not-nil-else-pop",
+        op::RETURN => "byte-return",
+        op::DISCARD => "byte-discard",
+        op::DUP => "byte-dup",
+        op::SAVE_EXCURSION => "byte-save-excursion",
+        op::SAVE_WINDOW_EXCURSION => "byte-save-window-excursion-OBSOLETE",
+        op::SAVE_RESTRICTION => "byte-save-restriction",
+        op::CATCH => "byte-catch",
+        op::UNWIND_PROTECT => "byte-unwind-protect",
+        op::CONDITION_CASE => "byte-condition-case",
+        op::TEMP_OUTPUT_BUFFER_SETUP => "byte-temp-output-buffer-setup-OBSOLETE",
+        op::TEMP_OUTPUT_BUFFER_SHOW => "byte-temp-output-buffer-show-OBSOLETE",
+        op::UNBIND_ALL => "byte-unbind-all",
+        op::SET_MARKER => "byte-set-marker",
+        op::MATCH_BEGINNING => "byte-match-beginning",
+        op::MATCH_END => "byte-match-end",
+        op::UPCASE => "byte-upcase",
+        op::DOWNCASE => "byte-downcase",
+        op::STRINGEQLSIGN => "byte-string=",
+        op::STRINGLSS => "byte-string<",
+        op::EQUAL => "byte-equal",
+        op::NTHCDR => "byte-nthcdr",
+        op::ELT => "byte-elt",
+        op::MEMBER => "byte-member",
+        op::ASSQ => "byte-assq",
+        op::NREVERSE => "byte-nreverse",
+        op::SETCAR => "byte-setcar",
+        op::SETCDR => "byte-setcdr",
+        op::CAR_SAFE => "byte-car-safe",
+        op::CDR_SAFE => "byte-cdr-safe",
+        op::NCONC => "byte-nconc",
+        op::QUO => "byte-quo",
+        op::REM => "byte-rem",
+        op::NUMBERP => "byte-numberp",
+        op::INTEGERP => "byte-integerp",
+        op::RGOTO => "byte-Rgoto",
+        op::RGOTOIFNIL => "byte-Rgoto-if-nil",
+        op::RGOTOIFNONNIL => "byte-Rgoto-if-not-nil",
+        op::RGOTOIFNILELSEPOP => "byte-Rgoto-if-nil-else-pop",
+        op::RGOTOIFNONNILELSEPOP => "byte-Rgoto-if-not-nil-else-pop",
+        op::LISTN => "byte-listN",
+        op::CONCATN => "byte-concatN",
+        op::INSERTN => "byte-insertN",
+        op::STACK_SET => "byte-stack-set",
+        op::STACK_SET2 => "byte-stack-set2",
+        op::DISCARDN => "byte-discardN",
+        op::SWITCH => "byte-switch",
+        op::CONSTANT..=0xFF => "byte-constant",
+        _ => return None,
+    };
+    Some(name)
+}
+
+fn operand_kind(opcode: u8) -> Operand {
+    match opcode {
+        op::STACK_REF1..=op::STACK_REF7 | op::VARREF..=op::UNBIND7 => match opcode & 7 {
+            6 => Operand::Byte,
+            7 => Operand::Word,
+            n => Operand::Implicit(n as usize),
+        },
+        op::CONSTANT..=0xFF => Operand::Implicit((opcode - op::CONSTANT) as usize),
+        op::CONSTANT2
+        | op::GOTO..=op::GOTOIFNONNILELSEPOP
+        | op::PUSHCATCH
+        | op::PUSHCONDITIONCASE
+        | op::STACK_SET2 => Operand::Word,
+        op::LISTN | op::CONCATN | op::INSERTN | op::STACK_SET | op::DISCARDN => Operand::Byte,
+        op::RGOTO..=op::RGOTOIFNONNILELSEPOP => Operand::Relative,
+        _ => Operand::Absent,
+    }
+}
+
+/// One instruction of a byte-code string.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Instruction {
+    pub pc: usize,
+    pub opcode: u8,
+    /// The operand, with implicit operands and relative jumps resolved:
+    /// a constant index, stack offset, count or absolute jump target.
+    pub operand: Option<usize>,
+    /// The pc of the following instruction.
+    pub next: usize,
+}
+
+impl Instruction {
+    fn is_constant_ref(self) -> bool {
+        match self.opcode {
+            op::VARREF..=op::VARBIND7 | op::CONSTANT2 | op::CONSTANT..=0xFF => true,
+            _ => false,
+        }
+    }
+
+    fn is_jump(self) -> bool {
+        match self.opcode {
+            op::GOTO..=op::GOTOIFNONNILELSEPOP
+            | op::RGOTO..=op::RGOTOIFNONNILELSEPOP
+            | op::PUSHCATCH
+            | op::PUSHCONDITIONCASE => true,
+            _ => false,
+        }
+    }
+
+    /// Return how many values the instruction pops and pushes when
+    /// execution falls through to the next instruction.
+    fn stack_effect(self) -> (usize, usize) {
+        let n = self.operand.unwrap_or(0);
+        match self.opcode {
+            op::STACK_REF1..=op::STACK_REF7
+            | op::VARREF..=op::VARREF7
+            | op::CONSTANT2
+            | op::CONSTANT..=0xFF
+            | op::DUP
+            | op::POINT
+            | op::POINT_MAX
+            | op::POINT_MIN
+            | op::FOLLOWING_CHAR
+            | op::PRECEDING_CHAR
+            | op::CURRENT_COLUMN
+            | op::EOLP
+            | op::EOBP
+            | op::BOLP
+            | op::BOBP
+            | op::CURRENT_BUFFER
+            | op::INTERACTIVE_P
+            | op::WIDEN => (0, 1),
+
+            op::VARSET..=op::VARBIND7
+            | op::GOTOIFNIL..=op::GOTOIFNONNILELSEPOP
+            | op::RGOTOIFNIL..=op::RGOTOIFNONNILELSEPOP
+            | op::PUSHCATCH
+            | op::PUSHCONDITIONCASE
+            | op::RETURN
+            | op::DISCARD
+            | op::UNWIND_PROTECT
+            | op::STACK_SET
+            | op::STACK_SET2 => (1, 0),
+
+            op::CALL..=op::CALL7 => (n + 1, 1),
+            op::LISTN | op::CONCATN | op::INSERTN => (n, 1),
+            op::DISCARDN => (n & 0x7F, 0),
+            op::SWITCH => (2, 0),
+
+            op::SYMBOLP
+            | op::CONSP
+            | op::STRINGP
+            | op::LISTP
+            | op::NOT
+            | op::CAR
+            | op::CDR
+            | op::LIST1
+            | op::LENGTH
+            | op::SYMBOL_VALUE
+            | op::SYMBOL_FUNCTION
+            | op::SUB1
+            | op::ADD1
+            | op::NEGATE
+            | op::GOTO_CHAR
+            | op::INSERT
+            | op::CHAR_AFTER
+            | op::INDENT_TO
+            | op::SET_BUFFER
+            | op::FORWARD_CHAR
+            | op::FORWARD_WORD
+            | op::FORWARD_LINE
+            | op::CHAR_SYNTAX
+            | op::END_OF_LINE
+            | op::SAVE_WINDOW_EXCURSION
+            | op::TEMP_OUTPUT_BUFFER_SETUP
+            | op::MATCH_BEGINNING
+            | op::MATCH_END
+            | op::UPCASE
+            | op::DOWNCASE
+            | op::NREVERSE
+            | op::CAR_SAFE
+            | op::CDR_SAFE
+            | op::NUMBERP
+            | op::INTEGERP => (1, 1),
+
+            op::ASET | op::SUBSTRING | op::SET_MARKER | op::CONDITION_CASE => (3, 1),
+            op::LIST3 | op::CONCAT3 => (3, 1),
+            op::LIST4 | op::CONCAT4 => (4, 1),
+
+            op::UNBIND..=op::UNBIND7
+            | op::POPHANDLER
+            | op::GOTO
+            | op::RGOTO
+            | op::SAVE_EXCURSION
+            | op::SAVE_RESTRICTION
+            | op::SAVE_CURRENT_BUFFER
+            | op::SAVE_CURRENT_BUFFER_1
+            | op::UNBIND_ALL => (0, 0),
+
+            // Everything else takes two arguments and returns one value.
+            _ => (2, 1),
+        }
     }
+
+    /// Return the stack offset that must lie within the current stack
+    /// for this instruction to be valid.
+    fn stack_offset(self) -> Option<usize> {
+        let n = self.operand.unwrap_or(0);
+        match self.opcode {
+            op::STACK_REF1..=op::STACK_REF7 | op::STACK_SET | op::STACK_SET2 => Some(n),
+            op::DISCARDN if n & 0x80 != 0 => Some(n & 0x7F),
+            _ => None,
+        }
+    }
+}
+
+/// Decode the instruction at PC in CODE.
+pub fn decode_instruction(code: &[u8], pc: usize) -> Result<Instruction, String> {
+    let opcode = code[pc];
+    if opcode_name(opcode).is_none() {
+        return Err(format!("invalid opcode {}", opcode));
+    }
+    let (operand, next) = match operand_kind(opcode) {
+        Operand::Absent => (None, pc + 1),
+        Operand::Implicit(n) => (Some(n), pc + 1),
+        Operand::Byte | Operand::Relative if pc + 1 >= code.len() => {
+            return Err("truncated operand".to_string());
+        }
+        Operand::Byte => (Some(code[pc + 1] as usize), pc + 2),
+        Operand::Relative => {
+            let target = (pc + 2) as isize + code[pc + 1] as isize - 128;
+            if target < 0 {
+                return Err(format!("jump to {} is out of range", target));
+            }
+            (Some(target as usize), pc + 2)
+        }
+        Operand::Word if pc + 2 >= code.len() => return Err("truncated operand".to_string()),
+        Operand::Word => (
+            Some(code[pc + 1] as usize + ((code[pc + 2] as usize) << 8)),
+            pc + 3,
+        ),
+    };
+    Ok(Instruction {
+        pc,
+        opcode,
+        operand,
+        next,
+    })
+}
+
+/// Decode all of CODE.
+pub fn decode_byte_code(code: &[u8]) -> Result<Vec<Instruction>, (usize, String)> {
+    let mut insns = Vec::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        let insn = decode_instruction(code, pc).map_err(|msg| (pc, msg))?;
+        pc = insn.next;
+        insns.push(insn);
+    }
+    Ok(insns)
+}
+
+/// Check CODE statically: every reachable instruction must be valid,
+/// refer to one of the NCONSTANTS constants, jump to the start of an
+/// instruction, and see the same stack depth on every path, between
+/// zero and MAXDEPTH.  Execution starts with INITIAL_DEPTH values
+/// (the arguments) on the stack and must end with a return.
+///
+/// SWITCH_TARGETS maps the constant index of a `byte-switch' jump
+/// table to its jump targets, or None if that constant is not a
+/// valid jump table.
+///
+/// On failure, return the pc of the offending instruction and a
+/// description of the problem.
+pub fn verify_byte_code<F>(
+    code: &[u8],
+    nconstants: usize,
+    maxdepth: usize,
+    initial_depth: usize,
+    switch_targets: F,
+) -> Result<(), (usize, String)>
+where
+    F: Fn(usize) -> Option<Vec<usize>>,
+{
+    let insns = decode_byte_code(code)?;
+    // Map each pc that starts an instruction to its index in INSNS.
+    let mut index = vec![None; code.len()];
+    for (i, insn) in insns.iter().enumerate() {
+        index[insn.pc] = Some(i);
+    }
+
+    let mut depths = vec![None; code.len()];
+    let mut pending = Vec::new();
+    if initial_depth > maxdepth {
+        return Err((0, format!("{} arguments exceed the stack depth", initial_depth)));
+    }
+    if code.is_empty() {
+        return Err((0, "no code".to_string()));
+    }
+    depths[0] = Some(initial_depth);
+    pending.push(0);
+
+    while let Some(pc) = pending.pop() {
+        let i = index[pc].unwrap();
+        let insn = insns[i];
+        let depth = depths[pc].unwrap();
+        let fail = |msg: String| Err((pc, msg));
+
+        if insn.is_constant_ref() && insn.operand.unwrap() >= nconstants {
+            return fail(format!("constant index {} is out of range", insn.operand.unwrap()));
+        }
+        if let Some(offset) = insn.stack_offset() {
+            if offset >= depth {
+                return fail(format!("stack offset {} exceeds depth {}", offset, depth));
+            }
+        }
+        let (pops, pushes) = insn.stack_effect();
+        if pops > depth {
+            return fail(format!("stack underflow (depth {})", depth));
+        }
+        let after = depth - pops + pushes;
+        if after > maxdepth {
+            return fail(format!("stack overflow (maximum depth {})", maxdepth));
+        }
+
+        let mut successors = Vec::new();
+        match insn.opcode {
+            op::RETURN => {}
+            op::GOTO | op::RGOTO => successors.push((insn.operand.unwrap(), depth)),
+            op::GOTOIFNILELSEPOP
+            | op::GOTOIFNONNILELSEPOP
+            | op::RGOTOIFNILELSEPOP
+            | op::RGOTOIFNONNILELSEPOP => {
+                successors.push((insn.operand.unwrap(), depth));
+                successors.push((insn.next, after));
+            }
+            op::PUSHCATCH | op::PUSHCONDITIONCASE => {
+                // The handler resumes with the stack as it was after
+                // popping the tag, plus the thrown value.
+                if after + 1 > maxdepth {
+                    return fail(format!("stack overflow (maximum depth {})", maxdepth));
+                }
+                successors.push((insn.operand.unwrap(), after + 1));
+                successors.push((insn.next, after));
+            }
+            op::SWITCH => {
+                // The compiler always pushes the jump table as a
+                // constant right before the switch.
+                let table = match i.checked_sub(1).map(|j| insns[j]) {
+                    Some(prev) if prev.opcode == op::CONSTANT2 || prev.opcode >= op::CONSTANT => {
+                        prev.operand
+                    }
+                    _ => None,
+                };
+                let targets = match table.and_then(|idx| switch_targets(idx)) {
+                    Some(targets) => targets,
+                    None => return fail("invalid jump table for byte-switch".to_string()),
+                };
+                for target in targets {
+                    successors.push((target, after));
+                }
+                successors.push((insn.next, after));
+            }
+            _ => {
+                if insn.is_jump() {
+                    successors.push((insn.operand.unwrap(), after));
+                }
+                successors.push((insn.next, after));
+            }
+        }
+
+        for (target, target_depth) in successors {
+            if target >= code.len() {
+                if target == insn.next {
+                    return fail("execution falls off the end of the code".to_string());
+                }
+                return fail(format!("jump to {} is out of range", target));
+            }
+            if index[target].is_none() {
+                return fail(format!("jump to {} is inside an instruction", target));
+            }
+            match depths[target] {
+                None => {
+                    depths[target] = Some(target_depth);
+                    pending.push(target);
+                }
+                Some(d) if d != target_depth => {
+                    return fail(format!(
+                        "stack depth at {} is both {} and {}",
+                        target, d, target_depth
+                    ));
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    Ok(())
 }
 
 /// Function used internally in byte-compiled code.
 /// The first argument, BYTESTR, is a string of byte code;
 /// the second, VECTOR, a vector of constants;
 /// the third, MAXDEPTH, the maximum stack depth used in this function.
-/// If the third argument is incorrect, Emacs may crash :(
+/// If MAXDEPTH is too small, or BYTESTR is malformed, an error is signaled.
 #[lisp_fn]
 pub fn byte_code(bytestr: LispObject, vector: LispObject, maxdepth: LispObject) -> LispObject {
-    rust_exec_byte_code(bytestr, vector, maxdepth, Qnil, &mut [])
+    exec_byte_code(bytestr, vector, maxdepth, Qnil, 0, std::ptr::null_mut())
+}
+
+/// The parts of a byte-code function that the verifier looks at.
+struct ByteCodeParts {
+    code: LispStringRef,
+    constants: LispVectorRef,
+    maxdepth: usize,
+    /// The number of arguments pushed on the stack on entry.
+    initial_depth: usize,
+}
+
+impl ByteCodeParts {
+    /// Get the parts of OBJECT, a byte-code function, or of a byte-code
+    /// string OBJECT with the given CONSTANTS and MAXDEPTH.
+    fn new(object: LispObject, constants: LispObject, maxdepth: LispObject) -> Self {
+        let (code, constants, maxdepth, initial_depth) =
+            match object.as_vectorlike().and_then(|v| v.as_compiled()) {
+                Some(fun) => {
+                    if fun.get(Lisp_Compiled::COMPILED_BYTECODE as usize).is_cons() {
+                        unsafe { Ffetch_bytecode(object) };
+                    }
+                    let args_template = fun.get(Lisp_Compiled::COMPILED_ARGLIST as usize);
+                    let initial_depth = args_template.as_fixnum().map_or(0, |at| {
+                        (at >> 8) as usize + if at & 128 != 0 { 1 } else { 0 }
+                    });
+                    (
+                        fun.get(Lisp_Compiled::COMPILED_BYTECODE as usize),
+                        fun.get(Lisp_Compiled::COMPILED_CONSTANTS as usize),
+                        fun.get(Lisp_Compiled::COMPILED_STACK_DEPTH as usize),
+                        initial_depth,
+                    )
+                }
+                None => (object, constants, maxdepth, 0),
+            };
+
+        Self {
+            code: unibyte_code(code),
+            constants: constants.as_vector_or_error(),
+            maxdepth: maxdepth.as_natnum_or_error() as usize,
+            initial_depth,
+        }
+    }
+
+    /// Return the jump targets of the `byte-switch' table at constant
+    /// index IDX.
+    fn switch_targets(&self, idx: usize) -> Option<Vec<usize>> {
+        let table = self.constants.get(idx);
+        if !table.is_hash_table() {
+            return None;
+        }
+        table
+            .as_hash_table_or_error()
+            .iter()
+            .map(|(_, target)| target.as_fixnum().filter(|&t| t >= 0).map(|t| t as usize))
+            .collect()
+    }
+}
+
+/// Return the instructions of the byte-code in OBJECT as a list.
+/// OBJECT is a byte-code function or a byte-code string.  Each element
+/// has the form (PC OPCODE OPERAND), where OPCODE is the name of the
+/// opcode in bytecomp.el, such as `byte-varref', and OPERAND is nil or
+/// an integer: a constant index, stack offset, argument count or jump
+/// target.  Relative jumps are resolved to absolute targets.
+#[lisp_fn]
+pub fn byte_code_decode(object: LispObject) -> LispObject {
+    let code = if object.is_string() {
+        unibyte_code(object)
+    } else {
+        ByteCodeParts::new(object, Qnil, Qnil).code
+    };
+    let insns = match decode_byte_code(code.as_slice()) {
+        Ok(insns) => insns,
+        Err((pc, msg)) => error!("Invalid byte-code at pc {}: {}", pc, msg),
+    };
+    let decoded: Vec<LispObject> = insns
+        .into_iter()
+        .map(|insn| {
+            list!(
+                LispObject::from(insn.pc),
+                intern(opcode_name(insn.opcode).unwrap()),
+                insn.operand.map_or(Qnil, LispObject::from)
+            )
+        })
+        .collect();
+    list(&decoded)
+}
+
+/// Check the byte-code in OBJECT for errors without running it.
+/// OBJECT is a byte-code function, or a byte-code string whose constants
+/// vector and maximum stack depth are CONSTANTS and MAXDEPTH.  Every
+/// reachable instruction must be a valid opcode, constant references and
+/// jumps must stay in range, the stack depth must be the same on every
+/// path to an instruction and stay within MAXDEPTH, and execution must
+/// not fall off the end of the code.
+///
+/// Return nil if the code passes, or a string describing the first
+/// problem found.
+#[lisp_fn(min = "1")]
+pub fn byte_code_verify(
+    object: LispObject,
+    constants: LispObject,
+    maxdepth: LispObject,
+) -> LispObject {
+    let parts = ByteCodeParts::new(object, constants, maxdepth);
+    match verify_byte_code(
+        parts.code.as_slice(),
+        parts.constants.len(),
+        parts.maxdepth,
+        parts.initial_depth,
+        |idx| parts.switch_targets(idx),
+    ) {
+        Ok(()) => Qnil,
+        Err((pc, msg)) => LispObject::from(format!("pc {}: {}", pc, msg).as_str()),
+    }
+}
+
+/// Increment the count of OPCODE in METER, a vector of fixnums
+/// indexed by opcode.  Counts stop at `most-positive-fixnum'.
+fn bump_meter(meter: LispObject, opcode: u8) {
+    if let Some(mut meter) = meter.as_vector() {
+        let idx = opcode as usize;
+        if idx < meter.len() {
+            let n = meter.get(idx).as_fixnum().unwrap_or(0);
+            if n < MOST_POSITIVE_FIXNUM {
+                meter.set(idx, LispObject::from(n + 1));
+            }
+        }
+    }
+}
+
+fn make_meter() -> LispObject {
+    unsafe { Fmake_vector(LispObject::from(256), LispObject::from(0)) }
+}
+
+/// Return the histogram in `byte-code-meter', making a new one if it
+/// has not been set up yet.
+fn global_meter() -> LispVectorRef {
+    let meter = unsafe { globals.Vbyte_code_meter };
+    match meter.as_vector() {
+        Some(v) if v.len() == 256 => v,
+        _ => {
+            let mut v = make_meter().as_vector_or_error();
+            for i in 0..256 {
+                v.set(i, make_meter());
+            }
+            unsafe { globals.Vbyte_code_meter = v.as_lisp_obj() };
+            v
+        }
+    }
+}
+
+// A weak hash table mapping the byte-code strings of metered
+// functions to their opcode counts.
+declare_GC_protected_static!(FUNCTION_METERS, Qnil);
+
+/// Return the opcode counts of the byte-code function whose code is
+/// BYTESTR.
+fn function_meter(bytestr: LispObject) -> LispVectorRef {
+    let table = unsafe {
+        if !FUNCTION_METERS.is_hash_table() {
+            let mut args = [QCtest, Qeq, QCweakness, Qkey];
+            FUNCTION_METERS = Fmake_hash_table(args.len() as isize, args.as_mut_ptr());
+        }
+        FUNCTION_METERS.as_hash_table_or_error()
+    };
+    let meter = gethash(bytestr, table, Qnil);
+    if meter.is_vector() {
+        meter.as_vector_or_error()
+    } else {
+        puthash(bytestr, make_meter(), table).as_vector_or_error()
+    }
+}
+
+/// Turn METER, a vector of counts indexed by opcode, into a list
+/// (TOTAL (OPCODE . COUNT)...), most frequent opcode first.  Opcodes
+/// with the same name, such as the variants of `byte-varref', are
+/// counted together.
+fn meter_report_entry(meter: LispObject) -> (EmacsInt, LispObject) {
+    let mut counts: Vec<(&'static str, EmacsInt)> = Vec::new();
+    if let Some(meter) = meter.as_vector() {
+        for (opcode, n) in meter.iter().take(256).enumerate() {
+            let n = n.as_fixnum().unwrap_or(0);
+            let name = match opcode_name(opcode as u8) {
+                Some(name) if n > 0 => name,
+                _ => continue,
+            };
+            match counts.iter_mut().find(|c| c.0 == name) {
+                Some(c) => c.1 += n,
+                None => counts.push((name, n)),
+            }
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1));
+    let total = counts.iter().map(|c| c.1).sum();
+    let counts: Vec<LispObject> = counts
+        .into_iter()
+        .map(|(name, n)| LispObject::cons(intern(name), LispObject::from(n)))
+        .collect();
+    (total, LispObject::cons(LispObject::from(total), list(&counts)))
+}
+
+/// Return the opcode counts collected while `byte-metering-on' is non-nil.
+/// The value is a list of elements (FUNCTION TOTAL (OPCODE . COUNT)...),
+/// where TOTAL is the number of instructions executed, and each OPCODE,
+/// a symbol such as `byte-varref', was executed COUNT times.  The most
+/// frequent opcodes come first.
+///
+/// The first element has t as FUNCTION and counts every metered
+/// function together.  The rest are for single functions, busiest
+/// first: FUNCTION is the symbol whose definition it is, or its
+/// byte-code string if it has no name.
+#[lisp_fn]
+pub fn byte_code_meter_report() -> LispObject {
+    let meters = unsafe { FUNCTION_METERS };
+    let mut functions: Vec<(EmacsInt, LispObject, LispObject)> = Vec::new();
+    if meters.is_hash_table() {
+        for (code, meter) in meters.as_hash_table_or_error().iter() {
+            let (total, entry) = meter_report_entry(meter);
+            functions.push((total, code, entry));
+        }
+    }
+
+    // Name the functions after the symbols they are defined on.
+    let obarray = LispObarrayRef::global().as_lisp_obj().as_vector_or_error();
+    for bucket in obarray.iter() {
+        if let Some(sym) = bucket.as_symbol() {
+            for s in sym.iter() {
+                let code = match s.get_function().as_vectorlike().and_then(|v| v.as_compiled()) {
+                    Some(fun) => fun.get(Lisp_Compiled::COMPILED_BYTECODE as usize),
+                    None => continue,
+                };
+                for f in functions.iter_mut().filter(|f| f.1.eq(code)) {
+                    f.1 = s.as_lisp_obj();
+                }
+            }
+        }
+    }
+
+    functions.sort_by(|a, b| b.0.cmp(&a.0));
+    let global = unsafe { globals.Vbyte_code_meter }
+        .as_vector()
+        .map_or(Qnil, |v| v.get(0));
+    let mut report = vec![LispObject::cons(Qt, meter_report_entry(global).1)];
+    report.extend(
+        functions
+            .into_iter()
+            .map(|(_, function, entry)| LispObject::cons(function, entry)),
+    );
+    list(&report)
+}
+
+/// Discard the opcode counts collected so far.
+/// See `byte-code-meter-report'.
+#[lisp_fn]
+pub fn byte_code_meter_reset() {
+    unsafe {
+        globals.Vbyte_code_meter = Qnil;
+        if FUNCTION_METERS.is_hash_table() {
+            FUNCTION_METERS.as_hash_table_or_error().clear();
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn syms_of_bytecode() {
+    /// A vector of vectors which holds a histogram of byte-code usage.
+    /// (aref (aref byte-code-meter 0) CODE) indicates how many times the byte
+    /// opcode CODE has been executed.
+    /// (aref (aref byte-code-meter CODE1) CODE2), where CODE1 is not 0,
+    /// indicates how many times the byte opcodes CODE1 and CODE2 have been
+    /// executed in succession.  The histogram is made when metering starts.
+    /// See also `byte-code-meter-report'.
+    defvar_lisp!(Vbyte_code_meter, "byte-code-meter", Qnil);
+
+    /// If non-nil, keep profiling information on byte code usage.
+    /// Each byte-code function called while this is non-nil counts the
+    /// opcodes it executes, both for itself and in `byte-code-meter'.
+    /// Use `byte-code-meter-report' to see the counts, and
+    /// `byte-code-meter-reset' to start afresh.
+    defvar_bool!(byte_metering_on, "byte-metering-on", false);
 }
 
 include!(concat!(env!("OUT_DIR"), "/bytecode_exports.rs"));
+
+#[test]
+fn test_decode_byte_code() {
+    // constant 0, varref 1, call 1, return
+    let insns = decode_byte_code(b"\xc0\x09\x21\x87").unwrap();
+    let decoded: Vec<_> = insns
+        .iter()
+        .map(|i| (i.pc, opcode_name(i.opcode).unwrap(), i.operand))
+        .collect();
+    assert_eq!(
+        decoded,
+        vec![
+            (0, "byte-constant", Some(0)),
+            (1, "byte-varref", Some(1)),
+            (2, "byte-call", Some(1)),
+            (3, "byte-return", None),
+        ]
+    );
+    // goto 3 with a two-byte operand; Rgoto back to 0.
+    let insns = decode_byte_code(b"\x82\x03\x00\xaa\x7b").unwrap();
+    assert_eq!(insns[0].operand, Some(3));
+    assert_eq!(insns[1].operand, Some(0));
+    assert!(decode_byte_code(b"\x82\x03").is_err());
+    assert!(decode_byte_code(b"\x00").is_err());
+}
+
+#[cfg(test)]
+fn no_switch(_: usize) -> Option<Vec<usize>> {
+    None
+}
+
+#[test]
+fn test_verify_byte_code() {
+    // constant 0, constant 1, plus, return
+    let code = b"\xc0\xc1\x5c\x87";
+    assert_eq!(verify_byte_code(code, 2, 2, 0, no_switch), Ok(()));
+    assert_eq!(verify_byte_code(code, 2, 1, 0, no_switch).unwrap_err().0, 1);
+    assert_eq!(verify_byte_code(code, 1, 2, 0, no_switch).unwrap_err().0, 1);
+    // discard on an empty stack
+    assert_eq!(verify_byte_code(b"\x88\x87", 0, 1, 0, no_switch).unwrap_err().0, 0);
+    // falls off the end
+    assert!(verify_byte_code(b"\xc0", 1, 1, 0, no_switch).is_err());
+    // jump into the middle of an instruction
+    assert!(verify_byte_code(b"\x82\x01\x00", 0, 1, 0, no_switch).is_err());
+    // stack-ref 1 with one argument on the stack
+    assert!(verify_byte_code(b"\x01\x87", 0, 2, 1, no_switch).is_err());
+    assert_eq!(verify_byte_code(b"\x01\x87", 0, 3, 2, no_switch), Ok(()));
+    // goto-if-nil merging different depths: arg, goto-if-nil 5, dup, return
+    assert!(verify_byte_code(b"\x89\x83\x05\x00\x89\x87", 0, 3, 1, no_switch).is_err());
+}
+
+#[test]
+fn test_verify_handlers_and_switch() {
+    // constant 0 (tag), pushcatch 7, constant 1, pophandler, return; handler: return
+    let code = b"\xc0\x32\x07\x00\xc1\x30\x87\x87";
+    assert_eq!(verify_byte_code(code, 2, 1, 0, no_switch), Ok(()));
+    // With the argument on the stack: constant 0 (the jump table), switch,
+    // constant 1, return; 4: constant 1, return
+    let code = b"\xc0\xb7\xc1\x87\xc1\x87";
+    assert_eq!(verify_byte_code(code, 2, 2, 1, |_| Some(vec![4])), Ok(()));
+    assert!(verify_byte_code(code, 2, 2, 1, |_| Some(vec![5])).is_err());
+    assert!(verify_byte_code(code, 2, 2, 1, no_switch).is_err());
+}
@@ -1,22 +1,744 @@
 //! Case conversion functions.
 use std::ffi::CString;
+use std::ops::Range;
+
+use libc::{c_char, ptrdiff_t};
 
 use remacs_macros::lisp_fn;
 
 use crate::{
+    buffers::validate_region,
+    chartable::LispCharTableRef,
     keymap::Ctl,
     lisp::defsubr,
     lisp::LispObject,
     lists::put,
+    marker::buf_charpos_to_bytepos,
+    multibyte::{
+        char_to_byte8, encode_codepoints, is_ascii, make_char_multibyte, write_codepoint,
+        Codepoint, LispStringRef, MAX_MULTIBYTE_LENGTH,
+    },
     obarray::intern,
-    remacs_sys::EmacsInt,
-    remacs_sys::{case_action, casify_object, casify_region, casify_region_nil},
+    remacs_sys::{case_action, char_bits, syntaxcode, EmacsInt, EmacsUint},
     remacs_sys::{control_x_map, initial_define_key, meta_map, scan_words, set_point},
-    remacs_sys::{Qdisabled, Qnil, Qt},
+    remacs_sys::{
+        downcase as downcase_char, make_buffer_string, make_multibyte_string, make_string,
+        modify_text, record_delete, record_insert, replace_range, replace_range_2,
+        signal_after_change, syntax_prefix_flag_p, syntax_property, uniprop_table,
+        update_compositions, upcase as upcase_char, SETUP_BUFFER_SYNTAX_TABLE,
+    },
+    remacs_sys::{Fcopy_sequence, Fset_case_table},
+    remacs_sys::{CHECK_HEAD, CHECK_INSIDE, CHECK_TAIL},
+    remacs_sys::{
+        Qcamel, Qchar_or_string_p, Qdisabled, Qerror, Qkebab, Qnil, Qpascal, Qscreaming, Qsnake,
+        Qstringp, Qt,
+    },
     symbols::symbol_value,
     threads::ThreadState,
 };
 
+// In Greek, lower case sigma has two forms: one when used in the
+// middle and one when used at the end of a word.  The rule does not
+// conflict with any other casing rules so while it is a conditional
+// one, it is independent of language.
+const GREEK_CAPITAL_LETTER_SIGMA: Codepoint = 0x03A3; // Σ
+const GREEK_SMALL_LETTER_FINAL_SIGMA: Codepoint = 0x03C2; // ς
+
+const LATIN_CAPITAL_LETTER_I: Codepoint = 0x0049;
+const COMBINING_DOT_ABOVE: Codepoint = 0x0307;
+
+/// Canonical combining class of combining marks placed above a
+/// character.
+const COMBINING_CLASS_ABOVE: u8 = 230;
+
+/// The languages with casing rules of their own in SpecialCasing.txt.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CaseLocale {
+    Root,
+    /// Turkish and Azerbaijani, which have a dotted and a dotless i.
+    Turkic,
+    /// Lithuanian, which keeps the dot of i under accents.
+    Lithuanian,
+}
+
+impl CaseLocale {
+    /// Parse a locale name such as "tr", "tr_TR.UTF-8" or "lt-LT".
+    /// Only the language part matters.
+    fn from_name(name: &str) -> CaseLocale {
+        let language = name
+            .split(|c| c == '_' || c == '-' || c == '.' || c == '@')
+            .next()
+            .unwrap_or("");
+        match language.to_ascii_lowercase().as_str() {
+            "tr" | "az" => CaseLocale::Turkic,
+            "lt" => CaseLocale::Lithuanian,
+            _ => CaseLocale::Root,
+        }
+    }
+
+    /// Convert the LOCALE argument of the casing functions.
+    fn from_lisp(locale: LispObject) -> CaseLocale {
+        if locale.is_nil() {
+            CaseLocale::Root
+        } else if let Some(string) = locale.as_string() {
+            CaseLocale::from_name(&string.to_string())
+        } else if let Some(symbol) = locale.as_symbol() {
+            CaseLocale::from_name(&symbol.symbol_name().as_string_or_error().to_string())
+        } else {
+            wrong_type!(Qstringp, locale)
+        }
+    }
+}
+
+/// Characters with the Soft_Dotted property, which lose their dot
+/// when an accent is placed above them.
+const SOFT_DOTTED: [(Codepoint, Codepoint); 32] = [
+    (0x0069, 0x006A),
+    (0x012F, 0x012F),
+    (0x0249, 0x0249),
+    (0x0268, 0x0268),
+    (0x029D, 0x029D),
+    (0x02B2, 0x02B2),
+    (0x03F3, 0x03F3),
+    (0x0456, 0x0456),
+    (0x0458, 0x0458),
+    (0x1D62, 0x1D62),
+    (0x1D96, 0x1D96),
+    (0x1DA4, 0x1DA4),
+    (0x1DA8, 0x1DA8),
+    (0x1E2D, 0x1E2D),
+    (0x1ECB, 0x1ECB),
+    (0x2071, 0x2071),
+    (0x2148, 0x2149),
+    (0x2C7C, 0x2C7C),
+    (0x1_D422, 0x1_D423),
+    (0x1_D456, 0x1_D457),
+    (0x1_D48A, 0x1_D48B),
+    (0x1_D4BE, 0x1_D4BF),
+    (0x1_D4F2, 0x1_D4F3),
+    (0x1_D526, 0x1_D527),
+    (0x1_D55A, 0x1_D55B),
+    (0x1_D58E, 0x1_D58F),
+    (0x1_D5C2, 0x1_D5C3),
+    (0x1_D5F6, 0x1_D5F7),
+    (0x1_D62A, 0x1_D62B),
+    (0x1_D65E, 0x1_D65F),
+    (0x1_D692, 0x1_D693),
+    (0x1_DF1A, 0x1_DF1A),
+];
+
+fn soft_dotted_p(c: Codepoint) -> bool {
+    SOFT_DOTTED.iter().any(|&(from, to)| from <= c && c <= to)
+}
+
+/// Whether a character before TEXT[I] satisfies PRED, with no
+/// intervening character of combining class 0 or 230.  This
+/// implements the After_Soft_Dotted and After_I conditions of
+/// SpecialCasing.txt.
+fn after_p(
+    text: &[Codepoint],
+    i: usize,
+    ccc: &dyn Fn(Codepoint) -> u8,
+    pred: fn(Codepoint) -> bool,
+) -> bool {
+    for &c in text[..i].iter().rev() {
+        if pred(c) {
+            return true;
+        }
+        let class = ccc(c);
+        if class == 0 || class == COMBINING_CLASS_ABOVE {
+            return false;
+        }
+    }
+    false
+}
+
+/// Whether TEXT[I] is followed by a character satisfying PRED, with no
+/// intervening character of combining class 0 or 230.  This implements
+/// the More_Above and Before_Dot conditions of SpecialCasing.txt.
+fn before_p(
+    text: &[Codepoint],
+    i: usize,
+    ccc: &dyn Fn(Codepoint) -> u8,
+    pred: &dyn Fn(Codepoint) -> bool,
+) -> bool {
+    for &c in &text[i + 1..] {
+        if pred(c) {
+            return true;
+        }
+        let class = ccc(c);
+        if class == 0 || class == COMBINING_CLASS_ABOVE {
+            return false;
+        }
+    }
+    false
+}
+
+/// Apply the language-sensitive rules of SpecialCasing.txt for LOCALE
+/// to the character at I in TEXT, cased according to FLAG, which is
+/// one of `CASE_UP`, `CASE_DOWN` or `CASE_CAPITALIZE`.  CCC returns the
+/// canonical combining class of a character.  Return None if no rule
+/// applies to the character.
+fn locale_special_case(
+    locale: CaseLocale,
+    flag: case_action,
+    text: &[Codepoint],
+    i: usize,
+    ccc: &dyn Fn(Codepoint) -> u8,
+) -> Option<&'static [Codepoint]> {
+    let down = flag == case_action::CASE_DOWN;
+    match (locale, text[i]) {
+        (CaseLocale::Turkic, 0x0130) if down => Some(&[0x0069]),
+        (CaseLocale::Turkic, COMBINING_DOT_ABOVE)
+            if down && after_p(text, i, ccc, |c| c == LATIN_CAPITAL_LETTER_I) =>
+        {
+            Some(&[])
+        }
+        (CaseLocale::Turkic, LATIN_CAPITAL_LETTER_I)
+            if down && !before_p(text, i, ccc, &|c| c == COMBINING_DOT_ABOVE) =>
+        {
+            Some(&[0x0131])
+        }
+        (CaseLocale::Turkic, 0x0069) if !down => Some(&[0x0130]),
+
+        (CaseLocale::Lithuanian, COMBINING_DOT_ABOVE)
+            if !down && after_p(text, i, ccc, soft_dotted_p) =>
+        {
+            Some(&[])
+        }
+        (CaseLocale::Lithuanian, c @ 0x0049)
+        | (CaseLocale::Lithuanian, c @ 0x004A)
+        | (CaseLocale::Lithuanian, c @ 0x012E)
+            if down && before_p(text, i, ccc, &|c| ccc(c) == COMBINING_CLASS_ABOVE) =>
+        {
+            Some(match c {
+                0x0049 => &[0x0069, COMBINING_DOT_ABOVE],
+                0x004A => &[0x006A, COMBINING_DOT_ABOVE],
+                _ => &[0x012F, COMBINING_DOT_ABOVE],
+            })
+        }
+        (CaseLocale::Lithuanian, 0x00CC) if down => Some(&[0x0069, COMBINING_DOT_ABOVE, 0x0300]),
+        (CaseLocale::Lithuanian, 0x00CD) if down => Some(&[0x0069, COMBINING_DOT_ABOVE, 0x0301]),
+        (CaseLocale::Lithuanian, 0x0128) if down => Some(&[0x0069, COMBINING_DOT_ABOVE, 0x0303]),
+
+        _ => None,
+    }
+}
+
+fn syntax_is_word(c: Codepoint) -> bool {
+    unsafe { syntax_property(c as i32, false) == syntaxcode::Sword }
+}
+
+/// State for casing individual characters.
+struct CasingContext {
+    /// A char-table with title-case character mappings or nil.  Non-nil
+    /// implies flag is `CASE_CAPITALIZE` or `CASE_CAPITALIZE_UP`.
+    titlecase_char_table: LispObject,
+
+    /// The unconditional special-casing Unicode property char tables
+    /// for upper casing, lower casing and title casing respectively.
+    specialcase_char_tables: [LispObject; 3],
+
+    /// The canonical combining class char-table, needed by the
+    /// context conditions of language-specific rules.
+    combining_class_char_table: LispObject,
+
+    /// User-requested action.
+    flag: case_action,
+
+    /// Language whose casing rules apply.
+    locale: CaseLocale,
+
+    /// If true, the function operates on a buffer as opposed to a
+    /// string or character.  When run on a buffer,
+    /// `syntax_prefix_flag_p` is taken into account when determining
+    /// whether the context is within a word.
+    inbuffer: bool,
+
+    /// Whether the context is within a word.
+    inword: bool,
+}
+
+impl CasingContext {
+    fn new(flag: case_action, inbuffer: bool, locale: CaseLocale) -> CasingContext {
+        let capitalize = flag == case_action::CASE_CAPITALIZE
+            || flag == case_action::CASE_CAPITALIZE_UP;
+        let uniprop = |name: &str| unsafe { uniprop_table(intern(name)) };
+        let ctx = CasingContext {
+            titlecase_char_table: if capitalize { uniprop("titlecase") } else { Qnil },
+            specialcase_char_tables: [
+                if flag == case_action::CASE_DOWN {
+                    Qnil
+                } else {
+                    uniprop("special-uppercase")
+                },
+                if flag == case_action::CASE_UP {
+                    Qnil
+                } else {
+                    uniprop("special-lowercase")
+                },
+                if capitalize {
+                    uniprop("special-titlecase")
+                } else {
+                    Qnil
+                },
+            ],
+            combining_class_char_table: if locale == CaseLocale::Root {
+                Qnil
+            } else {
+                uniprop("canonical-combining-class")
+            },
+            flag,
+            locale,
+            inbuffer,
+            inword: false,
+        };
+
+        // If the case table is flagged as modified, rescan it.
+        let downcase_table = ThreadState::current_buffer().downcase_table_;
+        let extras = unsafe { downcase_table.as_char_table_or_error().extras.as_slice(2) };
+        if extras[1].is_nil() {
+            unsafe { Fset_case_table(downcase_table) };
+        }
+
+        if inbuffer && capitalize {
+            // For syntax_prefix_flag_p.
+            unsafe { SETUP_BUFFER_SYNTAX_TABLE() };
+        }
+
+        ctx
+    }
+
+    fn combining_class(&self, c: Codepoint) -> u8 {
+        self.combining_class_char_table
+            .as_char_table()
+            .and_then(|table: LispCharTableRef| table.get(c as isize).as_fixnum())
+            .map_or(0, |class| class as u8)
+    }
+
+    /// Update the inword state for CH and return the action to apply to
+    /// it: one of `CASE_UP`, `CASE_DOWN` or `CASE_CAPITALIZE`, or None if
+    /// CH should be left alone.
+    fn next_action(&mut self, ch: Codepoint) -> Option<case_action> {
+        let was_inword = self.inword;
+        self.inword = syntax_is_word(ch)
+            && (!self.inbuffer || was_inword || !unsafe { syntax_prefix_flag_p(ch as i32) });
+
+        match self.flag {
+            case_action::CASE_CAPITALIZE if was_inword => Some(case_action::CASE_DOWN),
+            case_action::CASE_CAPITALIZE_UP if was_inword => None,
+            case_action::CASE_CAPITALIZE_UP => Some(case_action::CASE_CAPITALIZE),
+            flag => Some(flag),
+        }
+    }
+
+    /// Handle the simple, one-to-one case.
+    fn case_simple(&self, flag: case_action, ch: Codepoint) -> Codepoint {
+        if flag == case_action::CASE_DOWN {
+            return unsafe { downcase_char(ch as i32) } as Codepoint;
+        }
+        if let Some(table) = self.titlecase_char_table.as_char_table() {
+            if let Some(cased) = table.get(ch as isize).as_fixnum().filter(|&c| c >= 0) {
+                return cased as Codepoint;
+            }
+        }
+        unsafe { upcase_char(ch as i32) as Codepoint }
+    }
+
+    /// Case the character at I in TEXT, pushing the result onto OUT,
+    /// and return whether it was changed.  The rest of TEXT is the
+    /// context used by conditional rules.  If SPECIAL is false,
+    /// unconditional one-to-many mappings such as upcase(ß) = SS are
+    /// not applied.
+    fn case_character(
+        &mut self,
+        text: &[Codepoint],
+        i: usize,
+        special: bool,
+        out: &mut Vec<Codepoint>,
+    ) -> bool {
+        let ch = text[i];
+        let was_inword = self.inword;
+        let flag = match self.next_action(ch) {
+            Some(flag) => flag,
+            None => {
+                out.push(ch);
+                return false;
+            }
+        };
+
+        // Look through the language-specific rules first, as they
+        // override the unconditional ones.
+        if self.locale != CaseLocale::Root {
+            let rule = locale_special_case(self.locale, flag, text, i, &|c| {
+                self.combining_class(c)
+            });
+            if let Some(cased) = rule {
+                out.extend_from_slice(cased);
+                return cased != [ch];
+            }
+        }
+
+        // Look through the special casing entries.
+        if special {
+            let table = self.specialcase_char_tables[flag as usize];
+            if let Some(table) = table.as_char_table() {
+                if let Some(cased) = table.get(ch as isize).as_string() {
+                    out.extend(cased.chars());
+                    return true;
+                }
+            }
+        }
+
+        let cased = self.case_simple(flag, ch);
+
+        // If we have just down-cased a capital sigma and the next
+        // character no longer has a word syntax (i.e. current character
+        // is end of word), use final sigma.
+        if was_inword
+            && ch == GREEK_CAPITAL_LETTER_SIGMA
+            && cased != ch
+            && text.get(i + 1).map_or(true, |&next| !syntax_is_word(next))
+        {
+            out.push(GREEK_SMALL_LETTER_FINAL_SIGMA);
+            return true;
+        }
+
+        out.push(cased);
+        cased != ch
+    }
+
+    /// Return the characters just before START and just after END in
+    /// the current buffer that the context conditions of
+    /// `case_character` may look at: back to, and on to, the nearest
+    /// character of combining class 0 or 230.
+    fn region_context(&self, start: ptrdiff_t, end: ptrdiff_t) -> (Vec<Codepoint>, Vec<Codepoint>) {
+        let mut buffer = ThreadState::current_buffer();
+        let (begv, zv) = (buffer.begv, buffer.zv);
+        let mut char_at = |pos| {
+            let pos_byte = unsafe { buf_charpos_to_bytepos(buffer.as_mut(), pos) };
+            buffer.fetch_char(pos_byte) as Codepoint
+        };
+        let stops = |c| {
+            let class = self.combining_class(c);
+            class == 0 || class == COMBINING_CLASS_ABOVE
+        };
+
+        let mut before = Vec::new();
+        for pos in (begv..start).rev() {
+            let c = char_at(pos);
+            before.push(c);
+            if stops(c) {
+                break;
+            }
+        }
+        before.reverse();
+
+        let mut after = Vec::new();
+        for pos in end..zv {
+            let c = char_at(pos);
+            after.push(c);
+            if stops(c) {
+                break;
+            }
+        }
+        (before, after)
+    }
+
+    /// Case a character standing on its own.  For characters whose
+    /// casing results in multiple code points, return CH unchanged.
+    fn case_single_character(&mut self, ch: Codepoint) -> Codepoint {
+        let mut out = Vec::with_capacity(1);
+        self.case_character(&[ch], 0, false, &mut out);
+        if out.len() == 1 {
+            out[0]
+        } else {
+            ch
+        }
+    }
+}
+
+/// `MAKE_CHAR_UNIBYTE` macro
+fn make_char_unibyte(c: Codepoint) -> Codepoint {
+    if is_ascii(c) {
+        c
+    } else {
+        Codepoint::from(char_to_byte8(c))
+    }
+}
+
+fn do_casify_natnum(ctx: &mut CasingContext, obj: LispObject) -> LispObject {
+    let flagbits = char_bits::CHAR_ALT
+        | char_bits::CHAR_SUPER
+        | char_bits::CHAR_HYPER
+        | char_bits::CHAR_SHIFT
+        | char_bits::CHAR_CTL
+        | char_bits::CHAR_META;
+    let ch = obj.as_natnum_or_error();
+
+    // If the character has higher bits set above the flags, return it
+    // unchanged.  It is not a real character.
+    if ch > EmacsUint::from(flagbits) {
+        return obj;
+    }
+
+    let ch = ch as Codepoint;
+    let flags = ch & flagbits;
+    let mut ch = ch & !flagbits;
+
+    // FIXME: Even if enable-multibyte-characters is nil, we may
+    // manipulate multibyte chars.  This means we have a bug for latin-1
+    // chars since when we receive an int 128-255 we can't tell whether
+    // it's an eight-bit byte or a latin-1 char.
+    let multibyte = ch >= 256 || ThreadState::current_buffer().multibyte_characters_enabled();
+    if !multibyte {
+        ch = make_char_multibyte(ch);
+    }
+    let mut cased = ctx.case_single_character(ch);
+    if cased == ch {
+        return obj;
+    }
+
+    if !multibyte {
+        cased = make_char_unibyte(cased);
+    }
+    LispObject::from(EmacsInt::from(cased | flags))
+}
+
+fn do_casify_multibyte_string(ctx: &mut CasingContext, obj: LispStringRef) -> LispObject {
+    let text: Vec<Codepoint> = obj.chars().collect();
+    let mut cased = Vec::with_capacity(text.len());
+    for i in 0..text.len() {
+        ctx.case_character(&text, i, true, &mut cased);
+    }
+
+    let mut bytes = Vec::with_capacity(obj.len_bytes() as usize);
+    encode_codepoints(&cased, &mut bytes);
+    unsafe {
+        make_multibyte_string(
+            bytes.as_ptr() as *const c_char,
+            cased.len() as ptrdiff_t,
+            bytes.len() as ptrdiff_t,
+        )
+    }
+}
+
+fn do_casify_unibyte_string(ctx: &mut CasingContext, obj: LispObject) -> LispObject {
+    let obj = unsafe { Fcopy_sequence(obj) };
+    let mut string = obj.as_string_or_error();
+    for i in 0..string.len_chars() {
+        let ch = make_char_multibyte(Codepoint::from(string.byte_at(i)));
+        let cased = ctx.case_single_character(ch);
+        if ch == cased {
+            continue;
+        }
+        let cased = make_char_unibyte(cased);
+        // If the char can't be converted to a valid byte, just don't
+        // change it.
+        if cased < 256 {
+            string.set_byte(i, cased as u8);
+        }
+    }
+    obj
+}
+
+/// Common case-conversion routine, used by upcase, capitalize, etc.
+fn casify_object(flag: case_action, obj: LispObject, locale: LispObject) -> LispObject {
+    let mut ctx = CasingContext::new(flag, false, CaseLocale::from_lisp(locale));
+
+    if obj.is_natnum() {
+        do_casify_natnum(&mut ctx, obj)
+    } else if let Some(string) = obj.as_string() {
+        if string.len_chars() == 0 {
+            obj
+        } else if string.is_multibyte() {
+            do_casify_multibyte_string(&mut ctx, string)
+        } else {
+            do_casify_unibyte_string(&mut ctx, obj)
+        }
+    } else {
+        wrong_type!(Qchar_or_string_p, obj)
+    }
+}
+
+/// Based on CTX, case region in a unibyte buffer from *STARTP to *ENDP.
+///
+/// Save first and last positions that has changed in *STARTP and *ENDP
+/// respectively.  If no characters were changed, save -1 to *STARTP
+/// and leave *ENDP unspecified.
+///
+/// Always return 0.  This is so that interface of this function is the
+/// same as `do_casify_multibyte_region`.
+fn do_casify_unibyte_region(
+    ctx: &mut CasingContext,
+    startp: &mut ptrdiff_t,
+    endp: &mut ptrdiff_t,
+) -> ptrdiff_t {
+    let buffer = ThreadState::current_buffer();
+    // Position of first and last changes.
+    let (mut first, mut last) = (-1, -1);
+
+    for pos in *startp..*endp {
+        let ch = make_char_multibyte(Codepoint::from(buffer.fetch_byte(pos)));
+        let cased = ctx.case_single_character(ch);
+        if cased == ch {
+            continue;
+        }
+
+        last = pos + 1;
+        if first < 0 {
+            first = pos;
+        }
+
+        unsafe { *buffer.byte_pos_addr(pos) = make_char_unibyte(cased) as u8 };
+    }
+
+    *startp = first;
+    *endp = last;
+    0
+}
+
+/// Based on CTX, case region in a multibyte buffer from *STARTP to
+/// *ENDP.
+///
+/// Return number of added characters (may be negative if more
+/// characters were deleted then inserted), save first and last
+/// positions that has changed in *STARTP and *ENDP respectively.  If
+/// no characters were changed, return 0, save -1 to *STARTP and leave
+/// *ENDP unspecified.
+fn do_casify_multibyte_region(
+    ctx: &mut CasingContext,
+    startp: &mut ptrdiff_t,
+    endp: &mut ptrdiff_t,
+) -> ptrdiff_t {
+    let mut buffer = ThreadState::current_buffer();
+    // Position of first and last changes.
+    let (mut first, mut last) = (-1, -1);
+    let mut pos = *startp;
+    let mut pos_byte = unsafe { buf_charpos_to_bytepos(buffer.as_mut(), pos) };
+    let mut opoint = buffer.pt;
+    let mut added = 0;
+
+    // The original text is kept for the context conditions, since the
+    // buffer text is modified as we go.  These may look past either end
+    // of the region.
+    let (mut text, after) = ctx.region_context(*startp, *endp);
+    let region_start = text.len();
+    text.extend(
+        unsafe { make_buffer_string(*startp, *endp, false) }
+            .as_string_or_error()
+            .chars(),
+    );
+    let region_end = text.len();
+    text.extend(after);
+    let mut cased = Vec::new();
+    let mut bytes = Vec::new();
+    let mut scratch = [0; MAX_MULTIBYTE_LENGTH];
+
+    for i in region_start..region_end {
+        let len = write_codepoint(&mut scratch, text[i]) as ptrdiff_t;
+        cased.clear();
+        if !ctx.case_character(&text, i, true, &mut cased) {
+            pos_byte += len;
+            pos += 1;
+            continue;
+        }
+
+        bytes.clear();
+        encode_codepoints(&cased, &mut bytes);
+        let len_chars = cased.len() as ptrdiff_t;
+        let len_bytes = bytes.len() as ptrdiff_t;
+
+        last = pos + len_chars;
+        if first < 0 {
+            first = pos;
+        }
+
+        if len_chars == 1 && len_bytes == len {
+            for (offset, &byte) in bytes.iter().enumerate() {
+                unsafe { *buffer.byte_pos_addr(pos_byte + offset as ptrdiff_t) = byte };
+            }
+        } else {
+            // Replace one character with the other(s), keeping text
+            // properties the same.
+            unsafe {
+                replace_range_2(
+                    pos,
+                    pos_byte,
+                    pos + 1,
+                    pos_byte + len,
+                    bytes.as_ptr() as *const c_char,
+                    len_chars,
+                    len_bytes,
+                    false,
+                )
+            };
+            added += len_chars - 1;
+            if opoint > pos {
+                opoint += len_chars - 1;
+            }
+        }
+
+        pos_byte += len_bytes;
+        pos += len_chars;
+    }
+
+    if buffer.pt != opoint {
+        let opoint_byte = unsafe { buf_charpos_to_bytepos(buffer.as_mut(), opoint) };
+        buffer.set_pt_both(opoint, opoint_byte);
+    }
+
+    *startp = first;
+    *endp = last;
+    added
+}
+
+/// FLAG is `CASE_UP`, `CASE_DOWN`, `CASE_CAPITALIZE` or
+/// `CASE_CAPITALIZE_UP`.  B and E specify range of buffer to operate
+/// on.  Return character position of the end of the region after
+/// changes.
+fn casify_region(
+    flag: case_action,
+    mut b: LispObject,
+    mut e: LispObject,
+    locale: LispObject,
+) -> ptrdiff_t {
+    unsafe { validate_region(&mut b, &mut e) };
+    let mut start = b.as_fixnum_or_error() as ptrdiff_t;
+    let mut end = e.as_fixnum_or_error() as ptrdiff_t;
+    if start == end {
+        // Not modifying because nothing marked.
+        return end;
+    }
+    unsafe { modify_text(start, end) };
+    let mut ctx = CasingContext::new(flag, true, CaseLocale::from_lisp(locale));
+
+    let orig_end = end;
+    unsafe { record_delete(start, make_buffer_string(start, end, true), false) };
+    let added = if ThreadState::current_buffer().multibyte_characters_enabled() {
+        let (len, ostart) = (end - start, start);
+        let added = do_casify_multibyte_region(&mut ctx, &mut start, &mut end);
+        unsafe { record_insert(ostart, len + added) };
+        added
+    } else {
+        unsafe { record_insert(start, end - start) };
+        do_casify_unibyte_region(&mut ctx, &mut start, &mut end)
+    };
+
+    if start >= 0 {
+        unsafe {
+            signal_after_change(start, end - start - added, end - start);
+            update_compositions(
+                start,
+                end,
+                (CHECK_HEAD | CHECK_TAIL | CHECK_INSIDE) as i32,
+            );
+        }
+    }
+
+    orig_end + added
+}
+
 fn casify_word(flag: case_action, words: EmacsInt) {
     let buffer_ref = ThreadState::current_buffer();
 
@@ -31,17 +753,216 @@ fn casify_word(flag: case_action, words: EmacsInt) {
         n => n,
     };
 
-    let new_pos = unsafe {
-        casify_region(
-            flag,
-            LispObject::from(buffer_ref.pt),
-            LispObject::from(far_end),
-        )
-    };
+    let new_pos = casify_region(
+        flag,
+        LispObject::from(buffer_ref.pt),
+        LispObject::from(far_end),
+        Qnil,
+    );
 
     unsafe { set_point(new_pos) };
 }
 
+/// How a character takes part in splitting an identifier into words.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum IdentifierClass {
+    Upper,
+    Lower,
+    /// A letter without case, such as a CJK ideograph.
+    Caseless,
+    Digit,
+    /// A combining mark, which belongs to the word of the character
+    /// before it.
+    Mark,
+    Separator,
+}
+
+/// Classify C according to its Unicode general category.
+fn identifier_class(general_category: LispObject, c: Codepoint) -> IdentifierClass {
+    let category = general_category
+        .as_char_table()
+        .and_then(|table: LispCharTableRef| table.get(c as isize).as_symbol());
+    let name = match category {
+        Some(symbol) => symbol.symbol_name().as_string_or_error(),
+        // Raw bytes have no category.
+        None => return IdentifierClass::Caseless,
+    };
+    match name.as_slice() {
+        b"Lu" | b"Lt" => IdentifierClass::Upper,
+        b"Ll" => IdentifierClass::Lower,
+        b"Lm" | b"Lo" => IdentifierClass::Caseless,
+        b"Mn" | b"Mc" | b"Me" => IdentifierClass::Mark,
+        b"Nd" | b"Nl" | b"No" => IdentifierClass::Digit,
+        _ => IdentifierClass::Separator,
+    }
+}
+
+/// Split the identifier TEXT into words, returning the range of each
+/// word.  Words are separated by separator characters, by a lower case
+/// letter followed by an upper case one, by the last upper case letter
+/// of an acronym followed by a lower case letter ("HTTPServer") and by
+/// the boundaries between digits and letters.
+fn identifier_words(
+    text: &[Codepoint],
+    class: &dyn Fn(Codepoint) -> IdentifierClass,
+) -> Vec<Range<usize>> {
+    let classes: Vec<IdentifierClass> = text.iter().map(|&c| class(c)).collect();
+    let next_letter_is_lower = |i: usize| {
+        classes[i + 1..]
+            .iter()
+            .find(|&&class| class != IdentifierClass::Mark)
+            == Some(&IdentifierClass::Lower)
+    };
+
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut prev = IdentifierClass::Separator;
+    for (i, &class) in classes.iter().enumerate() {
+        let boundary = match (prev, class) {
+            (_, IdentifierClass::Mark) => false,
+            (_, IdentifierClass::Separator) => {
+                if let Some(start) = start.take() {
+                    words.push(start..i);
+                }
+                prev = class;
+                continue;
+            }
+            (IdentifierClass::Lower, IdentifierClass::Upper)
+            | (IdentifierClass::Caseless, IdentifierClass::Upper) => true,
+            (IdentifierClass::Upper, IdentifierClass::Upper) => next_letter_is_lower(i),
+            (IdentifierClass::Digit, IdentifierClass::Digit) => false,
+            (IdentifierClass::Digit, _) | (_, IdentifierClass::Digit) => true,
+            _ => false,
+        };
+
+        match start {
+            None => start = Some(i),
+            Some(word_start) if boundary => {
+                words.push(word_start..i);
+                start = Some(i);
+            }
+            Some(_) => {}
+        }
+        if class != IdentifierClass::Mark {
+            prev = class;
+        }
+    }
+    if let Some(start) = start {
+        words.push(start..text.len());
+    }
+
+    words
+}
+
+/// An identifier style of `string-convert-case'.
+struct CaseStyle {
+    separator: Option<Codepoint>,
+    first_word: case_action,
+    other_words: case_action,
+}
+
+impl CaseStyle {
+    fn from_lisp(style: LispObject) -> CaseStyle {
+        let (separator, first_word, other_words) = if style.eq(Qsnake) {
+            (Some('_'), case_action::CASE_DOWN, case_action::CASE_DOWN)
+        } else if style.eq(Qscreaming) {
+            (Some('_'), case_action::CASE_UP, case_action::CASE_UP)
+        } else if style.eq(Qkebab) {
+            (Some('-'), case_action::CASE_DOWN, case_action::CASE_DOWN)
+        } else if style.eq(Qcamel) {
+            (None, case_action::CASE_DOWN, case_action::CASE_CAPITALIZE)
+        } else if style.eq(Qpascal) {
+            (None, case_action::CASE_CAPITALIZE, case_action::CASE_CAPITALIZE)
+        } else {
+            xsignal!(Qerror, LispObject::from("Invalid case style"), style)
+        };
+
+        CaseStyle {
+            separator: separator.map(|c| c as Codepoint),
+            first_word,
+            other_words,
+        }
+    }
+
+    /// Convert the identifier TEXT to this style.
+    fn convert(&self, text: &[Codepoint]) -> Vec<Codepoint> {
+        let general_category = unsafe { uniprop_table(intern("general-category")) };
+        let words = identifier_words(text, &|c| identifier_class(general_category, c));
+
+        let mut converted = Vec::with_capacity(text.len() + words.len());
+        for (n, word) in words.into_iter().enumerate() {
+            let flag = if n == 0 {
+                self.first_word
+            } else {
+                if let Some(separator) = self.separator {
+                    converted.push(separator);
+                }
+                self.other_words
+            };
+            let word = &text[word];
+            let mut ctx = CasingContext::new(flag, false, CaseLocale::Root);
+            for i in 0..word.len() {
+                ctx.case_character(word, i, true, &mut converted);
+            }
+        }
+        converted
+    }
+}
+
+/// Convert the identifier STRING to the naming style STYLE and return
+/// the result.
+/// STRING is split into words at separators such as `_', `-' and
+/// spaces, where a lower case letter is followed by an upper case one,
+/// before the last letter of an upper case acronym followed by lower
+/// case letters, and between digits and letters.  The words are then
+/// joined according to STYLE, which is one of:
+///
+///   `snake'      snake_case
+///   `screaming'  SCREAMING_SNAKE_CASE
+///   `kebab'      kebab-case
+///   `camel'      camelCase
+///   `pascal'     PascalCase
+///
+/// For example, (string-convert-case "parseHTTPResponse2" \\='snake)
+/// returns "parse_http_response_2".
+#[lisp_fn]
+pub fn string_convert_case(string: LispStringRef, style: LispObject) -> LispObject {
+    let style = CaseStyle::from_lisp(style);
+    let text: Vec<Codepoint> = string.chars().collect();
+    let converted = style.convert(&text);
+
+    let mut bytes = Vec::with_capacity(converted.len());
+    encode_codepoints(&converted, &mut bytes);
+    unsafe { make_string(bytes.as_ptr() as *const c_char, bytes.len() as ptrdiff_t) }
+}
+
+/// Convert the identifier in the region to the naming style STYLE.
+/// When called from Lisp, BEG and END specify the region.  See
+/// `string-convert-case' for how the region is split into words and
+/// the supported styles.
+#[lisp_fn(intspec = "r\nSConvert to style (snake, screaming, kebab, camel or pascal): ")]
+pub fn convert_case_region(beg: LispObject, end: LispObject, style: LispObject) {
+    let style = CaseStyle::from_lisp(style);
+    let (mut beg, mut end) = (beg, end);
+    unsafe { validate_region(&mut beg, &mut end) };
+    let start = beg.as_fixnum_or_error() as ptrdiff_t;
+    let end = end.as_fixnum_or_error() as ptrdiff_t;
+
+    let original = unsafe { make_buffer_string(start, end, false) };
+    let text: Vec<Codepoint> = original.as_string_or_error().chars().collect();
+    let converted = style.convert(&text);
+    if converted == text {
+        return;
+    }
+
+    let mut bytes = Vec::with_capacity(converted.len());
+    encode_codepoints(&converted, &mut bytes);
+    unsafe {
+        let new = make_string(bytes.as_ptr() as *const c_char, bytes.len() as ptrdiff_t);
+        replace_range(start, end, new, true, false, true, false);
+    }
+}
+
 /// Convert argument to capitalized form and return that.
 /// This means that each word's first character is converted to either
 /// title case or upper case, and the rest to lower case.
@@ -49,9 +970,14 @@ fn casify_word(flag: case_action, words: EmacsInt) {
 /// The argument object is not altered--the value is a copy.  If argument
 /// is a character, characters which map to multiple code points when
 /// cased, e.g. ﬁ, are returned unchanged.
-#[lisp_fn]
-pub fn capitalize(object: LispObject) -> LispObject {
-    unsafe { casify_object(case_action::CASE_CAPITALIZE, object) }
+///
+/// Optional argument LOCALE, a string or symbol such as "tr" or
+/// `lt_LT', selects the language-specific casing rules of that
+/// language.  Turkish, Azerbaijani and Lithuanian have rules of their
+/// own; other languages use the default rules.
+#[lisp_fn(min = "1")]
+pub fn capitalize(object: LispObject, locale: LispObject) -> LispObject {
+    casify_object(case_action::CASE_CAPITALIZE, object, locale)
 }
 
 /// Convert the region to capitalized form.
@@ -59,9 +985,11 @@ pub fn capitalize(object: LispObject) -> LispObject {
 /// title case or upper case, and the rest to lower case.  In
 /// programs, give two arguments, the starting and ending character
 /// positions to operate on.
-#[lisp_fn(intspec = "r")]
-pub fn capitalize_region(beg: LispObject, end: LispObject) -> LispObject {
-    unsafe { casify_region_nil(case_action::CASE_CAPITALIZE, beg, end) }
+/// Optional argument LOCALE selects language-specific casing rules,
+/// as in `capitalize'.
+#[lisp_fn(min = "2", intspec = "r")]
+pub fn capitalize_region(beg: LispObject, end: LispObject, locale: LispObject) {
+    casify_region(case_action::CASE_CAPITALIZE, beg, end, locale);
 }
 
 /// Capitalize from point to the end of word, moving over.
@@ -81,15 +1009,19 @@ pub fn capitalize_word(words: EmacsInt) {
 /// Convert argument to lower case and return that.
 /// The argument may be a character or string.  The result has the same type.
 /// The argument object is not altered--the value is a copy.
-#[lisp_fn]
-pub fn downcase(object: LispObject) -> LispObject {
-    unsafe { casify_object(case_action::CASE_DOWN, object) }
+/// Optional argument LOCALE selects language-specific casing rules,
+/// as in `capitalize'.  For example, (downcase "I" "tr") is "ı".
+#[lisp_fn(min = "1")]
+pub fn downcase(object: LispObject, locale: LispObject) -> LispObject {
+    casify_object(case_action::CASE_DOWN, object, locale)
 }
 
 /// Convert the region to lower case.  In programs, wants two arguments.
 /// These arguments specify the starting and ending character numbers
 /// of the region to operate on.  When used as a command, the text
 /// between point and the mark is operated on.
+/// Optional argument LOCALE selects language-specific casing rules,
+/// as in `capitalize'.
 #[lisp_fn(
     min = "2",
     intspec = "(list (region-beginning) (region-end) (region-noncontiguous-p))"
@@ -98,8 +1030,15 @@ pub fn downcase_region(
     beg: LispObject,
     end: LispObject,
     region_noncontiguous_p: LispObject,
-) -> LispObject {
-    casefiddle_region(beg, end, region_noncontiguous_p, case_action::CASE_DOWN)
+    locale: LispObject,
+) {
+    casefiddle_region(
+        beg,
+        end,
+        region_noncontiguous_p,
+        case_action::CASE_DOWN,
+        locale,
+    );
 }
 
 /// Convert to lower case from point to end of word, moving over.
@@ -118,10 +1057,12 @@ pub fn downcase_word(words: EmacsInt) {
 /// The argument object is not altered--the value is a copy.  If argument
 /// is a character, characters which map to multiple code points when
 /// cased, e.g. ﬁ, are returned unchanged.
+/// Optional argument LOCALE selects language-specific casing rules,
+/// as in `capitalize'.  For example, (upcase "i" "tr") is "İ".
 /// See also `capitalize', `downcase' and `upcase-initials'.
-#[lisp_fn]
-pub fn upcase(object: LispObject) -> LispObject {
-    unsafe { casify_object(case_action::CASE_UP, object) }
+#[lisp_fn(min = "1")]
+pub fn upcase(object: LispObject, locale: LispObject) -> LispObject {
+    casify_object(case_action::CASE_UP, object, locale)
 }
 
 /* Like Fcapitalize but change only the initials.  */
@@ -133,9 +1074,11 @@ pub fn upcase(object: LispObject) -> LispObject {
 /// type.  The argument object is not altered--the value is a copy.
 /// If argument is a character, characters which map to multiple code
 /// points when cased, e.g. ﬁ, are returned unchanged.
-#[lisp_fn]
-pub fn upcase_initials(obj: LispObject) -> LispObject {
-    unsafe { casify_object(case_action::CASE_CAPITALIZE_UP, obj) }
+/// Optional argument LOCALE selects language-specific casing rules,
+/// as in `capitalize'.
+#[lisp_fn(min = "1")]
+pub fn upcase_initials(obj: LispObject, locale: LispObject) -> LispObject {
+    casify_object(case_action::CASE_CAPITALIZE_UP, obj, locale)
 }
 
 // Like Fcapitalize_region but change only the initials.
@@ -145,15 +1088,19 @@ pub fn upcase_initials(obj: LispObject) -> LispObject {
 /// title case or upper case, and the rest are left unchanged.  In
 /// programs, give two arguments, the starting and ending character
 /// positions to operate on.
-#[lisp_fn(intspec = "r")]
-pub fn upcase_initials_region(beg: LispObject, end: LispObject) -> LispObject {
-    unsafe { casify_region_nil(case_action::CASE_CAPITALIZE_UP, beg, end) }
+/// Optional argument LOCALE selects language-specific casing rules,
+/// as in `capitalize'.
+#[lisp_fn(min = "2", intspec = "r")]
+pub fn upcase_initials_region(beg: LispObject, end: LispObject, locale: LispObject) {
+    casify_region(case_action::CASE_CAPITALIZE_UP, beg, end, locale);
 }
 
 /// Convert the region to upper case.  In programs, wants two arguments.
 /// These arguments specify the starting and ending character numbers
 /// of the region to operate on.  When used as a command, the text
 /// between point and the mark is operated on.
+/// Optional argument LOCALE selects language-specific casing rules,
+/// as in `capitalize'.
 /// See also `capitalize-region'.
 #[lisp_fn(
     min = "2",
@@ -163,8 +1110,15 @@ pub fn upcase_region(
     beg: LispObject,
     end: LispObject,
     region_noncontiguous_p: LispObject,
-) -> LispObject {
-    casefiddle_region(beg, end, region_noncontiguous_p, case_action::CASE_UP)
+    locale: LispObject,
+) {
+    casefiddle_region(
+        beg,
+        end,
+        region_noncontiguous_p,
+        case_action::CASE_UP,
+        locale,
+    );
 }
 
 /// Convert to upper case from point to end of word, moving over.
@@ -186,9 +1140,10 @@ fn casefiddle_region(
     end: LispObject,
     region_noncontiguous_p: LispObject,
     action: case_action,
-) -> LispObject {
+    locale: LispObject,
+) {
     if region_noncontiguous_p.is_nil() {
-        unsafe { casify_region_nil(action, beg, end) }
+        casify_region(action, beg, end, locale);
     } else {
         let mut bounds = call!(
             symbol_value(intern("region-extract-function")),
@@ -197,11 +1152,9 @@ fn casefiddle_region(
 
         while let Some(cons) = bounds.as_cons() {
             let car = cons.car().as_cons_or_error();
-            unsafe { casify_region_nil(action, car.car(), car.cdr()) };
+            casify_region(action, car.car(), car.cdr(), locale);
             bounds = cons.cdr();
         }
-
-        Qnil
     }
 }
 
@@ -212,6 +1165,12 @@ pub extern "C" fn syms_of_casefiddle() {
     def_lisp_sym!(Qspecial_uppercase, "special-uppercase");
     def_lisp_sym!(Qspecial_lowercase, "special-lowercase");
     def_lisp_sym!(Qspecial_titlecase, "special-titlecase");
+
+    def_lisp_sym!(Qsnake, "snake");
+    def_lisp_sym!(Qscreaming, "screaming");
+    def_lisp_sym!(Qkebab, "kebab");
+    def_lisp_sym!(Qcamel, "camel");
+    def_lisp_sym!(Qpascal, "pascal");
 }
 
 #[no_mangle]
@@ -234,3 +1193,86 @@ pub extern "C" fn keys_of_casefiddle() {
 }
 
 include!(concat!(env!("OUT_DIR"), "/casefiddle_exports.rs"));
+
+#[cfg(test)]
+fn no_combining_class(c: Codepoint) -> u8 {
+    match c {
+        0x0300..=0x0314 => COMBINING_CLASS_ABOVE,
+        0x0323 => 220,
+        _ => 0,
+    }
+}
+
+#[test]
+fn test_case_locale_from_name() {
+    assert_eq!(CaseLocale::from_name("tr"), CaseLocale::Turkic);
+    assert_eq!(CaseLocale::from_name("az_AZ.UTF-8"), CaseLocale::Turkic);
+    assert_eq!(CaseLocale::from_name("lt-LT"), CaseLocale::Lithuanian);
+    assert_eq!(CaseLocale::from_name("en_US"), CaseLocale::Root);
+    assert_eq!(CaseLocale::from_name(""), CaseLocale::Root);
+}
+
+#[test]
+fn test_locale_special_case() {
+    let rule = |locale, flag, text: &[Codepoint], i| {
+        locale_special_case(locale, flag, text, i, &no_combining_class)
+    };
+    let down = case_action::CASE_DOWN;
+    let up = case_action::CASE_UP;
+
+    // Turkish dotted and dotless i.
+    assert_eq!(rule(CaseLocale::Turkic, up, &[0x69], 0), Some(&[0x130][..]));
+    assert_eq!(rule(CaseLocale::Turkic, down, &[0x49], 0), Some(&[0x131][..]));
+    assert_eq!(rule(CaseLocale::Turkic, down, &[0x49, 0x307], 0), None);
+    assert_eq!(rule(CaseLocale::Turkic, down, &[0x49, 0x307], 1), Some(&[][..]));
+    assert_eq!(rule(CaseLocale::Root, up, &[0x69], 0), None);
+
+    // Lithuanian keeps the dot of i under accents.
+    assert_eq!(
+        rule(CaseLocale::Lithuanian, down, &[0x49, 0x300], 0),
+        Some(&[0x69, 0x307][..])
+    );
+    assert_eq!(rule(CaseLocale::Lithuanian, down, &[0x49, 0x41], 0), None);
+    assert_eq!(
+        rule(CaseLocale::Lithuanian, up, &[0x69, 0x323, 0x307], 2),
+        Some(&[][..])
+    );
+    assert_eq!(rule(CaseLocale::Lithuanian, up, &[0x61, 0x307], 1), None);
+}
+
+#[cfg(test)]
+fn ascii_identifier_class(c: Codepoint) -> IdentifierClass {
+    match c {
+        0x41..=0x5A => IdentifierClass::Upper,
+        0x61..=0x7A => IdentifierClass::Lower,
+        0x30..=0x39 => IdentifierClass::Digit,
+        0x0301 => IdentifierClass::Mark,
+        0x4E00..=0x9FFF => IdentifierClass::Caseless,
+        _ => IdentifierClass::Separator,
+    }
+}
+
+#[test]
+fn test_identifier_words() {
+    let words = |s: &str| {
+        let text: Vec<Codepoint> = s.chars().map(|c| c as Codepoint).collect();
+        identifier_words(&text, &ascii_identifier_class)
+            .into_iter()
+            .map(|range| text[range].iter().map(|&c| std::char::from_u32(c).unwrap()).collect())
+            .collect::<Vec<String>>()
+    };
+
+    assert_eq!(words("snake_case_name"), ["snake", "case", "name"]);
+    assert_eq!(words("camelCaseName"), ["camel", "Case", "Name"]);
+    assert_eq!(words("parseHTTPResponse"), ["parse", "HTTP", "Response"]);
+    assert_eq!(words("HTTPServer"), ["HTTP", "Server"]);
+    assert_eq!(words("SCREAMING_CASE"), ["SCREAMING", "CASE"]);
+    assert_eq!(words("--kebab--case--"), ["kebab", "case"]);
+    assert_eq!(words("utf8Decoder2"), ["utf", "8", "Decoder", "2"]);
+    assert_eq!(words("v10"), ["v", "10"]);
+    assert_eq!(words("cafe\u{301}Bar"), ["cafe\u{301}", "Bar"]);
+    assert_eq!(words("ABC\u{301}def"), ["AB", "C\u{301}def"]);
+    assert_eq!(words("\u{4E2D}\u{6587}Name"), ["\u{4E2D}\u{6587}", "Name"]);
+    assert_eq!(words(""), Vec::<String>::new());
+    assert_eq!(words("__"), Vec::<String>::new());
+}
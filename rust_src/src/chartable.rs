@@ -68,7 +68,7 @@ impl LispObject {
     }
 }
 
-fn chartab_size(depth: i32) -> usize {
+pub fn chartab_size(depth: i32) -> usize {
     match depth {
         0 => 1 << CHARTAB_SIZE_BITS::CHARTAB_SIZE_BITS_0 as isize,
         1 => 1 << CHARTAB_SIZE_BITS::CHARTAB_SIZE_BITS_1 as isize,
@@ -33,6 +33,7 @@ use crate::{
         Qkill_forward_chars, Qnil, Qoverwrite_mode_binary, Qpost_self_insert_hook,
         Qundo_auto__this_command_amalgamating, Qundo_auto_amalgamate,
     },
+    segmentation::{grapheme_cluster_motion_p, grapheme_motion_target},
     threads::ThreadState,
 };
 
@@ -57,7 +58,11 @@ fn move_point(n: LispObject, forward: bool) -> () {
 
     let buffer = ThreadState::current_buffer();
     let mut signal = Qnil;
-    let mut new_point = buffer.pt + n;
+    let mut new_point = if grapheme_cluster_motion_p() {
+        grapheme_motion_target(buffer.pt, n)
+    } else {
+        buffer.pt + n
+    };
 
     if new_point < buffer.begv {
         new_point = buffer.begv;
@@ -77,6 +82,7 @@ fn move_point(n: LispObject, forward: bool) -> () {
 /// On reaching end or beginning of buffer, stop and signal error.
 /// Interactively, N is the numeric prefix argument.
 /// If N is omitted or nil, move point 1 character forward.
+/// If `grapheme-cluster-motion' is non-nil, N counts grapheme clusters.
 ///
 /// Depending on the bidirectional context, the movement may be to the
 /// right or to the left on the screen.  This is in contrast with
@@ -90,6 +96,7 @@ pub fn forward_char(n: LispObject) -> () {
 /// On attempt to pass beginning or end of buffer, stop and signal error.
 /// Interactively, N is the numeric prefix argument.
 /// If N is omitted or nil, move point 1 character backward.
+/// If `grapheme-cluster-motion' is non-nil, N counts grapheme clusters.
 ///
 /// Depending on the bidirectional context, the movement may be to the
 /// right or to the left on the screen.  This is in contrast with
@@ -212,6 +219,7 @@ pub fn forward_line(n: Option<EmacsInt>) -> EmacsInt {
 /// Optional second arg KILLFLAG non-nil means kill instead (save in kill ring).
 /// Interactively, N is the prefix arg, and KILLFLAG is set if
 /// N was explicitly specified.
+/// If `grapheme-cluster-motion' is non-nil, N counts grapheme clusters.
 ///
 /// The command `delete-forward-char' is preferable for interactive use, e.g.
 /// because it respects values of `delete-active-region' and `overwrite-mode'.
@@ -222,9 +230,13 @@ pub fn delete_char(n: EmacsInt, killflag: bool) -> () {
     }
 
     let buffer = ThreadState::current_buffer();
-    let pos = buffer.pt + n as isize;
+    let pos = if grapheme_cluster_motion_p() {
+        grapheme_motion_target(buffer.pt, n as isize)
+    } else {
+        buffer.pt + n as isize
+    };
     if killflag {
-        call_raw!(Qkill_forward_chars, LispObject::from(n));
+        call_raw!(Qkill_forward_chars, LispObject::from((pos - buffer.pt) as EmacsInt));
     } else if n < 0 {
         if pos < buffer.begv {
             xsignal!(Qbeginning_of_buffer);
@@ -537,6 +549,14 @@ pub extern "C" fn syms_of_cmds() {
     /// Hook run at the end of `self-insert-command'.
     /// This is run after inserting the character.
     defvar_lisp!(Vpost_self_insert_hook, "post-self-insert-hook", Qnil);
+
+    /// Non-nil means character motion and deletion work on grapheme clusters.
+    /// When this is non-nil, `forward-char', `backward-char' and
+    /// `delete-char' count grapheme clusters instead of characters, so that
+    /// a base character with its combining marks, or an emoji with its
+    /// modifiers, is moved over and deleted as a unit.  See
+    /// `string-graphemes' for what a grapheme cluster is.
+    defvar_lisp!(Vgrapheme_cluster_motion, "grapheme-cluster-motion", Qnil);
 }
 
 include!(concat!(env!("OUT_DIR"), "/cmds_exports.rs"));
@@ -351,7 +351,7 @@ pub fn byteorder() -> u8 {
 
 /// Return the default value of SYMBOL, but don't check for voidness.
 /// Return Qunbound if it is void.
-fn default_value(mut symbol: LispSymbolRef) -> LispObject {
+pub fn default_value(mut symbol: LispSymbolRef) -> LispObject {
     while symbol.get_redirect() == symbol_redirect::SYMBOL_VARALIAS {
         symbol = symbol.get_indirect_variable();
     }
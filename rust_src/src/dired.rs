@@ -4,7 +4,8 @@ use remacs_macros::lisp_fn;
 
 #[cfg(unix)]
 use crate::dired_unix::{
-    directory_files_and_attributes_intro, directory_files_intro, file_attributes_intro, get_users,
+    directory_files_and_attributes_intro, directory_files_intro, directory_walk_intro,
+    file_attributes_extended_intro, file_attributes_intro, get_users,
 };
 #[cfg(windows)]
 use dired_windows::{file_attributes_intro, get_users};
@@ -12,6 +13,7 @@ use dired_windows::{file_attributes_intro, get_users};
 use crate::{
     lisp::{defsubr, LispObject},
     lists::car,
+    remacs_sys::EmacsUint,
     strings::string_lessp,
 };
 
@@ -23,6 +25,8 @@ use crate::{
 /// If NOSORT is non-nil, the list is not sorted--its order is unpredictable.
 ///  Otherwise, the list returned is sorted with `string-lessp'.
 ///  NOSORT is useful if you plan to sort the result yourself.
+///  If NOSORT is the symbol `version', the list is sorted with
+///  `string-version-lessp' instead, so that "file2" precedes "file10".
 #[lisp_fn(min = "1")]
 pub fn directory_files(
     directory: LispObject,
@@ -40,6 +44,8 @@ pub fn directory_files(
 /// If MATCH is non-nil, mention only file names that match the regexp MATCH.
 /// If NOSORT is non-nil, the list is not sorted--its order is unpredictable.
 ///  NOSORT is useful if you plan to sort the result yourself.
+///  If NOSORT is the symbol `version', the list is sorted with
+///  `string-version-lessp'.
 /// ID-FORMAT specifies the preferred format of attributes uid and gid, see
 /// `file-attributes' for further documentation.
 /// On MS-Windows, performance depends on `w32-get-true-file-attributes',
@@ -55,6 +61,59 @@ pub fn directory_files_and_attributes(
     directory_files_and_attributes_intro(directory, full, match_re, nosort, id_format)
 }
 
+/// Return a list of all files under DIRECTORY, searching it recursively.
+/// Files are returned in "depth first" order: the contents of each
+/// subdirectory come before the files of the directory containing it,
+/// and the files of each directory are sorted in lexicographic order.
+/// Each file name is returned in its absolute form.
+///
+/// If MATCH is non-nil, mention only files whose names (without the
+///  directory part) match the regexp MATCH.  `case-fold-search' is
+///  respected, as for `string-match'.
+/// If INCLUDE-DIRECTORIES is non-nil, also mention directories whose names
+///  match MATCH, right after their contents.
+/// If MAX-DEPTH is non-nil, it is the number of directory levels below
+///  DIRECTORY to descend into; 0 means list DIRECTORY only.
+/// If FOLLOW-SYMLINKS is non-nil, descend into symbolic links to
+///  directories.  Each directory is still visited at most once.  Otherwise
+///  such links are treated as directories that are not descended into.
+/// If IGNORE-FILE is non-nil, it names a file, such as ".gitignore", whose
+///  patterns exclude files in the directory containing it and below, with
+///  the same syntax as for Git.
+/// If PREDICATE is non-nil, it is called with the absolute name of each
+///  directory before descending into it, which is only done if it returns
+///  non-nil.
+/// If ATTRIBUTES is non-nil, return a list of (NAME . ATTRIBUTES) pairs,
+///  where ATTRIBUTES is as returned by `file-attributes'.  ATTRIBUTES is
+///  used as its ID-FORMAT argument.
+///
+/// Subdirectories that cannot be read are silently skipped.
+///
+/// If DIRECTORY has a file name handler, it is called with the operation
+/// `directory-walk' and all the arguments.
+#[lisp_fn(min = "1")]
+pub fn directory_walk(
+    directory: LispObject,
+    match_re: LispObject,
+    include_directories: bool,
+    max_depth: Option<EmacsUint>,
+    follow_symlinks: bool,
+    ignore_file: LispObject,
+    predicate: LispObject,
+    attributes: LispObject,
+) -> LispObject {
+    directory_walk_intro(
+        directory,
+        match_re,
+        include_directories,
+        max_depth,
+        follow_symlinks,
+        ignore_file,
+        predicate,
+        attributes,
+    )
+}
+
 /// Return a list of attributes of file FILENAME.
 /// Value is nil if specified file cannot be opened.
 ///
@@ -109,6 +168,43 @@ pub fn file_attributes(filename: LispObject, id_format: LispObject) -> LispObjec
     file_attributes_intro(filename, id_format)
 }
 
+/// Return a plist of extended attributes of file FILENAME.
+/// Value is nil if specified file cannot be opened.
+/// If FOLLOW-SYMLINKS is non-nil and FILENAME is a symbolic link, describe
+/// the file it points to rather than the link itself.
+///
+/// The plist has the following properties:
+///  :birth-time   Creation time of the file, in the style of (current-time),
+///                or nil if the system or filesystem does not record it.
+///  :block-size   Preferred block size for I/O, in bytes.  This and the
+///                other numbers are floats if they are too large for fixnums.
+///  :blocks       Number of 512-byte blocks allocated to the file.
+///  :device-major Major and minor numbers of the device holding the file.
+///  :device-minor
+///  :rdev-major   Major and minor numbers of the device the file represents,
+///  :rdev-minor   for character and block special files; 0 otherwise.
+///  :xattrs       List of names of the file's extended attributes.
+///  :acl          t if the file has a POSIX access control list.  This is
+///                detected by the presence of the `system.posix_acl_access'
+///                or `system.posix_acl_default' extended attribute, so on
+///                systems that do not store ACLs that way it is always nil.
+#[lisp_fn(min = "1")]
+pub fn file_attributes_extended(filename: LispObject, follow_symlinks: bool) -> LispObject {
+    file_attributes_extended_intro(filename, follow_symlinks)
+}
+def_lisp_sym!(Qdirectory_walk, "directory-walk");
+def_lisp_sym!(Qfile_attributes_extended, "file-attributes-extended");
+def_lisp_sym!(QCbirth_time, ":birth-time");
+def_lisp_sym!(QCblock_size, ":block-size");
+def_lisp_sym!(QCblocks, ":blocks");
+def_lisp_sym!(QCdevice_major, ":device-major");
+def_lisp_sym!(QCdevice_minor, ":device-minor");
+def_lisp_sym!(QCrdev_major, ":rdev-major");
+def_lisp_sym!(QCrdev_minor, ":rdev-minor");
+def_lisp_sym!(QCxattrs, ":xattrs");
+def_lisp_sym!(QCacl, ":acl");
+def_lisp_sym!(Qversion, "version");
+
 /// Return t if first arg file attributes list is less than second.
 /// Comparison is in lexicographic order and case is significant.
 #[lisp_fn]
@@ -1,28 +1,46 @@
 use libc::{
     c_char, c_long, endpwent, getgrgid, getpwent, getpwuid, group, passwd, size_t, ssize_t,
-    timespec as c_timespec,
+    time_t, timespec as c_timespec,
 };
 
+use std::collections::HashSet;
 use std::ffi::{CStr, CString, OsStr};
 use std::fs;
 use std::io;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
 use std::os::unix::fs::MetadataExt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::ptr::null_mut;
 use std::slice;
+#[cfg(not(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "arm"
+    )
+)))]
+use std::time::UNIX_EPOCH;
 
 use crate::{
     lisp::LispObject,
     lists::list,
+    numbers::MOST_POSITIVE_FIXNUM,
     remacs_sys::{
-        build_string, compile_pattern, decode_file_name, file_attributes_c_internal,
-        filemode_string, globals, re_pattern_buffer, re_search,
+        build_string, compile_pattern, decode_file_name, encode_file_name,
+        file_attributes_c_internal, filemode_string, globals, make_unibyte_string,
+        re_pattern_buffer, re_search, EmacsDouble, EmacsUint,
     },
     remacs_sys::{Fexpand_file_name, Ffind_file_name_handler},
     remacs_sys::{
-        Qdirectory_files, Qdirectory_files_and_attributes, Qfile_attributes, Qfile_missing, Qnil,
-        Qt,
+        QCacl, QCbirth_time, QCblock_size, QCblocks, QCdevice_major, QCdevice_minor, QCrdev_major,
+        QCrdev_minor, QCxattrs, Qdirectory_files, Qdirectory_files_and_attributes,
+        Qdirectory_walk, Qfile_attributes, Qfile_attributes_extended, Qfile_missing, Qnil, Qt,
+        Qversion,
     },
+    strings::version_cmp,
+    threads::ThreadState,
     time::make_lisp_time,
 };
 
@@ -149,6 +167,27 @@ impl DirReq {
 enum SortFNames {
     No,
     Yes,
+    Version,
+}
+
+impl SortFNames {
+    fn from_nosort(nosort: LispObject) -> Self {
+        if nosort.is_nil() {
+            SortFNames::Yes
+        } else if nosort.eq(Qversion) {
+            SortFNames::Version
+        } else {
+            SortFNames::No
+        }
+    }
+
+    fn sort(&self, fnames: &mut Vec<String>) {
+        match *self {
+            SortFNames::No => {}
+            SortFNames::Yes => fnames.sort(),
+            SortFNames::Version => fnames.sort_by(|a, b| version_cmp(a.as_bytes(), b.as_bytes())),
+        }
+    }
 }
 
 enum FullPath {
@@ -172,18 +211,14 @@ impl DirData {
         match *self {
             DirData::Files { ref mut fnames } => {
                 fnames_from_os(fnames, &dr.dname, dr.match_re);
-                if let SortFNames::Yes = dr.sortmemaybe {
-                    fnames.sort();
-                }
+                dr.sortmemaybe.sort(fnames);
             }
             DirData::FilesAttrs {
                 ref mut fnames,
                 ref mut fattrs,
             } => {
                 fnames_from_os(fnames, &dr.dname, dr.match_re);
-                if let SortFNames::Yes = dr.sortmemaybe {
-                    fnames.sort();
-                }
+                dr.sortmemaybe.sort(fnames);
 
                 fattrs_from_os(fattrs, fnames, &dr.dname, dr.id_format);
             }
@@ -262,7 +297,7 @@ fn read_dir(dname: &str, fnames: &mut Vec<String>, match_re: Option<LispObject>)
 fn match_re_maybe(f: String, match_re: Option<LispObject>, re: &RegEx) -> Option<String> {
     match match_re {
         Some(_) => {
-            if re.is_match(f.as_str()) {
+            if re.is_match(f.as_bytes()) {
                 Some(f)
             } else {
                 None
@@ -343,11 +378,7 @@ pub fn directory_files_intro(
         } else {
             Some(match_re)
         },
-        if nosort.is_nil() {
-            SortFNames::Yes
-        } else {
-            SortFNames::No
-        },
+        SortFNames::from_nosort(nosort),
         Qnil,
     );
     let mut dd = DirData::Files { fnames: Vec::new() };
@@ -389,11 +420,7 @@ pub fn directory_files_and_attributes_intro(
         } else {
             Some(match_re)
         },
-        if nosort.is_nil() {
-            SortFNames::Yes
-        } else {
-            SortFNames::No
-        },
+        SortFNames::from_nosort(nosort),
         id_format,
     );
     let mut dd = DirData::FilesAttrs {
@@ -426,11 +453,7 @@ pub extern "C" fn directory_files_internal(
         } else {
             Some(match_re)
         },
-        if nosort.is_nil() {
-            SortFNames::Yes
-        } else {
-            SortFNames::No
-        },
+        SortFNames::from_nosort(nosort),
         id_format,
     );
 
@@ -446,21 +469,427 @@ pub extern "C" fn directory_files_internal(
     directory_files_core(&dr, &mut dd)
 }
 
+// Recursive directory walk request input
+struct WalkReq {
+    match_re: Option<LispObject>, // filter regexp for file names
+    translate: LispObject,        // case table for MATCH_RE, or nil
+    include_dirs: bool,           // also report directories matching MATCH_RE
+    max_depth: Option<EmacsUint>, // None means no limit
+    follow_symlinks: bool,
+    ignore_file: Option<String>, // e.g. ".gitignore"
+    predicate: LispObject,       // called before descending into a directory
+}
+
+// One entry found by the walk, in the order it will be returned.
+struct WalkEntry {
+    path: PathBuf, // encoded, as the file system knows it
+}
+
+// Decode NAME, an encoded file name, into a Lisp string.
+fn decode_file_name_bytes(name: &[u8]) -> LispObject {
+    unsafe {
+        decode_file_name(make_unibyte_string(
+            name.as_ptr() as *const c_char,
+            name.len() as isize,
+        ))
+    }
+}
+
+// The walk works on encoded names, so that every file can be reached
+// whatever `file-name-coding-system' makes of its name.  Names are only
+// decoded to be matched and returned.
+fn walk_dir(
+    wr: &WalkReq,
+    dname: &Path,
+    depth: EmacsUint,
+    ignores: &mut Vec<IgnoreRules>,
+    visited: &mut HashSet<(u64, u64)>,
+    found: &mut Vec<WalkEntry>,
+) -> io::Result<()> {
+    let dir = fs::read_dir(dname)?;
+
+    let pushed_rules = match wr.ignore_file {
+        Some(ref name) => match IgnoreRules::read(dname, name) {
+            Some(rules) => {
+                ignores.push(rules);
+                true
+            }
+            None => false,
+        },
+        None => false,
+    };
+
+    let mut entries = Vec::new();
+    for entry in dir {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+        let fpath = entry.path();
+
+        let ft = match entry.file_type() {
+            Ok(ft) => ft,
+            Err(_) => continue,
+        };
+        // A symbolic link to a directory counts as a directory even when
+        // it is not followed; it is then just not descended into.
+        let is_dir = if ft.is_symlink() {
+            fs::metadata(entry.path())
+                .map(|md| md.is_dir())
+                .unwrap_or(false)
+        } else {
+            ft.is_dir()
+        };
+        let may_descend = is_dir && (wr.follow_symlinks || !ft.is_symlink());
+
+        if !is_ignored(ignores, &fpath, is_dir) {
+            // Sort directories as "name/", as `file-name-all-completions'
+            // would return them.
+            let mut key = entry.file_name().into_vec();
+            if is_dir {
+                key.push(b'/');
+            }
+            entries.push((key, fpath, is_dir, may_descend));
+        }
+    }
+    entries.sort();
+
+    // Files in this directory are reported after the contents of its
+    // subdirectories, just like the Lisp `directory-files-recursively'.
+    let mut files = Vec::new();
+    for (_, fpath, is_dir, may_descend) in entries {
+        // The predicate may run arbitrary Lisp and thereby evict our
+        // pattern from the regexp cache, so compile it for each match.
+        let matches = wr.match_re.map_or(true, |re| {
+            let re = RegEx::with_translate(re, wr.translate);
+            let name = fpath.file_name().unwrap().as_bytes();
+            re.is_match(decode_file_name_bytes(name).as_string_or_error().as_slice())
+        });
+
+        if !is_dir {
+            if matches {
+                files.push(WalkEntry { path: fpath });
+            }
+            continue;
+        }
+
+        let descend = may_descend
+            && wr.max_depth.map_or(true, |max| depth < max)
+            && dir_not_visited(&fpath, visited)
+            && (wr.predicate.is_nil()
+                || call!(wr.predicate, decode_file_name_bytes(fpath.as_os_str().as_bytes()))
+                    .is_not_nil());
+        if descend {
+            // Unreadable subdirectories are skipped rather than aborting the
+            // whole walk.
+            let _ = walk_dir(wr, &fpath, depth + 1, ignores, visited, found);
+        }
+        if wr.include_dirs && matches {
+            found.push(WalkEntry { path: fpath });
+        }
+    }
+    found.append(&mut files);
+
+    if pushed_rules {
+        ignores.pop();
+    }
+
+    Ok(())
+}
+
+// Record the directory as visited, returning false if it was seen
+// before.  This keeps followed symlinks from looping forever.
+fn dir_not_visited(dname: &Path, visited: &mut HashSet<(u64, u64)>) -> bool {
+    match fs::metadata(dname) {
+        Ok(md) => visited.insert((md.dev(), md.ino())),
+        Err(_) => false,
+    }
+}
+
+pub fn directory_walk_intro(
+    directory: LispObject,
+    match_re: LispObject,
+    include_directories: bool,
+    max_depth: Option<EmacsUint>,
+    follow_symlinks: bool,
+    ignore_file: LispObject,
+    predicate: LispObject,
+    attributes: LispObject,
+) -> LispObject {
+    let dnexp = unsafe { Fexpand_file_name(directory, Qnil) };
+
+    let handler = unsafe { Ffind_file_name_handler(dnexp, Qdirectory_walk) };
+    if handler.is_not_nil() {
+        return call!(
+            handler,
+            Qdirectory_walk,
+            dnexp,
+            match_re,
+            LispObject::from(include_directories),
+            LispObject::from(max_depth),
+            LispObject::from(follow_symlinks),
+            ignore_file,
+            predicate,
+            attributes
+        );
+    }
+
+    let encoded = unsafe { encode_file_name(dnexp) }.as_string_or_error();
+    let dname = Path::new(OsStr::from_bytes(encoded.as_slice())).to_path_buf();
+
+    // Match like `string-match' does, honoring `case-fold-search'.
+    let buffer = ThreadState::current_buffer();
+    let wr = WalkReq {
+        match_re: if match_re.is_nil() {
+            None
+        } else {
+            Some(match_re)
+        },
+        translate: if buffer.case_fold_search().is_nil() {
+            Qnil
+        } else {
+            buffer.case_canon_table()
+        },
+        include_dirs: include_directories,
+        max_depth,
+        follow_symlinks,
+        ignore_file: if ignore_file.is_nil() {
+            None
+        } else {
+            Some(ignore_file.to_stdstring())
+        },
+        predicate,
+    };
+
+    let mut visited = HashSet::new();
+    dir_not_visited(&dname, &mut visited);
+    let mut found = Vec::new();
+    let res = walk_dir(&wr, &dname, 0, &mut Vec::new(), &mut visited, &mut found);
+    if res.is_err() {
+        xsignal!(
+            Qfile_missing,
+            format!("Opening directory: {}", res.unwrap_err()).to_bstring(),
+            dnexp
+        );
+    }
+
+    if attributes.is_nil() {
+        list(
+            &found
+                .iter()
+                .map(|x| decode_file_name_bytes(x.path.as_os_str().as_bytes()))
+                .collect::<Vec<_>>(),
+        )
+    } else {
+        list(
+            &found
+                .iter()
+                .map(|x| {
+                    let name = decode_file_name_bytes(x.path.as_os_str().as_bytes());
+                    LispObject::cons(name, file_attributes_core(name, attributes))
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+// A single pattern line of a .gitignore style file.
+struct IgnoreRule {
+    pattern: Vec<u8>,
+    negate: bool,   // "!pattern" re-includes what earlier rules excluded
+    dir_only: bool, // "pattern/" only matches directories
+    anchored: bool, // patterns containing a '/' match the relative path
+}
+
+// The rules of one ignore file, relative to the directory holding it.
+struct IgnoreRules {
+    base: PathBuf,
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+    fn read(dir: &Path, name: &str) -> Option<Self> {
+        let contents = fs::read(dir.join(name)).ok()?;
+        let rules: Vec<_> = contents
+            .split(|&b| b == b'\n')
+            .filter_map(IgnoreRule::parse)
+            .collect();
+
+        if rules.is_empty() {
+            None
+        } else {
+            Some(Self {
+                base: dir.to_path_buf(),
+                rules,
+            })
+        }
+    }
+}
+
+impl IgnoreRule {
+    fn parse(line: &[u8]) -> Option<Self> {
+        let mut line = line;
+        if line.ends_with(b"\r") {
+            line = &line[..line.len() - 1];
+        }
+        // Trailing spaces are ignored unless escaped.
+        while line.ends_with(b" ") && !line.ends_with(b"\\ ") {
+            line = &line[..line.len() - 1];
+        }
+        if line.is_empty() || line[0] == b'#' {
+            return None;
+        }
+
+        let negate = line[0] == b'!';
+        if negate {
+            line = &line[1..];
+        }
+        let dir_only = line.ends_with(b"/");
+        if dir_only {
+            line = &line[..line.len() - 1];
+        }
+        let anchored = line.contains(&b'/');
+        if line.starts_with(b"/") {
+            line = &line[1..];
+        }
+        if line.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            pattern: line.to_vec(),
+            negate,
+            dir_only,
+            anchored,
+        })
+    }
+
+    fn is_match(&self, relative: &[u8], is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if self.anchored {
+            glob_match(&self.pattern, relative)
+        } else {
+            let name = match relative.iter().rposition(|&b| b == b'/') {
+                Some(idx) => &relative[idx + 1..],
+                None => relative,
+            };
+            glob_match(&self.pattern, name)
+        }
+    }
+}
+
+// Whether PATH is excluded by the ignore files in effect.  Later rules,
+// and rules from deeper directories, take precedence.
+fn is_ignored(ignores: &[IgnoreRules], path: &Path, is_dir: bool) -> bool {
+    let mut ignored = false;
+    for rules in ignores {
+        let relative = match path.strip_prefix(&rules.base) {
+            Ok(relative) => relative.as_os_str().as_bytes(),
+            Err(_) => continue,
+        };
+        for rule in &rules.rules {
+            if rule.is_match(relative, is_dir) {
+                ignored = !rule.negate;
+            }
+        }
+    }
+    ignored
+}
+
+// Match NAME against the shell glob PATTERN as git does: `*' and `?'
+// never match a '/', `**' matches across directories, and `[...]'
+// is a character class.
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((&b'*', rest)) => {
+            if rest.first() == Some(&b'*') {
+                let rest = &rest[1..];
+                // "**/" also matches zero directories.
+                let rest = if rest.first() == Some(&b'/') {
+                    if glob_match(&rest[1..], name) {
+                        return true;
+                    }
+                    &rest[1..]
+                } else {
+                    rest
+                };
+                (0..=name.len()).any(|i| glob_match(rest, &name[i..]))
+            } else {
+                let limit = name.iter().position(|&b| b == b'/').unwrap_or(name.len());
+                (0..=limit).any(|i| glob_match(rest, &name[i..]))
+            }
+        }
+        Some((&b'?', rest)) => match name.split_first() {
+            Some((&c, name_rest)) if c != b'/' => glob_match(rest, name_rest),
+            _ => false,
+        },
+        Some((&b'[', rest)) => match name.split_first() {
+            Some((&c, name_rest)) if c != b'/' => match glob_class(rest, c) {
+                Some((true, after)) => glob_match(after, name_rest),
+                Some((false, _)) => false,
+                // An unterminated class is a literal '['.
+                None => c == b'[' && glob_match(rest, name_rest),
+            },
+            _ => false,
+        },
+        Some((&b'\\', rest)) if !rest.is_empty() => {
+            name.first() == Some(&rest[0]) && glob_match(&rest[1..], &name[1..])
+        }
+        Some((&p, rest)) => name.first() == Some(&p) && glob_match(rest, &name[1..]),
+    }
+}
+
+// Match C against the class starting just after a '['.  Return whether
+// it matched and the pattern following the closing ']', or None if the
+// class is not terminated.
+fn glob_class(class: &[u8], c: u8) -> Option<(bool, &[u8])> {
+    let (negate, class) = match class.first() {
+        Some(b'!') | Some(b'^') => (true, &class[1..]),
+        _ => (false, class),
+    };
+
+    let mut matched = false;
+    let mut i = 0;
+    while i < class.len() {
+        // A ']' right after the opening bracket is taken literally.
+        if class[i] == b']' && i > 0 {
+            return Some((matched != negate, &class[i + 1..]));
+        }
+        if i + 2 < class.len() && class[i + 1] == b'-' && class[i + 2] != b']' {
+            matched |= class[i] <= c && c <= class[i + 2];
+            i += 3;
+        } else {
+            matched |= class[i] == c;
+            i += 1;
+        }
+    }
+
+    None
+}
+
 struct RegEx {
     recomp: *mut re_pattern_buffer,
 }
 
 impl RegEx {
     fn new(match_re: LispObject) -> Self {
+        Self::with_translate(match_re, Qnil)
+    }
+
+    // TRANSLATE is a case table to fold case with, or nil.
+    fn with_translate(match_re: LispObject, translate: LispObject) -> Self {
         Self {
             // MATCH_RE might be a flawed regular expression.  Rather than
             // catching and signaling our own errors, we just call
             // compile_pattern to do the work for us.
-            recomp: unsafe { compile_pattern(match_re, null_mut(), Qnil, false, true) },
+            recomp: unsafe { compile_pattern(match_re, null_mut(), translate, false, true) },
         }
     }
 
-    fn is_match(&self, s: &str) -> bool {
+    fn is_match(&self, s: &[u8]) -> bool {
         unsafe {
             re_search(
                 self.recomp,
@@ -730,6 +1159,305 @@ fn file_attributes_core(fpath: LispObject, id_format: LispObject) -> LispObject
     }
 }
 
+pub fn file_attributes_extended_intro(filename: LispObject, follow_symlinks: bool) -> LispObject {
+    let fnexp = unsafe { Fexpand_file_name(filename, Qnil) };
+    let handler = unsafe { Ffind_file_name_handler(fnexp, Qfile_attributes_extended) };
+    if handler.is_not_nil() {
+        return call!(
+            handler,
+            Qfile_attributes_extended,
+            fnexp,
+            LispObject::from(follow_symlinks)
+        );
+    }
+
+    file_attributes_extended_core(&fnexp.to_stdstring(), follow_symlinks).unwrap_or(Qnil)
+}
+
+fn file_attributes_extended_core(fpath: &str, follow_symlinks: bool) -> io::Result<LispObject> {
+    let md = if follow_symlinks {
+        fs::metadata(fpath)?
+    } else {
+        fs::symlink_metadata(fpath)?
+    };
+    let c_path = CString::new(fpath)?;
+
+    let birth_time = match birth_time(&c_path, &md, follow_symlinks) {
+        Some(ts) => make_lisp_time(ts),
+        None => Qnil,
+    };
+
+    // ACLs are only detected through the extended attributes that Linux
+    // uses to store them, so this is always false elsewhere.
+    let xattrs = xattr_names(&c_path, follow_symlinks);
+    let has_acl = xattrs
+        .iter()
+        .any(|x| x == "system.posix_acl_access" || x == "system.posix_acl_default");
+
+    let (dev_major, dev_minor) = dev_major_minor(md.dev());
+    let (rdev_major, rdev_minor) = dev_major_minor(md.rdev());
+
+    Ok(list(&[
+        QCbirth_time,
+        birth_time,
+        QCblock_size,
+        natnum_or_float(md.blksize()),
+        QCblocks,
+        natnum_or_float(md.blocks()),
+        QCdevice_major,
+        natnum_or_float(dev_major),
+        QCdevice_minor,
+        natnum_or_float(dev_minor),
+        QCrdev_major,
+        natnum_or_float(rdev_major),
+        QCrdev_minor,
+        natnum_or_float(rdev_minor),
+        QCxattrs,
+        list(&xattrs.iter().map(|x| x.to_bstring()).collect::<Vec<_>>()),
+        QCacl,
+        LispObject::from(has_acl),
+    ]))
+}
+
+// A file attribute as a fixnum, or as a float when it is too large, as
+// the C code does for the attributes of `file-attributes'.
+fn natnum_or_float(n: u64) -> LispObject {
+    if n <= MOST_POSITIVE_FIXNUM as u64 {
+        LispObject::from_natnum(n as EmacsUint)
+    } else {
+        LispObject::from_float(n as EmacsDouble)
+    }
+}
+
+// Convert seconds since the epoch to a time_t, which may only be 32 bits.
+fn to_time_t(secs: i64) -> Option<time_t> {
+    let t = secs as time_t;
+    if i64::from(t) == secs {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+// Split a device number the way glibc's major() and minor() do.
+#[cfg(target_os = "linux")]
+fn dev_major_minor(dev: u64) -> (u64, u64) {
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & 0xffff_f000);
+    let minor = (dev & 0xff) | ((dev >> 12) & 0xffff_ff00);
+    (major, minor)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn dev_major_minor(dev: u64) -> (u64, u64) {
+    ((dev >> 24) & 0xff, dev & 0xff_ffff)
+}
+
+// The statx(2) interface, which the libc crate does not provide yet.
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "arm"
+    )
+))]
+mod statx {
+    use libc::{c_char, c_int, c_long, c_uint};
+
+    #[cfg(target_arch = "x86_64")]
+    pub const SYS_STATX: c_long = 332;
+    #[cfg(target_arch = "x86")]
+    pub const SYS_STATX: c_long = 383;
+    #[cfg(target_arch = "aarch64")]
+    pub const SYS_STATX: c_long = 291;
+    #[cfg(target_arch = "arm")]
+    pub const SYS_STATX: c_long = 397;
+
+    pub const STATX_BTIME: c_uint = 0x800;
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Default)]
+    pub struct StatxTimestamp {
+        pub tv_sec: i64,
+        pub tv_nsec: u32,
+        __reserved: i32,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    #[allow(dead_code)]
+    pub struct Statx {
+        pub stx_mask: u32,
+        pub stx_blksize: u32,
+        pub stx_attributes: u64,
+        pub stx_nlink: u32,
+        pub stx_uid: u32,
+        pub stx_gid: u32,
+        pub stx_mode: u16,
+        __spare0: u16,
+        pub stx_ino: u64,
+        pub stx_size: u64,
+        pub stx_blocks: u64,
+        pub stx_attributes_mask: u64,
+        pub stx_atime: StatxTimestamp,
+        pub stx_btime: StatxTimestamp,
+        pub stx_ctime: StatxTimestamp,
+        pub stx_mtime: StatxTimestamp,
+        pub stx_rdev_major: u32,
+        pub stx_rdev_minor: u32,
+        pub stx_dev_major: u32,
+        pub stx_dev_minor: u32,
+        __spare2: [u64; 14],
+    }
+
+    pub unsafe fn statx(
+        dirfd: c_int,
+        path: *const c_char,
+        flags: c_int,
+        mask: c_uint,
+        buf: *mut Statx,
+    ) -> c_int {
+        libc::syscall(SYS_STATX, dirfd, path, flags, mask, buf) as c_int
+    }
+}
+
+// The creation time of the file, if the kernel and filesystem record it.
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "arm"
+    )
+))]
+fn birth_time(c_path: &CStr, _md: &fs::Metadata, follow_symlinks: bool) -> Option<c_timespec> {
+    let mut stx = statx::Statx::default();
+    let flags = if follow_symlinks {
+        0
+    } else {
+        libc::AT_SYMLINK_NOFOLLOW
+    };
+    let res = unsafe {
+        statx::statx(
+            libc::AT_FDCWD,
+            c_path.as_ptr(),
+            flags,
+            statx::STATX_BTIME,
+            &mut stx,
+        )
+    };
+
+    if res != 0 || stx.stx_mask & statx::STATX_BTIME == 0 {
+        None
+    } else {
+        Some(c_timespec {
+            tv_sec: to_time_t(stx.stx_btime.tv_sec)?,
+            tv_nsec: stx.stx_btime.tv_nsec as c_long,
+        })
+    }
+}
+
+#[cfg(not(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "arm"
+    )
+)))]
+fn birth_time(_c_path: &CStr, md: &fs::Metadata, _follow_symlinks: bool) -> Option<c_timespec> {
+    let created = md.created().ok()?;
+    let since_epoch = created.duration_since(UNIX_EPOCH).ok()?;
+    if since_epoch.as_secs() > i64::max_value() as u64 {
+        return None;
+    }
+    Some(c_timespec {
+        tv_sec: to_time_t(since_epoch.as_secs() as i64)?,
+        tv_nsec: since_epoch.subsec_nanos() as c_long,
+    })
+}
+
+#[cfg(target_os = "linux")]
+extern "C" {
+    fn listxattr(path: *const c_char, list: *mut c_char, size: size_t) -> ssize_t;
+    fn llistxattr(path: *const c_char, list: *mut c_char, size: size_t) -> ssize_t;
+}
+
+#[cfg(target_os = "macos")]
+extern "C" {
+    fn listxattr(
+        path: *const c_char,
+        list: *mut c_char,
+        size: size_t,
+        options: libc::c_int,
+    ) -> ssize_t;
+}
+
+#[cfg(target_os = "linux")]
+fn list_xattr_raw(c_path: &CStr, buf: &mut [u8], follow_symlinks: bool) -> ssize_t {
+    let list = if buf.is_empty() {
+        null_mut()
+    } else {
+        buf.as_mut_ptr() as *mut c_char
+    };
+    unsafe {
+        if follow_symlinks {
+            listxattr(c_path.as_ptr(), list, buf.len())
+        } else {
+            llistxattr(c_path.as_ptr(), list, buf.len())
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn list_xattr_raw(c_path: &CStr, buf: &mut [u8], follow_symlinks: bool) -> ssize_t {
+    const XATTR_NOFOLLOW: libc::c_int = 1;
+    let list = if buf.is_empty() {
+        null_mut()
+    } else {
+        buf.as_mut_ptr() as *mut c_char
+    };
+    let options = if follow_symlinks { 0 } else { XATTR_NOFOLLOW };
+    unsafe { listxattr(c_path.as_ptr(), list, buf.len(), options) }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn list_xattr_raw(_c_path: &CStr, _buf: &mut [u8], _follow_symlinks: bool) -> ssize_t {
+    0
+}
+
+// Names of the extended attributes of the file.  Filesystems without
+// extended attribute support simply have none.
+fn xattr_names(c_path: &CStr, follow_symlinks: bool) -> Vec<String> {
+    let mut buf = Vec::new();
+    loop {
+        let size = list_xattr_raw(c_path, &mut [], follow_symlinks);
+        if size <= 0 {
+            return Vec::new();
+        }
+        buf.resize(size as usize, 0);
+
+        // The list may grow between the two calls, in which case we
+        // just try again.
+        let size = list_xattr_raw(c_path, &mut buf, follow_symlinks);
+        if size >= 0 {
+            buf.truncate(size as usize);
+            break;
+        }
+        if io::Error::last_os_error().raw_os_error() != Some(libc::ERANGE) {
+            return Vec::new();
+        }
+    }
+
+    buf.split(|&b| b == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .collect()
+}
+
 fn get_user_real_login_name() -> LispObject {
     unsafe { globals.Vuser_real_login_name }
 }
@@ -754,3 +1482,58 @@ pub fn get_users() -> LispObject {
 
     list(&unames)
 }
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match(b"*.o", b"foo.o"));
+    assert!(!glob_match(b"*.o", b"foo.c"));
+    assert!(!glob_match(b"*.o", b"dir/foo.o"));
+    assert!(glob_match(b"**/foo", b"foo"));
+    assert!(glob_match(b"**/foo", b"a/b/foo"));
+    assert!(glob_match(b"a/**/b", b"a/b"));
+    assert!(glob_match(b"a/**/b", b"a/x/y/b"));
+    assert!(glob_match(b"a/**", b"a/x/y"));
+    assert!(glob_match(b"fo?", b"foo"));
+    assert!(!glob_match(b"fo?", b"fo/"));
+    assert!(glob_match(b"[a-c]at", b"bat"));
+    assert!(!glob_match(b"[!a-c]at", b"bat"));
+    assert!(glob_match(b"[]]", b"]"));
+    assert!(glob_match(b"\\*", b"*"));
+    assert!(!glob_match(b"\\*", b"x"));
+}
+
+#[test]
+fn test_ignore_rules() {
+    let rules = IgnoreRules {
+        base: PathBuf::from("/src"),
+        rules: ["# comment", "", "target/", "*.log", "!keep.log", "/build"]
+            .iter()
+            .filter_map(|l| IgnoreRule::parse(l.as_bytes()))
+            .collect(),
+    };
+    assert_eq!(rules.rules.len(), 4);
+    let ignores = [rules];
+
+    assert!(is_ignored(&ignores, Path::new("/src/target"), true));
+    assert!(!is_ignored(&ignores, Path::new("/src/target"), false));
+    assert!(is_ignored(&ignores, Path::new("/src/a/b/x.log"), false));
+    assert!(!is_ignored(&ignores, Path::new("/src/a/keep.log"), false));
+    assert!(is_ignored(&ignores, Path::new("/src/build"), true));
+    assert!(!is_ignored(&ignores, Path::new("/src/a/build"), true));
+    assert!(!is_ignored(&ignores, Path::new("/other/x.log"), false));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_dev_major_minor() {
+    // As glibc's makedev() packs them.
+    fn makedev(major: u64, minor: u64) -> u64 {
+        (minor & 0xff) | ((major & 0xfff) << 8) | ((minor & !0xff) << 12) | ((major & !0xfff) << 32)
+    }
+    assert_eq!(dev_major_minor(makedev(8, 1)), (8, 1));
+    assert_eq!(dev_major_minor(makedev(0x12345, 0x6789a)), (0x12345, 0x6789a));
+    assert_eq!(
+        dev_major_minor(makedev(0xffff_ffff, 0xffff_ffff)),
+        (0xffff_ffff, 0xffff_ffff)
+    );
+}
@@ -909,7 +909,7 @@ pub fn insert_buffer_substring(
 /// message; let the minibuffer contents show.
 ///
 /// usage: (message-box FORMAT-STRING &rest ARGS)
-#[lisp_fn]
+#[lisp_fn(min = "1")]
 pub fn message_box(args: &mut [LispObject]) -> LispObject {
     unsafe {
         if args[0].is_nil() {
@@ -1032,3 +1032,17 @@ pub fn save_restriction(body: LispObject) -> LispObject {
 }
 
 include!(concat!(env!("OUT_DIR"), "/editfns_exports.rs"));
+
+#[cfg(test)]
+use remacs_macros::lisp_test;
+
+#[lisp_test]
+fn test_insert_char() {
+    // Each Lisp test starts out in an empty temporary buffer.
+    assert_eq!(point_max(), 1);
+    insert_char(Codepoint::from('x'), Some(3), false);
+    assert_eq!(point(), 4);
+    assert_eq!(following_char(), 0);
+    assert_eq!(preceding_char(), EmacsInt::from(b'x'));
+    assert!(crate::strings::string_equal(buffer_string(), LispObject::from("xxx")));
+}
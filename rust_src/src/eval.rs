@@ -1,29 +1,40 @@
 //! Generic Lisp eval functions
 
+use std::mem;
 use std::ptr;
+use std::slice;
+use std::time::{Duration, Instant};
+
+use libc::c_void;
 
 use remacs_macros::lisp_fn;
 
 use crate::{
-    data::{defalias, indirect_function, indirect_function_lisp, set, set_default},
+    alloc::bytes_consed,
+    data::{defalias, default_value, indirect_function, indirect_function_lisp, set, set_default},
     lisp::{defsubr, is_autoload},
-    lisp::{LispObject, LispSubrRef},
-    lists::{assq, car, cdr, get, memq, nth, put, Fcar, Fcdr, LispCons},
+    lisp::{LispObject, LispSubrRef, UNEVALLED},
+    lists::{assq, car, cdr, get, list, memq, nth, put, Fcar, Fcdr, LispCons},
     multibyte::LispStringRef,
-    obarray::loadhist_attach,
+    obarray::{intern, loadhist_attach},
     objects::equal,
     remacs_sys::{
-        backtrace_debug_on_exit, build_string, call_debugger, check_cons_list, do_debug_on_call,
-        eval_sub, find_symbol_value, funcall_lambda, funcall_subr, globals, list2, maybe_gc,
-        maybe_quit, record_in_backtrace, record_unwind_protect, record_unwind_save_match_data,
-        specbind, unbind_to, COMPILEDP, MODULE_FUNCTIONP,
+        backtrace_args, backtrace_debug_on_exit, backtrace_function, backtrace_nargs,
+        buffer_local_value, build_string, call_debugger, check_cons_list, do_debug_on_call,
+        eval_sub, find_symbol_value, funcall_lambda, funcall_subr, globals, internal_catch, list2,
+        maybe_gc, maybe_quit, record_in_backtrace, record_unwind_protect, record_unwind_protect_ptr,
+        record_unwind_save_match_data, specbind, specbind_tag, specbinding, specpdl_entry_kind,
+        specpdl_old_value, specpdl_saved_value, specpdl_symbol, specpdl_unwind_buffer,
+        specpdl_where, unbind_to, COMPILEDP, MODULE_FUNCTIONP,
     },
-    remacs_sys::{pvec_type, EmacsInt, Lisp_Compiled},
-    remacs_sys::{Fapply, Fcons, Fdefault_value, Ffset, Fload, Fpurecopy},
+    remacs_sys::{pvec_type, EmacsDouble, EmacsInt, Lisp_Compiled},
+    remacs_sys::{Fapply, Fcons, Fdefault_value, Ffset, Fload, Fnreverse, Fpurecopy},
+    remacs_sys::{Fsignal, Fthrow},
     remacs_sys::{
-        QCdocumentation, Qautoload, Qclosure, Qerror, Qexit, Qfunction, Qinteractive,
-        Qinteractive_form, Qinternal_interpreter_environment, Qinvalid_function, Qlambda, Qmacro,
-        Qnil, Qrisky_local_variable, Qsetq, Qt, Qunbound, Qvariable_documentation, Qvoid_function,
+        QCargs, QCbindings, QCbuffer, QCdocumentation, QCfunction, QCspecial_form, Qautoload,
+        Qclosure, Qerror, Qeval_limit_exceeded, Qexit, Qfunction, Qinteractive, Qinteractive_form,
+        Qinternal_interpreter_environment, Qinvalid_function, Qlambda, Qmacro, Qnil,
+        Qrisky_local_variable, Qsetq, Qt, Qunbound, Qvariable_documentation, Qvoid_function,
         Qwrong_number_of_arguments,
     },
     remacs_sys::{Vautoload_queue, Vrun_hooks},
@@ -564,6 +575,201 @@ pub fn eval(form: LispObject, lexical: LispObject) -> LispObject {
     unsafe { unbind_to(count, eval_sub(form)) }
 }
 
+/// A limit of `eval-with-limits': it is reached when a counter passes
+/// THRESHOLD.  GIVEN is the limit as given, to report it, and OWNER the
+/// limits of the `eval-with-limits' that set it, which reports it.
+#[derive(Clone, Copy)]
+struct Limit<T, U> {
+    threshold: T,
+    given: U,
+    owner: *const EvalLimits,
+}
+
+/// The limits that a call of `eval-with-limits' enforces, and the
+/// private tag it catches when one of them is reached.
+#[derive(Clone, Copy)]
+struct EvalLimits {
+    steps: Option<Limit<EmacsInt, EmacsInt>>,
+    consing: Option<Limit<EmacsInt, EmacsInt>>,
+    deadline: Option<Limit<Instant, EmacsDouble>>,
+    tag: LispObject,
+}
+
+impl EvalLimits {
+    const NONE: EvalLimits = EvalLimits {
+        steps: None,
+        consing: None,
+        deadline: None,
+        tag: Qnil,
+    };
+
+    /// Tighten the limits of SELF to those of OTHER, where they are
+    /// tighter.
+    fn tighten(&mut self, other: &EvalLimits) {
+        fn tighter<T: PartialOrd, U>(
+            a: Option<Limit<T, U>>,
+            b: Option<Limit<T, U>>,
+        ) -> Option<Limit<T, U>> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(if b.threshold < a.threshold { b } else { a }),
+                (a, None) => a,
+                (None, b) => b,
+            }
+        }
+
+        self.steps = tighter(self.steps, other.steps);
+        self.consing = tighter(self.consing, other.consing);
+        self.deadline = tighter(self.deadline, other.deadline);
+    }
+}
+
+/// Return the limits in effect in the current thread.
+fn current_eval_limits() -> EvalLimits {
+    let limits = ThreadState::current_thread().m_eval_limits as *const EvalLimits;
+    if limits.is_null() {
+        EvalLimits::NONE
+    } else {
+        unsafe { *limits }
+    }
+}
+
+/// Count an evaluation step, and throw to the `eval-with-limits' whose
+/// limit has been reached, if any.  `maybe_quit' calls this while
+/// `eval-with-limits' is in effect.
+///
+/// The throw cannot be caught by the code being evaluated, only its
+/// `unwind-protect' forms run.  Limits are not checked while they do,
+/// so that they can clean up.
+#[no_mangle]
+pub extern "C" fn check_eval_limits() {
+    let mut thread = ThreadState::current_thread();
+    thread.m_eval_steps += 1;
+    let limits = current_eval_limits();
+    if let Some(limit) = limits.steps {
+        if thread.m_eval_steps > limit.threshold {
+            throw_eval_limit(limit.owner, "steps", LispObject::from(limit.given));
+        }
+    }
+    if let Some(limit) = limits.consing {
+        if bytes_consed() > limit.threshold {
+            throw_eval_limit(limit.owner, "consing", LispObject::from(limit.given));
+        }
+    }
+    if let Some(limit) = limits.deadline {
+        if Instant::now() >= limit.threshold {
+            throw_eval_limit(limit.owner, "timeout", LispObject::from(limit.given));
+        }
+    }
+}
+
+fn throw_eval_limit(owner: *const EvalLimits, kind: &str, limit: LispObject) -> ! {
+    let mut thread = ThreadState::current_thread();
+    thread.m_eval_limit_exceeded = owner as *mut c_void;
+    thread.m_eval_limits_active = false;
+    unsafe { Fthrow((*owner).tag, list!(intern(kind), limit)) }
+}
+
+/// Reinstate the limits saved by `eval-with-limits', freeing the ones
+/// it set up.
+extern "C" fn restore_eval_limits(saved: *mut c_void) {
+    let mut thread = ThreadState::current_thread();
+    // The throw to these limits' catch may have been cut short by
+    // another non-local exit from an `unwind-protect' form.
+    if thread.m_eval_limit_exceeded == thread.m_eval_limits {
+        thread.m_eval_limit_exceeded = ptr::null_mut();
+    }
+    drop(unsafe { Box::from_raw(thread.m_eval_limits as *mut EvalLimits) });
+    thread.m_eval_limits = saved;
+    thread.m_eval_limits_active = !saved.is_null() && thread.m_eval_limit_exceeded.is_null();
+}
+
+/// Evaluate the form and lexical environment in ARG, a cons, for
+/// `internal_catch'.
+extern "C" fn eval_limited(arg: LispObject) -> LispObject {
+    let (form, lexical) = arg.as_cons_or_error().as_tuple();
+    eval(form, lexical)
+}
+
+/// Evaluate FORM like `eval', but give up once it takes too long.
+/// STEPS limits the number of evaluation steps.  A step is taken
+/// wherever Emacs checks for a quit: for each form evaluated and
+/// function called, and in loops that might run for a long time.
+/// CONSING limits the number of bytes of Lisp objects consed, and
+/// TIMEOUT the number of seconds that evaluation takes.  A nil limit
+/// does not apply.  LEXICAL is as for `eval'.
+///
+/// When a limit is reached, signal `eval-limit-exceeded' with data
+/// (KIND LIMIT), where KIND is `steps', `consing' or `timeout' and LIMIT
+/// is the limit given.  The signal comes from `eval-with-limits'
+/// itself, once FORM has been abandoned, so FORM cannot catch it: only
+/// its `unwind-protect' forms run, with no limit in effect.
+///
+/// Calls of `eval-with-limits' can be nested; the limits of the outer
+/// calls still apply to the inner ones.
+#[lisp_fn(min = "1")]
+pub fn eval_with_limits(
+    form: LispObject,
+    steps: LispObject,
+    consing: LispObject,
+    timeout: LispObject,
+    lexical: LispObject,
+) -> LispObject {
+    let mut limits = Box::new(EvalLimits::NONE);
+    let owner = &*limits as *const EvalLimits;
+    if steps.is_not_nil() {
+        let steps = steps.as_natnum_or_error() as EmacsInt;
+        let taken = ThreadState::current_thread().m_eval_steps;
+        limits.steps = Some(Limit {
+            threshold: taken.saturating_add(steps),
+            given: steps,
+            owner,
+        });
+    }
+    if consing.is_not_nil() {
+        let consing = consing.as_natnum_or_error() as EmacsInt;
+        limits.consing = Some(Limit {
+            threshold: bytes_consed().saturating_add(consing),
+            given: consing,
+            owner,
+        });
+    }
+    if timeout.is_not_nil() {
+        let seconds = timeout.any_to_float_or_error();
+        if seconds.is_nan() || seconds < 0.0 {
+            args_out_of_range!(timeout, LispObject::from_fixnum(0));
+        }
+        // Treat a timeout too long to represent as no limit at all.
+        if seconds < 1e9 {
+            let duration = Duration::new(seconds as u64, (seconds.fract() * 1e9) as u32);
+            limits.deadline = Some(Limit {
+                threshold: Instant::now() + duration,
+                given: seconds,
+                owner,
+            });
+        }
+    }
+    limits.tighten(&current_eval_limits());
+    // A fresh cons, which no Lisp code can throw to.  The catch keeps it
+    // alive.
+    let tag = unsafe { Fcons(Qeval_limit_exceeded, Qnil) };
+    limits.tag = tag;
+
+    let count = c_specpdl_index();
+    let mut thread = ThreadState::current_thread();
+    unsafe { record_unwind_protect_ptr(Some(restore_eval_limits), thread.m_eval_limits) };
+    thread.m_eval_limits = Box::into_raw(limits) as *mut c_void;
+    thread.m_eval_limits_active = thread.m_eval_limit_exceeded.is_null();
+
+    let value = unsafe { internal_catch(tag, Some(eval_limited), Fcons(form, lexical)) };
+    let exceeded = thread.m_eval_limit_exceeded == owner as *mut c_void;
+    let value = unsafe { unbind_to(count, value) };
+    if exceeded {
+        // VALUE is (KIND LIMIT), as thrown by `throw_eval_limit'.
+        unsafe { Fsignal(Qeval_limit_exceeded, value) };
+    }
+    value
+}
+
 /// Apply fn to arg.
 #[no_mangle]
 pub extern "C" fn apply1(mut func: LispObject, arg: LispObject) -> LispObject {
@@ -862,10 +1068,10 @@ pub fn autoload_do_load(
 /// Do not use `make-local-variable' to make a hook variable buffer-local.
 /// Instead, use `add-hook' and specify t for the LOCAL argument.
 /// usage: (run-hooks &rest HOOKS)
-#[lisp_fn]
-pub fn run_hooks(args: &[LispObject]) -> () {
-    for item in args {
-        run_hook(*item);
+#[lisp_fn(rest = "hooks")]
+pub fn run_hooks(hooks: Vec<LispSymbolRef>) -> () {
+    for hook in hooks {
+        run_hook(hook.into());
     }
 }
 
@@ -878,7 +1084,7 @@ pub fn run_hooks(args: &[LispObject]) -> () {
 /// Do not use `make-local-variable' to make a hook variable buffer-local.
 /// Instead, use `add-hook' and specify t for the LOCAL argument.
 /// usage: (run-hook-with-args HOOK &rest ARGS)
-#[lisp_fn]
+#[lisp_fn(min = "1")]
 pub fn run_hook_with_args(args: &mut [LispObject]) -> LispObject {
     run_hook_with_args_internal(args, funcall_nil)
 }
@@ -1023,7 +1229,7 @@ fn resolve_fun(fun: LispObject) -> Result<LispFun, LispFunError> {
 /// Thus, (funcall \\='cons \\='x \\='y) returns (x . y).
 /// usage: (funcall FUNCTION &rest ARGUMENTS)
 #[allow(unused_assignments)]
-#[lisp_fn]
+#[lisp_fn(min = "1")]
 pub fn funcall(args: &mut [LispObject]) -> LispObject {
     unsafe { maybe_quit() };
 
@@ -1098,4 +1304,147 @@ pub fn funcall(args: &mut [LispObject]) -> LispObject {
     val
 }
 
+/// Return the plist that `backtrace-frames-structured' reports for the
+/// backtrace entry PDL.
+fn backtrace_frame_plist(
+    pdl: *mut specbinding,
+    bindings: LispObject,
+    buffer: LispObject,
+) -> LispObject {
+    let nargs = unsafe { backtrace_nargs(pdl) };
+    let special_form = nargs == UNEVALLED as isize;
+    let args = if special_form {
+        unsafe { *backtrace_args(pdl) }
+    } else if nargs == 0 {
+        Qnil
+    } else {
+        list(unsafe { slice::from_raw_parts(backtrace_args(pdl), nargs as usize) })
+    };
+
+    list!(
+        QCfunction,
+        unsafe { backtrace_function(pdl) },
+        QCargs,
+        args,
+        QCspecial_form,
+        LispObject::from(special_form),
+        QCbindings,
+        bindings,
+        QCbuffer,
+        buffer
+    )
+}
+
+def_lisp_sym!(QCfunction, ":function");
+def_lisp_sym!(QCargs, ":args");
+def_lisp_sym!(QCspecial_form, ":special-form");
+def_lisp_sym!(QCbindings, ":bindings");
+
+/// Return the value of the innermost binding, of kind KIND, of SYMBOL.
+/// WHERE is the buffer of a buffer-local binding.
+fn current_binding_value(
+    kind: specbind_tag::Type,
+    symbol: LispObject,
+    where_: LispObject,
+) -> LispObject {
+    match kind {
+        specbind_tag::SPECPDL_LET_LOCAL => unsafe { buffer_local_value(symbol, where_) },
+        specbind_tag::SPECPDL_LET_DEFAULT => default_value(symbol.as_symbol_or_error()),
+        _ => unsafe { find_symbol_value(symbol) },
+    }
+}
+
+/// Return a description of each active Lisp function call in THREAD,
+/// innermost first.  THREAD defaults to the current thread, and then the
+/// call to this function is left out.  A thread that has exited has no
+/// calls.
+///
+/// Each description is a plist with these properties:
+///  :function      the function called, or the special form evaluated.
+///  :args          the list of argument values, or the list of argument
+///                 forms if :special-form is non-nil.
+///  :special-form  non-nil if the arguments are passed unevaluated, as
+///                 for a special form, or have not been evaluated yet.
+///  :bindings      the dynamic bindings made by the call, as an alist of
+///                 (SYMBOL . VALUE), outermost first.  The binding of a
+///                 void variable appears as just SYMBOL.
+///  :buffer        the buffer that was current when the call was made to
+///                 the next function, or the current buffer of THREAD for
+///                 the innermost call.
+#[lisp_fn(min = "0")]
+pub fn backtrace_frames_structured(thread: LispObject) -> LispObject {
+    let current_thread = ThreadState::current_thread();
+    let thread = if thread.is_nil() {
+        current_thread
+    } else {
+        thread.as_thread_or_error()
+    };
+    if !thread.is_alive() {
+        return Qnil;
+    }
+    let is_current = thread == current_thread;
+
+    let mut frames = Qnil;
+    let mut skip_frame = is_current;
+    let mut buffer: LispObject = thread.current_buffer().into();
+    let mut frame_buffer = buffer;
+    let mut bindings = Qnil;
+    // In the current thread, each variable bound so far, as (SYMBOL,
+    // WHERE, OLD-VALUE); the value of a binding shadowed by one of these
+    // is its OLD-VALUE.
+    let mut shadowing: Vec<(LispObject, LispObject, LispObject)> = Vec::new();
+
+    for i in (0..thread.specpdl_depth()).rev() {
+        let pdl = unsafe { thread.m_specpdl.add(i) };
+        let kind = unsafe { specpdl_entry_kind(pdl) };
+        if kind == specbind_tag::SPECPDL_BACKTRACE {
+            if skip_frame {
+                skip_frame = false;
+            } else {
+                let frame = backtrace_frame_plist(pdl, bindings, frame_buffer);
+                frames = LispObject::cons(frame, frames);
+            }
+            bindings = Qnil;
+            frame_buffer = buffer;
+        } else if kind == specbind_tag::SPECPDL_UNWIND {
+            let restored = unsafe { specpdl_unwind_buffer(pdl) };
+            if restored.is_not_nil() {
+                buffer = restored;
+            }
+        } else if kind >= specbind_tag::SPECPDL_LET {
+            let symbol = unsafe { specpdl_symbol(pdl) };
+            let where_ = if kind == specbind_tag::SPECPDL_LET_LOCAL {
+                unsafe { specpdl_where(pdl) }
+            } else {
+                Qnil
+            };
+            let old_value = unsafe { specpdl_old_value(pdl) };
+            let value = if !is_current {
+                // The bindings of a thread that is not running have been
+                // undone, and the value of each one saved in its entry.
+                unsafe { specpdl_saved_value(pdl) }
+            } else {
+                match shadowing
+                    .iter_mut()
+                    .find(|entry| entry.0.eq(symbol) && entry.1.eq(where_))
+                {
+                    Some(entry) => mem::replace(&mut entry.2, old_value),
+                    None => {
+                        shadowing.push((symbol, where_, old_value));
+                        current_binding_value(kind, symbol, where_)
+                    }
+                }
+            };
+            let entry = if value.eq(Qunbound) {
+                symbol
+            } else {
+                LispObject::cons(symbol, value)
+            };
+            bindings = LispObject::cons(entry, bindings);
+        }
+    }
+
+    unsafe { Fnreverse(frames) }
+}
+
 include!(concat!(env!("OUT_DIR"), "/eval_exports.rs"));
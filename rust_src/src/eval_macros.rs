@@ -175,14 +175,14 @@ macro_rules! defvar_lisp_nopro {
         #[allow(unused_unsafe)]
         unsafe {
             #[allow(const_err)]
-            static mut o_fwd: ::hacks::Hack<::data::Lisp_Objfwd> =
-                unsafe { ::hacks::Hack::uninitialized() };
-            ::remacs_sys::defvar_lisp_nopro(
+            static mut o_fwd: crate::hacks::Hack<crate::data::Lisp_Objfwd> =
+                unsafe { crate::hacks::Hack::uninitialized() };
+            crate::remacs_sys::defvar_lisp_nopro(
                 o_fwd.get_mut(),
                 concat!($lisp_name, "\0").as_ptr() as *const i8,
-                &mut ::remacs_sys::globals.$field_name,
+                &mut crate::remacs_sys::globals.$field_name,
             );
-            ::remacs_sys::globals.$field_name = $value;
+            crate::remacs_sys::globals.$field_name = $value;
         }
     }};
 }
@@ -192,14 +192,14 @@ macro_rules! defvar_bool {
         #[allow(unused_unsafe)]
         unsafe {
             #[allow(const_err)]
-            static mut o_fwd: ::hacks::Hack<::data::Lisp_Boolfwd> =
-                unsafe { ::hacks::Hack::uninitialized() };
-            ::remacs_sys::defvar_bool(
+            static mut o_fwd: crate::hacks::Hack<crate::data::Lisp_Boolfwd> =
+                unsafe { crate::hacks::Hack::uninitialized() };
+            crate::remacs_sys::defvar_bool(
                 o_fwd.get_mut(),
                 concat!($lisp_name, "\0").as_ptr() as *const i8,
-                &mut ::remacs_sys::globals.$field_name,
+                &mut crate::remacs_sys::globals.$field_name,
             );
-            ::remacs_sys::globals.$field_name = $value;
+            crate::remacs_sys::globals.$field_name = $value;
         }
     }};
 }
@@ -209,14 +209,14 @@ macro_rules! defvar_int {
         #[allow(unused_unsafe)]
         unsafe {
             #[allow(const_err)]
-            static mut o_fwd: ::hacks::Hack<::data::Lisp_Intfwd> =
-                unsafe { ::hacks::Hack::uninitialized() };
-            ::remacs_sys::defvar_int(
+            static mut o_fwd: crate::hacks::Hack<crate::data::Lisp_Intfwd> =
+                unsafe { crate::hacks::Hack::uninitialized() };
+            crate::remacs_sys::defvar_int(
                 o_fwd.get_mut(),
                 concat!($lisp_name, "\0").as_ptr() as *const i8,
-                &mut ::remacs_sys::globals.$field_name,
+                &mut crate::remacs_sys::globals.$field_name,
             );
-            ::remacs_sys::globals.$field_name = $value;
+            crate::remacs_sys::globals.$field_name = $value;
         }
     }};
 }
@@ -1,13 +1,21 @@
 //! Functions to deal with files
+use std::ffi::CString;
 use std::path;
 
+use remacs_lib::write_file_atomically;
 use remacs_macros::lisp_fn;
 
 use crate::{
-    lisp::defsubr,
+    buffers::validate_region,
+    lisp::{defsubr, LispObject},
     lists::LispCons,
     math::{arithcompare, ArithComparison},
     multibyte::LispStringRef,
+    remacs_sys::{
+        encode_file_name, globals, make_buffer_string, report_file_errno, Fencode_coding_string,
+        Fexpand_file_name, Ffind_file_name_handler,
+    },
+    remacs_sys::{Qnil, Qt, Qwrite_region_atomically},
     threads::ThreadState,
 };
 
@@ -49,4 +57,102 @@ pub fn recent_auto_save_p() -> bool {
     cur_buf.modifications_since_save() < cur_buf.auto_save_modified
 }
 
+/// Write current region into FILENAME, replacing the file atomically.
+/// The text is written to a temporary file in the same directory, which
+/// is flushed to disk and then renamed to FILENAME, so that a crash
+/// leaves either the old or the new contents of FILENAME, never a mix.
+/// An existing FILENAME keeps its file modes and, where permitted, its
+/// owner and group.
+///
+/// When called from a program, requires three arguments:
+/// START, END and FILENAME.  START and END are normally buffer positions
+/// specifying the part of the buffer to write.
+/// If START is nil, that means to use the entire buffer contents; END is
+/// ignored.
+/// If START is a string, then output that string to the file
+/// instead of any buffer contents; END is ignored.
+///
+/// Optional fourth argument CODING-SYSTEM specifies how to encode the
+/// text.  It defaults to `coding-system-for-write' if that is non-nil,
+/// and to `buffer-file-coding-system' otherwise.
+///
+/// Unlike `write-region', this does not visit the file, and does not
+/// append to it or lock it.
+#[lisp_fn(min = "3")]
+pub fn write_region_atomically(
+    start: LispObject,
+    end: LispObject,
+    filename: LispObject,
+    coding_system: LispObject,
+) {
+    let filename = unsafe { Fexpand_file_name(filename, Qnil) };
+
+    // If the file name has special constructs in it,
+    // call the corresponding file handler.
+    let handler = unsafe { Ffind_file_name_handler(filename, Qwrite_region_atomically) };
+    if handler.is_not_nil() {
+        call!(
+            handler,
+            Qwrite_region_atomically,
+            start,
+            end,
+            filename,
+            coding_system
+        );
+        return;
+    }
+
+    let cur_buf = ThreadState::current_buffer();
+    let text = if start.is_string() {
+        start
+    } else if start.is_nil() {
+        unsafe { make_buffer_string(cur_buf.beg(), cur_buf.z(), false) }
+    } else {
+        let (mut start, mut end) = (start, end);
+        unsafe {
+            validate_region(&mut start, &mut end);
+            make_buffer_string(
+                start.as_fixnum_or_error() as isize,
+                end.as_fixnum_or_error() as isize,
+                false,
+            )
+        }
+    };
+
+    let coding_system = if coding_system.is_not_nil() {
+        coding_system
+    } else if unsafe { globals.Vcoding_system_for_write }.is_not_nil() {
+        unsafe { globals.Vcoding_system_for_write }
+    } else {
+        cur_buf.buffer_file_coding_system_
+    };
+    let encoded = unsafe { Fencode_coding_string(text, coding_system, Qt, Qnil) };
+    let encoded_file = unsafe { encode_file_name(filename) }.as_string_or_error();
+
+    let path = encoded_file_path(encoded_file.as_slice());
+    if let Err(err) = write_file_atomically(&path, encoded.as_string_or_error().as_slice()) {
+        let message = CString::new("Writing atomically").unwrap();
+        unsafe {
+            report_file_errno(
+                message.as_ptr(),
+                filename,
+                err.raw_os_error().unwrap_or(libc::EIO),
+            )
+        };
+    }
+}
+
+#[cfg(unix)]
+fn encoded_file_path(name: &[u8]) -> path::PathBuf {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+    path::PathBuf::from(OsStr::from_bytes(name))
+}
+
+#[cfg(windows)]
+fn encoded_file_path(name: &[u8]) -> path::PathBuf {
+    path::PathBuf::from(String::from_utf8_lossy(name).into_owned())
+}
+
+def_lisp_sym!(Qwrite_region_atomically, "write-region-atomically");
+
 include!(concat!(env!("OUT_DIR"), "/fileio_exports.rs"));
@@ -1,3 +1,4 @@
+#[cfg(not(feature = "lisp-tests"))]
 use crate::remacs_sys::*;
 /// This module is loaded only in #[cfg(test)].
 /// It contains the definitions of C functions to be mocked in our tests
@@ -9,12 +10,16 @@ use crate::remacs_sys::*;
 /// Adding a function to this block is harmless.
 /// This module is only for testing, and you should add all
 /// definitions to remacs-sys first and foremost.
+#[cfg(not(feature = "lisp-tests"))]
 use libc::*;
 
+#[cfg(not(feature = "lisp-tests"))]
 use crate::{lisp::LispObject, remacs_sys::*};
 
 // The linker needs the symbol "lispsym" to exist, since certain
-// codepaths lead to it's usage.
+// codepaths lead to it's usage.  With the lisp-tests feature, this and
+// the mocks below come from the real C code instead.
+#[cfg(not(feature = "lisp-tests"))]
 #[no_mangle]
 pub static mut lispsym: EmacsInt = 0;
 
@@ -99,7 +104,7 @@ macro_rules! assert_nil {
 }
 
 // Note(db48x): see if we can go back to using mock-derive for these
-#[cfg(test)]
+#[cfg(all(test, not(feature = "lisp-tests")))]
 #[allow(unused_variables)]
 #[allow(dead_code)]
 #[no_mangle]
@@ -107,7 +112,7 @@ pub extern "C" fn Fcons(car: LispObject, cdr: LispObject) -> LispObject {
     return Qnil;
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(feature = "lisp-tests")))]
 #[allow(unused_variables)]
 #[allow(dead_code)]
 #[no_mangle]
@@ -115,7 +120,7 @@ pub extern "C" fn Fsignal(error_symbol: LispObject, data: LispObject) -> ! {
     panic!("Fsignal called during tests");
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(feature = "lisp-tests")))]
 #[allow(unused_variables)]
 #[allow(dead_code)]
 #[no_mangle]
@@ -123,7 +128,7 @@ pub extern "C" fn make_string(s: *const c_char, length: isize) -> LispObject {
     mock_multibyte_string!()
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(feature = "lisp-tests")))]
 #[allow(unused_variables)]
 #[allow(dead_code)]
 #[no_mangle]
@@ -0,0 +1,1323 @@
+//! File notification support, using inotify where available and
+//! falling back to polling.
+//!
+//! Watches are kept in `watch_list`, just like the C implementation
+//! did, so that `file-notify' sees the same descriptors and events
+//! whichever backend serves a watch.  Kernel watch descriptors are
+//! nonnegative; polled watches get negative descriptors.
+
+use std::collections::HashMap;
+use std::ffi::{CStr, CString, OsStr};
+use std::fs;
+use std::mem;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use libc::{c_char, c_int, c_void};
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    lisp::{defsubr, LispObject},
+    lists::nth,
+    remacs_sys::{
+        add_read_fd, decode_file_name, encode_file_name, globals,
+        kbd_buffer_store_file_notify_event, make_unibyte_string, EmacsInt, EmacsUint, Fsignal,
+    },
+    remacs_sys::{
+        Qaccess, Qall_events, Qattrib, Qclose, Qclose_nowrite, Qclose_write, Qcreate, Qdelete,
+        Qdelete_self, Qdont_follow, Qfile_notify_error, Qignored, Qisdir, Qmodify, Qmove,
+        Qmove_self, Qmoved_from, Qmoved_to, Qnil, Qonlydir, Qopen, Qq_overflow, Qrecursive, Qt,
+        Qunmount,
+    },
+};
+
+// The inotify(7) event bits.  Polled watches report their changes with
+// the same bits, so that the rest of this file need not care which
+// backend an event came from.
+const IN_ACCESS: u32 = 0x0000_0001;
+const IN_MODIFY: u32 = 0x0000_0002;
+const IN_ATTRIB: u32 = 0x0000_0004;
+const IN_CLOSE_WRITE: u32 = 0x0000_0008;
+const IN_CLOSE_NOWRITE: u32 = 0x0000_0010;
+const IN_OPEN: u32 = 0x0000_0020;
+const IN_MOVED_FROM: u32 = 0x0000_0040;
+const IN_MOVED_TO: u32 = 0x0000_0080;
+const IN_CREATE: u32 = 0x0000_0100;
+const IN_DELETE: u32 = 0x0000_0200;
+const IN_DELETE_SELF: u32 = 0x0000_0400;
+const IN_MOVE_SELF: u32 = 0x0000_0800;
+const IN_UNMOUNT: u32 = 0x0000_2000;
+const IN_Q_OVERFLOW: u32 = 0x0000_4000;
+const IN_IGNORED: u32 = 0x0000_8000;
+const IN_ONLYDIR: u32 = 0x0100_0000;
+const IN_DONT_FOLLOW: u32 = 0x0200_0000;
+const IN_EXCL_UNLINK: u32 = 0x0400_0000;
+const IN_MASK_ADD: u32 = 0x2000_0000;
+const IN_ISDIR: u32 = 0x4000_0000;
+
+const IN_CLOSE: u32 = IN_CLOSE_WRITE | IN_CLOSE_NOWRITE;
+const IN_MOVE: u32 = IN_MOVED_FROM | IN_MOVED_TO;
+const IN_ALL_EVENTS: u32 = 0x0000_0fff;
+
+// Events that are merged with an identical event for the same file
+// that was read just before it.
+const IN_COALESCE: u32 = IN_ACCESS | IN_MODIFY | IN_ATTRIB;
+
+// Whether syms_of_inotify has been called, i.e. Emacs was configured
+// with inotify or with polling and can deliver our events.
+static AVAILABLE: AtomicBool = AtomicBool::new(false);
+
+// Alist of files being watched.  We want the returned descriptor to
+// be unique for every watch, but inotify returns the same descriptor
+// WD for multiple calls to inotify_add_watch with the same file.
+// Supply a nonnegative integer ID, so that WD and ID together
+// uniquely identify a watch/file combination.
+//
+// Each element of this list is of the form (DESCRIPTOR . WATCHES)
+// where no two DESCRIPTOR values are the same.  DESCRIPTOR represents
+// the watch descriptor and WATCHES is a list with elements of the form
+// (ID FILENAME CALLBACK MASK RECURSIVE), where ID is the integer
+// described above, FILENAME names the file being watched, CALLBACK is
+// invoked when the event occurs, MASK represents the aspects being
+// watched and RECURSIVE is non-nil if subdirectories are watched too.
+// The WATCHES list is sorted by ID.
+declare_GC_protected_static!(watch_list, Qnil);
+
+/// A file system event, as read from inotify or found by polling.
+struct RawEvent {
+    wd: c_int,
+    mask: u32,
+    cookie: u32,
+    name: Option<Vec<u8>>,
+}
+
+/// Signal a `file-notify-error' for the system error ERRNO.
+fn report_file_notify_error(msg: &str, errno: c_int, name: LispObject) -> ! {
+    let errstring = unsafe { CStr::from_ptr(libc::strerror(errno)) }.to_string_lossy();
+    let data = if name.is_cons() || name.is_nil() {
+        name
+    } else {
+        list!(name)
+    };
+
+    unsafe {
+        Fsignal(
+            Qfile_notify_error,
+            LispObject::cons(
+                LispObject::from(msg),
+                LispObject::cons(LispObject::from(errstring.as_ref()), data),
+            ),
+        )
+    }
+}
+
+fn last_errno() -> c_int {
+    std::io::Error::last_os_error().raw_os_error().unwrap_or(0)
+}
+
+fn mask_to_aspects(mask: u32) -> LispObject {
+    let table = [
+        (IN_ACCESS, Qaccess),
+        (IN_ATTRIB, Qattrib),
+        (IN_CLOSE_WRITE, Qclose_write),
+        (IN_CLOSE_NOWRITE, Qclose_nowrite),
+        (IN_CREATE, Qcreate),
+        (IN_DELETE, Qdelete),
+        (IN_DELETE_SELF, Qdelete_self),
+        (IN_MODIFY, Qmodify),
+        (IN_MOVE_SELF, Qmove_self),
+        (IN_MOVED_FROM, Qmoved_from),
+        (IN_MOVED_TO, Qmoved_to),
+        (IN_OPEN, Qopen),
+        (IN_IGNORED, Qignored),
+        (IN_ISDIR, Qisdir),
+        (IN_Q_OVERFLOW, Qq_overflow),
+        (IN_UNMOUNT, Qunmount),
+    ];
+
+    table
+        .iter()
+        .filter(|&&(bit, _)| mask & bit != 0)
+        .fold(Qnil, |aspects, &(_, sym)| LispObject::cons(sym, aspects))
+}
+
+fn symbol_to_inotifymask(symb: LispObject) -> u32 {
+    let table = [
+        (Qaccess, IN_ACCESS),
+        (Qattrib, IN_ATTRIB),
+        (Qclose_write, IN_CLOSE_WRITE),
+        (Qclose_nowrite, IN_CLOSE_NOWRITE),
+        (Qcreate, IN_CREATE),
+        (Qdelete, IN_DELETE),
+        (Qdelete_self, IN_DELETE_SELF),
+        (Qmodify, IN_MODIFY),
+        (Qmove_self, IN_MOVE_SELF),
+        (Qmoved_from, IN_MOVED_FROM),
+        (Qmoved_to, IN_MOVED_TO),
+        (Qopen, IN_OPEN),
+        (Qmove, IN_MOVE),
+        (Qclose, IN_CLOSE),
+        (Qdont_follow, IN_DONT_FOLLOW),
+        (Qonlydir, IN_ONLYDIR),
+        (Qt, IN_ALL_EVENTS),
+        (Qall_events, IN_ALL_EVENTS),
+        // Handled by aspect_to_inotifymask.
+        (Qrecursive, 0),
+    ];
+
+    match table.iter().find(|&&(sym, _)| symb.eq(sym)) {
+        Some(&(_, mask)) => mask,
+        None => report_file_notify_error("Unknown aspect", libc::EINVAL, symb),
+    }
+}
+
+/// Return the inotify mask for ASPECT, and whether it asks for a
+/// recursive watch.
+fn aspect_to_inotifymask(aspect: LispObject) -> (u32, bool) {
+    if aspect.is_cons() || aspect.is_nil() {
+        let mask = aspect
+            .iter_cars()
+            .fold(0, |mask, sym| mask | symbol_to_inotifymask(sym));
+        (mask, memq_aspect(Qrecursive, aspect))
+    } else {
+        (symbol_to_inotifymask(aspect), aspect.eq(Qrecursive))
+    }
+}
+
+fn memq_aspect(sym: LispObject, aspect: LispObject) -> bool {
+    aspect.iter_cars_safe().any(|elt| elt.eq(sym))
+}
+
+fn watch_mask(watch: LispObject) -> u32 {
+    nth(3, watch).as_natnum_or_error() as u32
+}
+
+fn watch_recursive(watch: LispObject) -> bool {
+    nth(4, watch).is_not_nil()
+}
+
+fn inotifyevent_to_event(
+    watch: LispObject,
+    descriptor: LispObject,
+    ev: &RawEvent,
+    name: Option<&[u8]>,
+) -> LispObject {
+    if watch_mask(watch) & ev.mask == 0 {
+        return Qnil;
+    }
+
+    let name = match name {
+        Some(name) => unsafe {
+            decode_file_name(make_unibyte_string(
+                name.as_ptr() as *const c_char,
+                name.len() as isize,
+            ))
+        },
+        None => nth(1, watch),
+    };
+
+    list!(
+        list!(
+            LispObject::cons(descriptor, nth(0, watch)),
+            mask_to_aspects(ev.mask),
+            name,
+            LispObject::from_natnum(EmacsUint::from(ev.cookie))
+        ),
+        nth(2, watch)
+    )
+}
+
+/// Add a new watch to watch-descriptor WD watching FILENAME and using
+/// IMASK and CALLBACK.  Return a cons (DESCRIPTOR . ID) uniquely
+/// identifying the new watch.
+fn add_watch(
+    wd: c_int,
+    filename: LispObject,
+    imask: u32,
+    callback: LispObject,
+    recursive: bool,
+) -> LispObject {
+    let descriptor = LispObject::from(EmacsInt::from(wd));
+    let mut tail = match assq_descriptor(descriptor) {
+        Some(tail) => tail,
+        None => {
+            let tail = list!(descriptor);
+            unsafe { watch_list = LispObject::cons(tail, watch_list) };
+            tail
+        }
+    };
+
+    // Assign a watch ID that is not already in use, by looking for a
+    // gap in the existing sorted list.
+    let mut id: EmacsInt = 0;
+    while let Some(next) = tail.as_cons_or_error().cdr().as_cons() {
+        if !next.car().as_cons_or_error().car().eq(LispObject::from(id)) {
+            break;
+        }
+        tail = next.as_obj();
+        id += 1;
+    }
+
+    // Insert the newly-assigned ID into the previously-discovered gap,
+    // which is possibly at the end of the list.  Inserting it there
+    // keeps the list sorted.
+    let watch_id = LispObject::from(id);
+    let watch = list!(
+        watch_id,
+        filename,
+        callback,
+        LispObject::from_natnum(EmacsUint::from(imask)),
+        LispObject::from(recursive)
+    );
+    let cell = tail.as_cons_or_error();
+    cell.set_cdr(LispObject::cons(watch, cell.cdr()));
+
+    LispObject::cons(descriptor, watch_id)
+}
+
+/// Return the watch list element for DESCRIPTOR, if any.
+fn assq_descriptor(descriptor: LispObject) -> Option<LispObject> {
+    unsafe { watch_list }
+        .iter_cars()
+        .find(|elt| elt.as_cons_or_error().car().eq(descriptor))
+}
+
+/// Find the watch list element (if any) matching DESCRIPTOR.  Return
+/// nil if not found.  If found, return t if the first element matches
+/// DESCRIPTOR; otherwise, return the cons whose cdr matches
+/// DESCRIPTOR.  This lets the caller easily remove the element
+/// matching DESCRIPTOR without having to search for it again.
+fn find_descriptor(descriptor: LispObject) -> LispObject {
+    let mut prevtail = Qt;
+    for tail in unsafe { watch_list }.iter_tails() {
+        if tail.car().as_cons_or_error().car().eq(descriptor) {
+            return prevtail;
+        }
+        prevtail = tail.as_obj();
+    }
+    Qnil
+}
+
+fn descriptor_tail(prevtail: LispObject) -> LispObject {
+    match prevtail.as_cons() {
+        Some(prev) => prev.cdr(),
+        None => unsafe { watch_list },
+    }
+}
+
+/// Remove all watches associated with the watch list element after
+/// PREVTAIL, or after the first element if PREVTAIL is t.  If
+/// INVALID_P is true, the descriptor is already invalid, i.e., it
+/// received an IN_IGNORED event.  In this case skip removing it from
+/// the backend.
+fn remove_descriptor(prevtail: LispObject, invalid_p: bool) {
+    let tail = descriptor_tail(prevtail).as_cons_or_error();
+    let descriptor = tail.car().as_cons_or_error().car();
+    let wd = descriptor.as_fixnum_or_error() as c_int;
+
+    if prevtail.is_cons() {
+        prevtail.as_cons_or_error().set_cdr(tail.cdr());
+    } else {
+        unsafe { watch_list = tail.cdr() };
+    }
+    drop_recursion(wd);
+
+    let errno = if invalid_p {
+        0
+    } else if wd < 0 {
+        poll::remove(wd);
+        0
+    } else {
+        kernel::release(wd)
+    };
+    if unsafe { watch_list }.is_nil() {
+        kernel::close();
+    }
+
+    if errno != 0 {
+        report_file_notify_error("Could not rm watch", errno, descriptor);
+    }
+}
+
+/// Remove watch associated with (DESCRIPTOR . ID).
+fn remove_watch(descriptor: LispObject, id: LispObject) {
+    let prevtail = find_descriptor(descriptor);
+    if prevtail.is_nil() {
+        return;
+    }
+
+    let elt = descriptor_tail(prevtail).as_cons_or_error().car();
+    let mut prev = elt.as_cons_or_error();
+    while let Some(next) = prev.cdr().as_cons() {
+        if id.eq(next.car().as_cons_or_error().car()) {
+            prev.set_cdr(next.cdr());
+            if elt.as_cons_or_error().cdr().is_nil() {
+                remove_descriptor(prevtail, false);
+            } else if !elt.as_cons_or_error().cdr().iter_cars().any(watch_recursive) {
+                drop_recursion(descriptor.as_fixnum_or_error() as c_int);
+            }
+            break;
+        }
+        prev = next;
+    }
+}
+
+/// Merge runs of identical events for the same file.  A burst of
+/// writes to one file would otherwise flood the event queue with
+/// `modify' events that all have the same effect.
+fn coalesce(events: Vec<RawEvent>) -> Vec<RawEvent> {
+    let mut last: HashMap<(c_int, Option<Vec<u8>>), u32> = HashMap::new();
+    let mut result = Vec::with_capacity(events.len());
+
+    for ev in events {
+        let key = (ev.wd, ev.name.clone());
+        if ev.mask & !(IN_COALESCE | IN_ISDIR) == 0
+            && ev.cookie == 0
+            && last.get(&key) == Some(&ev.mask)
+        {
+            continue;
+        }
+        last.insert(key, ev.mask);
+        result.push(ev);
+    }
+
+    result
+}
+
+/// Queue an event for every watch on descriptor WD that wants EV.
+/// If RECURSIVE_ONLY, EV happened in a subdirectory and only recursive
+/// watches are interested; NAME is then relative to the watched
+/// directory.
+fn deliver(wd: c_int, ev: &RawEvent, name: Option<&[u8]>, recursive_only: bool) {
+    let descriptor = LispObject::from(EmacsInt::from(wd));
+    let elt = match assq_descriptor(descriptor) {
+        Some(elt) => elt,
+        None => return,
+    };
+
+    for watch in elt.as_cons_or_error().cdr().iter_cars() {
+        if recursive_only && !watch_recursive(watch) {
+            continue;
+        }
+        let event = inotifyevent_to_event(watch, descriptor, ev, name);
+        if event.is_not_nil() {
+            unsafe { kbd_buffer_store_file_notify_event(event) };
+        }
+    }
+}
+
+fn handle_events(events: Vec<RawEvent>) {
+    for ev in coalesce(events) {
+        deliver(ev.wd, &ev, ev.name.as_ref().map(Vec::as_slice), false);
+
+        if ev.wd >= 0 {
+            for sub in recursion_subwatches(ev.wd) {
+                let mut name = sub.rel.as_os_str().as_bytes().to_vec();
+                if let Some(ref evname) = ev.name {
+                    name.push(b'/');
+                    name.extend_from_slice(evname);
+                }
+                deliver(sub.root, &ev, Some(&name), true);
+            }
+
+            if ev.mask & IN_ISDIR != 0 && ev.mask & (IN_CREATE | IN_MOVED_TO) != 0 {
+                if let Some(ref name) = ev.name {
+                    watch_new_subdirectory(ev.wd, name);
+                }
+            }
+        }
+
+        // If the watch was removed automatically, drop it from the
+        // watch list.
+        if ev.mask & IN_IGNORED != 0 {
+            forget_subwatch(ev.wd);
+            let prevtail = find_descriptor(LispObject::from(EmacsInt::from(ev.wd)));
+            if prevtail.is_not_nil() {
+                remove_descriptor(prevtail, true);
+            }
+        }
+    }
+}
+
+// Recursive kernel watches.  Inotify only reports events for the
+// direct children of a directory, so a recursive watch adds a kernel
+// watch for every subdirectory, and events for those are forwarded to
+// the recursive watches of the root.
+
+#[derive(Clone)]
+struct SubWatch {
+    root: c_int,  // descriptor of the recursive watch
+    rel: PathBuf, // subdirectory relative to the root
+}
+
+#[derive(Default)]
+struct Recursion {
+    roots: HashMap<c_int, (PathBuf, u32)>, // root directory and its kernel mask
+    subs: HashMap<c_int, Vec<SubWatch>>,
+}
+
+lazy_static! {
+    static ref RECURSION: Mutex<Recursion> = Mutex::new(Recursion::default());
+}
+
+fn recursion_subwatches(wd: c_int) -> Vec<SubWatch> {
+    let recursion = RECURSION.lock().unwrap();
+    recursion.subs.get(&wd).cloned().unwrap_or_default()
+}
+
+fn forget_subwatch(wd: c_int) {
+    RECURSION.lock().unwrap().subs.remove(&wd);
+}
+
+/// Watch DIR, found at REL below the recursive watch ROOT, and all
+/// directories below it.
+fn watch_subtree(root: c_int, dir: &Path, rel: &Path, mask: u32) -> Result<(), c_int> {
+    let c_dir = match CString::new(dir.as_os_str().as_bytes()) {
+        Ok(c_dir) => c_dir,
+        Err(_) => return Ok(()),
+    };
+    // Other recursive watches may share this directory, so add to its
+    // mask rather than replacing it.
+    let wd = kernel::add(&c_dir, mask | IN_MASK_ADD | IN_ONLYDIR | IN_DONT_FOLLOW)?;
+    if wd == root {
+        // A bind mount or similar has led us back to the root.
+        return Ok(());
+    }
+
+    {
+        let mut recursion = RECURSION.lock().unwrap();
+        let subs = recursion.subs.entry(wd).or_insert_with(Vec::new);
+        if subs.iter().any(|sub| sub.root == root) {
+            // Already watched, e.g. because it was moved within the tree.
+            return Ok(());
+        }
+        subs.push(SubWatch {
+            root,
+            rel: rel.to_path_buf(),
+        });
+    }
+
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        if entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
+            let name = entry.file_name();
+            watch_subtree(root, &dir.join(&name), &rel.join(&name), mask)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Start watching the subdirectories of the recursive watch WD, whose
+/// directory is DIR.  Fails with ENOSPC when the kernel has run out of
+/// watches.
+fn start_recursion(wd: c_int, dir: &Path, imask: u32) -> Result<(), c_int> {
+    let mask = imask | IN_CREATE | IN_MOVED_TO;
+    {
+        let mut recursion = RECURSION.lock().unwrap();
+        let entry = recursion
+            .roots
+            .entry(wd)
+            .or_insert_with(|| (dir.to_path_buf(), 0));
+        entry.1 |= mask;
+    }
+
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        if entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
+            let name = entry.file_name();
+            watch_subtree(wd, &dir.join(&name), Path::new(&name), mask)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A directory NAME appeared in the directory watched by WD; watch it
+/// for every recursive watch it belongs to.
+fn watch_new_subdirectory(wd: c_int, name: &[u8]) {
+    let name = Path::new(OsStr::from_bytes(name));
+    let targets: Vec<(c_int, PathBuf, PathBuf, u32)> = {
+        let recursion = RECURSION.lock().unwrap();
+        let parents = recursion
+            .roots
+            .get(&wd)
+            .map(|_| (wd, PathBuf::new()))
+            .into_iter()
+            .chain(
+                recursion
+                    .subs
+                    .get(&wd)
+                    .into_iter()
+                    .flatten()
+                    .map(|sub| (sub.root, sub.rel.clone())),
+            );
+        parents
+            .filter_map(|(root, rel)| {
+                recursion.roots.get(&root).map(|&(ref dir, mask)| {
+                    let rel = rel.join(name);
+                    (root, dir.join(&rel), rel, mask)
+                })
+            })
+            .collect()
+    };
+
+    for (root, dir, rel, mask) in targets {
+        // If the kernel runs out of watches we cannot do better than
+        // miss events in the new directory; the existing watches keep
+        // working.
+        let _ = watch_subtree(root, &dir, &rel, mask);
+    }
+}
+
+/// Stop watching the subdirectories of the recursive watch WD.
+fn drop_recursion(wd: c_int) {
+    let unused: Vec<c_int> = {
+        let mut recursion = RECURSION.lock().unwrap();
+        if recursion.roots.remove(&wd).is_none() {
+            return;
+        }
+        let mut unused = Vec::new();
+        for (&sub_wd, subs) in recursion.subs.iter_mut() {
+            subs.retain(|sub| sub.root != wd);
+            if subs.is_empty() {
+                unused.push(sub_wd);
+            }
+        }
+        for sub_wd in &unused {
+            recursion.subs.remove(sub_wd);
+        }
+        unused
+    };
+
+    for sub_wd in unused {
+        if assq_descriptor(LispObject::from(EmacsInt::from(sub_wd))).is_none() {
+            kernel::rm(sub_wd);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod kernel {
+    use std::ffi::CStr;
+
+    use libc::{c_char, c_int, c_void};
+
+    use super::{handle_events, last_errno, report_file_notify_error, RawEvent, RECURSION};
+    use crate::remacs_sys::{add_read_fd, delete_read_fd, emacs_close, Qnil};
+
+    extern "C" {
+        fn inotify_init1(flags: c_int) -> c_int;
+        fn inotify_add_watch(fd: c_int, pathname: *const c_char, mask: u32) -> c_int;
+        fn inotify_rm_watch(fd: c_int, wd: c_int) -> c_int;
+    }
+
+    // File handle for inotify.  Only touched from the main thread.
+    static mut INOTIFYFD: c_int = -1;
+
+    /// Add a kernel watch for PATH, returning its descriptor or the
+    /// error number.
+    pub fn add(path: &CStr, mask: u32) -> Result<c_int, c_int> {
+        let mut fd = unsafe { INOTIFYFD };
+        if fd < 0 {
+            fd = unsafe { inotify_init1(libc::O_NONBLOCK | libc::O_CLOEXEC) };
+            if fd < 0 {
+                return Err(last_errno());
+            }
+            unsafe {
+                INOTIFYFD = fd;
+                add_read_fd(fd, Some(inotify_callback), std::ptr::null_mut());
+            }
+        }
+
+        let wd = unsafe { inotify_add_watch(fd, path.as_ptr(), mask) };
+        if wd < 0 {
+            Err(last_errno())
+        } else {
+            Ok(wd)
+        }
+    }
+
+    /// Remove the kernel watch WD, returning the error number or 0.
+    pub fn rm(wd: c_int) -> c_int {
+        let fd = unsafe { INOTIFYFD };
+        if fd < 0 || unsafe { inotify_rm_watch(fd, wd) } == 0 {
+            0
+        } else {
+            last_errno()
+        }
+    }
+
+    /// Remove the kernel watch WD unless a recursive watch still needs
+    /// it for a subdirectory.
+    pub fn release(wd: c_int) -> c_int {
+        if RECURSION.lock().unwrap().subs.contains_key(&wd) {
+            0
+        } else {
+            rm(wd)
+        }
+    }
+
+    /// Close the inotify file handle, once no watch needs it any more.
+    pub fn close() {
+        let fd = unsafe { INOTIFYFD };
+        if fd >= 0 && RECURSION.lock().unwrap().subs.is_empty() {
+            unsafe {
+                INOTIFYFD = -1;
+                delete_read_fd(fd);
+                emacs_close(fd);
+            }
+        }
+    }
+
+    /// This callback is called when the FD is available for read.  The
+    /// inotify events are read from FD and converted into input_events.
+    extern "C" fn inotify_callback(fd: c_int, _data: *mut c_void) {
+        const HEADER: usize = 16; // sizeof (struct inotify_event)
+
+        let mut buffer = vec![0u8; 64 * 1024];
+        let mut events = Vec::new();
+        loop {
+            let n = unsafe { libc::read(fd, buffer.as_mut_ptr() as *mut c_void, buffer.len()) };
+            if n < 0 {
+                let errno = last_errno();
+                if errno == libc::EAGAIN || errno == libc::EINTR {
+                    break;
+                }
+                report_file_notify_error("Error while reading file system events", errno, Qnil);
+            }
+            if n == 0 {
+                break;
+            }
+
+            let mut i = 0;
+            while i + HEADER <= n as usize {
+                let field = |at: usize| {
+                    let mut bytes = [0u8; 4];
+                    bytes.copy_from_slice(&buffer[i + at..i + at + 4]);
+                    u32::from_ne_bytes(bytes)
+                };
+                let len = field(12) as usize;
+                let name = &buffer[i + HEADER..i + HEADER + len];
+                let name_len = name.iter().position(|&b| b == 0).unwrap_or(len);
+                events.push(RawEvent {
+                    wd: field(0) as c_int,
+                    mask: field(4),
+                    cookie: field(8),
+                    name: if name_len > 0 {
+                        Some(name[..name_len].to_vec())
+                    } else {
+                        None
+                    },
+                });
+                i += HEADER + len;
+            }
+        }
+
+        handle_events(events);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod kernel {
+    use std::ffi::CStr;
+
+    use libc::c_int;
+
+    // Without inotify, every watch is polled.
+    pub fn add(_path: &CStr, _mask: u32) -> Result<c_int, c_int> {
+        Err(libc::ENOSYS)
+    }
+
+    pub fn rm(_wd: c_int) -> c_int {
+        0
+    }
+
+    pub fn release(_wd: c_int) -> c_int {
+        0
+    }
+
+    pub fn close() {}
+}
+
+// The polling backend.  A background thread rescans the polled files
+// every `inotify-poll-interval' seconds and wakes up the main loop
+// through a pipe after each scan; any events found are then delivered
+// from the main thread, which is the only one touching Lisp data.  The
+// main thread also rereads the interval then, so that a new value is
+// used from the scan after next.
+mod poll {
+    use super::*;
+
+    /// What we remember about a file between two scans.
+    #[derive(Clone, Copy, PartialEq)]
+    pub struct FileStamp {
+        ino: u64,
+        is_dir: bool,
+        size: u64,
+        mtime: (i64, i64),
+        ctime: (i64, i64),
+        mode: u32,
+        uid: u32,
+        gid: u32,
+    }
+
+    impl FileStamp {
+        fn new(md: &fs::Metadata) -> Self {
+            Self {
+                ino: md.ino(),
+                is_dir: md.is_dir(),
+                size: md.size(),
+                mtime: (md.mtime(), md.mtime_nsec()),
+                ctime: (md.ctime(), md.ctime_nsec()),
+                mode: md.mode(),
+                uid: md.uid(),
+                gid: md.gid(),
+            }
+        }
+    }
+
+    // Snapshot of a watched file or directory tree, keyed by the path
+    // relative to the watched file.  The empty path is the file itself.
+    pub type Snapshot = HashMap<PathBuf, FileStamp>;
+
+    struct PolledWatch {
+        path: PathBuf,
+        recursive: bool,
+        follow: bool,
+        snapshot: Snapshot,
+    }
+
+    #[derive(Default)]
+    struct PollState {
+        next_wd: c_int,
+        watches: HashMap<c_int, PolledWatch>,
+        events: Vec<RawEvent>,
+        pipe: Option<(c_int, c_int)>,
+    }
+
+    lazy_static! {
+        static ref STATE: Mutex<PollState> = Mutex::new(PollState::default());
+    }
+
+    // Milliseconds between two scans.
+    static INTERVAL: AtomicUsize = AtomicUsize::new(2000);
+
+    fn metadata(path: &Path, follow: bool) -> Option<fs::Metadata> {
+        if follow {
+            fs::metadata(path).ok()
+        } else {
+            fs::symlink_metadata(path).ok()
+        }
+    }
+
+    pub fn scan(path: &Path, recursive: bool, follow: bool) -> Option<Snapshot> {
+        let md = metadata(path, follow)?;
+        let mut snapshot = HashMap::new();
+        snapshot.insert(PathBuf::new(), FileStamp::new(&md));
+        if md.is_dir() {
+            scan_dir(path, Path::new(""), recursive, &mut snapshot);
+        }
+        Some(snapshot)
+    }
+
+    fn scan_dir(dir: &Path, rel: &Path, recursive: bool, snapshot: &mut Snapshot) {
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            let name = entry.file_name();
+            let path = dir.join(&name);
+            let rel = rel.join(&name);
+            if let Ok(md) = fs::symlink_metadata(&path) {
+                snapshot.insert(rel.clone(), FileStamp::new(&md));
+                if recursive && md.is_dir() {
+                    scan_dir(&path, &rel, recursive, snapshot);
+                }
+            }
+        }
+    }
+
+    /// Compare two snapshots of the watch WD, and turn the differences
+    /// into events like inotify would report them.
+    pub fn diff(wd: c_int, old: &Snapshot, new: &Snapshot, events: &mut Vec<RawEvent>) {
+        let event = |mask: u32, rel: &Path, stamp: &FileStamp| RawEvent {
+            wd,
+            mask: if stamp.is_dir { mask | IN_ISDIR } else { mask },
+            cookie: 0,
+            name: if rel.as_os_str().is_empty() {
+                None
+            } else {
+                Some(rel.as_os_str().as_bytes().to_vec())
+            },
+        };
+
+        for (rel, stamp) in old {
+            match new.get(rel) {
+                None => events.push(event(IN_DELETE, rel, stamp)),
+                Some(new_stamp) if new_stamp.ino != stamp.ino => {
+                    events.push(event(IN_DELETE, rel, stamp));
+                    events.push(event(IN_CREATE, rel, new_stamp));
+                }
+                // The times of a directory change whenever an entry is
+                // added or removed, which inotify does not report as
+                // `attrib' of the directory itself.
+                Some(new_stamp) if stamp.is_dir => {
+                    if new_stamp.mode != stamp.mode
+                        || new_stamp.uid != stamp.uid
+                        || new_stamp.gid != stamp.gid
+                    {
+                        events.push(event(IN_ATTRIB, rel, new_stamp));
+                    }
+                }
+                Some(new_stamp) => {
+                    if new_stamp.mtime != stamp.mtime || new_stamp.size != stamp.size {
+                        events.push(event(IN_MODIFY, rel, new_stamp));
+                    } else if new_stamp.ctime != stamp.ctime
+                        || new_stamp.mode != stamp.mode
+                        || new_stamp.uid != stamp.uid
+                        || new_stamp.gid != stamp.gid
+                    {
+                        events.push(event(IN_ATTRIB, rel, new_stamp));
+                    }
+                }
+            }
+        }
+        for (rel, stamp) in new {
+            if !old.contains_key(rel) {
+                events.push(event(IN_CREATE, rel, stamp));
+            }
+        }
+    }
+
+    fn poll_once() {
+        let targets: Vec<(c_int, PathBuf, bool, bool)> = {
+            let state = STATE.lock().unwrap();
+            state
+                .watches
+                .iter()
+                .map(|(&wd, w)| (wd, w.path.clone(), w.recursive, w.follow))
+                .collect()
+        };
+
+        let scans: Vec<_> = targets
+            .into_iter()
+            .map(|(wd, path, recursive, follow)| (wd, scan(&path, recursive, follow)))
+            .collect();
+
+        let mut state = STATE.lock().unwrap();
+        let mut events = Vec::new();
+        for (wd, snapshot) in scans {
+            let watch = match state.watches.get_mut(&wd) {
+                Some(watch) => watch,
+                None => continue,
+            };
+            match snapshot {
+                Some(snapshot) => {
+                    diff(wd, &watch.snapshot, &snapshot, &mut events);
+                    watch.snapshot = snapshot;
+                }
+                None => {
+                    let is_dir = watch.snapshot.get(Path::new("")).map_or(false, |s| s.is_dir);
+                    let isdir = if is_dir { IN_ISDIR } else { 0 };
+                    for &mask in &[IN_DELETE_SELF | isdir, IN_IGNORED] {
+                        events.push(RawEvent {
+                            wd,
+                            mask,
+                            cookie: 0,
+                            name: None,
+                        });
+                    }
+                    state.watches.remove(&wd);
+                }
+            }
+        }
+
+        state.events.append(&mut events);
+        if let Some((_, write_fd)) = state.pipe {
+            let byte = 0u8;
+            unsafe { libc::write(write_fd, &byte as *const u8 as *const c_void, 1) };
+        }
+    }
+
+    /// Set the time between two scans from `inotify-poll-interval'.
+    fn update_interval() {
+        let interval = unsafe { globals.Vinotify_poll_interval }
+            .any_to_float()
+            .filter(|&secs| secs > 0.0)
+            .unwrap_or(2.0);
+        INTERVAL.store((interval * 1000.0) as usize, Ordering::Relaxed);
+    }
+
+    /// Called from the main loop after each scan of the polling thread.
+    extern "C" fn poll_callback(fd: c_int, _data: *mut c_void) {
+        let mut buf = [0u8; 64];
+        while unsafe { libc::read(fd, buf.as_mut_ptr() as *mut c_void, buf.len()) } > 0 {}
+
+        update_interval();
+        let events = mem::replace(&mut STATE.lock().unwrap().events, Vec::new());
+        handle_events(events);
+    }
+
+    fn start(state: &mut PollState) -> Result<(), c_int> {
+        if state.pipe.is_some() {
+            return Ok(());
+        }
+
+        let mut fds = [0 as c_int; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(last_errno());
+        }
+        for &fd in &fds {
+            unsafe {
+                let flags = libc::fcntl(fd, libc::F_GETFL);
+                libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+                libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+            }
+        }
+        state.pipe = Some((fds[0], fds[1]));
+        unsafe { add_read_fd(fds[0], Some(poll_callback), ptr::null_mut()) };
+
+        thread::spawn(|| loop {
+            thread::sleep(Duration::from_millis(INTERVAL.load(Ordering::Relaxed) as u64));
+            poll_once();
+        });
+
+        Ok(())
+    }
+
+    /// Start polling PATH, returning the new watch descriptor or the
+    /// error number.
+    pub fn add(path: &Path, recursive: bool, follow: bool) -> Result<c_int, c_int> {
+        update_interval();
+
+        let snapshot = match scan(path, recursive, follow) {
+            Some(snapshot) => snapshot,
+            None => return Err(libc::ENOENT),
+        };
+
+        let mut state = STATE.lock().unwrap();
+        start(&mut state)?;
+        state.next_wd -= 1;
+        let wd = state.next_wd;
+        state.watches.insert(
+            wd,
+            PolledWatch {
+                path: path.to_path_buf(),
+                recursive,
+                follow,
+                snapshot,
+            },
+        );
+
+        Ok(wd)
+    }
+
+    pub fn remove(wd: c_int) {
+        STATE.lock().unwrap().watches.remove(&wd);
+    }
+}
+
+/// Add a watch for FILE-NAME to inotify.
+///
+/// Return a watch descriptor.  The watch will look for ASPECT events and
+/// invoke CALLBACK when an event occurs.
+///
+/// ASPECT might be one of the following symbols or a list of those symbols:
+///
+/// access
+/// attrib
+/// close-write
+/// close-nowrite
+/// create
+/// delete
+/// delete-self
+/// modify
+/// move-self
+/// moved-from
+/// moved-to
+/// open
+///
+/// all-events or t
+/// move
+/// close
+///
+/// ASPECT can also contain the following symbols, which control whether
+/// the watch descriptor will be created:
+///
+/// dont-follow
+/// onlydir
+///
+/// Watching a directory is not recursive, unless ASPECT contains the
+/// symbol `recursive'.  In that case, events for files in subdirectories
+/// are reported too, and directories created later are watched as well.
+///
+/// CALLBACK is passed a single argument EVENT which contains an event
+/// structure of the format
+///
+/// (WATCH-DESCRIPTOR ASPECTS NAME COOKIE)
+///
+/// WATCH-DESCRIPTOR is the same object that was returned by this function.  It can
+/// be tested for equality using `equal'.  ASPECTS describes the event.  It is a
+/// list of ASPECT symbols described above and can also contain one of the following
+/// symbols
+///
+/// ignored
+/// isdir
+/// q-overflow
+/// unmount
+///
+/// If a directory is watched then NAME is the name of file that caused the event.
+/// For recursive watches, it is relative to the watched directory.
+///
+/// COOKIE is an object that can be compared using `equal' to identify two matching
+/// renames (moved-from and moved-to).
+///
+/// Repeated `access', `attrib' or `modify' events for the same file that
+/// arrive together are reported only once.
+///
+/// If the system runs out of inotify watches, or inotify is not
+/// available, the file is polled every `inotify-poll-interval' seconds
+/// instead.  Polling only detects `create', `delete', `delete-self',
+/// `modify' and `attrib' events.
+///
+/// See inotify(7) and inotify_add_watch(2) for further information.  The
+/// inotify fd is managed internally and there is no corresponding
+/// inotify_init.  Use `inotify-rm-watch' to remove a watch.
+///
+/// The following inotify bit-masks cannot be used because descriptors are
+/// shared across different callers.
+///
+/// IN_EXCL_UNLINK
+/// IN_MASK_ADD
+/// IN_ONESHOT
+#[lisp_fn]
+pub fn inotify_add_watch(
+    file_name: LispObject,
+    aspect: LispObject,
+    callback: LispObject,
+) -> LispObject {
+    let (imask, recursive) = aspect_to_inotifymask(aspect);
+    let mask = imask | IN_MASK_ADD | IN_EXCL_UNLINK;
+
+    let filename = file_name.as_string_or_error();
+    if !AVAILABLE.load(Ordering::SeqCst) {
+        report_file_notify_error("File watching is not available", libc::ENOSYS, Qnil);
+    }
+
+    let encoded_file_name = unsafe { encode_file_name(filename.into()) }.as_string_or_error();
+    let path = Path::new(OsStr::from_bytes(encoded_file_name.as_slice())).to_path_buf();
+    let c_path = match CString::new(encoded_file_name.as_slice()) {
+        Ok(c_path) => c_path,
+        Err(_) => report_file_notify_error("Could not add watch for file", libc::EINVAL, file_name),
+    };
+
+    let had_recursion = |wd| RECURSION.lock().unwrap().roots.contains_key(&wd);
+    let kernel_wd = match kernel::add(&c_path, mask) {
+        Ok(wd) if recursive && path.is_dir() => {
+            let fresh = !had_recursion(wd);
+            match start_recursion(wd, &path, imask) {
+                Err(libc::ENOSPC) if fresh => {
+                    // Give the watches back and poll the tree instead.
+                    drop_recursion(wd);
+                    if assq_descriptor(LispObject::from(EmacsInt::from(wd))).is_none() {
+                        kernel::rm(wd);
+                    }
+                    None
+                }
+                _ => Some(wd),
+            }
+        }
+        Ok(wd) => Some(wd),
+        Err(errno) if errno == libc::ENOSPC || errno == libc::EMFILE || errno == libc::ENOSYS => {
+            None
+        }
+        Err(errno) => report_file_notify_error("Could not add watch for file", errno, file_name),
+    };
+
+    let wd = match kernel_wd {
+        Some(wd) => wd,
+        None => match poll::add(&path, recursive, imask & IN_DONT_FOLLOW == 0) {
+            Ok(wd) => wd,
+            Err(errno) => {
+                report_file_notify_error("Could not add watch for file", errno, file_name)
+            }
+        },
+    };
+
+    add_watch(wd, file_name, imask, callback, recursive)
+}
+
+fn valid_watch_descriptor(wd: LispObject) -> bool {
+    wd.as_cons().map_or(false, |cons| {
+        let (descriptor, id) = cons.as_tuple();
+        descriptor.as_fixnum().map_or(false, |d| {
+            d >= EmacsInt::from(c_int::min_value()) && d <= EmacsInt::from(c_int::max_value())
+        }) && id.is_natnum()
+    })
+}
+
+/// Remove an existing WATCH-DESCRIPTOR.
+///
+/// WATCH-DESCRIPTOR should be an object returned by `inotify-add-watch'.
+///
+/// See inotify_rm_watch(2) for more information.
+#[lisp_fn]
+pub fn inotify_rm_watch(watch_descriptor: LispObject) -> bool {
+    if !valid_watch_descriptor(watch_descriptor) {
+        report_file_notify_error("Invalid descriptor ", libc::EINVAL, watch_descriptor);
+    }
+
+    let (descriptor, id) = watch_descriptor.as_cons_or_error().as_tuple();
+    remove_watch(descriptor, id);
+
+    true
+}
+
+/// Check a watch specified by its WATCH-DESCRIPTOR.
+///
+/// WATCH-DESCRIPTOR should be an object returned by `inotify-add-watch'.
+///
+/// A watch can become invalid if the file or directory it watches is
+/// deleted, or if the watcher thread exits abnormally for any other
+/// reason.  Removing the watch by calling `inotify-rm-watch' also makes
+/// it invalid.
+#[lisp_fn]
+pub fn inotify_valid_p(watch_descriptor: LispObject) -> bool {
+    if !valid_watch_descriptor(watch_descriptor) {
+        return false;
+    }
+
+    let (descriptor, id) = watch_descriptor.as_cons_or_error().as_tuple();
+    assq_descriptor(descriptor).map_or(false, |elt| {
+        elt.as_cons_or_error()
+            .cdr()
+            .iter_cars()
+            .any(|watch| nth(0, watch).eq(id))
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rust_syms_of_inotify() {
+    AVAILABLE.store(true, Ordering::SeqCst);
+
+    def_lisp_sym!(Qaccess, "access"); // IN_ACCESS
+    def_lisp_sym!(Qattrib, "attrib"); // IN_ATTRIB
+    def_lisp_sym!(Qclose_write, "close-write"); // IN_CLOSE_WRITE
+    def_lisp_sym!(Qclose_nowrite, "close-nowrite"); // IN_CLOSE_NOWRITE
+    def_lisp_sym!(Qcreate, "create"); // IN_CREATE
+    def_lisp_sym!(Qdelete, "delete"); // IN_DELETE
+    def_lisp_sym!(Qdelete_self, "delete-self"); // IN_DELETE_SELF
+    def_lisp_sym!(Qmodify, "modify"); // IN_MODIFY
+    def_lisp_sym!(Qmove_self, "move-self"); // IN_MOVE_SELF
+    def_lisp_sym!(Qmoved_from, "moved-from"); // IN_MOVED_FROM
+    def_lisp_sym!(Qmoved_to, "moved-to"); // IN_MOVED_TO
+    def_lisp_sym!(Qopen, "open"); // IN_OPEN
+
+    def_lisp_sym!(Qall_events, "all-events"); // IN_ALL_EVENTS
+    def_lisp_sym!(Qmove, "move"); // IN_MOVE
+    def_lisp_sym!(Qclose, "close"); // IN_CLOSE
+
+    def_lisp_sym!(Qdont_follow, "dont-follow"); // IN_DONT_FOLLOW
+    def_lisp_sym!(Qonlydir, "onlydir"); // IN_ONLYDIR
+    def_lisp_sym!(Qrecursive, "recursive");
+
+    def_lisp_sym!(Qignored, "ignored"); // IN_IGNORED
+    def_lisp_sym!(Qisdir, "isdir"); // IN_ISDIR
+    def_lisp_sym!(Qq_overflow, "q-overflow"); // IN_Q_OVERFLOW
+    def_lisp_sym!(Qunmount, "unmount"); // IN_UNMOUNT
+
+    /// Seconds between two checks of a polled file watch.
+    /// Polling is used by `inotify-add-watch' when the system has run out of
+    /// inotify watches, or does not have inotify.  A new value takes effect
+    /// once the next scan is done.
+    defvar_lisp!(Vinotify_poll_interval, "inotify-poll-interval", LispObject::from(2));
+}
+
+include!(concat!(env!("OUT_DIR"), "/inotify_exports.rs"));
+
+#[cfg(test)]
+fn test_event(wd: c_int, mask: u32, name: &str) -> RawEvent {
+    RawEvent {
+        wd,
+        mask,
+        cookie: 0,
+        name: Some(name.as_bytes().to_vec()),
+    }
+}
+
+#[test]
+fn test_coalesce() {
+    let events = vec![
+        test_event(1, IN_MODIFY, "a"),
+        test_event(1, IN_MODIFY, "a"),
+        test_event(2, IN_MODIFY, "a"),
+        test_event(1, IN_ATTRIB, "a"),
+        test_event(1, IN_MODIFY, "b"),
+        test_event(1, IN_MODIFY, "a"),
+        test_event(1, IN_CREATE, "c"),
+        test_event(1, IN_CREATE, "c"),
+    ];
+    let masks: Vec<(c_int, u32)> = coalesce(events)
+        .iter()
+        .map(|ev| (ev.wd, ev.mask))
+        .collect();
+    assert_eq!(
+        masks,
+        vec![
+            (1, IN_MODIFY),
+            (2, IN_MODIFY),
+            (1, IN_ATTRIB),
+            (1, IN_MODIFY),
+            (1, IN_MODIFY),
+            (1, IN_CREATE),
+            (1, IN_CREATE),
+        ]
+    );
+}
+
+#[test]
+fn test_poll_diff() {
+    let dir = std::env::temp_dir().join(format!("remacs-inotify-{}", std::process::id()));
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("a"), b"1").unwrap();
+    fs::write(dir.join("gone"), b"").unwrap();
+
+    let changes = |old: &poll::Snapshot, new: &poll::Snapshot| {
+        let mut events = Vec::new();
+        poll::diff(-1, old, new, &mut events);
+        let mut changes: Vec<(u32, String)> = events
+            .iter()
+            .map(|ev| {
+                let name = ev.name.as_ref().map_or(&[][..], Vec::as_slice);
+                (ev.mask, String::from_utf8_lossy(name).into_owned())
+            })
+            .collect();
+        changes.sort();
+        changes
+    };
+
+    let old = poll::scan(&dir, true, true).unwrap();
+    fs::write(dir.join("a"), b"22").unwrap();
+    fs::write(dir.join("sub/new"), b"").unwrap();
+    fs::remove_file(dir.join("gone")).unwrap();
+    let new = poll::scan(&dir, true, true).unwrap();
+    // The directories themselves changed times, but report no `attrib'.
+    assert_eq!(
+        changes(&old, &new),
+        vec![
+            (IN_MODIFY, "a".to_string()),
+            (IN_CREATE, "sub/new".to_string()),
+            (IN_DELETE, "gone".to_string()),
+        ]
+    );
+
+    let flat = poll::scan(&dir, false, true).unwrap();
+    assert!(flat.contains_key(Path::new("sub")));
+    assert!(!flat.contains_key(Path::new("sub/new")));
+
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(poll::scan(&dir, true, true).is_none());
+}
@@ -1,26 +1,40 @@
 //! Keymap support
 
 use std;
+use std::ffi::CString;
 use std::ptr;
+use std::slice;
 
-use libc::c_void;
+use libc::{c_char, c_void};
 
 use remacs_macros::lisp_fn;
 
 use crate::{
-    data::{aref, indirect_function, set},
+    buffers::{set_buffer, LispBufferRef},
+    data::{aref, aset, indirect_function, set},
     eval::autoload_do_load,
+    hashtable::{gethash, puthash, LispHashTableRef},
     keyboard::lucid_event_type_list_p,
     lisp::{defsubr, LispObject},
-    lists::nth,
+    lists::{car, get, member, nth, LispCons},
+    multibyte::LispStringRef,
     obarray::intern,
     remacs_sys::{
-        access_keymap, make_save_funcptr_ptr_obj, map_char_table, map_keymap_call,
-        map_keymap_char_table_item, map_keymap_function_t, map_keymap_item, maybe_quit,
+        access_keymap, current_kboard, current_minor_maps, describe_map_tree, get_keyelt,
+        get_local_map, insert, make_save_funcptr_ptr_obj, map_char_table, map_keymap_call,
+        map_keymap_char_table_item, map_keymap_function_t, map_keymap_item, maybe_quit, nconc2,
+        parse_modifiers, parse_solitary_modifier, push_key_description,
     },
     remacs_sys::{char_bits, current_global_map as _current_global_map, globals, EmacsInt},
-    remacs_sys::{Fcons, Fevent_convert_list, Ffset, Fmake_char_table, Fpurecopy},
-    remacs_sys::{Qautoload, Qkeymap, Qkeymapp, Qnil, Qt},
+    remacs_sys::{
+        Faccessible_keymaps, Fcommand_remapping, Fcons, Fcopy_sequence, Fcurrent_active_maps,
+        Fevent_convert_list, Ffset, Findent_to, Fmake_char_table, Fmake_hash_table, Fnreverse,
+        Fpurecopy, Freverse, Fsubstring, Fvconcat, Fvector,
+    },
+    remacs_sys::{
+        QCadvertised_binding, QCtest, Qautoload, Qequal, Qheader_line, Qkeymap, Qkeymapp,
+        Qlocal_map, Qmenu_bar, Qmode_line, Qnil, Qnon_ascii, Qremap, Qt, Qtool_bar,
+    },
     threads::ThreadState,
 };
 
@@ -28,37 +42,29 @@ pub fn Ctl(c: char) -> i32 {
     (c as i32) & 0x1f
 }
 
-/// Hash table used to cache a reverse-map to speed up calls to where-is.
+/// Reverse-map caches used to speed up calls to where-is, most
+/// recently used first.  Each element looks like (KEYMAPS . TABLE),
+/// where TABLE is a hash table mapping every binding found in KEYMAPS,
+/// ignoring menus, to the key sequences bound to it.  Any change to a
+/// keymap through `define-key' and friends flushes all of them.
+///
+/// As in C, only lookups that ignore menus and look inside menu items
+/// use the cache, which is what looking for the first binding of a
+/// command does.  Menu bindings can change behind our back, through
+/// `:filter' functions, and so can keymaps edited as lists.
 declare_GC_protected_static!(where_is_cache, Qnil);
 
-/// Allows the C code to get the value of `where_is_cache`
-#[no_mangle]
-pub extern "C" fn get_where_is_cache() -> LispObject {
-    unsafe { where_is_cache }
-}
-
-/// Allows the C code to set the value of `where_is_cache`
-#[no_mangle]
-pub extern "C" fn set_where_is_cache(val: LispObject) {
-    unsafe {
-        where_is_cache = val;
-    }
-}
+/// The number of sets of keymaps kept in `where_is_cache`.
+const WHERE_IS_CACHE_SIZE: usize = 8;
 
-/// Which keymaps are reverse-stored in the cache.
-declare_GC_protected_static!(where_is_cache_keymaps, Qt);
+/// The maximum byte size consumed by `push_key_description`.
+const KEY_DESCRIPTION_SIZE: usize = (2 * 6) + 1 + (char_bits::CHARACTERBITS as usize / 3) + 1 + 1;
 
-/// Allows the C code to get the value of `where_is_cache_keymaps`
+/// Flush the reverse-map caches after a keymap has changed.
 #[no_mangle]
-pub extern "C" fn get_where_is_cache_keymaps() -> LispObject {
-    unsafe { where_is_cache_keymaps }
-}
-
-/// Allows the C code to set the value of `where_is_cache_keymaps`
-#[no_mangle]
-pub extern "C" fn set_where_is_cache_keymaps(val: LispObject) {
+pub extern "C" fn where_is_cache_invalidate() {
     unsafe {
-        where_is_cache_keymaps = val;
+        where_is_cache = Qnil;
     }
 }
 
@@ -207,10 +213,7 @@ pub extern "C" fn keymap_memberp(map: LispObject, maps: LispObject) -> bool {
 #[lisp_fn]
 pub fn set_keymap_parent(keymap: LispObject, parent: LispObject) -> LispObject {
     // Flush any reverse-map cache
-    unsafe {
-        where_is_cache = Qnil;
-        where_is_cache_keymaps = Qt;
-    }
+    where_is_cache_invalidate();
 
     let mut parent = parent;
     let keymap = get_keymap(keymap, true, true);
@@ -569,4 +572,894 @@ pub fn make_sparse_keymap(string: LispObject) -> LispObject {
     }
 }
 
+/// Return 0 if SEQ uses non-preferred modifiers or non-char events.
+/// Else, return 2 if SEQ uses the PREFERRED_MODIFIER, and 1 otherwise.
+fn preferred_sequence_p(seq: LispObject, preferred_modifier: EmacsInt) -> i32 {
+    let mut result = 1;
+    let len = seq.as_vector_or_string_length() as EmacsInt;
+
+    for i in 0..len {
+        match aref(seq, i).as_fixnum() {
+            None => return 0,
+            Some(elt) => {
+                let modifiers = elt
+                    & EmacsInt::from(char_bits::CHAR_MODIFIER_MASK & !char_bits::CHAR_META);
+                if modifiers == preferred_modifier {
+                    result = 2;
+                } else if modifiers != 0 {
+                    return 0;
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// Like `lookup_key`, but uses a list of keymaps SHADOW instead of a
+/// single map.  Returns the first non-nil binding found in any of those
+/// maps.  If REMAP is true, pass the result of the lookup through
+/// command remapping before returning it.
+#[no_mangle]
+pub extern "C" fn shadow_lookup(
+    shadow: LispObject,
+    key: LispObject,
+    flag: LispObject,
+    remap: bool,
+) -> LispObject {
+    for map in shadow.iter_cars_safe() {
+        let value = lookup_key(map, key, flag);
+        if value.is_natnum() {
+            let prefix = unsafe { Fsubstring(key, LispObject::from(0), value) };
+            if lookup_key(map, prefix, flag).is_not_nil() {
+                return Qnil;
+            }
+        } else if value.is_not_nil() {
+            if remap && value.is_symbol() {
+                let remapping = unsafe { Fcommand_remapping(value, Qnil, shadow) };
+                if remapping.is_not_nil() {
+                    return remapping;
+                }
+            }
+            return value;
+        }
+    }
+    Qnil
+}
+
+/// Whether a key sequence starting with EVENT leads to menus or mouse
+/// bindings rather than keyboard bindings.
+fn mouse_event_prefix_p(event: LispObject) -> bool {
+    if !event.is_symbol() {
+        return false;
+    }
+    let base = unsafe { parse_modifiers(event) }.as_cons_or_error().car();
+    [Qmenu_bar, Qtool_bar, Qheader_line, Qmode_line]
+        .iter()
+        .any(|&sym| base.eq(sym))
+        || (1..=5).any(|n| base.eq(intern(format!("mouse-{}", n))))
+}
+
+struct WhereIsData {
+    definition: LispObject,
+    this: LispObject,
+    last: EmacsInt,
+    last_is_meta: bool,
+    noindirect: bool,
+    // When filling a cache, every binding is recorded here instead of
+    // only those matching DEFINITION.
+    table: Option<LispHashTableRef>,
+    sequences: LispObject,
+}
+
+/// Append a key to the end of a key sequence.  We always make a vector.
+fn append_key(key_sequence: LispObject, key: LispObject) -> LispObject {
+    let mut args = [key_sequence, list!(key)];
+    unsafe { Fvconcat(args.len() as isize, args.as_mut_ptr()) }
+}
+
+/// This function can GC because `get_keyelt` can.
+unsafe extern "C" fn where_is_internal_1(
+    key: LispObject,
+    binding: LispObject,
+    _args: LispObject,
+    data: *mut c_void,
+) {
+    let d = &mut *(data as *mut WhereIsData);
+
+    // Search through indirections unless that's not wanted.
+    let binding = if d.noindirect {
+        binding
+    } else {
+        get_keyelt(binding, false)
+    };
+
+    // End this iteration if this element does not match the target.
+    // Everything matches while filling a cache.
+    if d.table.is_none()
+        && !(binding.eq(d.definition) || (d.definition.is_cons() && binding.equal(d.definition)))
+    {
+        return;
+    }
+
+    // We have found a match.  Construct the key sequence where we found it.
+    let sequence = match key.as_fixnum() {
+        Some(c) if d.last_is_meta => {
+            let sequence = Fcopy_sequence(d.this);
+            aset(
+                sequence,
+                d.last,
+                LispObject::from(c | EmacsInt::from(char_bits::CHAR_META)),
+            );
+            sequence
+        }
+        _ => {
+            let key = match key.as_cons() {
+                Some(cons) => LispObject::cons(cons.car(), cons.cdr()),
+                None => key,
+            };
+            append_key(d.this, key)
+        }
+    };
+
+    match d.table {
+        Some(table) => {
+            let sequences = gethash(binding, table, Qnil);
+            puthash(binding, LispObject::cons(sequence, sequences), table);
+        }
+        None => d.sequences = LispObject::cons(sequence, d.sequences),
+    }
+}
+
+/// Walk all the maps accessible from KEYMAPS and call
+/// `where_is_internal_1` for every binding in them.
+fn where_is_scan(keymaps: LispObject, nomenus: bool, data: &mut WhereIsData) {
+    let mut maps = Qnil;
+    for keymap in keymaps.iter_cars_safe() {
+        let accessible = unsafe { Faccessible_keymaps(get_keymap(keymap, true, false), Qnil) };
+        maps = unsafe { nconc2(maps, accessible) };
+    }
+
+    for elt in maps.iter_cars_safe() {
+        // Key sequence to reach map, and the map that it reaches.
+        let (this, map) = elt.as_cons_or_error().as_tuple();
+
+        // In order to fold [META-PREFIX-CHAR CHAR] sequences into
+        // [M-CHAR] sequences, check if last character of the sequence
+        // is the meta-prefix char.
+        let last = this.as_vector_or_string_length() as EmacsInt - 1;
+        let last_is_meta = last >= 0 && aref(this, last).eq(unsafe { globals.meta_prefix_char });
+
+        // If no menu entries should be returned, skip over the keymaps
+        // bound to `menu-bar' and `tool-bar' and other non-ascii
+        // prefixes like `C-down-mouse-2'.
+        if nomenus && last >= 0 && mouse_event_prefix_p(aref(this, 0)) {
+            continue;
+        }
+
+        unsafe { maybe_quit() };
+
+        data.this = this;
+        data.last = last;
+        data.last_is_meta = last_is_meta;
+
+        if map.is_cons() {
+            unsafe {
+                map_keymap(
+                    map,
+                    Some(where_is_internal_1),
+                    Qnil,
+                    data as *mut WhereIsData as *mut c_void,
+                    false,
+                )
+            };
+        }
+    }
+}
+
+/// Whether the lists of keymaps A and B contain the same keymaps.
+fn same_keymaps_p(a: LispObject, b: LispObject) -> bool {
+    let mut a = a.iter_cars_safe();
+    let mut b = b.iter_cars_safe();
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return true,
+            (Some(x), Some(y)) if x.eq(y) => {}
+            _ => return false,
+        }
+    }
+}
+
+/// Return the reverse map of KEYMAPS, ignoring menus, from
+/// `where_is_cache`, building it if necessary.
+fn where_is_cache_table(keymaps: LispObject) -> LispHashTableRef {
+    let mut prev: Option<LispCons> = None;
+    let mut count = 0;
+    for tail in unsafe { where_is_cache }.iter_tails_safe() {
+        let entry = tail.car();
+        let (maps, table) = entry.as_cons_or_error().as_tuple();
+
+        if same_keymaps_p(maps, keymaps) {
+            // Move the entry to the front.
+            if let Some(prev) = prev {
+                prev.set_cdr(tail.cdr());
+                unsafe { where_is_cache = LispObject::cons(entry, where_is_cache) };
+            }
+            return table.as_hash_table_or_error();
+        }
+
+        count += 1;
+        if count == WHERE_IS_CACHE_SIZE {
+            // Drop the least recently used entry; a new one is added below.
+            tail.set_cdr(Qnil);
+            break;
+        }
+        prev = Some(tail);
+    }
+
+    // Build the table completely before recording it, so that a quit
+    // while scanning doesn't leave a partial cache behind.
+    let table = where_is_reverse_map(keymaps, false, true);
+
+    let entry = LispObject::cons(keymaps, LispObject::from(table));
+    unsafe { where_is_cache = LispObject::cons(entry, where_is_cache) };
+    table
+}
+
+/// Return a new hash table mapping every binding found in KEYMAPS to
+/// the key sequences bound to it, without going through the cache.
+fn where_is_reverse_map(
+    keymaps: LispObject,
+    noindirect: bool,
+    nomenus: bool,
+) -> LispHashTableRef {
+    let table = unsafe { Fmake_hash_table(0, ptr::null_mut()) }.as_hash_table_or_error();
+    let mut data = WhereIsData {
+        definition: Qnil,
+        this: Qnil,
+        last: 0,
+        last_is_meta: false,
+        noindirect,
+        table: Some(table),
+        sequences: Qnil,
+    };
+    where_is_scan(keymaps, nomenus, &mut data);
+    table
+}
+
+/// Return the list of bindings found.  This list is ordered "longest
+/// to shortest".  It may include bindings that are actually shadowed
+/// by others, as well as duplicate bindings and remapping bindings.
+/// The list returned is potentially shared with `where_is_cache`, so
+/// be careful not to modify it via side-effects.
+fn where_is_internal(
+    definition: LispObject,
+    keymaps: LispObject,
+    noindirect: bool,
+    nomenus: bool,
+) -> LispObject {
+    // Bindings are found in the cache with `eql', so look for lists
+    // (lambda expressions, menu items) the slow way.
+    if nomenus && !noindirect && !definition.is_cons() {
+        let table = where_is_cache_table(keymaps);
+        return gethash(definition, table, Qnil);
+    }
+
+    let mut data = WhereIsData {
+        definition,
+        this: Qnil,
+        last: 0,
+        last_is_meta: false,
+        noindirect,
+        table: None,
+        sequences: Qnil,
+    };
+    where_is_scan(keymaps, nomenus, &mut data);
+    data.sequences
+}
+
+/// Return list of keys that invoke DEFINITION.
+/// If KEYMAP is a keymap, search only KEYMAP and the global keymap.
+/// If KEYMAP is nil, search all the currently active keymaps, except
+///  for `overriding-local-map' (which is ignored).
+/// If KEYMAP is a list of keymaps, search only those keymaps.
+///
+/// If optional 3rd arg FIRSTONLY is non-nil, return the first key sequence found,
+/// rather than a list of all possible key sequences.
+/// If FIRSTONLY is the symbol `non-ascii', return the first binding found,
+/// no matter what it is.
+/// If FIRSTONLY has another non-nil value, prefer bindings
+/// that use the modifier key specified in `where-is-preferred-modifier'
+/// (or their meta variants) and entirely reject menu bindings.
+///
+/// If optional 4th arg NOINDIRECT is non-nil, don't extract the commands inside
+/// menu-items.  This makes it possible to search for a menu-item itself.
+///
+/// The optional 5th arg NO-REMAP alters how command remapping is handled:
+///
+/// - If another command OTHER-COMMAND is remapped to DEFINITION, normally
+///   search for the bindings of OTHER-COMMAND and include them in the
+///   returned list.  But if NO-REMAP is non-nil, include the vector
+///   [remap OTHER-COMMAND] in the returned list instead, without
+///   searching for those other bindings.
+///
+/// - If DEFINITION is remapped to OTHER-COMMAND, normally return the
+///   bindings for OTHER-COMMAND.  But if NO-REMAP is non-nil, return the
+///   bindings for DEFINITION instead, ignoring its remapping.
+///
+/// When FIRSTONLY rejects menu bindings and NOINDIRECT is nil, the
+/// bindings of each set of keymaps are indexed the first time they are
+/// searched, and the index is reused until a keymap is changed with
+/// `define-key' or the like.
+#[lisp_fn(min = "1", c_name = "where_is_internal", name = "where-is-internal")]
+pub fn where_is_internal_lisp(
+    definition: LispObject,
+    keymap: LispObject,
+    firstonly: LispObject,
+    noindirect: LispObject,
+    no_remap: LispObject,
+) -> LispObject {
+    let mut definition = definition;
+    // True means ignore all menu bindings entirely.
+    let nomenus = firstonly.is_not_nil() && !firstonly.eq(Qnon_ascii);
+    let noindirect = noindirect.is_not_nil();
+    let preferred_modifier =
+        EmacsInt::from(unsafe { parse_solitary_modifier(globals.Vwhere_is_preferred_modifier) });
+
+    // Find the relevant keymaps.
+    let keymaps = match keymap.as_cons() {
+        Some(cons) if keymapp(cons.car()) => keymap,
+        _ if keymap.is_not_nil() => list!(keymap, current_global_map()),
+        _ => unsafe { Fcurrent_active_maps(Qnil, Qnil) },
+    };
+
+    // If DEFINITION is remapped to another command, all keys bound to
+    // DEFINITION (or to that command) will run the same command.  For
+    // menu-shortcut purposes, which is the most common use, we want
+    // the keys bound (maybe via remapping) to that command.
+    let tem = unsafe { Fcommand_remapping(definition, Qnil, keymaps) };
+    if no_remap.is_nil() && tem.is_not_nil() {
+        definition = tem;
+    }
+
+    if firstonly.is_not_nil() {
+        if let Some(sym) = definition.as_symbol() {
+            // We have a list of advertised bindings.
+            let advertised = get(sym, QCadvertised_binding);
+            let mut tail = advertised;
+            while let Some(cons) = tail.as_cons() {
+                if shadow_lookup(keymaps, cons.car(), Qnil, false).eq(definition) {
+                    return cons.car();
+                }
+                tail = cons.cdr();
+            }
+            if tail.is_not_nil() && shadow_lookup(keymaps, tail, Qnil, false).eq(definition) {
+                return tail;
+            }
+        }
+    }
+
+    // Potentially relevant bindings in "shortest to longest" order.
+    let mut sequences =
+        unsafe { Freverse(where_is_internal(definition, keymaps, noindirect, nomenus)) };
+    // List of sequences found via remapping.  Keep them in a separate
+    // variable, so as to push them later, since we prefer non-remapped
+    // bindings.
+    let mut remapped_sequences = Qnil;
+    // Whether we're handling remapped sequences.  This is needed because
+    // remapping is not done recursively by `command-remapping': you
+    // can't remap a remapped command.
+    let mut remapped = false;
+    // Actually relevant bindings.
+    let mut found = Qnil;
+
+    loop {
+        let sequence = match sequences.as_cons() {
+            Some(cons) => {
+                sequences = cons.cdr();
+                cons.car()
+            }
+            None if !remapped && remapped_sequences.is_cons() => {
+                // We're at the end of the list and haven't considered the
+                // remapped sequences yet.
+                remapped = true;
+                sequences = remapped_sequences;
+                continue;
+            }
+            None => break,
+        };
+
+        // Verify that this key binding is not shadowed by another
+        // binding for the same key, before we say it exists.
+        if !shadow_lookup(keymaps, sequence, Qnil, remapped).eq(definition) {
+            continue;
+        }
+
+        // If the current sequence is a command remapping with format
+        // [remap COMMAND], find the key sequences which run COMMAND, and
+        // use those sequences instead.
+        if no_remap.is_nil() && !remapped {
+            if let Some(vec) = sequence.as_vector() {
+                if vec.len() == 2 && vec.get(0).eq(Qremap) && vec.get(1).is_symbol() {
+                    let seqs = where_is_internal(vec.get(1), keymaps, noindirect, nomenus);
+                    remapped_sequences = unsafe { nconc2(Freverse(seqs), remapped_sequences) };
+                    continue;
+                }
+            }
+        }
+
+        // Don't annoy user with strings from a menu such as the entries
+        // from the "Edit => Paste from Kill Menu".  Change them all to
+        // "(any string)", so that there seems to be only one menu item
+        // to report.
+        if let Some(vec) = sequence.as_vector() {
+            let last = vec.len() as EmacsInt - 1;
+            if last >= 0 && aref(sequence, last).is_string() {
+                aset(sequence, last, LispObject::from("(any string)"));
+            }
+        }
+
+        // It is a true unshadowed match.  Record it, unless it's already
+        // been seen (as could happen when inheriting keymaps).
+        if member(sequence, found).is_nil() {
+            found = LispObject::cons(sequence, found);
+        }
+
+        // If FIRSTONLY is `non-ascii', then we can return the first
+        // binding we find.  If it is another non-nil value, we should
+        // return the first ascii-only binding we find.
+        if firstonly.eq(Qnon_ascii)
+            || (firstonly.is_not_nil() && preferred_sequence_p(sequence, preferred_modifier) == 2)
+        {
+            return sequence;
+        }
+    }
+
+    let found = unsafe { Fnreverse(found) };
+
+    // FIRSTONLY may have been t, but we may have gone all the way
+    // through the keymaps without finding an all-ASCII key sequence.
+    // So just return the best we could find.
+    if firstonly.is_nil() {
+        found
+    } else if preferred_modifier == 0 {
+        car(found)
+    } else {
+        // Maybe we did not find a preferred_modifier binding, but we did
+        // find some ASCII binding.
+        found
+            .iter_cars_safe()
+            .find(|&seq| preferred_sequence_p(seq, preferred_modifier) != 0)
+            .unwrap_or_else(|| car(found))
+    }
+}
+
+def_lisp_sym!(Qnon_ascii, "non-ascii");
+def_lisp_sym!(QCadvertised_binding, ":advertised-binding");
+
+/// Whether BINDING, as returned by `lookup_key`, is a complete binding
+/// rather than a prefix keymap, a too-long key or no binding at all.
+fn complete_binding_p(binding: LispObject) -> bool {
+    binding.is_not_nil() && !binding.is_natnum() && !keymapp(binding)
+}
+
+/// Return the bindings in KEYMAPS that are hidden by other keymaps.
+/// KEYMAPS is a list of keymaps ordered from highest to lowest
+/// precedence, as returned by `current-active-maps'.
+///
+/// A binding of KEY in one keymap is hidden when a keymap earlier in
+/// KEYMAPS binds KEY to something else, binds a prefix of KEY to a
+/// command, or uses KEY as a prefix key.
+///
+/// The value is a list of elements of the form
+///   (KEY BINDING INDEX SHADOWING-KEY SHADOWING-BINDING SHADOWING-INDEX)
+/// where BINDING is hidden in the keymap at position INDEX in KEYMAPS,
+/// and SHADOWING-KEY is KEY or the prefix of it that is bound to
+/// SHADOWING-BINDING in the keymap at position SHADOWING-INDEX.
+/// SHADOWING-BINDING is a keymap for prefix key conflicts.  Keys
+/// bound identically in both keymaps are not reported.
+#[lisp_fn]
+pub fn keymap_shadowed_bindings(keymaps: LispObject) -> LispObject {
+    let maps: Vec<LispObject> = keymaps
+        .iter_cars()
+        .map(|map| get_keymap(map, true, true))
+        .collect();
+
+    // Collect every key sequence bound in any of the maps, in the
+    // order they are found.
+    let mut test_args = [QCtest, Qequal];
+    let seen = unsafe { Fmake_hash_table(test_args.len() as isize, test_args.as_mut_ptr()) }
+        .as_hash_table_or_error();
+    for &map in &maps {
+        // Each map is scanned once, so don't evict the entries of
+        // `where-is-internal' from the cache for tables used only here.
+        let table = where_is_reverse_map(list!(map), false, false);
+        for (binding, sequences) in table.iter() {
+            if binding.is_nil() {
+                continue;
+            }
+            for sequence in sequences.iter_cars_safe() {
+                // Character ranges of char-tables can't be looked up
+                // as keys.
+                let len = sequence.as_vector_or_string_length() as EmacsInt;
+                if (0..len).all(|i| !aref(sequence, i).is_cons()) {
+                    puthash(sequence, Qt, seen);
+                }
+            }
+        }
+    }
+
+    let mut result = Qnil;
+    for (key, _) in seen.iter() {
+        let lookups: Vec<LispObject> = maps
+            .iter()
+            .map(|&map| lookup_key(map, key, Qnil))
+            .collect();
+
+        let shadowing_index = match lookups.iter().position(|binding| binding.is_not_nil()) {
+            Some(index) => index,
+            None => continue,
+        };
+        let shadowing = lookups[shadowing_index];
+        let (shadowing_key, shadowing_binding) = if shadowing.is_natnum() {
+            let prefix = unsafe { Fsubstring(key, LispObject::from(0), shadowing) };
+            (prefix, lookup_key(maps[shadowing_index], prefix, Qnil))
+        } else {
+            (key, shadowing)
+        };
+
+        for (index, &binding) in lookups.iter().enumerate().skip(shadowing_index + 1) {
+            if complete_binding_p(binding) && !binding.eq(shadowing) {
+                result = LispObject::cons(
+                    list!(
+                        key,
+                        binding,
+                        LispObject::from(index as EmacsInt),
+                        shadowing_key,
+                        shadowing_binding,
+                        LispObject::from(shadowing_index as EmacsInt)
+                    ),
+                    result,
+                );
+            }
+        }
+    }
+
+    unsafe { Fnreverse(result) }
+}
+
+/// Insert the list of all defined keys and their definitions.
+/// The list is inserted in the current buffer, while the bindings are
+/// looked up in BUFFER.
+/// The optional argument PREFIX, if non-nil, should be a key sequence;
+/// then we display only bindings that start with that prefix.
+/// The optional argument MENUS, if non-nil, says to mention menu bindings.
+/// (Ordinarily these are omitted from the output.)
+#[lisp_fn(min = "1")]
+pub fn describe_buffer_bindings(
+    mut buffer: LispBufferRef,
+    prefix: LispObject,
+    menus: LispObject,
+) {
+    let nomenu = menus.is_nil();
+    let outbuf = ThreadState::current_buffer();
+    let kboard = unsafe { &*current_kboard };
+    let mut shadow = Qnil;
+
+    let describe = |map: LispObject,
+                    partial: bool,
+                    shadow: LispObject,
+                    title: &str,
+                    transl: bool,
+                    always_title: bool| {
+        let title = CString::new(title).unwrap();
+        unsafe {
+            describe_map_tree(
+                map,
+                partial,
+                shadow,
+                prefix,
+                title.as_ptr(),
+                nomenu,
+                transl,
+                always_title,
+                false,
+            )
+        };
+    };
+
+    // Report on alternates for keys.
+    if kboard.Vkeyboard_translate_table_.is_string() && prefix.is_not_nil() {
+        let mut heading = Some(
+            "Keyboard translations:\n\nYou type        Translation\n--------        -----------\n",
+        );
+        let mut buf = [0 as c_char; KEY_DESCRIPTION_SIZE];
+        let mut insert_key = |c: EmacsInt| unsafe {
+            let end = push_key_description(c, buf.as_mut_ptr());
+            insert(buf.as_ptr(), end as isize - buf.as_ptr() as isize);
+        };
+
+        // Insertion may GC, so fetch the table afresh every time.
+        let translate_len = kboard.Vkeyboard_translate_table_.as_string_or_error().len_chars();
+        for c in 0..translate_len {
+            let table = kboard.Vkeyboard_translate_table_.as_string_or_error();
+            let translated = table.as_slice()[c as usize];
+            if EmacsInt::from(translated) != c as EmacsInt {
+                if let Some(heading) = heading.take() {
+                    insert_str(heading);
+                }
+                insert_key(EmacsInt::from(translated));
+                unsafe { Findent_to(LispObject::from(16), LispObject::from(1)) };
+                insert_key(c as EmacsInt);
+                insert_str("\n");
+            }
+        }
+
+        insert_str("\n");
+    }
+
+    let key_translation_map = unsafe { globals.Vkey_translation_map };
+    if key_translation_map.is_not_nil() {
+        describe(key_translation_map, false, Qnil, "Key translations", true, false);
+    }
+
+    // Print the (major mode) local map.
+    let overriding = if kboard.Voverriding_terminal_local_map_.is_not_nil() {
+        kboard.Voverriding_terminal_local_map_
+    } else {
+        unsafe { globals.Voverriding_local_map }
+    };
+
+    if overriding.is_not_nil() {
+        describe(overriding, true, shadow, "\x0c\nOverriding Bindings", false, false);
+        shadow = LispObject::cons(overriding, shadow);
+    } else {
+        // Temporarily switch to BUFFER, so that we can get that buffer's
+        // minor modes correctly.
+        set_buffer(buffer.as_lisp_obj().into());
+        let minor_maps: Vec<(LispObject, LispObject)> = unsafe {
+            let mut modes: *mut LispObject = ptr::null_mut();
+            let mut maps: *mut LispObject = ptr::null_mut();
+            let nmaps = current_minor_maps(&mut modes, &mut maps) as usize;
+            if nmaps == 0 {
+                Vec::new()
+            } else {
+                let modes = slice::from_raw_parts(modes, nmaps);
+                let maps = slice::from_raw_parts(maps, nmaps);
+                modes.iter().cloned().zip(maps.iter().cloned()).collect()
+            }
+        };
+        set_buffer(outbuf.as_lisp_obj().into());
+
+        let start1 = unsafe { get_local_map(buffer.pt, buffer.as_mut(), Qkeymap) };
+        if start1.is_not_nil() {
+            describe(start1, true, shadow, "\x0c\n`keymap' Property Bindings", false, false);
+            shadow = LispObject::cons(start1, shadow);
+        }
+
+        // Print the minor mode maps.
+        for (mode, map) in minor_maps {
+            let name = mode.as_symbol_or_error().symbol_name().as_string_or_error();
+            let title = format!(
+                "\x0c\n`{}' Minor Mode Bindings",
+                String::from_utf8_lossy(name.as_slice())
+            );
+            describe(map, true, shadow, &title, false, false);
+            shadow = LispObject::cons(map, shadow);
+        }
+
+        let start1 = unsafe { get_local_map(buffer.pt, buffer.as_mut(), Qlocal_map) };
+        if start1.is_not_nil() {
+            let title = if start1.eq(buffer.keymap_) {
+                "\x0c\nMajor Mode Bindings"
+            } else {
+                "\x0c\n`local-map' Property Bindings"
+            };
+            describe(start1, true, shadow, title, false, false);
+            shadow = LispObject::cons(start1, shadow);
+        }
+    }
+
+    describe(
+        current_global_map(),
+        true,
+        shadow,
+        "\x0c\nGlobal Bindings",
+        false,
+        true,
+    );
+
+    // Print the function-key-map translations under this prefix.
+    if kboard.Vlocal_function_key_map_.is_not_nil() {
+        describe(
+            kboard.Vlocal_function_key_map_,
+            false,
+            Qnil,
+            "\x0c\nFunction key map translations",
+            true,
+            false,
+        );
+    }
+
+    // Print the input-decode-map translations under this prefix.
+    if kboard.Vinput_decode_map_.is_not_nil() {
+        describe(
+            kboard.Vinput_decode_map_,
+            false,
+            Qnil,
+            "\x0c\nInput decoding map translations",
+            true,
+            false,
+        );
+    }
+}
+
+fn insert_str(s: &str) {
+    unsafe { insert(s.as_ptr() as *const c_char, s.len() as isize) };
+}
+
+/// Modifier prefixes accepted by `key-parse', with the names
+/// `event-convert-list' knows them by.
+const KEY_MODIFIERS: [(char, &str); 6] = [
+    ('A', "alt"),
+    ('C', "control"),
+    ('H', "hyper"),
+    ('M', "meta"),
+    ('S', "shift"),
+    ('s', "super"),
+];
+
+/// Keys that are written by name rather than as a single character.
+const NAMED_KEYS: [(&str, char); 7] = [
+    ("NUL", '\0'),
+    ("RET", '\r'),
+    ("LFD", '\n'),
+    ("TAB", '\t'),
+    ("ESC", '\x1b'),
+    ("SPC", ' '),
+    ("DEL", '\x7f'),
+];
+
+#[derive(Debug, PartialEq)]
+enum KeyBase<'a> {
+    Char(char),
+    Symbol(&'a str),
+}
+
+/// A single key of a key sequence in `key-parse' syntax.
+#[derive(Debug, PartialEq)]
+struct ParsedKey<'a> {
+    modifiers: Vec<&'static str>,
+    base: KeyBase<'a>,
+}
+
+/// Parse one space-separated word of a key sequence, such as "C-M-a"
+/// or "s-<f1>".
+fn parse_key(word: &str) -> Result<ParsedKey, String> {
+    let mut modifiers = Vec::new();
+    let mut rest = word;
+
+    // A modifier prefix is only a prefix if something follows it, so
+    // that "C--" is control-minus.
+    while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+        let prefix = rest.as_bytes()[0] as char;
+        let name = match KEY_MODIFIERS.iter().find(|&&(c, _)| c == prefix) {
+            Some(&(_, name)) => name,
+            None => break,
+        };
+        if modifiers.contains(&name) {
+            return Err(format!("duplicate modifier `{}-' in `{}'", prefix, word));
+        }
+        modifiers.push(name);
+        rest = &rest[2..];
+    }
+
+    let mut chars = rest.chars();
+    let base = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyBase::Char(c),
+        _ if rest.len() > 2 && rest.starts_with('<') && rest.ends_with('>') => {
+            let name = &rest[1..rest.len() - 1];
+            if !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return Err(format!("invalid function key name `{}' in `{}'", rest, word));
+            }
+            KeyBase::Symbol(name)
+        }
+        _ => match NAMED_KEYS.iter().find(|&&(name, _)| name == rest) {
+            Some(&(_, c)) => KeyBase::Char(c),
+            None if rest.is_empty() || rest.ends_with('-') => {
+                return Err(format!("missing key after the modifiers in `{}'", word));
+            }
+            None if rest.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') => {
+                return Err(format!(
+                    "`{}' is not a key; function keys are written as `<{}>'",
+                    rest, rest
+                ));
+            }
+            None => return Err(format!("invalid key `{}'", word)),
+        },
+    };
+
+    Ok(ParsedKey { modifiers, base })
+}
+
+/// Parse KEYS, a key sequence in `key-parse' syntax, into its keys.
+fn parse_key_sequence(keys: &str) -> Result<Vec<ParsedKey>, String> {
+    if keys.is_empty() {
+        return Err("empty key sequence".to_string());
+    }
+
+    keys.split(' ')
+        .enumerate()
+        .map(|(i, word)| {
+            if word.is_empty() {
+                Err(format!(
+                    "empty key at position {}; keys are separated by a single space",
+                    i + 1
+                ))
+            } else {
+                parse_key(word)
+            }
+        })
+        .collect()
+}
+
+impl<'a> ParsedKey<'a> {
+    fn to_event(&self) -> LispObject {
+        let base = match self.base {
+            KeyBase::Char(c) => LispObject::from(c as EmacsInt),
+            KeyBase::Symbol(name) => intern(name),
+        };
+        if self.modifiers.is_empty() {
+            return base;
+        }
+
+        let desc = self
+            .modifiers
+            .iter()
+            .rev()
+            .fold(list!(base), |desc, &name| LispObject::cons(intern(name), desc));
+        unsafe { Fevent_convert_list(desc) }
+    }
+}
+
+/// Convert KEYS to the internal Emacs key representation.
+/// KEYS should be a string that satisfies `key-valid-p', such as
+/// "C-x C-f" or "M-<f1>", and the return value is a vector of events
+/// in the format returned by `read-key-sequence'.  `key-description'
+/// converts such a vector back to the same string.
+///
+/// Unlike `kbd', this signals an error describing the first problem
+/// found if KEYS is not valid.
+#[lisp_fn]
+pub fn key_parse(keys: LispStringRef) -> LispObject {
+    let string = keys.to_string();
+    match parse_key_sequence(&string) {
+        Ok(parsed) => {
+            let mut events: Vec<LispObject> = parsed.iter().map(ParsedKey::to_event).collect();
+            unsafe { Fvector(events.len() as isize, events.as_mut_ptr()) }
+        }
+        Err(msg) => error!("Invalid key sequence \"{}\": {}", string, msg),
+    }
+}
+
+/// Say whether KEYS is a valid key sequence for `key-parse'.
+/// A key sequence is a string of keys separated by single spaces.
+/// Each key is one of:
+///
+/// - a single character, such as "a", "%" or "é";
+/// - one of the named keys NUL, RET, LFD, TAB, ESC, SPC and DEL;
+/// - a function key in angle brackets, such as "<f1>" or "<return>".
+///
+/// Each key may be preceded by any of the modifiers "A-" (alt), "C-"
+/// (control), "H-" (hyper), "M-" (meta), "S-" (shift) and "s-" (super),
+/// each at most once.
+#[lisp_fn]
+pub fn key_valid_p(keys: LispObject) -> bool {
+    keys.as_string()
+        .map_or(false, |s| parse_key_sequence(&s.to_string()).is_ok())
+}
+
 include!(concat!(env!("OUT_DIR"), "/keymap_exports.rs"));
@@ -86,16 +86,21 @@ mod fonts;
 mod frames;
 mod hashtable;
 mod indent;
+#[cfg(unix)]
+mod inotify;
 mod interactive;
 mod keyboard;
 mod keymap;
 mod libm;
+#[cfg(all(test, feature = "lisp-tests"))]
+mod lisp_test;
 mod lists;
 mod lread;
 mod marker;
 mod math;
 mod minibuf;
 mod multibyte;
+mod normalize;
 mod numbers;
 mod obarray;
 mod objects;
@@ -104,6 +109,7 @@ mod profiler;
 #[allow(clippy::all)]
 mod remacs_sys;
 mod search;
+mod segmentation;
 mod strings;
 mod symbols;
 mod syntax;
@@ -123,10 +129,12 @@ use alloc_unexecmacosx::OsxUnexecAlloc;
 #[global_allocator]
 static ALLOCATOR: OsxUnexecAlloc = OsxUnexecAlloc;
 
-#[cfg(not(test))]
+// The C side of Emacs, linked into the tests with `lisp-tests`, needs
+// the exported functions and `rust_init_syms` just like temacs does.
+#[cfg(any(not(test), feature = "lisp-tests"))]
 include!(concat!(env!("OUT_DIR"), "/c_exports.rs"));
 
-#[cfg(test)]
+#[cfg(all(test, not(feature = "lisp-tests")))]
 pub use crate::functions::{lispsym, make_string, make_unibyte_string, Fcons, Fsignal};
 
 #[cfg(feature = "compile-errors")]
@@ -20,7 +20,8 @@ use crate::{
         pvec_type, EmacsDouble, EmacsInt, EmacsUint, EqualKind, Lisp_Bits, USE_LSB_TAG, VALMASK,
     },
     remacs_sys::{Lisp_Misc_Any, Lisp_Misc_Type, Lisp_Subr, Lisp_Type},
-    remacs_sys::{Qautoload, Qlistp, Qnil, Qsubrp, Qt, Vbuffer_alist},
+    remacs_sys::{Qautoload, Qinvalid_keyword_argument, Qlistp, Qmissing_keyword_value},
+    remacs_sys::{Qnil, Qsubrp, Qt, Vbuffer_alist},
 };
 
 // TODO: tweak Makefile to rebuild C files if this changes.
@@ -63,6 +64,19 @@ impl LispObject {
         self.0 as EmacsUint
     }
 
+    /// Smuggle PTR through C functions such as `internal_catch` and
+    /// `internal_condition_case_1`, which pass a single Lisp argument on
+    /// to their body function.  The result is not a Lisp object: only
+    /// that body may see it, to get PTR back with `to_raw_ptr`.
+    pub fn from_raw_ptr<T>(ptr: *mut T) -> LispObject {
+        Self::from_C(ptr as EmacsInt)
+    }
+
+    /// Return the pointer smuggled in by `from_raw_ptr`.
+    pub fn to_raw_ptr<T>(self) -> *mut T {
+        self.0 as *mut T
+    }
+
     pub fn from_bool(v: bool) -> LispObject {
         if v {
             Qt
@@ -548,6 +562,46 @@ impl LispObject {
 /// of arguments.
 pub const MANY: i16 = -2;
 
+/// Used to denote special forms, whose arguments are passed
+/// unevaluated as a single list.
+pub const UNEVALLED: i16 = -1;
+
+/// A set of keyword arguments, as taken by a `#[lisp_fn(keys = "...")]`
+/// function.  Implement this with `#[derive(LispKeywords)]`: every field
+/// `foo_bar` becomes the keyword `:foo-bar`, which must be declared with
+/// `def_lisp_sym!(QCfoo_bar, ":foo-bar")`, and is converted from its
+/// value (or from nil, when the keyword is absent) with `From<LispObject>`.
+pub trait LispKeywords: Sized {
+    /// The keywords, in the order of the fields.
+    const KEYWORDS: &'static [LispObject];
+
+    fn from_keyword_args(args: &[LispObject]) -> Self;
+}
+
+/// Parse ARGS as a keyword-value list, storing the value of KEYWORDS[i]
+/// in VALUES[i].  Keywords that are not given are left alone; when a
+/// keyword is repeated, the first occurrence wins, as with `plist-get`.
+/// Signals `invalid-keyword-argument` for an unknown keyword and
+/// `missing-keyword-value` for a keyword without a value.
+pub fn parse_keyword_args(args: &[LispObject], keywords: &[LispObject], values: &mut [LispObject]) {
+    debug_assert_eq!(keywords.len(), values.len());
+    let mut seen = vec![false; keywords.len()];
+    for pair in args.chunks(2) {
+        let keyword = pair[0];
+        let i = match keywords.iter().position(|&k| k.eq(keyword)) {
+            Some(i) => i,
+            None => xsignal!(Qinvalid_keyword_argument, keyword),
+        };
+        if pair.len() < 2 {
+            xsignal!(Qmissing_keyword_value, keyword);
+        }
+        if !seen[i] {
+            seen[i] = true;
+            values[i] = pair[1];
+        }
+    }
+}
+
 /// Internal function to get a displayable string out of a Lisp string.
 fn display_string(obj: LispObject) -> String {
     let s = obj.as_string().unwrap();
@@ -0,0 +1,168 @@
+//! Running `#[lisp_test]` functions inside a live Lisp runtime.
+//!
+//! With the `lisp-tests` feature, the test binary is linked against the
+//! C side of Emacs (see `check-lisp-tests` in src/Makefile.in).  The
+//! first `#[lisp_test]` to run starts a thread that boots Emacs in batch
+//! mode, exactly like temacs does, but instead of entering the command
+//! loop that thread then runs the tests it is sent, one at a time.  Each
+//! test runs in a fresh temporary buffer, and a Lisp error that escapes
+//! it fails the test with the error's message.
+//!
+//! All tests run on the one thread because Emacs is single-threaded,
+//! and because the garbage collector only scans the stack of the thread
+//! that booted it.
+
+use libc::{c_char, c_int};
+use std::any::Any;
+use std::ffi::CString;
+use std::panic;
+use std::ptr;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::{
+    buffers::{set_buffer, LispBufferOrName},
+    lisp::LispObject,
+    remacs_sys::{internal_condition_case_1, lisp_test_runner, record_unwind_current_buffer},
+    remacs_sys::{Ferror_message_string, Fgenerate_new_buffer_name, Fget_buffer_create},
+    remacs_sys::{unbind_to, Fkill_buffer, Qnil, Qt},
+    threads::c_specpdl_index,
+};
+
+extern "C" {
+    /// The `main` of emacs.c, renamed when it is compiled for the tests.
+    fn emacs_main(argc: c_int, argv: *mut *mut c_char) -> c_int;
+}
+
+/// Emacs wants a deep stack, as its main thread usually has.
+const LISP_STACK_SIZE: usize = 64 * 1024 * 1024;
+
+type TestResult = Result<(), String>;
+
+struct TestRequest {
+    body: fn(),
+    reply: Sender<TestResult>,
+}
+
+lazy_static! {
+    static ref LISP_THREAD: Mutex<Sender<TestRequest>> = Mutex::new(start_lisp_thread());
+}
+
+/// The requests for the Lisp thread.  Only that thread touches this.
+static mut REQUESTS: Option<Receiver<TestRequest>> = None;
+
+/// Run BODY, the body of the `#[lisp_test]` NAME, on the Lisp thread and
+/// panic if it fails.
+pub fn run(name: &str, body: fn()) {
+    let (reply, result) = channel();
+    LISP_THREAD
+        .lock()
+        .unwrap()
+        .send(TestRequest { body, reply })
+        .unwrap();
+    match result.recv() {
+        Ok(Ok(())) => {}
+        Ok(Err(message)) => panic!("{}: {}", name, message),
+        Err(_) => panic!("{}: the Lisp thread died", name),
+    }
+}
+
+fn start_lisp_thread() -> Sender<TestRequest> {
+    let (sender, requests) = channel();
+    thread::Builder::new()
+        .name("lisp".to_string())
+        .stack_size(LISP_STACK_SIZE)
+        .spawn(move || {
+            let args: Vec<CString> = ["remacs-test", "--batch"]
+                .iter()
+                .map(|&arg| CString::new(arg).unwrap())
+                .collect();
+            let mut argv: Vec<*mut c_char> = args
+                .iter()
+                .map(|arg| arg.as_ptr() as *mut c_char)
+                .collect();
+            argv.push(ptr::null_mut());
+            unsafe {
+                REQUESTS = Some(requests);
+                lisp_test_runner = Some(serve_requests as unsafe extern "C" fn());
+                emacs_main(args.len() as c_int, argv.as_mut_ptr());
+            }
+        })
+        .unwrap();
+    sender
+}
+
+/// Called by `emacs_main` once Emacs is initialized, instead of the
+/// command loop.  This never returns while there are tests to run.
+extern "C" fn serve_requests() {
+    let requests = unsafe { REQUESTS.take().unwrap() };
+    for request in requests {
+        // The test thread may have given up on us; nothing to do then.
+        let _ = request.reply.send(run_in_temp_buffer(request.body));
+    }
+}
+
+/// The state of one test, shared with `run_test_body`.
+struct TestCall {
+    body: fn(),
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+fn run_in_temp_buffer(body: fn()) -> TestResult {
+    let count = c_specpdl_index();
+    unsafe { record_unwind_current_buffer() };
+    let name = unsafe { Fgenerate_new_buffer_name(LispObject::from(" *lisp-test*"), Qnil) };
+    let buffer = unsafe { Fget_buffer_create(name) };
+    set_buffer(LispBufferOrName::from(buffer));
+
+    let mut call = TestCall { body, panic: None };
+    let signal = unsafe {
+        internal_condition_case_1(
+            Some(run_test_body),
+            LispObject::from_raw_ptr(&mut call as *mut TestCall),
+            Qt,
+            Some(test_signalled),
+        )
+    };
+    let result = if let Some(payload) = call.panic {
+        Err(panic_message(payload.as_ref()))
+    } else if signal.is_nil() {
+        Ok(())
+    } else {
+        let message = unsafe { Ferror_message_string(signal) };
+        Err(format!("signalled {}", message.as_string_or_error()))
+    };
+
+    unsafe {
+        unbind_to(count, Qnil);
+        Fkill_buffer(buffer);
+    }
+    result
+}
+
+/// Run the test body.  A panic must not unwind through the C frames of
+/// `internal_condition_case_1`, so it is caught here and re-reported by
+/// `run_in_temp_buffer`.
+extern "C" fn run_test_body(arg: LispObject) -> LispObject {
+    let call = unsafe { &mut *arg.to_raw_ptr::<TestCall>() };
+    if let Err(payload) = panic::catch_unwind(call.body) {
+        call.panic = Some(payload);
+    }
+    Qnil
+}
+
+/// Return the error that escaped the test body, as `(SYMBOL . DATA)`.
+extern "C" fn test_signalled(err: LispObject) -> LispObject {
+    err
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panicked".to_string()
+    }
+}
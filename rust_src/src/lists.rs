@@ -442,7 +442,6 @@ pub fn rassq(key: LispObject, list: LispObject) -> LispObject {
 
 /// Return non-nil if KEY is `equal' to the cdr of an element of LIST.
 /// The value is actually the first element of LIST whose cdr equals KEY.
-/// (fn KEY LIST)
 #[lisp_fn]
 pub fn rassoc(key: LispObject, list: LispObject) -> LispObject {
     rassoc_impl(key, list, |k, item| k.eq(item) || k.equal(item))
@@ -658,25 +657,7 @@ pub fn safe_length(list: LispObject) -> usize {
     list.iter_tails_safe().count()
 }
 
-// Used by sort() in vectors.rs.
-
-pub fn sort_list(list: LispObject, pred: LispObject) -> LispObject {
-    let length = safe_length(list) as EmacsInt;
-    if length < 2 {
-        return list;
-    }
-
-    let item = nthcdr(length / 2 - 1, list);
-    let back = cdr(item);
-    setcdr(item.as_cons_or_error(), Qnil);
-
-    let front = sort_list(list, pred);
-    let back = sort_list(back, pred);
-    merge(front, back, pred)
-}
-
-// also needed by vectors.rs
-pub fn inorder(pred: LispObject, a: LispObject, b: LispObject) -> bool {
+fn inorder(pred: LispObject, a: LispObject, b: LispObject) -> bool {
     call!(pred, b, a).is_nil()
 }
 
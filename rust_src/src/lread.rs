@@ -1,28 +1,61 @@
 //! Lisp parsing and input streams.
 
 use field_offset::FieldOffset;
-use libc;
+use libc::{self, c_char, c_int, c_void, ptrdiff_t};
 use std::ffi::CString;
 use std::ptr;
+use std::slice;
 
 use remacs_macros::lisp_fn;
 
 use crate::{
+    buffers::{set_buffer, LispBufferOrName},
+    chartable::chartab_size,
     data::{
         Lisp_Boolfwd, Lisp_Buffer_Objfwd, Lisp_Fwd, Lisp_Fwd_Bool, Lisp_Fwd_Buffer_Obj,
         Lisp_Fwd_Int, Lisp_Fwd_Kboard_Obj, Lisp_Fwd_Obj, Lisp_Intfwd, Lisp_Kboard_Objfwd,
         Lisp_Objfwd,
     },
+    editfns::{goto_char, point, point_max},
+    fns::concat,
+    hashtable::{
+        puthash,
+        HashLookupResult::{Found, Missing},
+    },
     lisp::{defsubr, LispObject},
-    obarray::{intern, intern_c_string_1},
+    lists::{car, car_safe, cdr, cdr_safe, list, memq, nth, plist_get, LispCons},
+    multibyte::{
+        char_byte8_p, char_to_byte8, is_ascii, is_single_byte_char, multibyte_char_at,
+        multibyte_chars_in_text, raw_byte_codepoint, str_as_unibyte, write_codepoint, Codepoint,
+        MAX_CHAR, MAX_MULTIBYTE_LENGTH,
+    },
+    numbers::MOST_POSITIVE_FIXNUM,
+    obarray::{check_obarray, intern, intern_c_string_1, intern_driver},
     remacs_sys,
     remacs_sys::{
-        build_string, read_internal_start, readevalloop, specbind, staticpro, symbol_redirect,
-        unbind_to, Fcons,
+        bool_vector_uchar_data, build_string, char_bits, char_table_specials,
+        empty_unibyte_string, get_lazy_string, internal_condition_case_1, make_buffer_string,
+        make_byte_code, make_pure_string, make_specified_string, make_unibyte_string,
+        make_uninit_bool_vector, make_uninit_sub_char_table, oblookup, pvec_type,
+        read_from_string_setup, readchar, readevalloop, record_unwind_current_buffer,
+        skip_doc_string, skip_dyn_eof, specbind, staticpro, string_intervals, string_overflow,
+        string_to_number, symbol_redirect, traverse_intervals_noorder, unbind_to, unreadchar,
+        Fcons, Fdecode_coding_string, Ferror_message_string, Ffile_name_nondirectory,
+        Fmake_hash_table, Fmake_record, Fmake_symbol, Fmake_vector, Fnreverse,
+        Fread_from_string, Fset_text_properties, Lisp_Compiled, More_Lisp_Bits,
+        BOOL_VECTOR_BITS_PER_CHAR, INTERVAL, PSEUDOVECTOR_FLAG,
     },
     remacs_sys::{globals, EmacsInt},
-    remacs_sys::{Qeval_buffer_list, Qnil, Qread_char, Qstandard_output, Qsymbolp},
+    remacs_sys::{
+        QCpurecopy, QCrehash_size, QCrehash_threshold, QCsize, QCtest, QCweakness, Qbackquote,
+        Qchar_from_name, Qcomma, Qcomma_at, Qcomma_dot, Qdata, Qemacs_mule, Qend_of_file, Qeq,
+        Qerror, Qeval_buffer_list, Qfunction, Qget_emacs_mule_file_char, Qhash_table,
+        Qinvalid_read_syntax, Qlistp, Qnil, Qpurecopy, Qquote, Qread_char, Qrehash_size,
+        Qrehash_threshold, Qsequencep, Qsize, Qstandard_output, Qsymbolp, Qt, Qtest, Qweakness,
+    },
+    strings::string_as_multibyte,
     threads::{c_specpdl_index, ThreadState},
+    vectors::length,
 };
 
 // Define an "integer variable"; a symbol whose value is forwarded to a
@@ -153,6 +186,1429 @@ pub unsafe fn defvar_per_buffer_offset(
     }
 }
 
+/// Signal an `end-of-file' error, if possible with file name
+/// information.
+fn end_of_file_error() -> ! {
+    let file_name = unsafe { globals.Vload_file_name };
+    if file_name.is_string() {
+        xsignal!(Qend_of_file, file_name);
+    }
+    xsignal!(Qend_of_file);
+}
+
+/// Signal an `invalid-read-syntax' error with message S.
+fn invalid_syntax(s: &str) -> ! {
+    xsignal!(Qinvalid_read_syntax, LispObject::from(s));
+}
+
+/// No-break space, which the reader treats as whitespace.
+const NO_BREAK_SPACE: Codepoint = 0xA0;
+
+/// Bound on the length of a Unicode character name.  As of Unicode
+/// 9.0.0 the maximum is 83, so this should be safe.
+const UNICODE_CHARACTER_NAME_LENGTH_BOUND: usize = 200;
+
+/// Room for a sign, a leading 0 and the digits of a 64-bit number.
+const INTEGER_LENGTH_BOUND: usize = 1 + 1 + 64;
+
+/// Return true if C is one of the ASCII characters in CHARS.
+fn is_one_of(c: Codepoint, chars: &[u8]) -> bool {
+    c < 0x80 && chars.contains(&(c as u8))
+}
+
+/// Return true if C can continue a symbol or number.
+fn is_symbol_constituent(c: Codepoint) -> bool {
+    c > 0o40 && c != NO_BREAK_SPACE && !is_one_of(c, b"\"';()[]#`,")
+}
+
+/// Return true if C is whitespace, as far as character names go.
+fn is_c_space(c: Codepoint) -> bool {
+    is_one_of(c, b" \t\n\x0b\x0c\r")
+}
+
+/// Return the digit that C stands for in the given BASE.  Return -1
+/// if C is out of range for BASE, and -2 if C is not valid for any
+/// supported BASE.
+fn digit_to_number(c: Option<Codepoint>, base: EmacsInt) -> i32 {
+    match c.and_then(std::char::from_u32).and_then(|c| c.to_digit(36)) {
+        Some(digit) if EmacsInt::from(digit) < base => digit as i32,
+        Some(_) => -1,
+        None => -2,
+    }
+}
+
+/// Return the value of C as a hexadecimal digit, if it is one.
+fn hex_digit_value(c: Option<Codepoint>) -> Option<Codepoint> {
+    c.and_then(std::char::from_u32).and_then(|c| c.to_digit(16))
+}
+
+/// Convert the NUL-free text in BUF to a number in BASE, as
+/// `string_to_number' does, or return nil.
+fn buffer_to_number(buf: &[u8], base: EmacsInt) -> LispObject {
+    let cstr = CString::new(buf).unwrap();
+    unsafe { string_to_number(cstr.as_ptr(), base as c_int, false) }
+}
+
+/// Return the scalar value that has the Unicode character name NAME.
+/// Raise `invalid-read-syntax' if there is no such character.
+fn character_name_to_code(name: &[u8]) -> Codepoint {
+    // For "U+XXXX", pass the leading '+' to string_to_number to
+    // reject monstrosities like "U+-0000".
+    let code = if name.starts_with(b"U+") {
+        buffer_to_number(&name[1..], 16)
+    } else {
+        let string = unsafe {
+            make_unibyte_string(name.as_ptr() as *const c_char, name.len() as ptrdiff_t)
+        };
+        call!(Qchar_from_name, string, Qt)
+    };
+
+    match code.as_fixnum() {
+        Some(c) if 0 <= c && c <= 0x10_FFFF && !(0xD800 <= c && c <= 0xDFFF) => c as Codepoint,
+        _ => {
+            let message = format!("\\N{{{}}}", String::from_utf8_lossy(name));
+            xsignal!(Qinvalid_read_syntax, LispObject::from(message.as_str()));
+        }
+    }
+}
+
+/// Where a datum starts in the text being read.
+#[derive(Clone, Copy)]
+struct Position {
+    /// Line number, counting from 1.
+    line: EmacsInt,
+    /// Column in characters, counting from 0.
+    column: EmacsInt,
+    /// Byte offset from where reading started.
+    offset: EmacsInt,
+}
+
+impl Position {
+    fn start() -> Self {
+        Self {
+            line: 1,
+            column: 0,
+            offset: 0,
+        }
+    }
+
+    /// Move past C, which was read from a multibyte source if
+    /// MULTIBYTE.
+    fn advance(&mut self, c: Codepoint, multibyte: bool) {
+        self.offset += if multibyte {
+            let mut buf = [0; MAX_MULTIBYTE_LENGTH];
+            write_codepoint(&mut buf, c) as EmacsInt
+        } else {
+            1
+        };
+        if c == Codepoint::from(b'\n') {
+            self.line += 1;
+            self.column = 0;
+        } else {
+            self.column += 1;
+        }
+    }
+}
+
+/// What `read1' found: either a datum, or one of the tokens `)', `]'
+/// and `.' that end a list or a vector.
+#[derive(Clone, Copy)]
+enum Token {
+    Datum(LispObject),
+    Close(char),
+}
+
+/// How `read_list' treats a list whose first element is `#$'.
+#[derive(PartialEq)]
+enum DocReference {
+    /// An ordinary list.
+    Plain,
+    /// A reference to a doc string in etc/DOC; read it as 0.
+    Zero,
+    /// A reference to a doc string in the file being loaded; read it
+    /// as the string itself.
+    Lazy,
+}
+
+/// The state of one top-level read.  A `Reader' lives on the stack,
+/// so the objects it refers to are protected from GC.
+struct Reader {
+    readcharfun: LispObject,
+    /// Number of characters read so far, for
+    /// `read-symbol-positions-list'.
+    count: EmacsInt,
+    /// True while reading the body of a new-style backquote.
+    new_backquote: bool,
+    /// Maps each N of `#N=' to its object, or to a placeholder while
+    /// the object is being read.  nil until `#N=' is first seen.
+    objects_map: LispObject,
+    /// The objects read with `#N=' that may be part of a cycle.
+    objects_completed: LispObject,
+    /// Where the next character starts, and where the last one did, so
+    /// that a character can be unread.
+    pos: Position,
+    prev: Position,
+    /// Whether to record the position of every datum read.
+    record_positions: bool,
+    /// The recorded entries (DATUM LINE COLUMN OFFSET), most recently
+    /// completed datum first.
+    positions: LispObject,
+}
+
+impl Reader {
+    fn new(readcharfun: LispObject) -> Self {
+        Self {
+            readcharfun,
+            count: 0,
+            new_backquote: false,
+            objects_map: Qnil,
+            objects_completed: Qnil,
+            pos: Position::start(),
+            prev: Position::start(),
+            record_positions: false,
+            positions: Qnil,
+        }
+    }
+
+    /// Read one object, setting up the global state that toplevel
+    /// reads need.  START and END are only used when reading from a
+    /// string.
+    fn read_toplevel(&mut self, start: LispObject, end: LispObject) -> LispObject {
+        let stream = self.readcharfun;
+        let symbol_positions = self.records_symbol_positions();
+        if symbol_positions {
+            unsafe { globals.Vread_symbol_positions_list = Qnil };
+        }
+
+        if stream.is_string() || stream.as_cons().map_or(false, |c| c.car().is_string()) {
+            unsafe { read_from_string_setup(stream, start, end) };
+        }
+
+        let val = self.read0();
+        if symbol_positions {
+            unsafe {
+                globals.Vread_symbol_positions_list =
+                    Fnreverse(globals.Vread_symbol_positions_list)
+            };
+        }
+        val
+    }
+
+    fn records_symbol_positions(&self) -> bool {
+        let with_positions = unsafe { globals.Vread_with_symbol_positions };
+        with_positions.is_t() || with_positions.eq(self.readcharfun)
+    }
+
+    /// Read a character, and whether the source is multibyte.
+    fn read_char_report_multibyte(&mut self) -> (Option<Codepoint>, bool) {
+        let mut multibyte = false;
+        let c = unsafe { readchar(self.readcharfun, &mut multibyte) };
+        self.count += 1;
+        self.prev = self.pos;
+        if c < 0 {
+            (None, multibyte)
+        } else {
+            self.pos.advance(c as Codepoint, multibyte);
+            (Some(c as Codepoint), multibyte)
+        }
+    }
+
+    fn read_char(&mut self) -> Option<Codepoint> {
+        self.read_char_report_multibyte().0
+    }
+
+    fn read_char_or_eof_error(&mut self) -> Codepoint {
+        self.read_char().unwrap_or_else(|| end_of_file_error())
+    }
+
+    /// Unread C, the last character read.  Only one character can be
+    /// unread in a row.
+    fn unread(&mut self, c: Option<Codepoint>) {
+        self.count -= 1;
+        self.pos = self.prev;
+        unsafe { unreadchar(self.readcharfun, c.map_or(-1, |c| c as c_int)) };
+    }
+
+    fn record_position(&mut self, object: LispObject, start: Position) {
+        if self.record_positions {
+            let entry = list!(
+                object,
+                LispObject::from_fixnum(start.line),
+                LispObject::from_fixnum(start.column),
+                LispObject::from_fixnum(start.offset)
+            );
+            self.positions = LispObject::cons(entry, self.positions);
+        }
+    }
+
+    /// Drop the entry just recorded for OBJECT, whose position is
+    /// recorded by the caller instead.
+    fn forget_position(&mut self, object: LispObject) {
+        if let Some(last) = self.positions.as_cons() {
+            if car(last.car()).eq(object) {
+                self.positions = last.cdr();
+            }
+        }
+    }
+
+    /// Use this for recursive reads, in contexts where internal tokens
+    /// are not allowed.
+    fn read0(&mut self) -> LispObject {
+        match self.read1(false) {
+            Token::Datum(val) => val,
+            Token::Close(c) => {
+                xsignal!(Qinvalid_read_syntax, LispObject::from(c.to_string().as_str()));
+            }
+        }
+    }
+
+    /// Read a datum or a closing token, skipping whitespace and
+    /// comments.  FIRST_IN_LIST is true if this is the first element of
+    /// a list.
+    fn read1(&mut self, first_in_list: bool) -> Token {
+        loop {
+            let (c, multibyte) = self.read_char_report_multibyte();
+            let c = c.unwrap_or_else(|| end_of_file_error());
+            let start = self.prev;
+            if let Some(token) = self.read_token(c, multibyte, first_in_list) {
+                if let Token::Datum(object) = token {
+                    self.record_position(object, start);
+                }
+                return token;
+            }
+        }
+    }
+
+    /// Read the token that starts with C.  Return None if there was
+    /// nothing but whitespace or a comment to read.
+    fn read_token(&mut self, c: Codepoint, multibyte: bool, first_in_list: bool) -> Option<Token> {
+        let val = match std::char::from_u32(c) {
+            Some('(') => self.read_list(false),
+            Some('[') => self.read_vector(false),
+            Some(c @ ')') | Some(c @ ']') => return Some(Token::Close(c)),
+            Some('#') => return self.read_hash_syntax(multibyte, first_in_list),
+            Some(';') => {
+                while let Some(c) = self.read_char() {
+                    if c == Codepoint::from(b'\n') {
+                        break;
+                    }
+                }
+                return None;
+            }
+            Some('\'') => list!(Qquote, self.read0()),
+            Some('`') => {
+                let next_char = self.read_char();
+                self.unread(next_char);
+                // Transition from old-style to new-style: if we see "(`"
+                // it used to mean old-style.  We distinguish the two by
+                // the fact that we can either write "( `foo" or "(` foo",
+                // where the first intends to use new-style whereas the
+                // second intends to use old-style.
+                if !self.new_backquote && first_in_list && next_char == Some(0o40) {
+                    unsafe { globals.Vlread_old_style_backquotes = Qt };
+                    self.read_atom(c, multibyte, false)
+                } else {
+                    let saved_new_backquote = self.new_backquote;
+                    self.new_backquote = true;
+                    let value = self.read0();
+                    self.new_backquote = saved_new_backquote;
+                    list!(Qbackquote, value)
+                }
+            }
+            Some(',') => {
+                let next_char = self.read_char();
+                self.unread(next_char);
+                // Old-style unquotes are only recognized when first on a
+                // list, and when followed by a space.  Because it's more
+                // difficult to peek 2 chars ahead, a new-style ,@ can
+                // still not be used outside of a `, unless it's in the
+                // middle of a list.
+                if self.new_backquote
+                    || !first_in_list
+                    || (next_char != Some(0o40) && next_char != Some(Codepoint::from(b'@')))
+                {
+                    let ch = self.read_char();
+                    let comma_type = if ch == Some(Codepoint::from(b'@')) {
+                        Qcomma_at
+                    } else if ch == Some(Codepoint::from(b'.')) {
+                        Qcomma_dot
+                    } else {
+                        if ch.is_some() {
+                            self.unread(ch);
+                        }
+                        Qcomma
+                    };
+                    list!(comma_type, self.read0())
+                } else {
+                    unsafe { globals.Vlread_old_style_backquotes = Qt };
+                    self.read_atom(c, multibyte, false)
+                }
+            }
+            Some('?') => self.read_character_literal(),
+            Some('"') => self.read_string(),
+            Some('.') => {
+                let next_char = self.read_char();
+                self.unread(next_char);
+                if next_char.map_or(true, |c| c <= 0o40 || is_one_of(c, b"\"';([#?`,")) {
+                    return Some(Token::Close('.'));
+                }
+                // The atom-reading loop will now read at least once,
+                // assuring that we will not try to unread two characters
+                // in a row.
+                self.read_atom(c, multibyte, false)
+            }
+            _ => {
+                if c <= 0o40 || c == NO_BREAK_SPACE {
+                    return None;
+                }
+                self.read_atom(c, multibyte, false)
+            }
+        };
+        Some(Token::Datum(val))
+    }
+
+    /// Read the syntax that starts with `#'.  Return None if the syntax
+    /// was skipped.
+    fn read_hash_syntax(&mut self, multibyte: bool, first_in_list: bool) -> Option<Token> {
+        let c = self.read_char();
+        let val = match c.and_then(std::char::from_u32) {
+            Some('s') => {
+                let c = self.read_char();
+                if c != Some(Codepoint::from(b'(')) {
+                    self.unread(c);
+                    invalid_syntax("#");
+                }
+                self.read_record_or_hash_table()
+            }
+            Some('^') => match self.read_char().and_then(std::char::from_u32) {
+                Some('[') => self.read_char_table(),
+                Some('^') => match self.read_char().and_then(std::char::from_u32) {
+                    Some('[') => self.read_sub_char_table(),
+                    _ => invalid_syntax("#^^"),
+                },
+                _ => invalid_syntax("#^"),
+            },
+            Some('&') => self.read_bool_vector(first_in_list),
+            Some('[') => {
+                // Accept compiled functions at read-time so that we don't
+                // have to build them using function calls.
+                let tmp = self.read_vector(true);
+                let mut vec = tmp.as_vector_or_error();
+                if vec.len() == 0 {
+                    invalid_syntax("Empty byte-code object");
+                }
+                unsafe { make_byte_code(vec.as_mut()) };
+                tmp
+            }
+            Some('(') => self.read_propertized_string(),
+            Some('@') => {
+                // #@NUMBER is used to skip NUMBER following bytes.  That's
+                // used in .elc files to skip over doc strings and function
+                // definitions.
+                let mut nskip: ptrdiff_t = 0;
+                let mut digits = 0;
+                let mut c = self.read_char();
+                while let Some(digit) = c.filter(|&c| c < 0x80 && (c as u8).is_ascii_digit()) {
+                    nskip = nskip
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add((digit - Codepoint::from(b'0')) as ptrdiff_t))
+                        .unwrap_or_else(|| unsafe { string_overflow() });
+                    digits += 1;
+                    if digits == 2 && nskip == 0 {
+                        // We've just seen #@00, which means "skip to end".
+                        unsafe { skip_dyn_eof(self.readcharfun) };
+                        return Some(Token::Datum(Qnil));
+                    }
+                    c = self.read_char();
+                }
+                if nskip > 0 {
+                    // We can't unread here, because skip_doc_string
+                    // side-steps readchar.  Instead, assume the first char
+                    // after #@NNN occupies a single byte, which is the case
+                    // normally since it's just a space.
+                    nskip -= 1;
+                } else {
+                    self.unread(c);
+                }
+                unsafe { skip_doc_string(self.readcharfun, nskip) };
+                // The skipped bytes are not seen by `read_char', so only
+                // the byte offset can account for them.
+                self.pos.offset += nskip as EmacsInt;
+                return None;
+            }
+            Some('!') => {
+                // #! appears at the beginning of an executable file.
+                // Skip the first line.
+                while let Some(c) = self.read_char() {
+                    if c == Codepoint::from(b'\n') {
+                        break;
+                    }
+                }
+                return None;
+            }
+            Some('$') => unsafe { globals.Vload_file_name },
+            Some('\'') => list!(Qfunction, self.read0()),
+            Some(':') => {
+                // #:foo is the uninterned symbol named foo.
+                match self.read_char() {
+                    Some(c) if is_symbol_constituent(c) => self.read_atom(c, multibyte, true),
+                    c => {
+                        // No symbol character follows, this is the empty
+                        // symbol.
+                        self.unread(c);
+                        unsafe { Fmake_symbol(empty_unibyte_string) }
+                    }
+                }
+            }
+            // ## is the empty symbol.
+            Some('#') => intern(""),
+            Some('0'..='9') => self.read_labeled_object(c),
+            Some('x') | Some('X') => self.read_integer(16),
+            Some('o') | Some('O') => self.read_integer(8),
+            Some('b') | Some('B') => self.read_integer(2),
+            _ => {
+                self.unread(c);
+                invalid_syntax("#");
+            }
+        };
+        Some(Token::Datum(val))
+    }
+
+    /// Read the forms that start with `#' followed by the digit C:
+    /// `#N=', `#N#' and `#NrDIGITS'.
+    fn read_labeled_object(&mut self, mut c: Option<Codepoint>) -> LispObject {
+        let mut n: EmacsInt = 0;
+        let mut overflow = false;
+
+        // Read a non-negative integer.
+        loop {
+            match digit_to_number(c, 10) {
+                digit if digit >= 0 => {
+                    match n.checked_mul(10).and_then(|n| n.checked_add(EmacsInt::from(digit))) {
+                        Some(m) => n = m,
+                        None => overflow = true,
+                    }
+                    c = self.read_char();
+                }
+                _ => break,
+            }
+        }
+
+        if !overflow && n <= MOST_POSITIVE_FIXNUM {
+            if c == Some(Codepoint::from(b'r')) || c == Some(Codepoint::from(b'R')) {
+                return self.read_integer(n);
+            }
+
+            if unsafe { globals.Vread_circle }.is_not_nil() {
+                // #n=object returns object, but associates it with n for
+                // #n#.
+                if c == Some(Codepoint::from(b'=')) {
+                    return self.read_labeled_definition(n);
+                }
+
+                // #n# returns a previously read object.
+                if c == Some(Codepoint::from(b'#')) && self.objects_map.is_hash_table() {
+                    let map = self.objects_map.as_hash_table_or_error();
+                    if let Found(i) = map.lookup(LispObject::from(n)) {
+                        return map.get_hash_value(i);
+                    }
+                }
+            }
+        }
+
+        self.unread(c);
+        invalid_syntax("#");
+    }
+
+    /// Read the object after `#N=' and associate it with N.
+    fn read_labeled_definition(&mut self, n: EmacsInt) -> LispObject {
+        if !self.objects_map.is_hash_table() {
+            let mut args = [QCtest, Qeq];
+            unsafe {
+                self.objects_map = Fmake_hash_table(args.len() as isize, args.as_mut_ptr());
+                self.objects_completed = Fmake_hash_table(args.len() as isize, args.as_mut_ptr());
+            }
+        }
+        let map = self.objects_map.as_hash_table_or_error();
+        let completed = self.objects_completed.as_hash_table_or_error();
+
+        // Make a placeholder for #n# to use temporarily.
+        let placeholder = LispObject::cons(Qnil, Qnil);
+        let number = LispObject::from(n);
+        match map.lookup(number) {
+            // Not normal, but input could be malformed.
+            Found(i) => map.set_hash_value(i, placeholder),
+            Missing(hash) => {
+                map.put(number, placeholder, hash);
+            }
+        }
+
+        // Read the object itself.  Its position is that of the label.
+        let tem = self.read0();
+        self.forget_position(tem);
+
+        // If it can be recursive, remember it for future substitutions.
+        if !tem.is_symbol()
+            && !tem.is_number()
+            && !(tem.is_string() && unsafe { string_intervals(tem) }.is_null())
+        {
+            if let Missing(hash) = completed.lookup(tem) {
+                completed.put(tem, Qnil, hash);
+            }
+        }
+
+        // Now put it everywhere the placeholder was...
+        if let Some(cons) = tem.as_cons() {
+            let placeholder = placeholder.as_cons_or_error();
+            placeholder.set_car(cons.car());
+            placeholder.set_cdr(cons.cdr());
+            placeholder.as_obj()
+        } else {
+            lread__substitute_object_in_subtree(tem, placeholder, self.objects_completed);
+
+            // ...and #n# will use the real value from now on.
+            if let Found(i) = map.lookup(number) {
+                map.set_hash_value(i, tem);
+            }
+            tem
+        }
+    }
+
+    /// Read `#s(...)': a hash table if the first element is
+    /// `hash-table', and a record otherwise.
+    fn read_record_or_hash_table(&mut self) -> LispObject {
+        let tmp = self.read_list(false);
+        let head = car_safe(tmp);
+
+        if !head.eq(Qhash_table) {
+            let size = length(tmp).as_fixnum_or_error();
+            let record = unsafe { Fmake_record(head, LispObject::from(size - 1), Qnil) };
+            let mut slots = record.as_vectorlike_or_error().as_record().unwrap();
+            for (i, elt) in tmp.iter_cars().enumerate().skip(1) {
+                slots.set(i, elt);
+            }
+            return record;
+        }
+
+        // Accept extended format for hash tables (extensible to other
+        // types), e.g.
+        // #s(hash-table size 2 test equal data (k1 v1 k2 v2))
+        let plist = cdr_safe(tmp);
+        let keywords = [
+            (QCsize, Qsize),
+            (QCtest, Qtest),
+            (QCweakness, Qweakness),
+            (QCrehash_size, Qrehash_size),
+            (QCrehash_threshold, Qrehash_threshold),
+            (QCpurecopy, Qpurecopy),
+        ];
+        let mut params = [Qnil; 12];
+        let mut param_count = 0;
+        for &(keyword, name) in keywords.iter() {
+            let value = plist_get(plist, name);
+            if value.is_not_nil() {
+                params[param_count] = keyword;
+                params[param_count + 1] = value;
+                param_count += 2;
+            }
+        }
+
+        // Now use params to make a new hash table and fill it.
+        let ht = unsafe { Fmake_hash_table(param_count as isize, params.as_mut_ptr()) };
+        let table = ht.as_hash_table_or_error();
+        let mut data = plist_get(plist, Qdata);
+        while let Some(cell) = data.as_cons() {
+            let (key, rest) = cell.as_tuple();
+            let (val, rest) = match rest.as_cons() {
+                Some(cell) => cell.as_tuple(),
+                None => error!("Odd number of elements in hash table data"),
+            };
+            puthash(key, val, table);
+            data = rest;
+        }
+        ht
+    }
+
+    /// Read the rest of `#^[...]'.
+    fn read_char_table(&mut self) -> LispObject {
+        let tmp = self.read_vector(false);
+        let mut vec = tmp.as_vector_or_error();
+        if vec.len() < char_table_specials::CHAR_TABLE_STANDARD_SLOTS as usize {
+            error!("Invalid size char-table");
+        }
+        unsafe {
+            vec.header.size |= (PSEUDOVECTOR_FLAG
+                | ((pvec_type::PVEC_CHAR_TABLE as usize)
+                    << More_Lisp_Bits::PSEUDOVECTOR_AREA_BITS))
+                as isize;
+        }
+        tmp
+    }
+
+    /// Read the rest of `#^^[...]'.  A sub char-table can't be read as
+    /// a regular vector because of its two C integer fields.
+    fn read_sub_char_table(&mut self) -> LispObject {
+        let tmp = self.read_list(true);
+        let size = length(tmp).as_fixnum_or_error() as usize;
+        if size == 0 {
+            error!("Zero-sized sub char-table");
+        }
+
+        let depth = match car(tmp).as_fixnum() {
+            Some(depth) if 1 <= depth && depth <= 3 => depth as i32,
+            _ => error!("Invalid depth in sub char-table"),
+        };
+        if size < 2 || chartab_size(depth) != size - 2 {
+            error!("Invalid size in sub char-table");
+        }
+        let tmp = cdr(tmp);
+
+        let min_char = match car(tmp).as_fixnum() {
+            Some(c) if 0 <= c && c <= EmacsInt::from(MAX_CHAR) => c as i32,
+            _ => error!("Invalid minimum character in sub-char-table"),
+        };
+        let tmp = cdr(tmp);
+
+        let tbl = unsafe { make_uninit_sub_char_table(depth, min_char) };
+        let mut sub = tbl.as_sub_char_table().unwrap();
+        let contents = unsafe { sub.contents.as_mut_slice(size - 2) };
+        for (slot, elt) in contents.iter_mut().zip(tmp.iter_cars()) {
+            *slot = elt;
+        }
+        tbl
+    }
+
+    /// Read the rest of `#&N"DATA"'.
+    fn read_bool_vector(&mut self, first_in_list: bool) -> LispObject {
+        let length = match self.read1(first_in_list) {
+            Token::Datum(length) => length.as_natnum_or_error() as EmacsInt,
+            Token::Close(_) => invalid_syntax("#&..."),
+        };
+        let c = self.read_char();
+        if c != Some(Codepoint::from(b'"')) {
+            invalid_syntax("#&...");
+        }
+        self.unread(c);
+
+        let tmp = match self.read1(first_in_list) {
+            Token::Datum(tmp) if tmp.is_string() => tmp.as_string_or_error(),
+            _ => invalid_syntax("#&..."),
+        };
+        let bits_per_char = EmacsInt::from(BOOL_VECTOR_BITS_PER_CHAR);
+        let size_in_chars = (length + bits_per_char - 1) / bits_per_char;
+        let nchars = tmp.len_chars() as EmacsInt;
+        if tmp.is_multibyte()
+            || (size_in_chars != nchars
+                // We used to print 1 char too many when the number of
+                // bits was a multiple of 8.  Accept such input in case it
+                // came from an old version.
+                && length != (nchars - 1) * bits_per_char)
+        {
+            invalid_syntax("#&...");
+        }
+
+        let val = unsafe { make_uninit_bool_vector(length) };
+        let size = size_in_chars as usize;
+        let data = unsafe { slice::from_raw_parts_mut(bool_vector_uchar_data(val), size) };
+        data.copy_from_slice(&tmp.as_slice()[..size]);
+        // Clear the extraneous bits in the last byte.
+        if length != size_in_chars * bits_per_char {
+            data[size - 1] &= (1 << (length % bits_per_char)) - 1;
+        }
+        val
+    }
+
+    /// Read the rest of `#("STRING" START END PLIST...)'.
+    fn read_propertized_string(&mut self) -> LispObject {
+        // Read the string itself.
+        let tmp = match self.read1(false) {
+            Token::Datum(tmp) if tmp.is_string() => tmp,
+            _ => invalid_syntax("#"),
+        };
+        // Read the intervals and their properties.
+        loop {
+            let beg = match self.read1(false) {
+                Token::Datum(beg) => beg,
+                Token::Close(')') => return tmp,
+                Token::Close(_) => invalid_syntax("Invalid string property list"),
+            };
+            let mut prop = || match self.read1(false) {
+                Token::Datum(val) => val,
+                Token::Close(_) => invalid_syntax("Invalid string property list"),
+            };
+            let end = prop();
+            let plist = prop();
+            unsafe { Fset_text_properties(beg, end, plist, tmp) };
+        }
+    }
+
+    /// Read a `\'-escape sequence, assuming we already read the `\'.
+    /// If the escape sequence forces unibyte, return an eight-bit char.
+    /// Return None for `\' followed by a newline, or in a string by a
+    /// space; such an escape stands for nothing.
+    fn read_escape(&mut self, stringp: bool) -> Option<Codepoint> {
+        let c = self.read_char_or_eof_error();
+        // \u allows up to four hex digits, \U up to eight.
+        let mut unicode_hex_count = 4;
+
+        let value = match std::char::from_u32(c) {
+            Some('a') => 0o7,
+            Some('b') => 0o10,
+            Some('d') => 0o177,
+            Some('e') => 0o33,
+            Some('f') => 0o14,
+            Some('n') => 0o12,
+            Some('r') => 0o15,
+            Some('t') => 0o11,
+            Some('v') => 0o13,
+            Some('\n') => return None,
+            Some(' ') if stringp => return None,
+            Some('M') => self.read_modified_escape(char_bits::CHAR_META),
+            Some('S') => self.read_modified_escape(char_bits::CHAR_SHIFT),
+            Some('H') => self.read_modified_escape(char_bits::CHAR_HYPER),
+            Some('A') => self.read_modified_escape(char_bits::CHAR_ALT),
+            Some('s') => {
+                let c = self.read_char();
+                if stringp || c != Some(Codepoint::from(b'-')) {
+                    self.unread(c);
+                    Codepoint::from(b' ')
+                } else {
+                    self.read_escape_operand() | char_bits::CHAR_SUPER
+                }
+            }
+            Some('C') | Some('^') => {
+                if c == Codepoint::from(b'C') && self.read_char() != Some(Codepoint::from(b'-')) {
+                    error!("Invalid escape character syntax");
+                }
+                let c = self.read_escape_operand();
+                let modifiers = c & char_bits::CHAR_MODIFIER_MASK;
+                // ASCII control chars are made from letters (both cases),
+                // as well as the non-letters within 0100...0137.
+                if c & !char_bits::CHAR_MODIFIER_MASK == Codepoint::from(b'?') {
+                    0o177 | modifiers
+                } else if !is_single_byte_char(c & !char_bits::CHAR_MODIFIER_MASK) {
+                    c | char_bits::CHAR_CTL
+                } else if (c & 0o137) >= 0o101 && (c & 0o137) <= 0o132 {
+                    c & (0o37 | !0o177)
+                } else if (c & 0o177) >= 0o100 && (c & 0o177) <= 0o137 {
+                    c & (0o37 | !0o177)
+                } else {
+                    c | char_bits::CHAR_CTL
+                }
+            }
+            Some('0'..='7') => {
+                // An octal escape, as in ANSI C.
+                let mut i = c - Codepoint::from(b'0');
+                for _ in 1..3 {
+                    match self.read_char() {
+                        Some(c) if Codepoint::from(b'0') <= c && c <= Codepoint::from(b'7') => {
+                            i = i * 8 + (c - Codepoint::from(b'0'));
+                        }
+                        c => {
+                            self.unread(c);
+                            break;
+                        }
+                    }
+                }
+                if 0x80 <= i && i < 0x100 {
+                    raw_byte_codepoint(i as u8)
+                } else {
+                    i
+                }
+            }
+            Some('x') => {
+                // A hex escape, as in ANSI C.
+                let mut i: Codepoint = 0;
+                let mut count = 0;
+                loop {
+                    let c = self.read_char();
+                    let digit = match hex_digit_value(c) {
+                        Some(digit) => digit,
+                        None => {
+                            self.unread(c);
+                            break;
+                        }
+                    };
+                    i = (i << 4) + digit;
+                    // Allow hex escapes as large as ?\xfffffff, because
+                    // some packages use them to denote characters with
+                    // modifiers.
+                    if (char_bits::CHAR_META | (char_bits::CHAR_META - 1)) < i {
+                        error!("Hex character out of range: \\x{:x}...", i);
+                    }
+                    if count < 3 {
+                        count += 1;
+                    }
+                }
+                if count < 3 && i >= 0x80 {
+                    raw_byte_codepoint(i as u8)
+                } else {
+                    i
+                }
+            }
+            Some('U') | Some('u') => {
+                // A Unicode escape.  We only permit them in strings and
+                // characters, not arbitrarily in the source code, as in
+                // some other languages.
+                if c == Codepoint::from(b'U') {
+                    // Post-Unicode-2.0: Up to eight hex chars.
+                    unicode_hex_count = 8;
+                }
+                let mut i: Codepoint = 0;
+                for _ in 0..unicode_hex_count {
+                    let c = self.read_char();
+                    match hex_digit_value(c) {
+                        Some(digit) => i = (i << 4) + digit,
+                        None => error!("Non-hex digit used for Unicode escape"),
+                    }
+                }
+                if i > 0x10_FFFF {
+                    error!("Non-Unicode character: 0x{:x}", i);
+                }
+                i
+            }
+            Some('N') => {
+                // Named character.
+                if self.read_char() != Some(Codepoint::from(b'{')) {
+                    invalid_syntax("Expected opening brace after \\N");
+                }
+                let mut name = Vec::new();
+                let mut whitespace = false;
+                loop {
+                    let c = self.read_char_or_eof_error();
+                    if c == Codepoint::from(b'}') {
+                        break;
+                    }
+                    if !(0 < c && c < 0x80) {
+                        let message = format!("Invalid character U+{:04X} in character name", c);
+                        xsignal!(Qinvalid_read_syntax, LispObject::from(message.as_str()));
+                    }
+                    // Treat multiple adjacent whitespace characters as a
+                    // single space character.  This makes it easier to use
+                    // character names in e.g. multi-line strings.
+                    if is_c_space(c) {
+                        if whitespace {
+                            continue;
+                        }
+                        whitespace = true;
+                        name.push(b' ');
+                    } else {
+                        whitespace = false;
+                        name.push(c as u8);
+                    }
+                    if name.len() > UNICODE_CHARACTER_NAME_LENGTH_BOUND {
+                        invalid_syntax("Character name too long");
+                    }
+                }
+                if name.is_empty() {
+                    invalid_syntax("Empty character name");
+                }
+                character_name_to_code(&name)
+            }
+            _ => c,
+        };
+        Some(value)
+    }
+
+    /// Read the character after `\X', which may itself be an escape
+    /// sequence.
+    fn read_escape_operand(&mut self) -> Codepoint {
+        let c = self.read_char_or_eof_error();
+        if c == Codepoint::from(b'\\') {
+            self.read_escape(false)
+                .unwrap_or_else(|| error!("Invalid escape character syntax"))
+        } else {
+            c
+        }
+    }
+
+    /// Read the rest of an escape like `\M-x', which adds MODIFIER.
+    fn read_modified_escape(&mut self, modifier: Codepoint) -> Codepoint {
+        if self.read_char() != Some(Codepoint::from(b'-')) {
+            error!("Invalid escape character syntax");
+        }
+        self.read_escape_operand() | modifier
+    }
+
+    /// Read an integer in radix RADIX.  Signal an error if RADIX is not
+    /// in the interval [2..36], or if the syntax is invalid.
+    fn read_integer(&mut self, radix: EmacsInt) -> LispObject {
+        // Some(true) if valid, Some(false) if not, None if incomplete.
+        let mut valid = None;
+        let mut buf = Vec::new();
+
+        if radix < 2 || radix > 36 {
+            valid = Some(false);
+        } else {
+            let mut c = self.read_char();
+            if c == Some(Codepoint::from(b'-')) || c == Some(Codepoint::from(b'+')) {
+                buf.push(c.unwrap() as u8);
+                c = self.read_char();
+            }
+
+            if c == Some(Codepoint::from(b'0')) {
+                buf.push(b'0');
+                valid = Some(true);
+
+                // Ignore redundant leading zeros, so the buffer doesn't
+                // fill up with them.
+                while c == Some(Codepoint::from(b'0')) {
+                    c = self.read_char();
+                }
+            }
+
+            loop {
+                let digit = digit_to_number(c, radix);
+                if digit < -1 {
+                    break;
+                }
+                if digit == -1 {
+                    valid = Some(false);
+                }
+                if valid.is_none() {
+                    valid = Some(true);
+                }
+                if buf.len() < INTEGER_LENGTH_BOUND {
+                    buf.push(c.unwrap() as u8);
+                } else {
+                    valid = Some(false);
+                }
+                c = self.read_char();
+            }
+
+            self.unread(c);
+        }
+
+        if valid != Some(true) {
+            invalid_syntax(&format!("integer, radix {}", radix));
+        }
+
+        buffer_to_number(&buf, radix)
+    }
+
+    /// Read the rest of a character literal `?C'.
+    fn read_character_literal(&mut self) -> LispObject {
+        let mut c = self.read_char_or_eof_error();
+
+        // Accept `single space' syntax like (list ? x) where the
+        // whitespace character is SPC or TAB.  Other literal whitespace
+        // like NL, CR, and FF are not accepted, as there are
+        // well-established escape sequences for these.
+        if c == Codepoint::from(b' ') || c == Codepoint::from(b'\t') {
+            return LispObject::from(EmacsInt::from(c));
+        }
+
+        if is_one_of(c, b"()[]\";") {
+            let literals = unsafe { globals.Vlread_unescaped_character_literals };
+            if !literals.is_list() {
+                wrong_type!(Qlistp, literals);
+            }
+            let char_obj = LispObject::from(EmacsInt::from(c));
+            if memq(char_obj, literals).is_nil() {
+                unsafe {
+                    globals.Vlread_unescaped_character_literals =
+                        LispObject::cons(char_obj, literals)
+                };
+            }
+        }
+
+        if c == Codepoint::from(b'\\') {
+            c = self
+                .read_escape(false)
+                .unwrap_or_else(|| error!("Invalid escape character syntax"));
+        }
+        let modifiers = c & char_bits::CHAR_MODIFIER_MASK;
+        c &= !char_bits::CHAR_MODIFIER_MASK;
+        if char_byte8_p(c) {
+            c = Codepoint::from(char_to_byte8(c));
+        }
+        c |= modifiers;
+
+        let next_char = self.read_char();
+        let ok = next_char.map_or(true, |c| c <= 0o40 || is_one_of(c, b"\"';()[]#?`,."));
+        self.unread(next_char);
+        if !ok {
+            invalid_syntax("?");
+        }
+        LispObject::from(EmacsInt::from(c))
+    }
+
+    /// Read the rest of a string literal.
+    fn read_string(&mut self) -> LispObject {
+        let mut buf = Vec::new();
+        let mut bytes = [0; MAX_MULTIBYTE_LENGTH];
+        // True if we saw an escape sequence specifying a multibyte
+        // character.
+        let mut force_multibyte = false;
+        // True if we saw an escape sequence specifying a single-byte
+        // character.
+        let mut force_singlebyte = false;
+        let mut cancel = false;
+        let mut nchars: ptrdiff_t = 0;
+
+        loop {
+            let mut ch = self.read_char_or_eof_error();
+            if ch == Codepoint::from(b'"') {
+                break;
+            }
+
+            if ch == Codepoint::from(b'\\') {
+                ch = match self.read_escape(true) {
+                    Some(ch) => ch,
+                    // \ newline or \ space has just been seen.
+                    None => {
+                        if buf.is_empty() {
+                            cancel = true;
+                        }
+                        continue;
+                    }
+                };
+
+                let mut modifiers = ch & char_bits::CHAR_MODIFIER_MASK;
+                ch &= !char_bits::CHAR_MODIFIER_MASK;
+
+                if char_byte8_p(ch) {
+                    force_singlebyte = true;
+                } else if !is_ascii(ch) {
+                    force_multibyte = true;
+                } else {
+                    // Allow `\C- ' and `\C-?'.
+                    if modifiers == char_bits::CHAR_CTL {
+                        if ch == Codepoint::from(b' ') {
+                            ch = 0;
+                            modifiers = 0;
+                        } else if ch == Codepoint::from(b'?') {
+                            ch = 127;
+                            modifiers = 0;
+                        }
+                    }
+                    if modifiers & char_bits::CHAR_SHIFT != 0 {
+                        // Shift modifier is valid only with [A-Za-z].
+                        if Codepoint::from(b'A') <= ch && ch <= Codepoint::from(b'Z') {
+                            modifiers &= !char_bits::CHAR_SHIFT;
+                        } else if Codepoint::from(b'a') <= ch && ch <= Codepoint::from(b'z') {
+                            ch -= Codepoint::from(b'a' - b'A');
+                            modifiers &= !char_bits::CHAR_SHIFT;
+                        }
+                    }
+
+                    if modifiers & char_bits::CHAR_META != 0 {
+                        // Move the meta bit to the right place for a
+                        // string.
+                        modifiers &= !char_bits::CHAR_META;
+                        ch = raw_byte_codepoint((ch | 0x80) as u8);
+                        force_singlebyte = true;
+                    }
+                }
+
+                // Any modifiers remaining are invalid.
+                if modifiers != 0 {
+                    error!("Invalid modifier in string");
+                }
+            } else if char_byte8_p(ch) {
+                force_singlebyte = true;
+            } else if !is_ascii(ch) {
+                force_multibyte = true;
+            }
+
+            let len = write_codepoint(&mut bytes, ch);
+            buf.extend_from_slice(&bytes[..len]);
+            nchars += 1;
+        }
+
+        // If purifying, and string starts with \ newline, return zero
+        // instead.  This is for doc strings that we are really going to
+        // find in etc/DOC.nn.nn.
+        if unsafe { globals.Vpurify_flag.is_not_nil() && globals.Vdoc_file_name.is_nil() }
+            && cancel
+        {
+            return LispObject::from_fixnum(0);
+        }
+
+        let mut nbytes = buf.len() as ptrdiff_t;
+        if !force_multibyte && force_singlebyte {
+            // BUF contains raw 8-bit bytes and no multibyte forms.
+            // Convert it to unibyte.
+            nchars = unsafe { str_as_unibyte(buf.as_mut_ptr(), nbytes) };
+            nbytes = nchars;
+        }
+
+        unsafe {
+            make_specified_string(
+                buf.as_ptr() as *const c_char,
+                nchars,
+                nbytes,
+                force_multibyte || nbytes != nchars,
+            )
+        }
+    }
+
+    /// Read a symbol or a number whose first character is C.  MULTIBYTE
+    /// is the multibyteness of the source.  If UNINTERNED, make an
+    /// uninterned symbol as for `#:'.
+    fn read_atom(&mut self, mut c: Codepoint, multibyte: bool, uninterned: bool) -> LispObject {
+        let start_position = self.count - 1;
+        let mut buf = Vec::new();
+        let mut bytes = [0; MAX_MULTIBYTE_LENGTH];
+        let mut quoted = false;
+
+        loop {
+            if c == Codepoint::from(b'\\') {
+                c = self.read_char_or_eof_error();
+                quoted = true;
+            }
+
+            if multibyte {
+                let len = write_codepoint(&mut bytes, c);
+                buf.extend_from_slice(&bytes[..len]);
+            } else {
+                buf.push(c as u8);
+            }
+
+            match self.read_char() {
+                Some(next) if is_symbol_constituent(next) => c = next,
+                next => {
+                    self.unread(next);
+                    break;
+                }
+            }
+        }
+
+        if !quoted && !uninterned {
+            let result = buffer_to_number(&buf, 10);
+            if result.is_not_nil() {
+                return result;
+            }
+        }
+        if !quoted && multibyte {
+            let (ch, _) = multibyte_char_at(&buf);
+            match ch {
+                0x2018 // LEFT SINGLE QUOTATION MARK
+                | 0x2019 // RIGHT SINGLE QUOTATION MARK
+                | 0x201B // SINGLE HIGH-REVERSED-9 QUOTATION MARK
+                | 0x201C // LEFT DOUBLE QUOTATION MARK
+                | 0x201D // RIGHT DOUBLE QUOTATION MARK
+                | 0x201F // DOUBLE HIGH-REVERSED-9 QUOTATION MARK
+                | 0x301E // DOUBLE PRIME QUOTATION MARK
+                | 0xFF02 // FULLWIDTH QUOTATION MARK
+                | 0xFF07 // FULLWIDTH APOSTROPHE
+                => {
+                    let quote = std::char::from_u32(ch).unwrap().to_string();
+                    xsignal!(
+                        Qinvalid_read_syntax,
+                        LispObject::from("strange quote"),
+                        LispObject::from(quote.as_str())
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        let nbytes = buf.len() as ptrdiff_t;
+        let ptr = buf.as_ptr() as *const c_char;
+        let nchars = if multibyte {
+            unsafe { multibyte_chars_in_text(buf.as_ptr(), nbytes) }
+        } else {
+            nbytes
+        };
+
+        let result = if uninterned {
+            let name = unsafe {
+                if globals.Vpurify_flag.is_not_nil() {
+                    make_pure_string(ptr, nchars, nbytes, multibyte)
+                } else {
+                    make_specified_string(ptr, nchars, nbytes, multibyte)
+                }
+            };
+            unsafe { Fmake_symbol(name) }
+        } else {
+            // Don't create the string object for the name unless we're
+            // going to retain it in a new symbol.
+            let obarray = check_obarray(unsafe { globals.Vobarray });
+            let tem = unsafe { oblookup(obarray, ptr, nchars, nbytes) };
+            if tem.is_symbol() {
+                tem
+            } else {
+                let name = unsafe { make_specified_string(ptr, nchars, nbytes, multibyte) };
+                intern_driver(name, obarray, tem)
+            }
+        };
+
+        if self.records_symbol_positions() {
+            unsafe {
+                globals.Vread_symbol_positions_list = LispObject::cons(
+                    LispObject::cons(result, LispObject::from(start_position)),
+                    globals.Vread_symbol_positions_list,
+                )
+            };
+        }
+        result
+    }
+
+    /// Read the elements of a list, or of a vector if VECTOR.  The
+    /// opening delimiter has been read already.
+    fn read_list(&mut self, vector: bool) -> LispObject {
+        let mut val = Qnil;
+        let mut tail: Option<LispCons> = None;
+        let mut doc_reference = DocReference::Plain;
+        let mut first_in_list = !vector;
+
+        loop {
+            let token = self.read1(first_in_list);
+            first_in_list = false;
+
+            let ch = match token {
+                Token::Datum(mut elt) => {
+                    let load_file_name = unsafe { globals.Vload_file_name };
+                    if elt.eq(load_file_name) && elt.is_not_nil() {
+                        // While building, if the list starts with #$,
+                        // treat it specially.
+                        if unsafe { globals.Vpurify_flag }.is_not_nil() {
+                            if unsafe { globals.Vdoc_file_name }.is_nil() {
+                                // We have not yet called
+                                // Snarf-documentation, so assume this file
+                                // is described in the DOC file and
+                                // Snarf-documentation will fill in the
+                                // right value later.  For now, replace the
+                                // whole list with 0.
+                                doc_reference = DocReference::Zero;
+                            } else {
+                                // We have already called
+                                // Snarf-documentation, so make a relative
+                                // file name for this file, so it can be
+                                // found properly in the installed Lisp
+                                // directory.
+                                let nondirectory = unsafe { Ffile_name_nondirectory(elt) };
+                                elt = concat(&mut [LispObject::from("../lisp/"), nondirectory]);
+                            }
+                        } else if unsafe { globals.load_force_doc_strings } {
+                            doc_reference = DocReference::Lazy;
+                        }
+                    }
+
+                    let tem = LispObject::cons(elt, Qnil);
+                    match tail {
+                        Some(cell) => cell.set_cdr(tem),
+                        None => val = tem,
+                    }
+                    tail = tem.as_cons();
+                    continue;
+                }
+                Token::Close(ch) => ch,
+            };
+
+            if vector {
+                if ch == ']' {
+                    return val;
+                }
+                invalid_syntax(") or . in a vector");
+            }
+            match ch {
+                ')' => return val,
+                '.' => {
+                    let rest = self.read0();
+                    match tail {
+                        Some(cell) => cell.set_cdr(rest),
+                        None => val = rest,
+                    }
+                    match self.read1(false) {
+                        Token::Close(')') => {}
+                        _ => invalid_syntax(". in wrong context"),
+                    }
+                    return match doc_reference {
+                        DocReference::Zero => LispObject::from_fixnum(0),
+                        // Get a doc string from the file we are loading.
+                        DocReference::Lazy if cdr(val).is_integer() => unsafe {
+                            get_lazy_string(val)
+                        },
+                        _ => val,
+                    };
+                }
+                _ => invalid_syntax("] in a list"),
+            }
+        }
+    }
+
+    /// Read the elements of a vector, or of a byte-code object if
+    /// BYTECODE.  The opening delimiter has been read already.
+    fn read_vector(&mut self, bytecode: bool) -> LispObject {
+        let tem = self.read_list(true);
+        let vector = unsafe { Fmake_vector(length(tem), Qnil) };
+        let mut vec = vector.as_vector_or_error();
+        let bytecode_slot = Lisp_Compiled::COMPILED_BYTECODE as usize;
+        let constants_slot = Lisp_Compiled::COMPILED_CONSTANTS as usize;
+        let doc_string_slot = Lisp_Compiled::COMPILED_DOC_STRING as usize;
+
+        for (i, mut item) in tem.iter_cars().enumerate() {
+            // If `load-force-doc-strings' is t when reading a
+            // lazily-loaded bytecode object, the docstring containing the
+            // bytecode and constants values must be treated as unibyte
+            // and passed to `read', to get the actual bytecode string and
+            // constants vector.
+            if bytecode && unsafe { globals.load_force_doc_strings } {
+                if i == bytecode_slot {
+                    if !item.is_string() {
+                        error!("Invalid byte code");
+                    }
+                    // Delay handling the bytecode slot until we know
+                    // whether it is lazily-loaded (we can tell by whether
+                    // the constants slot is nil).
+                    vec.set_checked(constants_slot, item);
+                    item = Qnil;
+                } else if i == constants_slot {
+                    let mut bytestr = vec.get(constants_slot);
+                    if item.is_nil() {
+                        // Coerce string to unibyte (like
+                        // string-as-unibyte, but without generating extra
+                        // garbage and guaranteeing no change in the
+                        // contents).
+                        let mut s = bytestr.as_string_or_error();
+                        let nbytes = s.len_bytes();
+                        let string = s.as_mut();
+                        unsafe {
+                            (*string).u.s.size = nbytes;
+                            (*string).u.s.size_byte = -1;
+                        }
+
+                        let pair = read(LispObject::cons(bytestr, self.readcharfun));
+                        let (code, constants) = match pair.as_cons() {
+                            Some(cell) => cell.as_tuple(),
+                            None => error!("Invalid byte code"),
+                        };
+                        bytestr = code;
+                        item = constants;
+                    }
+
+                    // Now handle the bytecode slot.
+                    vec.set(bytecode_slot, bytestr);
+                } else if i == doc_string_slot
+                    && item.as_string().map_or(false, |s| !s.is_multibyte())
+                {
+                    item = if self.readcharfun.eq(Qget_emacs_mule_file_char) {
+                        unsafe { Fdecode_coding_string(item, Qemacs_mule, Qnil, Qnil) }
+                    } else {
+                        string_as_multibyte(item.as_string_or_error())
+                    };
+                }
+            }
+            vec.set(i, item);
+        }
+        vector
+    }
+}
+
+/// Read one object from STREAM, setting up the global context that
+/// toplevel read calls need.  START and END are only used when STREAM
+/// is a string.
+#[no_mangle]
+pub extern "C" fn read_internal_start(
+    stream: LispObject,
+    start: LispObject,
+    end: LispObject,
+) -> LispObject {
+    Reader::new(stream).read_toplevel(start, end)
+}
+
 /// Read one Lisp expression as text from STREAM, return as Lisp object.
 /// If STREAM is nil, use the value of `standard-input' (which see).
 /// STREAM or the value of `standard-input' may be:
@@ -186,8 +1642,196 @@ pub fn read(stream: LispObject) -> LispObject {
             build_string(cs.as_ptr())
         })
     } else {
-        unsafe { read_internal_start(input, Qnil, Qnil) }
+        read_internal_start(input, Qnil, Qnil)
+    }
+}
+
+/// Read one Lisp expression as text from STREAM and return it together
+/// with the positions of its parts, as (OBJECT . POSITIONS).
+/// POSITIONS has an entry (DATUM LINE COLUMN OFFSET) for OBJECT and for
+/// every object read as part of it, in the order in which they appear
+/// in the text.  LINE counts from 1 and COLUMN from 0, in characters;
+/// OFFSET is the number of bytes from where reading started to the
+/// start of DATUM.  An object labeled with `#N=' is located at its
+/// label.
+/// STREAM is interpreted as by `read'.  Text read from the minibuffer
+/// is located relative to the start of the input.
+#[lisp_fn(min = "0")]
+pub fn read_with_positions(stream: LispObject) -> LispObject {
+    let mut input = if stream.is_not_nil() {
+        stream
+    } else {
+        unsafe { globals.Vstandard_input }
+    };
+    if input.is_t() || input.eq(Qread_char) {
+        input = call!(intern("read-string"), LispObject::from("Lisp expression: "));
+    }
+
+    let mut reader = Reader::new(input);
+    reader.record_positions = true;
+    let object = reader.read_toplevel(Qnil, Qnil);
+
+    // Each entry was recorded when its datum was complete, so a list
+    // comes after its elements.  Sort the entries by where they start.
+    // READER keeps them reachable while only the vector holds them.
+    let mut entries: Vec<LispObject> = reader.positions.iter_cars().collect();
+    entries.sort_by_key(|&entry| nth(3, entry).as_fixnum_or_error());
+    LispObject::cons(object, list(&entries))
+}
+
+/// The state of `read-tolerant' while it reads one form of STRING.
+struct TolerantRead {
+    string: LispObject,
+    start: EmacsInt,
+    /// The form read and the index after it, if reading succeeded.
+    value: Option<(LispObject, EmacsInt)>,
+}
+
+/// `internal_condition_case_1' passes a single Lisp argument through to
+/// its body function; smuggle the state pointer in it.
+extern "C" fn read_tolerant_form(arg: LispObject) -> LispObject {
+    let state = unsafe { &mut *arg.to_raw_ptr::<TolerantRead>() };
+    let result = unsafe {
+        Fread_from_string(state.string, LispObject::from(state.start), Qnil)
+    };
+    let (form, end) = result.as_cons_or_error().as_tuple();
+    state.value = Some((form, end.as_fixnum_or_error()));
+    Qnil
+}
+
+extern "C" fn read_tolerant_error(err: LispObject) -> LispObject {
+    err
+}
+
+/// Return the index of the first character at or after I in CHARS that
+/// is neither whitespace nor part of a comment.
+fn skip_blanks(chars: &[Codepoint], mut i: usize) -> usize {
+    while i < chars.len() {
+        let c = chars[i];
+        if c == Codepoint::from(b';') {
+            while i < chars.len() && chars[i] != Codepoint::from(b'\n') {
+                i += 1;
+            }
+        } else if c > 0o40 && c != NO_BREAK_SPACE {
+            break;
+        }
+        i += 1;
+    }
+    i
+}
+
+/// Return the index of the next `(' after I in CHARS that starts a
+/// line, which is where the next toplevel form probably starts.
+fn next_toplevel_start(chars: &[Codepoint], i: usize) -> Option<usize> {
+    (i + 1..chars.len()).find(|&j| {
+        chars[j] == Codepoint::from(b'(') && chars[j - 1] == Codepoint::from(b'\n')
+    })
+}
+
+/// Return the index of the start of the line after the one containing
+/// I in CHARS, or the length of CHARS if there is none.
+fn next_line_start(chars: &[Codepoint], i: usize) -> usize {
+    (i..chars.len())
+        .find(|&j| chars[j] == Codepoint::from(b'\n'))
+        .map_or(chars.len(), |j| j + 1)
+}
+
+/// Read all the Lisp expressions in STREAM, recovering from syntax errors.
+/// Return (FORMS . DIAGNOSTICS), where FORMS is the list of the forms
+/// that could be read, in order, and DIAGNOSTICS has an entry (POSITION
+/// MESSAGE) for each form that could not be read.  POSITION is where the
+/// bad form starts, and MESSAGE describes the error.
+///
+/// After an unbalanced close paren, reading resumes right after it.
+/// After any other error, it resumes at the next line that starts with
+/// an open paren, as a new toplevel form probably starts there.  If
+/// there is no such line, any text on the lines after the error gets a
+/// diagnostic of its own.
+///
+/// STREAM may be a string, in which case positions are indices into it,
+/// or a buffer, which defaults to the current buffer.  A buffer is read
+/// from point to the end of its accessible portion; positions are buffer
+/// positions, and point is left at the end.
+#[lisp_fn(min = "0")]
+pub fn read_tolerant(stream: LispObject) -> LispObject {
+    if stream.is_string() {
+        return read_tolerant_string(stream, 0);
+    }
+
+    let count = c_specpdl_index();
+    unsafe { record_unwind_current_buffer() };
+    if stream.is_not_nil() {
+        set_buffer(LispBufferOrName::from(stream));
+    }
+    let start = point();
+    let end = point_max();
+    let text = unsafe { make_buffer_string(start as ptrdiff_t, end as ptrdiff_t, false) };
+    let result = read_tolerant_string(text, start);
+    goto_char(LispObject::from(end));
+    unsafe { unbind_to(count, Qnil) };
+    result
+}
+
+/// Do the work of `read-tolerant' on STRING, whose first character is
+/// at position ORIGIN.
+fn read_tolerant_string(string: LispObject, origin: EmacsInt) -> LispObject {
+    let chars: Vec<Codepoint> = string.as_string_or_error().chars().collect();
+    let mut state = TolerantRead {
+        string,
+        start: 0,
+        value: None,
+    };
+    let mut forms = Qnil;
+    let mut diagnostics = Qnil;
+    let mut i = 0;
+    // Where the text skipped for good after an error starts.
+    let mut unread = chars.len();
+
+    loop {
+        i = skip_blanks(&chars, i);
+        if i >= chars.len() {
+            break;
+        }
+        state.start = i as EmacsInt;
+        let err = unsafe {
+            internal_condition_case_1(
+                Some(read_tolerant_form),
+                LispObject::from_raw_ptr(&mut state as *mut TolerantRead),
+                Qerror,
+                Some(read_tolerant_error),
+            )
+        };
+        match state.value.take() {
+            Some((form, end)) => {
+                forms = LispObject::cons(form, forms);
+                i = end as usize;
+            }
+            None => {
+                let message = unsafe { Ferror_message_string(err) };
+                let position = LispObject::from(origin + i as EmacsInt);
+                diagnostics = LispObject::cons(list!(position, message), diagnostics);
+                if is_one_of(chars[i], b")]") {
+                    i += 1;
+                } else {
+                    match next_toplevel_start(&chars, i) {
+                        Some(next) => i = next,
+                        None => {
+                            unread = skip_blanks(&chars, next_line_start(&chars, i));
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if unread < chars.len() {
+        let position = LispObject::from(origin + unread as EmacsInt);
+        let message = LispObject::from("Text after a read error not read");
+        diagnostics = LispObject::cons(list!(position, message), diagnostics);
     }
+
+    unsafe { LispObject::cons(Fnreverse(forms), Fnreverse(diagnostics)) }
 }
 
 /// Execute the region as Lisp code.
@@ -241,4 +1885,164 @@ pub fn eval_region(
     }
 }
 
+/// The state of `lread--substitute-object-in-subtree'.
+struct Substitution {
+    object: LispObject,
+    placeholder: LispObject,
+    completed: LispObject,
+    seen: LispObject,
+}
+
+impl Substitution {
+    fn substitute(&mut self, subtree: LispObject) -> LispObject {
+        // If we find the placeholder, return the target object.
+        if self.placeholder.eq(subtree) {
+            return self.object;
+        }
+
+        // For common object types that can't contain other objects, don't
+        // bother looking them up; we're done.
+        if subtree.is_symbol()
+            || (subtree.is_string() && unsafe { string_intervals(subtree) }.is_null())
+            || subtree.is_number()
+        {
+            return subtree;
+        }
+
+        // If we've been to this node before, don't explore it again.
+        if memq(subtree, self.seen).is_not_nil() {
+            return subtree;
+        }
+
+        // If this node can be the entry point to a cycle, remember that
+        // we've seen it.  It can only be such an entry point if it was
+        // made by #n=, which means that we can find it as a value in
+        // COMPLETED.
+        if self.completed.is_t() {
+            self.seen = LispObject::cons(subtree, self.seen);
+        } else if let Found(_) = self.completed.as_hash_table_or_error().lookup(subtree) {
+            self.seen = LispObject::cons(subtree, self.seen);
+        }
+
+        if let Some(vl) = subtree.as_vectorlike() {
+            let length = if vl.is_pseudovector(pvec_type::PVEC_BOOL_VECTOR) {
+                // No sub-objects anyway.
+                return subtree;
+            } else if vl.is_pseudovector(pvec_type::PVEC_CHAR_TABLE)
+                || vl.is_pseudovector(pvec_type::PVEC_SUB_CHAR_TABLE)
+                || vl.is_pseudovector(pvec_type::PVEC_COMPILED)
+                || vl.is_pseudovector(pvec_type::PVEC_HASH_TABLE)
+                || vl.is_pseudovector(pvec_type::PVEC_RECORD)
+            {
+                vl.pseudovector_size() as usize
+            } else if let Some(v) = vl.as_vector() {
+                v.len()
+            } else {
+                // An unknown pseudovector may contain non-Lisp fields, so
+                // we can't just blindly traverse all its fields.
+                wrong_type!(Qsequencep, subtree);
+            };
+
+            let start = if vl.is_pseudovector(pvec_type::PVEC_SUB_CHAR_TABLE) {
+                2
+            } else {
+                0
+            };
+            let mut v = unsafe { vl.as_vector_unchecked() };
+            for i in start..length {
+                let elt = self.substitute(unsafe { v.get_unchecked(i) });
+                unsafe { v.set_unchecked(i, elt) };
+            }
+        } else if let Some(cons) = subtree.as_cons() {
+            cons.set_car(self.substitute(cons.car()));
+            cons.set_cdr(self.substitute(cons.cdr()));
+        } else if subtree.is_string() {
+            // Check for text properties in each interval.
+            unsafe {
+                traverse_intervals_noorder(
+                    string_intervals(subtree),
+                    Some(substitute_in_interval),
+                    self as *mut Substitution as *mut c_void,
+                )
+            };
+        }
+
+        subtree
+    }
+}
+
+extern "C" fn substitute_in_interval(interval: INTERVAL, arg: *mut c_void) {
+    let subst = unsafe { &mut *(arg as *mut Substitution) };
+    unsafe { (*interval).plist = subst.substitute((*interval).plist) };
+}
+
+/// In OBJECT, replace every occurrence of PLACEHOLDER with OBJECT.
+/// COMPLETED is a hash table of objects that might be circular, or is t
+/// if any object might be circular.
+#[lisp_fn(name = "lread--substitute-object-in-subtree")]
+pub fn lread__substitute_object_in_subtree(
+    object: LispObject,
+    placeholder: LispObject,
+    completed: LispObject,
+) {
+    let mut subst = Substitution {
+        object,
+        placeholder,
+        completed,
+        seen: Qnil,
+    };
+    let check_object = subst.substitute(object);
+
+    // The returned object here is expected to always eq the original.
+    if !check_object.eq(object) {
+        error!("Unexpected mutation error in reader");
+    }
+}
+
 include!(concat!(env!("OUT_DIR"), "/lread_exports.rs"));
+
+#[test]
+fn test_digit_to_number() {
+    let digit = |c: u8, base| digit_to_number(Some(Codepoint::from(c)), base);
+    assert_eq!(digit(b'7', 10), 7);
+    assert_eq!(digit(b'f', 16), 15);
+    assert_eq!(digit(b'Z', 36), 35);
+    assert_eq!(digit(b'8', 8), -1);
+    assert_eq!(digit(b'-', 10), -2);
+    assert_eq!(digit_to_number(None, 10), -2);
+    assert_eq!(digit_to_number(Some(0x3FFF80), 10), -2);
+}
+
+#[test]
+fn test_is_symbol_constituent() {
+    assert!(is_symbol_constituent(Codepoint::from(b'a')));
+    assert!(is_symbol_constituent(Codepoint::from(b'.')));
+    assert!(is_symbol_constituent(0x3BB));
+    assert!(!is_symbol_constituent(Codepoint::from(b' ')));
+    assert!(!is_symbol_constituent(Codepoint::from(b'(')));
+    assert!(!is_symbol_constituent(NO_BREAK_SPACE));
+}
+
+#[test]
+fn test_position_advance() {
+    let mut pos = Position::start();
+    pos.advance(Codepoint::from(b'a'), true);
+    pos.advance(0x3BB, true);
+    assert_eq!((pos.line, pos.column, pos.offset), (1, 2, 3));
+    pos.advance(Codepoint::from(b'\n'), false);
+    assert_eq!((pos.line, pos.column, pos.offset), (2, 0, 4));
+    pos.advance(0x3FFF80, false);
+    assert_eq!((pos.line, pos.column, pos.offset), (2, 1, 5));
+}
+
+#[test]
+fn test_tolerant_resync() {
+    let chars: Vec<Codepoint> = " ; note\n  (a\n(b)".bytes().map(Codepoint::from).collect();
+    assert_eq!(skip_blanks(&chars, 0), 10);
+    assert_eq!(skip_blanks(&chars, 12), 13);
+    assert_eq!(skip_blanks(&chars, 16), 16);
+    assert_eq!(next_toplevel_start(&chars, 10), Some(13));
+    assert_eq!(next_toplevel_start(&chars, 13), None);
+    assert_eq!(next_line_start(&chars, 10), 13);
+    assert_eq!(next_line_start(&chars, 13), chars.len());
+}
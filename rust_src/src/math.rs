@@ -13,7 +13,6 @@ use crate::{
 /// Return X modulo Y.
 /// The result falls between zero (inclusive) and Y (exclusive).
 /// Both X and Y must be numbers or markers.
-/// (fn X Y)
 #[lisp_fn(name = "mod", c_name = "mod")]
 pub fn lisp_mod(x: LispNumber, y: LispNumber) -> LispObject {
     match (x, y) {
@@ -1,23 +1,30 @@
 //! Minibuffer input and completion.
 
+use std::cmp::min;
+
 use remacs_macros::lisp_fn;
 
 use crate::{
     buffers::{current_buffer, LispBufferOrName},
     editfns::field_end,
+    eval::commandp,
+    hashtable::HashLookupResult::{Found, Missing},
     keymap::get_keymap,
     lisp::defsubr,
     lisp::LispObject,
     lists::{car_safe, cdr_safe, memq},
-    obarray::{intern, lisp_intern},
+    multibyte::{unibyte_to_char, Codepoint, LispStringRef},
+    obarray::{check_obarray, intern, lisp_intern, LispObarrayRef},
     remacs_sys::{
-        globals, Qcommandp, Qcustom_variable_p, Qfield, Qminibuffer_completion_table,
-        Qminibuffer_history, Qnil, Qt, Vminibuffer_list,
+        globals, Qcase_fold_search, Qcommandp, Qcustom_variable_p, Qfield, Qlambda,
+        Qminibuffer_completion_table, Qminibuffer_history, Qnil, Qt, Vminibuffer_list,
     },
     remacs_sys::{
         make_buffer_string, minibuf_level, minibuf_prompt, minibuf_window, read_minibuf, specbind,
-        unbind_to, EmacsInt, Fcopy_sequence, Ffuncall,
+        unbind_to, EmacsInt, Fassoc_string, Fcompare_strings, Fcopy_sequence, Ffuncall,
+        Fnreverse, Fstring_make_multibyte, Fstring_make_unibyte, Fsubstring,
     },
+    search::string_match,
     symbols::symbol_value,
     textprop::get_char_property,
     threads::{c_specpdl_index, ThreadState},
@@ -432,4 +439,552 @@ pub fn read_no_blanks_input(
     }
 }
 
+// Basic completion.
+
+/// The kinds of COLLECTION accepted by `try-completion',
+/// `all-completions' and `test-completion'.
+#[derive(Clone, Copy, PartialEq)]
+enum CompletionTable {
+    List,
+    Obarray,
+    HashTable,
+    Function,
+}
+
+impl CompletionTable {
+    fn of(collection: LispObject) -> Self {
+        if collection.is_hash_table() {
+            CompletionTable::HashTable
+        } else if collection.is_vector() {
+            CompletionTable::Obarray
+        } else if collection.is_nil() || (collection.is_cons() && !collection.is_function()) {
+            CompletionTable::List
+        } else {
+            CompletionTable::Function
+        }
+    }
+}
+
+/// Call F on every possible completion in COLLECTION, in the order
+/// the C implementation used to visit them.  F is given the element
+/// as seen by the predicate, the string or symbol to complete against,
+/// and for hash tables the value stored under that key.  Iteration
+/// stops as soon as F returns false.
+fn map_completion_candidates<F>(collection: LispObject, table: CompletionTable, mut f: F)
+where
+    F: FnMut(LispObject, LispObject, LispObject) -> bool,
+{
+    match table {
+        CompletionTable::List => {
+            for elt in collection.iter_cars_safe() {
+                let eltstring = elt.as_cons().map_or(elt, |cons| cons.car());
+                if !f(elt, eltstring, Qnil) {
+                    return;
+                }
+            }
+        }
+        CompletionTable::Obarray => {
+            let obarray = check_obarray(collection).as_vector_or_error();
+            let zero = LispObject::from(0);
+            for bucket in obarray.iter() {
+                if bucket.eq(zero) {
+                    continue;
+                }
+                let sym = bucket
+                    .as_symbol()
+                    .unwrap_or_else(|| error!("Bad data in guts of obarray"));
+                for sym in sym.iter() {
+                    let elt = sym.as_lisp_obj();
+                    if !f(elt, elt, Qnil) {
+                        return;
+                    }
+                }
+            }
+        }
+        CompletionTable::HashTable => {
+            let table = collection.as_hash_table_or_error();
+            for (key, value) in table.iter() {
+                if !f(key, key, value) {
+                    return;
+                }
+            }
+        }
+        CompletionTable::Function => unreachable!(),
+    }
+}
+
+/// Return the string to complete against for ELTSTRING, or None if
+/// it is neither a string nor a symbol.
+fn completion_candidate_string(eltstring: LispObject) -> Option<LispStringRef> {
+    match eltstring.as_symbol() {
+        Some(sym) => sym.symbol_name().as_string(),
+        None => eltstring.as_string(),
+    }
+}
+
+/// Return the number of leading characters, at most LIMIT, in which
+/// S1 and S2 agree.  Raw bytes of unibyte strings are compared as
+/// their multibyte equivalents, just like `compare-strings' does.
+fn common_prefix_chars(s1: LispStringRef, s2: LispStringRef, limit: usize) -> usize {
+    let as_multibyte = |c: Codepoint, multibyte: bool| {
+        if multibyte {
+            c
+        } else {
+            unibyte_to_char(c)
+        }
+    };
+    let (m1, m2) = (s1.is_multibyte(), s2.is_multibyte());
+
+    s1.chars()
+        .zip(s2.chars())
+        .take(limit)
+        .take_while(|&(c1, c2)| as_multibyte(c1, m1) == as_multibyte(c2, m2))
+        .count()
+}
+
+/// Like `common_prefix_chars', but ignore case differences if
+/// IGNORE_CASE is true.  Case folding is left to `compare-strings' so
+/// that the current case table is respected.
+fn matching_prefix_length(
+    s1: LispStringRef,
+    s2: LispStringRef,
+    limit: isize,
+    ignore_case: bool,
+) -> isize {
+    if !ignore_case {
+        return common_prefix_chars(s1, s2, limit as usize) as isize;
+    }
+
+    let zero = LispObject::from(0);
+    let end = LispObject::from(limit);
+    let tem = unsafe { Fcompare_strings(s1.into(), zero, end, s2.into(), zero, end, Qt) };
+    if tem.is_t() {
+        limit
+    } else {
+        (tem.as_fixnum_or_error().abs() - 1) as isize
+    }
+}
+
+/// Return true if CANDIDATE begins with STRING.
+///
+/// When case is significant and both strings share the same
+/// representation this is a plain byte comparison, so matching a list
+/// of strings never has to go through Lisp.
+fn completion_prefix_p(string: LispStringRef, candidate: LispStringRef, ignore_case: bool) -> bool {
+    let nchars = string.len_chars();
+    if nchars > candidate.len_chars() {
+        return false;
+    }
+
+    if !ignore_case && string.is_multibyte() == candidate.is_multibyte() {
+        return candidate.as_slice().starts_with(string.as_slice());
+    }
+
+    matching_prefix_length(candidate, string, nchars, ignore_case) == nchars
+}
+
+/// Return true if S1 and S2 contain the same characters, ignoring
+/// case differences if IGNORE_CASE is true.
+fn completion_string_equal(s1: LispStringRef, s2: LispStringRef, ignore_case: bool) -> bool {
+    s1.len_chars() == s2.len_chars() && completion_prefix_p(s1, s2, ignore_case)
+}
+
+/// Filter for `completion-regexp-list'.  `case-fold-search' is bound
+/// to `completion-ignore-case' the first time a regexp actually has to
+/// be matched, and stays bound until `unbind' is called.
+struct CompletionRegexps {
+    count: Option<isize>,
+}
+
+impl CompletionRegexps {
+    fn new() -> Self {
+        Self { count: None }
+    }
+
+    fn matches(&mut self, string: LispStringRef) -> bool {
+        let regexps = unsafe { globals.Vcompletion_regexp_list };
+
+        for regexp in regexps.iter_cars_safe() {
+            if self.count.is_none() {
+                self.count = Some(c_specpdl_index());
+                let ignore_case = unsafe { globals.completion_ignore_case };
+                unsafe { specbind(Qcase_fold_search, LispObject::from(ignore_case)) };
+            }
+            if string_match(regexp, string.into(), Qnil).is_nil() {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn unbind(&mut self) {
+        if let Some(count) = self.count.take() {
+            unsafe { unbind_to(count, Qnil) };
+        }
+    }
+}
+
+/// Return true if PREDICATE accepts the completion candidate ELT.
+/// VALUE is only passed on for hash tables.  `commandp' is handled
+/// directly, since it is by far the most common predicate.
+fn completion_predicate_p(
+    predicate: LispObject,
+    table: CompletionTable,
+    elt: LispObject,
+    value: LispObject,
+    regexps: &mut CompletionRegexps,
+) -> bool {
+    if predicate.is_nil() {
+        true
+    } else if predicate.eq(Qcommandp) {
+        commandp(elt, false)
+    } else {
+        // The predicate must not see our binding of `case-fold-search'.
+        regexps.unbind();
+        let result = if table == CompletionTable::HashTable {
+            call!(predicate, elt, value)
+        } else {
+            call!(predicate, elt)
+        };
+        result.is_not_nil()
+    }
+}
+
+/// Return STRING in the same representation (unibyte or multibyte) as
+/// BASIS.
+fn minibuf_conform_representation(string: LispStringRef, basis: LispStringRef) -> LispObject {
+    if string.is_multibyte() == basis.is_multibyte() {
+        string.into()
+    } else if string.is_multibyte() {
+        unsafe { Fstring_make_unibyte(string.into()) }
+    } else {
+        unsafe { Fstring_make_multibyte(string.into()) }
+    }
+}
+
+/// Return common substring of all completions of STRING in COLLECTION.
+/// Test each possible completion specified by COLLECTION
+/// to see if it begins with STRING.  The possible completions may be
+/// strings or symbols.  Symbols are converted to strings before testing,
+/// see `symbol-name'.
+/// All that match STRING are compared together; the longest initial sequence
+/// common to all these matches is the return value.
+/// If there is no match at all, the return value is nil.
+/// For a unique match which is exact, the return value is t.
+///
+/// If COLLECTION is an alist, the keys (cars of elements) are the
+/// possible completions.  If an element is not a cons cell, then the
+/// element itself is the possible completion.
+/// If COLLECTION is a hash-table, all the keys that are strings or symbols
+/// are the possible completions.
+/// If COLLECTION is an obarray, the names of all symbols in the obarray
+/// are the possible completions.
+///
+/// COLLECTION can also be a function to do the completion itself.
+/// It receives three arguments: the values STRING, PREDICATE and nil.
+/// Whatever it returns becomes the value of `try-completion'.
+///
+/// If optional third argument PREDICATE is non-nil,
+/// it is used to test each possible match.
+/// The match is a candidate only if PREDICATE returns non-nil.
+/// The argument given to PREDICATE is the alist element
+/// or the symbol from the obarray.  If COLLECTION is a hash-table,
+/// predicate is called with two arguments: the key and the value.
+/// Additionally to this predicate, `completion-regexp-list'
+/// is used to further constrain the set of candidates.
+#[lisp_fn(min = "2")]
+pub fn try_completion(
+    string: LispStringRef,
+    collection: LispObject,
+    predicate: LispObject,
+) -> LispObject {
+    let table = CompletionTable::of(collection);
+    if table == CompletionTable::Function {
+        return call!(collection, string.into(), predicate, Qnil);
+    }
+
+    let ignore_case = unsafe { globals.completion_ignore_case };
+    let nchars = string.len_chars();
+    let mut bestmatch: Option<LispStringRef> = None;
+    // Size in characters of the part of BESTMATCH all matches agree on.
+    let mut bestmatchsize = 0;
+    let mut matchcount = 0;
+    let mut regexps = CompletionRegexps::new();
+
+    map_completion_candidates(collection, table, |elt, eltstring, value| {
+        let eltstring = match completion_candidate_string(eltstring) {
+            Some(s) => s,
+            None => return true,
+        };
+
+        if !completion_prefix_p(string, eltstring, ignore_case)
+            || !regexps.matches(eltstring)
+            || !completion_predicate_p(predicate, table, elt, value, &mut regexps)
+        {
+            return true;
+        }
+
+        // Update computation of how much all possible completions match.
+        let best = match bestmatch {
+            None => {
+                matchcount = 1;
+                bestmatch = Some(eltstring);
+                bestmatchsize = eltstring.len_chars();
+                return true;
+            }
+            Some(best) => best,
+        };
+
+        let eltsize = eltstring.len_chars();
+        let compare = min(bestmatchsize, eltsize);
+        let matchsize = matching_prefix_length(best, eltstring, compare, ignore_case);
+
+        if ignore_case {
+            // If this is an exact match except for case, use it as the
+            // best match rather than one that is not an exact match.
+            // This way, we get the case pattern of the actual match.
+            // If there is more than one exact match ignoring case, and
+            // one of them is exact including case, prefer that one.  If
+            // there is no exact match ignoring case, prefer a match that
+            // does not change the case of the input.
+            let exact = matchsize == eltsize;
+            let best_exact = matchsize == best.len_chars();
+            if (exact && !best_exact)
+                || (exact == best_exact
+                    && completion_prefix_p(string, eltstring, false)
+                    && !completion_prefix_p(string, best, false))
+            {
+                bestmatch = Some(eltstring);
+            }
+        }
+
+        if bestmatchsize != eltsize || bestmatchsize != matchsize {
+            // Don't count the same string multiple times.
+            if matchcount <= 1 {
+                matchcount += 1;
+            }
+        }
+        bestmatchsize = matchsize;
+
+        // If completion-ignore-case is non-nil, don't short-circuit
+        // because we want to find the best possible match *including*
+        // case differences.
+        !(matchsize <= nchars && !ignore_case && matchcount > 1)
+    });
+    regexps.unbind();
+
+    let bestmatch = match bestmatch {
+        // No completions found.
+        None => return Qnil,
+        Some(best) => best,
+    };
+
+    // If we are ignoring case, and there is no exact match, and no
+    // additional text was supplied, don't change the case of what the
+    // user typed.
+    if ignore_case && bestmatchsize == nchars && bestmatch.len_chars() > bestmatchsize {
+        return minibuf_conform_representation(string, bestmatch);
+    }
+
+    // Return t if the supplied string is an exact match (counting case);
+    // it does not require any change to be made.
+    if matchcount == 1 && bestmatch.as_lisp_obj().equal(string.into()) {
+        return Qt;
+    }
+
+    // Else extract the part in which all completions agree.
+    unsafe {
+        Fsubstring(
+            bestmatch.into(),
+            LispObject::from(0),
+            LispObject::from(bestmatchsize),
+        )
+    }
+}
+
+/// Search for partial matches to STRING in COLLECTION.
+/// Test each of the possible completions specified by COLLECTION
+/// to see if it begins with STRING.  The possible completions may be
+/// strings or symbols.  Symbols are converted to strings before testing,
+/// see `symbol-name'.
+/// The value is a list of all the possible completions that match STRING.
+///
+/// If COLLECTION is an alist, the keys (cars of elements) are the
+/// possible completions.  If an element is not a cons cell, then the
+/// element itself is the possible completion.
+/// If COLLECTION is a hash-table, all the keys that are strings or symbols
+/// are the possible completions.
+/// If COLLECTION is an obarray, the names of all symbols in the obarray
+/// are the possible completions.
+///
+/// COLLECTION can also be a function to do the completion itself.
+/// It receives three arguments: the values STRING, PREDICATE and t.
+/// Whatever it returns becomes the value of `all-completions'.
+///
+/// If optional third argument PREDICATE is non-nil,
+/// it is used to test each possible match.
+/// The match is a candidate only if PREDICATE returns non-nil.
+/// The argument given to PREDICATE is the alist element
+/// or the symbol from the obarray.  If COLLECTION is a hash-table,
+/// predicate is called with two arguments: the key and the value.
+/// Additionally to this predicate, `completion-regexp-list'
+/// is used to further constrain the set of candidates.
+///
+/// An obsolete optional fourth argument HIDE-SPACES is still accepted for
+/// backward compatibility.  If non-nil, strings in COLLECTION that start
+/// with a space are ignored unless STRING itself starts with a space.
+#[lisp_fn(min = "2")]
+pub fn all_completions(
+    string: LispStringRef,
+    collection: LispObject,
+    predicate: LispObject,
+    hide_spaces: bool,
+) -> LispObject {
+    let table = CompletionTable::of(collection);
+    if table == CompletionTable::Function {
+        return call!(collection, string.into(), predicate, Qt);
+    }
+
+    let ignore_case = unsafe { globals.completion_ignore_case };
+    let starts_with_space = |s: LispStringRef| s.len_bytes() > 0 && s.byte_at(0) == b' ';
+    // If HIDE_SPACES, reject alternatives that start with space unless
+    // the input starts with space.
+    let hide_spaces = hide_spaces && !starts_with_space(string);
+    // The matches are consed up as we go, rather than collected in a
+    // Vec, so that they stay visible to the garbage collector while the
+    // predicate runs.
+    let mut allmatches = Qnil;
+    let mut regexps = CompletionRegexps::new();
+
+    map_completion_candidates(collection, table, |elt, eltstring, value| {
+        if let Some(eltstring) = completion_candidate_string(eltstring) {
+            if !(hide_spaces && starts_with_space(eltstring))
+                && completion_prefix_p(string, eltstring, ignore_case)
+                && regexps.matches(eltstring)
+                && completion_predicate_p(predicate, table, elt, value, &mut regexps)
+            {
+                allmatches = LispObject::cons(eltstring.into(), allmatches);
+            }
+        }
+        true
+    });
+    regexps.unbind();
+
+    unsafe { Fnreverse(allmatches) }
+}
+
+/// Look STRING up in OBARRAY for `test-completion', returning the
+/// matching symbol if there is one.
+fn obarray_completion_lookup(
+    string: LispStringRef,
+    obarray: LispObject,
+    ignore_case: bool,
+) -> Option<LispObject> {
+    let ob = LispObarrayRef::new(check_obarray(obarray));
+
+    // Bypass intern-soft as that loses for nil.
+    let mut tem = ob.lookup(string.into());
+    if !tem.is_symbol() {
+        let other = if string.is_multibyte() {
+            unsafe { Fstring_make_unibyte(string.into()) }
+        } else {
+            unsafe { Fstring_make_multibyte(string.into()) }
+        };
+        tem = ob.lookup(other);
+    }
+    if tem.is_symbol() {
+        return Some(tem);
+    }
+
+    if !ignore_case {
+        return None;
+    }
+
+    let zero = LispObject::from(0);
+    ob.as_lisp_obj()
+        .as_vector_or_error()
+        .iter()
+        .filter(|bucket| !bucket.eq(zero))
+        .filter_map(|bucket| bucket.as_symbol())
+        .find_map(|bucket| {
+            bucket
+                .iter()
+                .find(|sym| {
+                    sym.symbol_name()
+                        .as_string()
+                        .map_or(false, |name| completion_string_equal(string, name, true))
+                })
+                .map(|sym| sym.as_lisp_obj())
+        })
+}
+
+/// Return non-nil if STRING is a valid completion.
+/// Takes the same arguments as `all-completions' and `try-completion'.
+/// If COLLECTION is a function, it is called with three arguments:
+/// the values STRING, PREDICATE and `lambda'.
+#[lisp_fn(min = "2")]
+pub fn test_completion(
+    string: LispStringRef,
+    collection: LispObject,
+    predicate: LispObject,
+) -> LispObject {
+    let ignore_case = unsafe { globals.completion_ignore_case };
+
+    let (tem, value) = match CompletionTable::of(collection) {
+        CompletionTable::List => {
+            let tem =
+                unsafe { Fassoc_string(string.into(), collection, LispObject::from(ignore_case)) };
+            if tem.is_nil() {
+                return Qnil;
+            }
+            (tem, Qnil)
+        }
+        CompletionTable::Obarray => {
+            match obarray_completion_lookup(string, collection, ignore_case) {
+                Some(sym) => (sym, Qnil),
+                None => return Qnil,
+            }
+        }
+        CompletionTable::HashTable => {
+            let table = collection.as_hash_table_or_error();
+            match table.lookup(string.into()) {
+                Found(idx) => (table.get_hash_key(idx), table.get_hash_value(idx)),
+                Missing(_) => {
+                    let found = table.iter().find(|&(key, _)| {
+                        completion_candidate_string(key)
+                            .map_or(false, |s| completion_string_equal(string, s, ignore_case))
+                    });
+                    match found {
+                        Some(entry) => entry,
+                        None => return Qnil,
+                    }
+                }
+            }
+        }
+        CompletionTable::Function => {
+            return call!(collection, string.into(), predicate, Qlambda);
+        }
+    };
+
+    // Reject this element if it fails to match all the regexps.  We
+    // can test against STRING, because if we got here, then the
+    // element is equivalent to it.
+    let mut regexps = CompletionRegexps::new();
+    let matches = regexps.matches(string);
+    regexps.unbind();
+    if !matches {
+        return Qnil;
+    }
+
+    // Finally, check the predicate.
+    if predicate.is_nil() {
+        Qt
+    } else if collection.is_hash_table() {
+        call!(predicate, tem, value)
+    } else {
+        call!(predicate, tem)
+    }
+}
+
 include!(concat!(env!("OUT_DIR"), "/minibuf_exports.rs"));
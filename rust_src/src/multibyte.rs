@@ -225,7 +225,6 @@ impl LispStringRef {
         }
     }
 
-    #[allow(dead_code)]
     pub fn chars(&self) -> LispStringRefCharIterator {
         LispStringRefCharIterator(self.char_indices())
     }
@@ -387,6 +386,15 @@ pub fn write_codepoint(to: &mut [c_uchar], cp: Codepoint) -> usize {
     }
 }
 
+/// Append the multibyte representation of CHARS to BYTES.
+pub fn encode_codepoints(chars: &[Codepoint], bytes: &mut Vec<u8>) {
+    let mut buf = [0; MAX_MULTIBYTE_LENGTH];
+    for &c in chars {
+        let len = write_codepoint(&mut buf, c);
+        bytes.extend_from_slice(&buf[..len]);
+    }
+}
+
 /// If character code C has modifier masks, reflect them to the
 /// character code if possible.  Return the resulting code.
 #[no_mangle]
@@ -0,0 +1,440 @@
+//! Unicode normalization.
+//!
+//! The decomposition mappings and canonical combining classes come
+//! from the `decomposition' and `canonical-combining-class' character
+//! properties.  They are read once, on first use, into the tables of
+//! `UnicodeData', so that normalizing large texts does not go through
+//! Lisp for every character.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use libc::{c_char, ptrdiff_t};
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    buffers::validate_region,
+    lisp::defsubr,
+    lisp::LispObject,
+    multibyte::{encode_codepoints, Codepoint, LispStringRef},
+    obarray::intern,
+    remacs_sys::{make_buffer_string, make_string, replace_range},
+    remacs_sys::{Qerror, Qnfc, Qnfd, Qnfkc, Qnfkd},
+};
+
+// Hangul syllables are decomposed and composed algorithmically, see
+// section 3.12 of the Unicode Standard.
+const HANGUL_S_BASE: Codepoint = 0xAC00;
+const HANGUL_L_BASE: Codepoint = 0x1100;
+const HANGUL_V_BASE: Codepoint = 0x1161;
+const HANGUL_T_BASE: Codepoint = 0x11A7;
+const HANGUL_L_COUNT: Codepoint = 19;
+const HANGUL_V_COUNT: Codepoint = 21;
+const HANGUL_T_COUNT: Codepoint = 28;
+const HANGUL_N_COUNT: Codepoint = HANGUL_V_COUNT * HANGUL_T_COUNT;
+const HANGUL_S_COUNT: Codepoint = HANGUL_L_COUNT * HANGUL_N_COUNT;
+
+/// Characters whose canonical decomposition is never recomposed.
+/// Singletons and non-starter decompositions are excluded as well, but
+/// those can be recognized from the data itself.
+const COMPOSITION_EXCLUSIONS: &[Codepoint] = &[
+    0x0958, 0x0959, 0x095A, 0x095B, 0x095C, 0x095D, 0x095E, 0x095F, 0x09DC, 0x09DD, 0x09DF,
+    0x0A33, 0x0A36, 0x0A59, 0x0A5A, 0x0A5B, 0x0A5E, 0x0B5C, 0x0B5D, 0x0F43, 0x0F4D, 0x0F52,
+    0x0F57, 0x0F5C, 0x0F69, 0x0F76, 0x0F78, 0x0F93, 0x0F9D, 0x0FA2, 0x0FA7, 0x0FAC, 0x0FB9,
+    0xFB1D, 0xFB1F, 0xFB2A, 0xFB2B, 0xFB2C, 0xFB2D, 0xFB2E, 0xFB2F, 0xFB30, 0xFB31, 0xFB32,
+    0xFB33, 0xFB34, 0xFB35, 0xFB36, 0xFB38, 0xFB39, 0xFB3A, 0xFB3B, 0xFB3C, 0xFB3E, 0xFB40,
+    0xFB41, 0xFB43, 0xFB44, 0xFB46, 0xFB47, 0xFB48, 0xFB49, 0xFB4A, 0xFB4B, 0xFB4C, 0xFB4D,
+    0xFB4E, 0x2ADC, 0x1D15E, 0x1D15F, 0x1D160, 0x1D161, 0x1D162, 0x1D163, 0x1D164, 0x1D1BB,
+    0x1D1BC, 0x1D1BD, 0x1D1BE, 0x1D1BF, 0x1D1C0,
+];
+
+/// The ranges of characters that have a decomposition or a non-zero
+/// canonical combining class.  Hangul syllables are left out.
+const PROPERTY_RANGES: &[(Codepoint, Codepoint)] = &[
+    (0x00A0, 0x33FF),
+    (0xA000, 0xABFF),
+    (0xF900, 0x1FFFF),
+    (0x2F800, 0x2FAFF),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NormalizationForm {
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+
+impl NormalizationForm {
+    pub fn from_lisp(form: LispObject) -> Self {
+        if form.eq(Qnfc) {
+            NormalizationForm::Nfc
+        } else if form.eq(Qnfd) {
+            NormalizationForm::Nfd
+        } else if form.eq(Qnfkc) {
+            NormalizationForm::Nfkc
+        } else if form.eq(Qnfkd) {
+            NormalizationForm::Nfkd
+        } else {
+            xsignal!(Qerror, LispObject::from("Invalid normalization form"), form)
+        }
+    }
+
+    fn is_compatibility(self) -> bool {
+        self == NormalizationForm::Nfkc || self == NormalizationForm::Nfkd
+    }
+
+    fn is_composed(self) -> bool {
+        self == NormalizationForm::Nfc || self == NormalizationForm::Nfkc
+    }
+}
+
+struct Decomposition {
+    compatibility: bool,
+    chars: Vec<Codepoint>,
+}
+
+#[derive(Default)]
+pub struct UnicodeData {
+    decompositions: HashMap<Codepoint, Decomposition>,
+    combining_classes: HashMap<Codepoint, u8>,
+    compositions: HashMap<(Codepoint, Codepoint), Codepoint>,
+}
+
+impl UnicodeData {
+    /// Read the tables from the character property database.
+    fn load() -> Self {
+        let get_char_code_property = intern("get-char-code-property");
+        let decomposition = intern("decomposition");
+        let canonical_combining_class = intern("canonical-combining-class");
+
+        let mut data = Self::default();
+        for &(start, end) in PROPERTY_RANGES {
+            for c in start..=end {
+                let class = call!(
+                    get_char_code_property,
+                    LispObject::from(c),
+                    canonical_combining_class
+                );
+                if let Some(class) = class.as_fixnum().filter(|&class| class != 0) {
+                    data.combining_classes.insert(c, class as u8);
+                }
+
+                let value = call!(get_char_code_property, LispObject::from(c), decomposition);
+                let compatibility = value.as_cons().map_or(false, |cons| cons.car().is_symbol());
+                let chars: Vec<Codepoint> = value
+                    .iter_cars_safe()
+                    .filter_map(|elt| elt.as_fixnum())
+                    .map(|c| c as Codepoint)
+                    .collect();
+                if !chars.is_empty() && chars != [c] {
+                    data.decompositions.insert(
+                        c,
+                        Decomposition {
+                            compatibility,
+                            chars,
+                        },
+                    );
+                }
+            }
+        }
+        data.compute_compositions();
+        data
+    }
+
+    /// Fill in the map from pairs of characters to the primary
+    /// composite they form.
+    fn compute_compositions(&mut self) {
+        let mut compositions = HashMap::new();
+        for (&c, decomposition) in &self.decompositions {
+            let chars = &decomposition.chars;
+            if chars.len() == 2
+                && !decomposition.compatibility
+                && self.combining_class(chars[0]) == 0
+                && !COMPOSITION_EXCLUSIONS.contains(&c)
+            {
+                compositions.insert((chars[0], chars[1]), c);
+            }
+        }
+        self.compositions = compositions;
+    }
+
+    pub fn combining_class(&self, c: Codepoint) -> u8 {
+        self.combining_classes.get(&c).cloned().unwrap_or(0)
+    }
+
+    /// Append the full decomposition of C to OUT.
+    fn decompose_char(&self, c: Codepoint, compatibility: bool, out: &mut Vec<Codepoint>) {
+        if c >= HANGUL_S_BASE && c < HANGUL_S_BASE + HANGUL_S_COUNT {
+            let index = c - HANGUL_S_BASE;
+            out.push(HANGUL_L_BASE + index / HANGUL_N_COUNT);
+            out.push(HANGUL_V_BASE + (index % HANGUL_N_COUNT) / HANGUL_T_COUNT);
+            if index % HANGUL_T_COUNT != 0 {
+                out.push(HANGUL_T_BASE + index % HANGUL_T_COUNT);
+            }
+            return;
+        }
+
+        match self.decompositions.get(&c) {
+            Some(decomposition) if compatibility || !decomposition.compatibility => {
+                for &d in &decomposition.chars {
+                    self.decompose_char(d, compatibility, out);
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+
+    /// Put each run of non-starters in TEXT into canonical order.
+    fn reorder(&self, text: &mut [Codepoint]) {
+        let mut start = 0;
+        while start < text.len() {
+            if self.combining_class(text[start]) == 0 {
+                start += 1;
+                continue;
+            }
+            let end = text[start..]
+                .iter()
+                .position(|&c| self.combining_class(c) == 0)
+                .map_or(text.len(), |len| start + len);
+            // A stable sort, so characters of the same class keep their
+            // relative order.
+            text[start..end].sort_by_key(|&c| self.combining_class(c));
+            start = end;
+        }
+    }
+
+    fn compose_pair(&self, first: Codepoint, second: Codepoint) -> Option<Codepoint> {
+        if first >= HANGUL_L_BASE
+            && first < HANGUL_L_BASE + HANGUL_L_COUNT
+            && second >= HANGUL_V_BASE
+            && second < HANGUL_V_BASE + HANGUL_V_COUNT
+        {
+            let l_index = first - HANGUL_L_BASE;
+            let v_index = second - HANGUL_V_BASE;
+            return Some(HANGUL_S_BASE + (l_index * HANGUL_V_COUNT + v_index) * HANGUL_T_COUNT);
+        }
+        if first >= HANGUL_S_BASE
+            && first < HANGUL_S_BASE + HANGUL_S_COUNT
+            && (first - HANGUL_S_BASE) % HANGUL_T_COUNT == 0
+            && second > HANGUL_T_BASE
+            && second < HANGUL_T_BASE + HANGUL_T_COUNT
+        {
+            return Some(first + (second - HANGUL_T_BASE));
+        }
+        self.compositions.get(&(first, second)).cloned()
+    }
+
+    /// Apply the canonical composition algorithm to the decomposed
+    /// TEXT.
+    fn compose(&self, text: &[Codepoint]) -> Vec<Codepoint> {
+        let mut result: Vec<Codepoint> = Vec::with_capacity(text.len());
+        let mut starter: Option<usize> = None;
+        // The combining class of the last character appended after the
+        // starter, if any.
+        let mut last_class: Option<u8> = None;
+        for &c in text {
+            let class = self.combining_class(c);
+            if let Some(starter) = starter {
+                let blocked = last_class.map_or(false, |last| last == 0 || last >= class);
+                if !blocked {
+                    if let Some(composite) = self.compose_pair(result[starter], c) {
+                        result[starter] = composite;
+                        continue;
+                    }
+                }
+            }
+            if class == 0 {
+                starter = Some(result.len());
+                last_class = None;
+            } else {
+                last_class = Some(class);
+            }
+            result.push(c);
+        }
+        result
+    }
+
+    /// Return TEXT normalized to FORM.
+    pub fn normalize(&self, text: &[Codepoint], form: NormalizationForm) -> Vec<Codepoint> {
+        let mut decomposed = Vec::with_capacity(text.len());
+        for &c in text {
+            self.decompose_char(c, form.is_compatibility(), &mut decomposed);
+        }
+        self.reorder(&mut decomposed);
+        if form.is_composed() {
+            self.compose(&decomposed)
+        } else {
+            decomposed
+        }
+    }
+}
+
+lazy_static! {
+    static ref UNICODE_DATA: Mutex<Option<Arc<UnicodeData>>> = Mutex::new(None);
+}
+
+/// Return the normalization tables, loading them if necessary.  The
+/// lock is not held while loading, as that runs Lisp code.
+fn unicode_data() -> Arc<UnicodeData> {
+    if let Some(ref data) = *UNICODE_DATA.lock().unwrap() {
+        return Arc::clone(data);
+    }
+    let data = Arc::new(UnicodeData::load());
+    *UNICODE_DATA.lock().unwrap() = Some(Arc::clone(&data));
+    data
+}
+
+/// Return TEXT normalized to FORM, or None if it already is.
+fn normalize_codepoints(text: &[Codepoint], form: NormalizationForm) -> Option<Vec<Codepoint>> {
+    // ASCII text is in every normalization form.
+    if text.iter().all(|&c| c < 0x80) {
+        return None;
+    }
+    let normalized = unicode_data().normalize(text, form);
+    if normalized[..] == *text {
+        None
+    } else {
+        Some(normalized)
+    }
+}
+
+fn make_multibyte_lisp_string(text: &[Codepoint]) -> LispObject {
+    let mut bytes = Vec::with_capacity(text.len());
+    encode_codepoints(text, &mut bytes);
+    unsafe { make_string(bytes.as_ptr() as *const c_char, bytes.len() as ptrdiff_t) }
+}
+
+/// Return STRING normalized to the Unicode normalization FORM.
+/// FORM is one of the symbols `nfc', `nfd', `nfkc' and `nfkd'.
+/// `nfd' decomposes characters into base characters and combining
+/// marks, and `nfc' composes them again wherever a precomposed
+/// character exists.  `nfkd' and `nfkc' also replace compatibility
+/// characters, such as ligatures and full-width forms, with their
+/// ordinary equivalents.
+/// If STRING is already in FORM, it is returned unchanged; otherwise
+/// the result is a new string without text properties.  Unibyte
+/// strings are always returned unchanged.
+#[lisp_fn]
+pub fn string_normalize(string: LispStringRef, form: LispObject) -> LispObject {
+    let form = NormalizationForm::from_lisp(form);
+    if !string.is_multibyte() {
+        return string.as_lisp_obj();
+    }
+    let text: Vec<Codepoint> = string.chars().collect();
+    match normalize_codepoints(&text, form) {
+        Some(normalized) => make_multibyte_lisp_string(&normalized),
+        None => string.as_lisp_obj(),
+    }
+}
+
+/// Return t if STRING is in the Unicode normalization FORM.
+/// FORM is one of the symbols `nfc', `nfd', `nfkc' and `nfkd'; see
+/// `string-normalize'.
+#[lisp_fn]
+pub fn string_normalized_p(string: LispStringRef, form: LispObject) -> bool {
+    let form = NormalizationForm::from_lisp(form);
+    if !string.is_multibyte() {
+        return true;
+    }
+    let text: Vec<Codepoint> = string.chars().collect();
+    normalize_codepoints(&text, form).is_none()
+}
+
+/// Normalize the text of the region to the Unicode normalization FORM.
+/// In Lisp code, the region is given by START and END, and FORM is one
+/// of the symbols `nfc', `nfd', `nfkc' and `nfkd'; see
+/// `string-normalize'.  The buffer is modified only if some of the text
+/// is not already in FORM.
+#[lisp_fn(intspec = "r\nSNormalization form (nfc, nfd, nfkc or nfkd): ")]
+pub fn normalize_region(start: LispObject, end: LispObject, form: LispObject) {
+    let form = NormalizationForm::from_lisp(form);
+    let (mut start, mut end) = (start, end);
+    unsafe { validate_region(&mut start, &mut end) };
+    let start = start.as_fixnum_or_error() as ptrdiff_t;
+    let end = end.as_fixnum_or_error() as ptrdiff_t;
+
+    let original = unsafe { make_buffer_string(start, end, false) };
+    let text: Vec<Codepoint> = original.as_string_or_error().chars().collect();
+    if let Some(normalized) = normalize_codepoints(&text, form) {
+        let new = make_multibyte_lisp_string(&normalized);
+        unsafe { replace_range(start, end, new, true, false, true, false) };
+    }
+}
+
+def_lisp_sym!(Qnfc, "nfc");
+def_lisp_sym!(Qnfd, "nfd");
+def_lisp_sym!(Qnfkc, "nfkc");
+def_lisp_sym!(Qnfkd, "nfkd");
+
+include!(concat!(env!("OUT_DIR"), "/normalize_exports.rs"));
+
+#[cfg(test)]
+fn test_data() -> UnicodeData {
+    let mut data = UnicodeData::default();
+    let canonical = |chars: &[Codepoint]| Decomposition {
+        compatibility: false,
+        chars: chars.to_vec(),
+    };
+    // é, ê, ế, the ohm sign, the "fi" ligature and an excluded character.
+    data.decompositions.insert(0xE9, canonical(&[0x65, 0x301]));
+    data.decompositions.insert(0xEA, canonical(&[0x65, 0x302]));
+    data.decompositions.insert(0x1EBF, canonical(&[0xEA, 0x301]));
+    data.decompositions.insert(0x2126, canonical(&[0x3A9]));
+    data.decompositions.insert(
+        0xFB01,
+        Decomposition {
+            compatibility: true,
+            chars: vec![0x66, 0x69],
+        },
+    );
+    data.decompositions.insert(0x0958, canonical(&[0x915, 0x93C]));
+    data.combining_classes.insert(0x301, 230);
+    data.combining_classes.insert(0x302, 230);
+    data.combining_classes.insert(0x323, 220);
+    data.combining_classes.insert(0x93C, 7);
+    data.compute_compositions();
+    data
+}
+
+#[test]
+fn test_normalize_decompose() {
+    let data = test_data();
+    assert_eq!(
+        data.normalize(&[0x1EBF], NormalizationForm::Nfd),
+        vec![0x65, 0x302, 0x301]
+    );
+    assert_eq!(data.normalize(&[0x2126], NormalizationForm::Nfd), vec![0x3A9]);
+    assert_eq!(data.normalize(&[0xFB01], NormalizationForm::Nfd), vec![0xFB01]);
+    assert_eq!(data.normalize(&[0xFB01], NormalizationForm::Nfkd), vec![0x66, 0x69]);
+    // Combining marks are put into canonical order.
+    assert_eq!(
+        data.normalize(&[0x65, 0x301, 0x323], NormalizationForm::Nfd),
+        vec![0x65, 0x323, 0x301]
+    );
+    // Hangul syllables are decomposed algorithmically.
+    assert_eq!(
+        data.normalize(&[0xD55C], NormalizationForm::Nfd),
+        vec![0x1112, 0x1161, 0x11AB]
+    );
+}
+
+#[test]
+fn test_normalize_compose() {
+    let data = test_data();
+    assert_eq!(data.normalize(&[0x65, 0x301], NormalizationForm::Nfc), vec![0xE9]);
+    assert_eq!(data.normalize(&[0x65, 0x302, 0x301], NormalizationForm::Nfc), vec![0x1EBF]);
+    assert_eq!(data.normalize(&[0x2126], NormalizationForm::Nfc), vec![0x3A9]);
+    assert_eq!(data.normalize(&[0x958], NormalizationForm::Nfc), vec![0x915, 0x93C]);
+    // A mark of the same class in between blocks the composition.
+    assert_eq!(
+        data.normalize(&[0x65, 0x302, 0x302, 0x301], NormalizationForm::Nfc),
+        vec![0xEA, 0x302, 0x301]
+    );
+    assert_eq!(
+        data.normalize(&[0x1112, 0x1161, 0x11AB], NormalizationForm::Nfc),
+        vec![0xD55C]
+    );
+    assert_eq!(data.normalize(&[0xFB01], NormalizationForm::Nfkc), vec![0x66, 0x69]);
+}
@@ -0,0 +1,705 @@
+//! Text segmentation into grapheme clusters and words, following
+//! Unicode Standard Annex #29.
+//!
+//! The break properties of characters are derived from their general
+//! category, with small tables for the characters that the category
+//! does not tell apart.
+
+use std::cmp;
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    chartable::LispCharTableRef,
+    lisp::defsubr,
+    lisp::LispObject,
+    lists::list,
+    multibyte::{Codepoint, LispStringRef},
+    obarray::intern,
+    remacs_sys::{make_buffer_string, uniprop_table, EmacsInt, Fsubstring},
+    remacs_sys::globals,
+    threads::ThreadState,
+};
+
+/// The Grapheme_Cluster_Break property.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GraphemeBreak {
+    CR,
+    LF,
+    Control,
+    Extend,
+    ZWJ,
+    RegionalIndicator,
+    Prepend,
+    SpacingMark,
+    L,
+    V,
+    T,
+    LV,
+    LVT,
+    ExtendedPictographic,
+    Other,
+}
+
+/// The Word_Break property.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WordBreak {
+    CR,
+    LF,
+    Newline,
+    Extend,
+    ZWJ,
+    RegionalIndicator,
+    Format,
+    Katakana,
+    HebrewLetter,
+    ALetter,
+    SingleQuote,
+    DoubleQuote,
+    MidNumLet,
+    MidLetter,
+    MidNum,
+    Numeric,
+    ExtendNumLet,
+    WSegSpace,
+    ExtendedPictographic,
+    Other,
+}
+
+const ZERO_WIDTH_NON_JOINER: Codepoint = 0x200C;
+const ZERO_WIDTH_JOINER: Codepoint = 0x200D;
+
+/// Characters with the Extended_Pictographic property.
+const EXTENDED_PICTOGRAPHIC: &[(Codepoint, Codepoint)] = &[
+    (0x00A9, 0x00A9),
+    (0x00AE, 0x00AE),
+    (0x203C, 0x203C),
+    (0x2049, 0x2049),
+    (0x2122, 0x2122),
+    (0x2139, 0x2139),
+    (0x2194, 0x2199),
+    (0x21A9, 0x21AA),
+    (0x231A, 0x231B),
+    (0x2328, 0x2328),
+    (0x2388, 0x2388),
+    (0x23CF, 0x23CF),
+    (0x23E9, 0x23F3),
+    (0x23F8, 0x23FA),
+    (0x24C2, 0x24C2),
+    (0x25AA, 0x25AB),
+    (0x25B6, 0x25B6),
+    (0x25C0, 0x25C0),
+    (0x25FB, 0x25FE),
+    (0x2600, 0x27BF),
+    (0x2934, 0x2935),
+    (0x2B05, 0x2B07),
+    (0x2B1B, 0x2B1C),
+    (0x2B50, 0x2B50),
+    (0x2B55, 0x2B55),
+    (0x3030, 0x3030),
+    (0x303D, 0x303D),
+    (0x3297, 0x3297),
+    (0x3299, 0x3299),
+    (0x1F000, 0x1F0FF),
+    (0x1F10D, 0x1F10F),
+    (0x1F12F, 0x1F12F),
+    (0x1F16C, 0x1F171),
+    (0x1F17E, 0x1F17F),
+    (0x1F18E, 0x1F18E),
+    (0x1F191, 0x1F19A),
+    (0x1F1AD, 0x1F1E5),
+    (0x1F201, 0x1F20F),
+    (0x1F21A, 0x1F21A),
+    (0x1F22F, 0x1F22F),
+    (0x1F232, 0x1F23A),
+    (0x1F23C, 0x1F23F),
+    (0x1F249, 0x1F3FA),
+    (0x1F400, 0x1F53D),
+    (0x1F546, 0x1F64F),
+    (0x1F680, 0x1F6FF),
+    (0x1F774, 0x1F77F),
+    (0x1F7D5, 0x1F7FF),
+    (0x1F80C, 0x1F80F),
+    (0x1F848, 0x1F84F),
+    (0x1F85A, 0x1F85F),
+    (0x1F888, 0x1F88F),
+    (0x1F8AE, 0x1F8FF),
+    (0x1F90C, 0x1F93A),
+    (0x1F93C, 0x1F945),
+    (0x1F947, 0x1FAFF),
+    (0x1FC00, 0x1FFFD),
+];
+
+/// Characters with Grapheme_Cluster_Break=Prepend.
+const PREPEND: &[(Codepoint, Codepoint)] = &[
+    (0x0600, 0x0605),
+    (0x06DD, 0x06DD),
+    (0x070F, 0x070F),
+    (0x08E2, 0x08E2),
+    (0x0D4E, 0x0D4E),
+    (0x110BD, 0x110BD),
+    (0x110CD, 0x110CD),
+    (0x111C2, 0x111C3),
+];
+
+const KATAKANA: &[(Codepoint, Codepoint)] = &[
+    (0x3031, 0x3035),
+    (0x309B, 0x309C),
+    (0x30A0, 0x30FA),
+    (0x30FC, 0x30FF),
+    (0x31F0, 0x31FF),
+    (0x32D0, 0x32FE),
+    (0x3300, 0x3357),
+    (0xFF66, 0xFF9D),
+    (0x1B000, 0x1B000),
+];
+
+const HEBREW_LETTER: &[(Codepoint, Codepoint)] = &[
+    (0x05D0, 0x05EA),
+    (0x05EF, 0x05F2),
+    (0xFB1D, 0xFB1D),
+    (0xFB1F, 0xFB28),
+    (0xFB2A, 0xFB4F),
+];
+
+/// Letters of scripts that are neither split into words nor joined
+/// into them by these rules: ideographs, Hiragana, and the scripts
+/// written without spaces between words.
+const NOT_ALETTER: &[(Codepoint, Codepoint)] = &[
+    (0x0E00, 0x0EFF),
+    (0x1000, 0x109F),
+    (0x1780, 0x17FF),
+    (0x3040, 0x309F),
+    (0x3400, 0x4DBF),
+    (0x4E00, 0x9FFF),
+    (0xF900, 0xFAFF),
+    (0x20000, 0x3FFFF),
+];
+
+const MID_NUM_LET: &[Codepoint] = &[0x2E, 0x2018, 0x2019, 0x2024, 0xFE52, 0xFF07, 0xFF0E];
+const MID_LETTER: &[Codepoint] = &[0x3A, 0xB7, 0x387, 0x55F, 0x5F4, 0x2027, 0xFE13, 0xFE55, 0xFF1A];
+const MID_NUM: &[Codepoint] = &[
+    0x2C, 0x3B, 0x37E, 0x589, 0x60C, 0x60D, 0x66C, 0x7F8, 0x2044, 0xFE10, 0xFE14, 0xFE50, 0xFE54,
+    0xFF0C, 0xFF1B,
+];
+
+fn in_ranges(ranges: &[(Codepoint, Codepoint)], c: Codepoint) -> bool {
+    ranges.iter().any(|&(start, end)| c >= start && c <= end)
+}
+
+fn is_regional_indicator(c: Codepoint) -> bool {
+    c >= 0x1F1E6 && c <= 0x1F1FF
+}
+
+fn is_emoji_modifier(c: Codepoint) -> bool {
+    c >= 0x1F3FB && c <= 0x1F3FF
+}
+
+/// Return the Grapheme_Cluster_Break property of C, whose general
+/// category is CATEGORY.
+pub fn grapheme_break(c: Codepoint, category: &[u8]) -> GraphemeBreak {
+    match c {
+        0x0D => return GraphemeBreak::CR,
+        0x0A => return GraphemeBreak::LF,
+        ZERO_WIDTH_JOINER => return GraphemeBreak::ZWJ,
+        ZERO_WIDTH_NON_JOINER | 0xFF9E | 0xFF9F => return GraphemeBreak::Extend,
+        0x0E33 | 0x0EB3 => return GraphemeBreak::SpacingMark,
+        0x1100..=0x115F | 0xA960..=0xA97C => return GraphemeBreak::L,
+        0x1160..=0x11A7 | 0xD7B0..=0xD7C6 => return GraphemeBreak::V,
+        0x11A8..=0x11FF | 0xD7CB..=0xD7FB => return GraphemeBreak::T,
+        0xAC00..=0xD7A3 => {
+            return if (c - 0xAC00) % 28 == 0 {
+                GraphemeBreak::LV
+            } else {
+                GraphemeBreak::LVT
+            };
+        }
+        0xE0020..=0xE007F => return GraphemeBreak::Extend,
+        _ => {}
+    }
+    if is_regional_indicator(c) {
+        GraphemeBreak::RegionalIndicator
+    } else if is_emoji_modifier(c) {
+        GraphemeBreak::Extend
+    } else if in_ranges(PREPEND, c) {
+        GraphemeBreak::Prepend
+    } else if in_ranges(EXTENDED_PICTOGRAPHIC, c) {
+        GraphemeBreak::ExtendedPictographic
+    } else {
+        match category {
+            b"Cc" | b"Cf" | b"Zl" | b"Zp" => GraphemeBreak::Control,
+            b"Mn" | b"Me" => GraphemeBreak::Extend,
+            b"Mc" => GraphemeBreak::SpacingMark,
+            _ => GraphemeBreak::Other,
+        }
+    }
+}
+
+/// Return the Word_Break property of C, whose general category is
+/// CATEGORY.
+pub fn word_break(c: Codepoint, category: &[u8]) -> WordBreak {
+    match c {
+        0x0D => return WordBreak::CR,
+        0x0A => return WordBreak::LF,
+        0x0B | 0x0C | 0x85 | 0x2028 | 0x2029 => return WordBreak::Newline,
+        ZERO_WIDTH_JOINER => return WordBreak::ZWJ,
+        ZERO_WIDTH_NON_JOINER | 0xFF9E | 0xFF9F => return WordBreak::Extend,
+        0x22 => return WordBreak::DoubleQuote,
+        0x27 => return WordBreak::SingleQuote,
+        0x202F => return WordBreak::ExtendNumLet,
+        _ => {}
+    }
+    if MID_NUM_LET.contains(&c) {
+        WordBreak::MidNumLet
+    } else if MID_LETTER.contains(&c) {
+        WordBreak::MidLetter
+    } else if MID_NUM.contains(&c) {
+        WordBreak::MidNum
+    } else if is_regional_indicator(c) {
+        WordBreak::RegionalIndicator
+    } else if is_emoji_modifier(c) {
+        WordBreak::Extend
+    } else if in_ranges(KATAKANA, c) {
+        WordBreak::Katakana
+    } else if in_ranges(HEBREW_LETTER, c) {
+        WordBreak::HebrewLetter
+    } else if in_ranges(EXTENDED_PICTOGRAPHIC, c) {
+        WordBreak::ExtendedPictographic
+    } else {
+        match category {
+            b"Mn" | b"Me" | b"Mc" => WordBreak::Extend,
+            b"Cf" => WordBreak::Format,
+            b"Nd" => WordBreak::Numeric,
+            b"Pc" => WordBreak::ExtendNumLet,
+            // No-break spaces do not separate words.
+            b"Zs" if c != 0xA0 && c != 0x2007 => WordBreak::WSegSpace,
+            b"Lu" | b"Ll" | b"Lt" | b"Lm" | b"Lo" | b"Nl" if !in_ranges(NOT_ALETTER, c) => {
+                WordBreak::ALetter
+            }
+            _ => WordBreak::Other,
+        }
+    }
+}
+
+/// Return whether there is a grapheme cluster boundary between PREV
+/// and NEXT.  RI_COUNT is the number of regional indicators ending
+/// with PREV, and ZWJ_AFTER_PICTOGRAPHIC tells whether PREV is a
+/// zero-width joiner ending an emoji sequence.
+fn is_grapheme_break(
+    prev: GraphemeBreak,
+    next: GraphemeBreak,
+    ri_count: usize,
+    zwj_after_pictographic: bool,
+) -> bool {
+    use self::GraphemeBreak::*;
+    match (prev, next) {
+        (CR, LF) => false,
+        (CR, _) | (LF, _) | (Control, _) => true,
+        (_, CR) | (_, LF) | (_, Control) => true,
+        (L, L) | (L, V) | (L, LV) | (L, LVT) => false,
+        (LV, V) | (LV, T) | (V, V) | (V, T) => false,
+        (LVT, T) | (T, T) => false,
+        (_, Extend) | (_, ZWJ) | (_, SpacingMark) => false,
+        (Prepend, _) => false,
+        (ZWJ, ExtendedPictographic) => !zwj_after_pictographic,
+        (RegionalIndicator, RegionalIndicator) => ri_count % 2 == 0,
+        _ => true,
+    }
+}
+
+/// Return the grapheme cluster boundaries of text whose characters
+/// have the break properties PROPS, as indexes into PROPS.  Both ends
+/// of non-empty text are boundaries.
+pub fn grapheme_boundaries(props: &[GraphemeBreak]) -> Vec<usize> {
+    let mut boundaries = vec![0];
+    let mut ri_count = 0;
+    // Whether the text so far ends with Extended_Pictographic Extend*,
+    // and with Extended_Pictographic Extend* ZWJ.
+    let mut in_pictographic = false;
+    let mut zwj_after_pictographic = false;
+    for (i, &prop) in props.iter().enumerate() {
+        if i > 0 && is_grapheme_break(props[i - 1], prop, ri_count, zwj_after_pictographic) {
+            boundaries.push(i);
+        }
+        ri_count = if prop == GraphemeBreak::RegionalIndicator {
+            ri_count + 1
+        } else {
+            0
+        };
+        zwj_after_pictographic = prop == GraphemeBreak::ZWJ && in_pictographic;
+        in_pictographic = prop == GraphemeBreak::ExtendedPictographic
+            || (in_pictographic && prop == GraphemeBreak::Extend);
+    }
+    if !props.is_empty() {
+        boundaries.push(props.len());
+    }
+    boundaries
+}
+
+fn is_word_ignorable(prop: WordBreak) -> bool {
+    prop == WordBreak::Extend || prop == WordBreak::Format || prop == WordBreak::ZWJ
+}
+
+/// Return whether there is a word boundary before index I of PROPS,
+/// where 0 < I < PROPS.len().
+fn is_word_break(props: &[WordBreak], i: usize) -> bool {
+    use self::WordBreak::*;
+    let is_newline = |prop| prop == Newline || prop == CR || prop == LF;
+    let is_ahletter = |prop| prop == ALetter || prop == HebrewLetter;
+    let is_mid_num_let_q = |prop| prop == MidNumLet || prop == SingleQuote;
+
+    let next = props[i];
+    match (props[i - 1], next) {
+        (CR, LF) => return false,
+        (prev, _) if is_newline(prev) => return true,
+        (_, next) if is_newline(next) => return true,
+        (ZWJ, ExtendedPictographic) => return false,
+        (WSegSpace, WSegSpace) => return false,
+        (_, next) if is_word_ignorable(next) => return false,
+        _ => {}
+    }
+
+    // Extend, Format and ZWJ characters are attached to the character
+    // before them and otherwise ignored.
+    let skip_back = |from: usize| (0..from).rev().find(|&k| !is_word_ignorable(props[k]));
+    let prev_index = match skip_back(i) {
+        Some(index) => index,
+        None => return true,
+    };
+    let prev = props[prev_index];
+    if is_newline(prev) {
+        return true;
+    }
+    let prev2 = skip_back(prev_index).map(|k| props[k]);
+    let next2 = (i + 1..props.len())
+        .find(|&k| !is_word_ignorable(props[k]))
+        .map(|k| props[k]);
+
+    match (prev, next) {
+        (a, b) if is_ahletter(a) && is_ahletter(b) => false,
+        (a, b)
+            if is_ahletter(a)
+                && (b == MidLetter || is_mid_num_let_q(b))
+                && next2.map_or(false, is_ahletter) =>
+        {
+            false
+        }
+        (a, b)
+            if (a == MidLetter || is_mid_num_let_q(a))
+                && is_ahletter(b)
+                && prev2.map_or(false, is_ahletter) =>
+        {
+            false
+        }
+        (HebrewLetter, SingleQuote) => false,
+        (HebrewLetter, DoubleQuote) if next2 == Some(HebrewLetter) => false,
+        (DoubleQuote, HebrewLetter) if prev2 == Some(HebrewLetter) => false,
+        (Numeric, Numeric) => false,
+        (a, Numeric) if is_ahletter(a) => false,
+        (Numeric, b) if is_ahletter(b) => false,
+        (Numeric, b) if (b == MidNum || is_mid_num_let_q(b)) && next2 == Some(Numeric) => false,
+        (a, Numeric) if (a == MidNum || is_mid_num_let_q(a)) && prev2 == Some(Numeric) => false,
+        (Katakana, Katakana) => false,
+        (a, ExtendNumLet) if is_ahletter(a) || a == Numeric || a == Katakana => false,
+        (ExtendNumLet, ExtendNumLet) => false,
+        (ExtendNumLet, b) if is_ahletter(b) || b == Numeric || b == Katakana => false,
+        (RegionalIndicator, RegionalIndicator) => {
+            // Count the regional indicators before NEXT, ignoring
+            // attached characters; only pairs are kept together.
+            let count = (0..i)
+                .rev()
+                .filter(|&k| !is_word_ignorable(props[k]))
+                .take_while(|&k| props[k] == RegionalIndicator)
+                .count();
+            count % 2 == 0
+        }
+        _ => true,
+    }
+}
+
+/// Return the word boundaries of text whose characters have the break
+/// properties PROPS, as indexes into PROPS.  Both ends of non-empty
+/// text are boundaries.
+pub fn word_boundaries(props: &[WordBreak]) -> Vec<usize> {
+    let mut boundaries = vec![0];
+    boundaries.extend((1..props.len()).filter(|&i| is_word_break(props, i)));
+    if !props.is_empty() {
+        boundaries.push(props.len());
+    }
+    boundaries
+}
+
+/// Return the name of the general category of C, as in the
+/// `general-category' character property.
+fn general_category_name(general_category: LispObject, c: Codepoint) -> Vec<u8> {
+    general_category
+        .as_char_table()
+        .and_then(|table: LispCharTableRef| table.get(c as isize).as_symbol())
+        .map_or_else(Vec::new, |symbol| {
+            symbol.symbol_name().as_string_or_error().as_slice().to_vec()
+        })
+}
+
+fn grapheme_props(text: &[Codepoint]) -> Vec<GraphemeBreak> {
+    let general_category = unsafe { uniprop_table(intern("general-category")) };
+    text.iter()
+        .map(|&c| grapheme_break(c, &general_category_name(general_category, c)))
+        .collect()
+}
+
+fn word_props(text: &[Codepoint]) -> Vec<WordBreak> {
+    let general_category = unsafe { uniprop_table(intern("general-category")) };
+    text.iter()
+        .map(|&c| word_break(c, &general_category_name(general_category, c)))
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Segmentation {
+    Graphemes,
+    Words,
+}
+
+impl Segmentation {
+    fn boundaries(self, text: &[Codepoint]) -> Vec<usize> {
+        match self {
+            Segmentation::Graphemes => grapheme_boundaries(&grapheme_props(text)),
+            Segmentation::Words => word_boundaries(&word_props(text)),
+        }
+    }
+
+    /// Return the segments of STRING, sharing its text properties.
+    fn string_segments(self, string: LispStringRef) -> LispObject {
+        let text: Vec<Codepoint> = string.chars().collect();
+        let boundaries = self.boundaries(&text);
+        let segments: Vec<LispObject> = boundaries
+            .windows(2)
+            .map(|w| unsafe {
+                Fsubstring(
+                    string.as_lisp_obj(),
+                    LispObject::from(w[0]),
+                    LispObject::from(w[1]),
+                )
+            })
+            .collect();
+        list(&segments)
+    }
+
+    fn boundary_p(self, position: LispObject, string: LispObject) -> bool {
+        if string.is_not_nil() {
+            let string = string.as_string_or_error();
+            let pos = position.as_fixnum_or_error();
+            if pos < 0 || pos > string.len_chars() as EmacsInt {
+                args_out_of_range!(string, position);
+            }
+            let text: Vec<Codepoint> = string.chars().collect();
+            return self.boundaries(&text).contains(&(pos as usize));
+        }
+
+        let buffer = ThreadState::current_buffer();
+        let pos = position.as_fixnum_coerce_marker_or_error() as isize;
+        if pos < buffer.begv || pos > buffer.zv {
+            args_out_of_range!(
+                position,
+                LispObject::from(buffer.begv as EmacsInt),
+                LispObject::from(buffer.zv as EmacsInt)
+            );
+        }
+        let (start, boundaries) = self.buffer_boundaries(pos);
+        boundaries.contains(&(pos - start))
+    }
+
+    /// Return the boundaries of the buffer text around POS, relative to
+    /// the returned start of the examined text.  Segments longer than
+    /// `SEGMENTATION_CONTEXT' characters may be split.
+    fn buffer_boundaries(self, pos: isize) -> (isize, Vec<isize>) {
+        let buffer = ThreadState::current_buffer();
+        let start = cmp::max(buffer.begv, pos - SEGMENTATION_CONTEXT);
+        let end = cmp::min(buffer.zv, pos + SEGMENTATION_CONTEXT);
+        let text: Vec<Codepoint> = unsafe { make_buffer_string(start, end, false) }
+            .as_string_or_error()
+            .chars()
+            .collect();
+        let boundaries = self
+            .boundaries(&text)
+            .into_iter()
+            .map(|b| b as isize)
+            .collect();
+        (start, boundaries)
+    }
+}
+
+/// The number of characters on each side of a buffer position that are
+/// examined to find the boundaries around it.
+const SEGMENTATION_CONTEXT: isize = 128;
+
+/// Return the position N grapheme clusters after FROM in the current
+/// buffer, or before it if N is negative.  If the accessible portion of
+/// the buffer ends first, the result lies beyond it by the number of
+/// clusters that remain, so that callers can treat it like FROM + N.
+pub fn grapheme_motion_target(from: isize, n: isize) -> isize {
+    let buffer = ThreadState::current_buffer();
+    let mut pos = from;
+    for moved in 0..n.abs() {
+        let limit = if n > 0 { buffer.zv } else { buffer.begv };
+        if pos == limit {
+            return pos + n.signum() * (n.abs() - moved);
+        }
+        let (start, boundaries) = Segmentation::Graphemes.buffer_boundaries(pos);
+        let relative = pos - start;
+        pos = if n > 0 {
+            boundaries
+                .iter()
+                .find(|&&b| b > relative)
+                .map_or(limit, |&b| start + b)
+        } else {
+            boundaries
+                .iter()
+                .rev()
+                .find(|&&b| b < relative)
+                .map_or(limit, |&b| start + b)
+        };
+    }
+    pos
+}
+
+/// Return non-nil if `forward-char' and similar commands should move
+/// over grapheme clusters.
+pub fn grapheme_cluster_motion_p() -> bool {
+    unsafe { globals.Vgrapheme_cluster_motion }.is_not_nil()
+}
+
+/// Return a list of the grapheme clusters of STRING.
+/// A grapheme cluster is what a user perceives as a single character,
+/// such as a base character with its combining marks, a Hangul
+/// syllable written with conjoining jamo, or an emoji sequence with
+/// modifiers and zero-width joiners.  Clusters are determined by the
+/// rules of Unicode Standard Annex #29.  The substrings keep the text
+/// properties of STRING.
+#[lisp_fn]
+pub fn string_graphemes(string: LispStringRef) -> LispObject {
+    Segmentation::Graphemes.string_segments(string)
+}
+
+/// Return t if there is a grapheme cluster boundary at POSITION.
+/// POSITION is a buffer position in the current buffer, or, if STRING
+/// is non-nil, an index into STRING.  The beginning and end of the text
+/// are always boundaries.  See `string-graphemes' for what a grapheme
+/// cluster is.
+#[lisp_fn(min = "1")]
+pub fn grapheme_boundary_p(position: LispObject, string: LispObject) -> bool {
+    Segmentation::Graphemes.boundary_p(position, string)
+}
+
+/// Return a list of the substrings of STRING between word boundaries.
+/// Boundaries are determined by the rules of Unicode Standard Annex
+/// #29, so that, for example, "can't" and "3.14" are kept together.
+/// The spaces and punctuation between words are segments of their own.
+/// Ideographs and scripts written without spaces between words are
+/// split into single characters.  The substrings keep the text
+/// properties of STRING.
+#[lisp_fn]
+pub fn string_word_segments(string: LispStringRef) -> LispObject {
+    Segmentation::Words.string_segments(string)
+}
+
+/// Return t if there is a word boundary at POSITION.
+/// POSITION is a buffer position in the current buffer, or, if STRING
+/// is non-nil, an index into STRING.  See `string-word-segments' for
+/// how boundaries are determined.
+#[lisp_fn(min = "1")]
+pub fn word_boundary_p(position: LispObject, string: LispObject) -> bool {
+    Segmentation::Words.boundary_p(position, string)
+}
+
+include!(concat!(env!("OUT_DIR"), "/segmentation_exports.rs"));
+
+#[cfg(test)]
+fn test_grapheme_props(text: &[Codepoint]) -> Vec<GraphemeBreak> {
+    text.iter()
+        .map(|&c| {
+            let category: &[u8] = match c {
+                0x301 | 0xFE0F => b"Mn",
+                0x903 => b"Mc",
+                0x07 => b"Cc",
+                _ => b"Lo",
+            };
+            grapheme_break(c, category)
+        })
+        .collect()
+}
+
+#[test]
+fn test_grapheme_boundaries() {
+    let boundaries = |text: &[Codepoint]| grapheme_boundaries(&test_grapheme_props(text));
+    assert_eq!(boundaries(&[]), vec![0]);
+    assert_eq!(boundaries(&[0x61, 0x62]), vec![0, 1, 2]);
+    // CR LF, and controls.
+    assert_eq!(boundaries(&[0x0D, 0x0A, 0x07]), vec![0, 2, 3]);
+    // Combining marks and spacing marks.
+    assert_eq!(boundaries(&[0x65, 0x301, 0x915, 0x903]), vec![0, 2, 4]);
+    // Hangul jamo.
+    assert_eq!(boundaries(&[0x1112, 0x1161, 0x11AB, 0xD55C]), vec![0, 3, 4]);
+    // Emoji with a skin tone modifier, and a ZWJ sequence.
+    assert_eq!(boundaries(&[0x1F44B, 0x1F3FD, 0x61]), vec![0, 2, 3]);
+    assert_eq!(
+        boundaries(&[0x1F469, 0x200D, 0x2764, 0xFE0F, 0x200D, 0x1F468]),
+        vec![0, 6]
+    );
+    // A ZWJ not following a pictograph does not join.
+    assert_eq!(boundaries(&[0x61, 0x200D, 0x1F468]), vec![0, 2, 3]);
+    // Regional indicators pair up.
+    assert_eq!(
+        boundaries(&[0x1F1EB, 0x1F1F7, 0x1F1E9, 0x1F1EA, 0x1F1EF]),
+        vec![0, 2, 4, 5]
+    );
+}
+
+#[cfg(test)]
+fn test_word_props(text: &str) -> Vec<WordBreak> {
+    text.chars()
+        .map(|c| {
+            let category: &[u8] = if c.is_alphabetic() {
+                b"Ll"
+            } else if c.is_numeric() {
+                b"Nd"
+            } else if c == ' ' {
+                b"Zs"
+            } else if c == '_' {
+                b"Pc"
+            } else if c == '\u{301}' {
+                b"Mn"
+            } else {
+                b"Po"
+            };
+            word_break(c as Codepoint, category)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+fn test_word_segments(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    word_boundaries(&test_word_props(text))
+        .windows(2)
+        .map(|w| chars[w[0]..w[1]].iter().collect())
+        .collect()
+}
+
+#[test]
+fn test_word_boundaries() {
+    assert_eq!(
+        test_word_segments("The quick (\"brown\") fox can't jump 32.3 feet, right?"),
+        vec![
+            "The", " ", "quick", " ", "(", "\"", "brown", "\"", ")", " ", "fox", " ", "can't",
+            " ", "jump", " ", "32.3", " ", "feet", ",", " ", "right", "?"
+        ]
+    );
+    assert_eq!(
+        test_word_segments("snake_case x2 1,000"),
+        vec!["snake_case", " ", "x2", " ", "1,000"]
+    );
+    // Combining marks stay with their base, and runs of spaces together.
+    assert_eq!(test_word_segments("cafe\u{301}  a"), vec!["cafe\u{301}", "  ", "a"]);
+    assert_eq!(test_word_segments("a.\n"), vec!["a", ".", "\n"]);
+}
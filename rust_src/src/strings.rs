@@ -1,5 +1,8 @@
 //! Functions operating on strings.
 
+use std::cmp::{self, Ordering};
+use std::collections::HashMap;
+use std::hash::Hash;
 use std::ptr;
 
 use libc;
@@ -10,10 +13,10 @@ use crate::{
     lisp::defsubr,
     lisp::LispObject,
     multibyte,
-    multibyte::LispStringRef,
-    remacs_sys::EmacsInt,
+    multibyte::{Codepoint, LispStringRef},
+    remacs_sys::{EmacsDouble, EmacsInt},
     remacs_sys::{
-        make_unibyte_string, make_uninit_multibyte_string,
+        downcase, make_unibyte_string, make_uninit_multibyte_string,
         string_to_multibyte as c_string_to_multibyte,
     },
 };
@@ -146,7 +149,18 @@ pub fn string_lessp(string1: LispObject, string2: LispObject) -> bool {
     let s1 = LispObject::symbol_or_string_as_string(string1);
     let s2 = LispObject::symbol_or_string_as_string(string2);
 
-    s1.as_slice() < s2.as_slice()
+    string_cmp(s1, s2) == Ordering::Less
+}
+
+/// Compare S1 and S2 character by character, the order of `string<'.
+/// The bytes of a unibyte string are taken as characters, so it compares
+/// with a multibyte string as its contents were inserted into a buffer.
+pub fn string_cmp(s1: LispStringRef, s2: LispStringRef) -> Ordering {
+    if !s1.is_multibyte() && !s2.is_multibyte() {
+        s1.as_slice().cmp(s2.as_slice())
+    } else {
+        s1.chars().cmp(s2.chars())
+    }
 }
 
 /// Return t if OBJECT is a multibyte string.
@@ -167,6 +181,507 @@ pub fn clear_string(mut string: LispStringRef) -> () {
     string.mark_as_unibyte();
 }
 
+/// Return the Levenshtein distance between A and B, or `None` if
+/// MAX is given and the distance exceeds it.  Rows are abandoned as
+/// soon as their minimum exceeds MAX, since it can only grow.
+pub fn levenshtein_distance<T: PartialEq>(
+    a: &[T],
+    b: &[T],
+    max: Option<usize>,
+) -> Option<usize> {
+    let (a, b) = if a.len() < b.len() { (b, a) } else { (a, b) };
+    if max.map_or(false, |max| a.len() - b.len() > max) {
+        return None;
+    }
+
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        let mut row_min = row[0];
+        for (j, y) in b.iter().enumerate() {
+            let substitution = if x == y { diagonal } else { diagonal + 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = cmp::min(substitution, cmp::min(row[j], diagonal) + 1);
+            row_min = cmp::min(row_min, row[j + 1]);
+        }
+        if max.map_or(false, |max| row_min > max) {
+            return None;
+        }
+    }
+
+    let distance = row[b.len()];
+    match max {
+        Some(max) if distance > max => None,
+        _ => Some(distance),
+    }
+}
+
+/// Return the Damerau-Levenshtein distance between A and B, counting
+/// a transposition of two elements as a single edit even when other
+/// edits happen between them.  Like `levenshtein_distance`, return
+/// `None` if the distance exceeds MAX.
+pub fn damerau_distance<T: Eq + Hash>(a: &[T], b: &[T], max: Option<usize>) -> Option<usize> {
+    let (m, n) = (a.len(), b.len());
+    let lengths_differ_by = if m > n { m - n } else { n - m };
+    if max.map_or(false, |max| lengths_differ_by > max) {
+        return None;
+    }
+
+    // The matrix has an extra leading row and column holding an
+    // "infinite" distance, so that transpositions reaching before the
+    // start of either sequence never win.  A transposition reaching
+    // back more than MAX rows costs more than MAX, so only the last
+    // MAX + 2 rows are kept, in a ring.
+    let infinity = m + n;
+    let keep = cmp::min(max.unwrap_or(m), m) + 2;
+    let mut d = vec![vec![infinity; n + 2]; keep];
+    for j in 0..=n {
+        d[1][j + 1] = j;
+    }
+
+    // Last row of A in which each element was seen.
+    let mut last_row: HashMap<&T, usize> = HashMap::new();
+    for i in 1..=m {
+        let (row, prev) = ((i + 1) % keep, i % keep);
+        d[row][1] = i;
+        let mut row_min = i;
+        // Last column of B in this row that matched.
+        let mut last_match_col = 0;
+        for j in 1..=n {
+            let i1 = *last_row.get(&b[j - 1]).unwrap_or(&0);
+            let j1 = last_match_col;
+            let cost = if a[i - 1] == b[j - 1] {
+                last_match_col = j;
+                0
+            } else {
+                1
+            };
+            let transposition = if i1 > 0 && i + 1 - i1 < keep {
+                d[i1 % keep][j1] + (i - i1 - 1) + 1 + (j - j1 - 1)
+            } else {
+                infinity
+            };
+            d[row][j + 1] = cmp::min(
+                cmp::min(d[prev][j] + cost, transposition),
+                cmp::min(d[row][j], d[prev][j + 1]) + 1,
+            );
+            row_min = cmp::min(row_min, d[row][j + 1]);
+        }
+        // No later row can get back under MAX.
+        if max.map_or(false, |max| row_min > max) {
+            return None;
+        }
+        last_row.insert(&a[i - 1], i);
+    }
+
+    let distance = d[(m + 1) % keep][n + 1];
+    match max {
+        Some(max) if distance > max => None,
+        _ => Some(distance),
+    }
+}
+
+/// Return the Jaro-Winkler similarity of A and B, between 0.0 (nothing
+/// in common) and 1.0 (identical).  When the Jaro similarity is above
+/// the usual threshold of 0.7, up to four elements of common prefix
+/// are rewarded with the standard scaling factor of 0.1.
+pub fn jaro_winkler_similarity<T: PartialEq>(a: &[T], b: &[T]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let window = (cmp::max(a.len(), b.len()) / 2).saturating_sub(1);
+    let mut a_matched = vec![false; a.len()];
+    let mut b_matched = vec![false; b.len()];
+    let mut matches = 0;
+    for (i, x) in a.iter().enumerate() {
+        let end = cmp::min(i + window + 1, b.len());
+        for j in i.saturating_sub(window)..end {
+            if !b_matched[j] && b[j] == *x {
+                a_matched[i] = true;
+                b_matched[j] = true;
+                matches += 1;
+                break;
+            }
+        }
+    }
+    if matches == 0 {
+        return 0.0;
+    }
+
+    let mut transpositions = 0;
+    let mut j = 0;
+    let matched_a = a.iter().zip(&a_matched).filter(|&(_, &matched)| matched);
+    for (x, _) in matched_a {
+        while !b_matched[j] {
+            j += 1;
+        }
+        if *x != b[j] {
+            transpositions += 1;
+        }
+        j += 1;
+    }
+
+    let m = matches as f64;
+    let jaro =
+        (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions as f64 / 2.0) / m) / 3.0;
+    if jaro <= 0.7 {
+        return jaro;
+    }
+    let prefix = a.iter().zip(b).take(4).take_while(|(x, y)| x == y).count();
+    jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+/// Return the length of the longest common subsequence of A and B.
+pub fn lcs_length<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    let mut row = vec![0; b.len() + 1];
+    for x in a {
+        let mut diagonal = 0;
+        for (j, y) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if x == y {
+                diagonal + 1
+            } else {
+                cmp::max(above, row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Convert the optional MAX-DISTANCE argument of the distance
+/// functions into a cut-off.
+fn max_distance_arg(max_distance: LispObject) -> Option<usize> {
+    if max_distance.is_nil() {
+        None
+    } else {
+        Some(max_distance.as_natnum_or_error() as usize)
+    }
+}
+
+fn string_codepoints(string: LispStringRef) -> Vec<Codepoint> {
+    string.chars().collect()
+}
+
+/// Return Levenshtein distance between STRING1 and STRING2.
+/// The distance is the number of deletions, insertions, and
+/// substitutions required to transform STRING1 into STRING2.
+/// If BYTECOMPARE is nil, compute the distance in terms of characters;
+/// otherwise compare bytes.
+/// If MAX-DISTANCE is non-nil, it must be a natural number; return nil
+/// instead of the distance when the distance exceeds it.  This lets the
+/// comparison stop early, which helps when ranking many candidates.
+/// Letter-case is significant, but text properties are ignored.
+#[lisp_fn(min = "2")]
+pub fn string_distance(
+    string1: LispStringRef,
+    string2: LispStringRef,
+    bytecompare: bool,
+    max_distance: LispObject,
+) -> Option<EmacsInt> {
+    let max = max_distance_arg(max_distance);
+    let distance = if bytecompare {
+        levenshtein_distance(string1.as_slice(), string2.as_slice(), max)
+    } else {
+        levenshtein_distance(&string_codepoints(string1), &string_codepoints(string2), max)
+    };
+    distance.map(|d| d as EmacsInt)
+}
+
+/// Return Damerau-Levenshtein distance between STRING1 and STRING2.
+/// This is like `string-distance', except that swapping two characters
+/// counts as a single edit.  Characters are compared, not bytes.
+/// If MAX-DISTANCE is non-nil, return nil when the distance exceeds it.
+#[lisp_fn(min = "2")]
+pub fn string_damerau_distance(
+    string1: LispStringRef,
+    string2: LispStringRef,
+    max_distance: LispObject,
+) -> Option<EmacsInt> {
+    damerau_distance(
+        &string_codepoints(string1),
+        &string_codepoints(string2),
+        max_distance_arg(max_distance),
+    )
+    .map(|d| d as EmacsInt)
+}
+
+/// Return the Jaro-Winkler similarity of STRING1 and STRING2.
+/// The result is a float between 0.0, when the strings have no
+/// characters in common, and 1.0, when they are equal.  Strings sharing
+/// a common prefix score higher, which suits comparing a mistyped name
+/// against a list of candidates.
+#[lisp_fn]
+pub fn string_jaro_winkler(string1: LispStringRef, string2: LispStringRef) -> EmacsDouble {
+    jaro_winkler_similarity(&string_codepoints(string1), &string_codepoints(string2))
+}
+
+/// Return the length of the longest common subsequence of STRING1 and STRING2.
+/// A subsequence is a sequence of characters appearing in the same order
+/// in both strings, though not necessarily contiguously.
+#[lisp_fn]
+pub fn string_lcs_length(string1: LispStringRef, string2: LispStringRef) -> EmacsInt {
+    lcs_length(&string_codepoints(string1), &string_codepoints(string2)) as EmacsInt
+}
+
+fn is_ascii_digit<T: Copy + Into<Codepoint>>(c: T) -> bool {
+    let c: Codepoint = c.into();
+    c >= Codepoint::from(b'0') && c <= Codepoint::from(b'9')
+}
+
+fn strip_leading_zeros<T: Copy + Into<Codepoint>>(run: &[T]) -> &[T] {
+    let zero = Codepoint::from(b'0');
+    let start = run
+        .iter()
+        .position(|&c| c.into() != zero)
+        .unwrap_or_else(|| run.len());
+    &run[start..]
+}
+
+/// Compare two runs of ASCII digits.  If IGNORE_LEADING_ZEROS, or if
+/// neither run starts with a zero, the runs are compared by numeric
+/// value.  Otherwise a leading zero marks a fractional part, as in
+/// "1.05" and "1.5", and the runs are compared digit by digit.
+fn compare_digit_runs<T: Copy + Ord + Into<Codepoint>>(
+    a: &[T],
+    b: &[T],
+    ignore_leading_zeros: bool,
+) -> Ordering {
+    let zero = Codepoint::from(b'0');
+    let starts_with_zero = |run: &[T]| run.first().map_or(false, |&c| c.into() == zero);
+    if !ignore_leading_zeros && (starts_with_zero(a) || starts_with_zero(b)) {
+        return a.cmp(b);
+    }
+
+    let (a, b) = (strip_leading_zeros(a), strip_leading_zeros(b));
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/// Compare A and B in natural order: runs of ASCII digits are compared
+/// as numbers, everything else element by element after applying
+/// FOLD.  Strings that differ only in the spelling of their numbers
+/// (such as "a7" and "a007" when ignoring leading zeros) compare equal.
+pub fn natural_cmp<T, F>(a: &[T], b: &[T], ignore_leading_zeros: bool, fold: F) -> Ordering
+where
+    T: Copy + Ord + Into<Codepoint>,
+    F: Fn(T) -> T,
+{
+    let digit_run_end = |s: &[T], start: usize| {
+        s[start..]
+            .iter()
+            .position(|&c| !is_ascii_digit(c))
+            .map_or(s.len(), |len| start + len)
+    };
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let ordering = if is_ascii_digit(a[i]) && is_ascii_digit(b[j]) {
+            let (end_i, end_j) = (digit_run_end(a, i), digit_run_end(b, j));
+            let ordering = compare_digit_runs(&a[i..end_i], &b[j..end_j], ignore_leading_zeros);
+            i = end_i;
+            j = end_j;
+            ordering
+        } else {
+            let ordering = fold(a[i]).cmp(&fold(b[j]));
+            i += 1;
+            j += 1;
+            ordering
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    (a.len() - i).cmp(&(b.len() - j))
+}
+
+/// Return the start of the file name suffix of S, matching the regexp
+/// `\(\.[A-Za-z~][A-Za-z0-9~]*\)*\'', if it has one.
+fn version_suffix(s: &[u8]) -> Option<usize> {
+    let mut suffix = None;
+    let mut read_alpha = false;
+    for (i, &c) in s.iter().enumerate() {
+        if read_alpha {
+            read_alpha = false;
+            if !c.is_ascii_alphabetic() && c != b'~' {
+                suffix = None;
+            }
+        } else if c == b'.' {
+            read_alpha = true;
+            suffix = suffix.or(Some(i));
+        } else if !c.is_ascii_alphanumeric() && c != b'~' {
+            suffix = None;
+        }
+    }
+    suffix
+}
+
+/// The weight of C, or of the end of the string, outside of digit runs:
+/// `~' sorts before everything, even the end of the string, and other
+/// non-letters after all letters.
+fn version_order(c: Option<&u8>) -> i32 {
+    match c {
+        None => 0,
+        Some(&c) if c.is_ascii_digit() => 0,
+        Some(&c) if c.is_ascii_alphabetic() => i32::from(c),
+        Some(b'~') => -1,
+        Some(&c) => i32::from(c) + 256,
+    }
+}
+
+/// Compare A and B with the version comparison of Debian's dpkg.
+fn verrevcmp(a: &[u8], b: &[u8]) -> Ordering {
+    let is_digit = |c: Option<&u8>| c.map_or(false, u8::is_ascii_digit);
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        while (i < a.len() && !a[i].is_ascii_digit()) || (j < b.len() && !b[j].is_ascii_digit()) {
+            let (x, y) = (version_order(a.get(i)), version_order(b.get(j)));
+            if x != y {
+                return x.cmp(&y);
+            }
+            i += 1;
+            j += 1;
+        }
+        while a.get(i) == Some(&b'0') {
+            i += 1;
+        }
+        while b.get(j) == Some(&b'0') {
+            j += 1;
+        }
+        let mut first_diff = Ordering::Equal;
+        while is_digit(a.get(i)) && is_digit(b.get(j)) {
+            if first_diff == Ordering::Equal {
+                first_diff = a[i].cmp(&b[j]);
+            }
+            i += 1;
+            j += 1;
+        }
+        if is_digit(a.get(i)) {
+            return Ordering::Greater;
+        }
+        if is_digit(b.get(j)) {
+            return Ordering::Less;
+        }
+        if first_diff != Ordering::Equal {
+            return first_diff;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Compare the file names A and B, which contain no null bytes, as
+/// version strings.  This is gnulib's `filevercmp'.
+fn filevercmp(a: &[u8], b: &[u8]) -> Ordering {
+    let simple_cmp = a.cmp(b);
+    if simple_cmp == Ordering::Equal {
+        return simple_cmp;
+    }
+
+    // "", "." and ".." come first, then the other hidden files.
+    for &special in &[&b""[..], b".", b".."] {
+        if a == special {
+            return Ordering::Less;
+        }
+        if b == special {
+            return Ordering::Greater;
+        }
+    }
+    let (a, b) = match (a[0] == b'.', b[0] == b'.') {
+        (true, false) => return Ordering::Less,
+        (false, true) => return Ordering::Greater,
+        (true, true) => (&a[1..], &b[1..]),
+        (false, false) => (a, b),
+    };
+
+    // Cut off the file suffixes, unless that leaves the names equal.
+    let (suffix_a, suffix_b) = (version_suffix(a), version_suffix(b));
+    let mut len_a = suffix_a.unwrap_or_else(|| a.len());
+    let mut len_b = suffix_b.unwrap_or_else(|| b.len());
+    if (suffix_a.is_some() || suffix_b.is_some()) && a[..len_a] == b[..len_b] {
+        len_a = a.len();
+        len_b = b.len();
+    }
+
+    match verrevcmp(&a[..len_a], &b[..len_b]) {
+        Ordering::Equal => simple_cmp,
+        ordering => ordering,
+    }
+}
+
+/// Compare A and B as version strings, the way `string-version-lessp'
+/// does.  Null bytes separate parts that are compared one by one.
+pub fn version_cmp(a: &[u8], b: &[u8]) -> Ordering {
+    let (mut parts_a, mut parts_b) = (a.split(|&c| c == 0), b.split(|&c| c == 0));
+    loop {
+        match (parts_a.next(), parts_b.next()) {
+            (Some(a), Some(b)) => match filevercmp(a, b) {
+                Ordering::Equal => {}
+                ordering => return ordering,
+            },
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (None, None) => return Ordering::Equal,
+        }
+    }
+}
+
+/// Return non-nil if S1 is less than S2, as version strings.
+///
+/// This function compares version strings S1 and S2:
+///    1) By prefix lexicographically.
+///    2) Then by version (similarly to version comparison of Debian's dpkg).
+///       Leading zeros in version numbers are ignored.
+///    3) If both prefix and version are equal, compare as ordinary strings.
+///
+/// For example, "foo2.png" compares less than "foo12.png".
+/// Case is significant.
+/// Symbols are also allowed; their print names are used instead.
+#[lisp_fn]
+pub fn string_version_lessp(string1: LispObject, string2: LispObject) -> bool {
+    let s1 = LispObject::symbol_or_string_as_string(string1);
+    let s2 = LispObject::symbol_or_string_as_string(string2);
+
+    version_cmp(s1.as_slice(), s2.as_slice()) == Ordering::Less
+}
+
+/// Return non-nil if STRING1 sorts before STRING2 in natural order.
+/// As in `string-version-lessp', runs of ASCII digits are compared as
+/// numbers.
+/// If IGNORE-CASE is non-nil, characters are compared after downcasing
+/// them with the current buffer's case table.
+/// If IGNORE-LEADING-ZEROS is nil, a run of digits that starts with zero
+/// is treated as a fraction, so "1.05" sorts before "1.5"; otherwise
+/// every run is compared by its numeric value.
+/// Strings that are equal under these rules are ordered by
+/// `string-version-lessp'.
+/// Symbols are also allowed; their print names are used instead.
+#[lisp_fn(min = "2")]
+pub fn string_collate_natural(
+    string1: LispObject,
+    string2: LispObject,
+    ignore_case: bool,
+    ignore_leading_zeros: bool,
+) -> bool {
+    let (string1, string2) = (
+        LispObject::symbol_or_string_as_string(string1),
+        LispObject::symbol_or_string_as_string(string2),
+    );
+    let (s1, s2) = (string_codepoints(string1), string_codepoints(string2));
+
+    let ordering = if ignore_case {
+        natural_cmp(&s1, &s2, ignore_leading_zeros, |c| unsafe {
+            downcase(c as libc::c_int) as Codepoint
+        })
+    } else {
+        natural_cmp(&s1, &s2, ignore_leading_zeros, |c| c)
+    };
+    ordering.then_with(|| version_cmp(string1.as_slice(), string2.as_slice())) == Ordering::Less
+}
+
 include!(concat!(env!("OUT_DIR"), "/strings_exports.rs"));
 
 #[test]
@@ -205,3 +720,96 @@ fn test_stringlessp() {
     assert!(string_lessp(string, string2));
     assert!(!string_lessp(string2, string));
 }
+
+#[test]
+fn test_levenshtein_distance() {
+    let kitten: Vec<char> = "kitten".chars().collect();
+    let sitting: Vec<char> = "sitting".chars().collect();
+    assert_eq!(levenshtein_distance(&kitten, &sitting, None), Some(3));
+    assert_eq!(levenshtein_distance(&kitten, &sitting, Some(3)), Some(3));
+    assert_eq!(levenshtein_distance(&kitten, &sitting, Some(2)), None);
+    assert_eq!(levenshtein_distance(&[], &kitten, None), Some(6));
+    assert_eq!(levenshtein_distance("äb".as_bytes(), "ab".as_bytes(), None), Some(2));
+}
+
+#[test]
+fn test_damerau_distance() {
+    assert_eq!(damerau_distance(b"ab", b"ba", None), Some(1));
+    assert_eq!(damerau_distance(b"ca", b"abc", None), Some(2));
+    assert_eq!(damerau_distance(b"kitten", b"sitting", None), Some(3));
+    assert_eq!(damerau_distance(b"kitten", b"sitting", Some(1)), None);
+    assert_eq!(damerau_distance(b"", b"", None), Some(0));
+    assert_eq!(damerau_distance(b"abcdef", b"bacdfe", Some(2)), Some(2));
+    assert_eq!(damerau_distance(b"abcxdef", b"xabcdef", Some(2)), Some(2));
+    assert_eq!(damerau_distance(b"abcxdef", b"xabcdef", Some(1)), None);
+    assert_eq!(damerau_distance(b"aaaaaaaa", b"bbbbbbbb", Some(3)), None);
+}
+
+#[test]
+fn test_jaro_winkler_similarity() {
+    assert!((jaro_winkler_similarity(b"MARTHA", b"MARHTA") - 0.9611).abs() < 1e-3);
+    assert!((jaro_winkler_similarity(b"DWAYNE", b"DUANE") - 0.84).abs() < 1e-3);
+    assert!((jaro_winkler_similarity(b"DIXON", b"DICKSONX") - 0.8133).abs() < 1e-3);
+    assert_eq!(jaro_winkler_similarity(b"abc", b"xyz"), 0.0);
+    assert_eq!(jaro_winkler_similarity(b"", b""), 1.0);
+    // Below the threshold, a common prefix earns nothing.
+    let jaro = jaro_winkler_similarity(b"abcdwxyz", b"abcdpqrs");
+    assert!((jaro - 0.6667).abs() < 1e-3);
+}
+
+#[test]
+fn test_lcs_length() {
+    assert_eq!(lcs_length(b"ABCBDAB", b"BDCABA"), 4);
+    assert_eq!(lcs_length(b"", b"abc"), 0);
+}
+
+#[test]
+fn test_version_cmp() {
+    assert_eq!(version_cmp(b"file2", b"file10"), Ordering::Less);
+    assert_eq!(version_cmp(b"1.10", b"1.9"), Ordering::Greater);
+    assert_eq!(version_cmp(b"emacs-26.1", b"emacs-26.1.90"), Ordering::Less);
+    assert_eq!(version_cmp(b"a007", b"a7"), Ordering::Less);
+    assert_eq!(version_cmp(b"", b""), Ordering::Equal);
+    // A tilde sorts before anything, even the end of the string.
+    assert_eq!(version_cmp(b"1.0~rc1", b"1.0"), Ordering::Less);
+    assert_eq!(version_cmp(b"~", b"a"), Ordering::Less);
+    // Hidden files come first, and suffixes are compared last.
+    assert_eq!(version_cmp(b"z", b".a"), Ordering::Greater);
+    assert_eq!(version_cmp(b"..", b".a"), Ordering::Less);
+    assert_eq!(version_cmp(b"foo-1.2.tar.gz", b"foo-1.10.tar"), Ordering::Less);
+    // Parts separated by null bytes are compared one by one.
+    assert_eq!(version_cmp(b"a\0b10", b"a\0b9"), Ordering::Greater);
+    assert_eq!(version_cmp(b"a", b"a\0"), Ordering::Less);
+}
+
+#[test]
+fn test_natural_cmp() {
+    let fold_ascii = |c: u8| c.to_ascii_lowercase();
+    assert_eq!(natural_cmp(b"a7", b"a007", true, |c| c), Ordering::Equal);
+    assert_eq!(natural_cmp(b"1.05", b"1.5", false, |c| c), Ordering::Less);
+    assert_eq!(natural_cmp(b"1.05", b"1.5", true, |c| c), Ordering::Equal);
+    assert_eq!(natural_cmp(b"File10", b"file9", false, fold_ascii), Ordering::Greater);
+    assert_eq!(natural_cmp(b"B", b"a", false, fold_ascii), Ordering::Greater);
+}
+
+#[cfg(test)]
+use remacs_macros::lisp_test;
+
+#[lisp_test]
+fn test_string_distance() {
+    let kitten = LispObject::from("kitten").as_string_or_error();
+    let sitting = LispObject::from("sitting").as_string_or_error();
+    let max_distance = crate::remacs_sys::Qnil;
+    assert_eq!(string_distance(kitten, sitting, false, max_distance), Some(3));
+}
+
+#[lisp_test]
+fn test_string_to_multibyte() {
+    let string = LispObject::from("hello");
+    assert!(!multibyte_string_p(string));
+    let multibyte = string_to_multibyte(string.as_string_or_error());
+    assert!(multibyte_string_p(multibyte));
+    assert!(string_equal(string, multibyte));
+    let unibyte = string_to_unibyte(multibyte.as_string_or_error());
+    assert!(!multibyte_string_p(unibyte));
+}
@@ -12,7 +12,8 @@ use crate::{
     lisp::{ExternalPtr, LispObject},
     remacs_sys::Qthreadp,
     remacs_sys::{
-        current_thread as current_thread_pointer, pvec_type, thread_state, Lisp_Type, SPECPDL_INDEX,
+        current_thread as current_thread_pointer, pvec_type, specbinding, thread_state, Lisp_Type,
+        SPECPDL_INDEX,
     },
 };
 
@@ -39,6 +40,16 @@ impl ThreadStateRef {
         !self.m_specpdl.is_null()
     }
 
+    /// The buffer that is current in this thread.
+    pub fn current_buffer(self) -> LispBufferRef {
+        unsafe { mem::transmute(self.m_current_buffer) }
+    }
+
+    /// The number of entries on this thread's specpdl.
+    pub fn specpdl_depth(self) -> usize {
+        (self.m_specpdl_ptr as usize - self.m_specpdl as usize) / mem::size_of::<specbinding>()
+    }
+
     pub fn as_lisp_obj(self) -> LispObject {
         LispObject::tag_ptr(self, Lisp_Type::Lisp_Vectorlike)
     }
@@ -6,7 +6,7 @@ use std::ptr;
 
 use libc::ptrdiff_t;
 
-use remacs_macros::lisp_fn;
+use remacs_macros::{lisp_fn, LispKeywords};
 
 use crate::{
     buffers::LispBufferRef,
@@ -14,16 +14,19 @@ use crate::{
     data::aref,
     frames::LispFrameRef,
     lisp::defsubr,
-    lisp::{ExternalPtr, LispObject, LispSubrRef},
-    lists::{inorder, nth, sort_list},
+    lisp::{ExternalPtr, LispKeywords, LispObject, LispSubrRef},
+    lists::{circular_list, nth, TailsIter},
+    math::{arithcompare, ArithComparison},
     multibyte::MAX_CHAR,
     numbers::MOST_POSITIVE_FIXNUM,
-    process::LispProcessRef,
+    process::{process_name, LispProcessRef},
     remacs_sys::{
-        pvec_type, EmacsInt, Lisp_Bool_Vector, Lisp_Type, Lisp_Vector, Lisp_Vectorlike,
-        Lisp_Vectorlike_With_Slots, More_Lisp_Bits, BITS_PER_BITS_WORD, PSEUDOVECTOR_FLAG,
+        maybe_quit, pvec_type, EmacsInt, Fcopy_sequence, Fmake_vector, Lisp_Bool_Vector, Lisp_Type,
+        Lisp_Vector, Lisp_Vectorlike, Lisp_Vectorlike_With_Slots, More_Lisp_Bits,
+        BITS_PER_BITS_WORD, PSEUDOVECTOR_FLAG,
     },
-    remacs_sys::{Qarrayp, Qsequencep, Qvectorp},
+    remacs_sys::{Qarrayp, Qnil, Qsequencep, Qtype_mismatch, Qvectorp},
+    strings::string_cmp,
     threads::ThreadStateRef,
     windows::LispWindowRef,
 };
@@ -490,37 +493,317 @@ pub fn elt(sequence: LispObject, n: EmacsInt) -> LispObject {
     }
 }
 
-/// Sort SEQ, stably, comparing elements using PREDICATE.
-/// Returns the sorted sequence.  SEQ should be a list or vector.  SEQ is
-/// modified by side effects.  PREDICATE is called with two elements of
-/// SEQ, and should return non-nil if the first element should sort before
-/// the second.
-#[lisp_fn]
-pub fn sort(seq: LispObject, predicate: LispObject) -> LispObject {
-    if seq.is_cons() {
-        sort_list(seq, predicate)
-    } else if let Some(mut vec) = seq.as_vectorlike().and_then(|v| v.as_vector()) {
-        vec.as_mut_slice().sort_by(|&a, &b| {
-            // XXX: since the `sort' predicate is a two-outcome comparison
-            // Less/!Less, and slice::sort_by() uses Greater/!Greater
-            // (which is not guaranteed anyway), this requires two calls
-            // instead of one in some cases.
-            if !inorder(predicate, a, b) {
-                Ordering::Greater
-            } else if inorder(predicate, b, a) {
-                Ordering::Equal
+/// The keyword arguments of `sort`.
+#[derive(LispKeywords)]
+struct SortKeywords {
+    key: LispObject,
+    lessp: LispObject,
+    reverse: bool,
+    in_place: bool,
+}
+
+/// The arguments of `sort` after SEQ: either keyword arguments, or a
+/// lone predicate in the old calling convention.
+pub struct SortArgs(SortKeywords);
+
+impl LispKeywords for SortArgs {
+    const KEYWORDS: &'static [LispObject] = SortKeywords::KEYWORDS;
+
+    fn from_keyword_args(args: &[LispObject]) -> Self {
+        // A lone keyword is a keyword argument missing its value, not a
+        // predicate.
+        if args.len() == 1 && !Self::KEYWORDS.contains(&args[0]) {
+            SortArgs(SortKeywords {
+                key: Qnil,
+                lessp: args[0],
+                reverse: false,
+                in_place: true,
+            })
+        } else {
+            SortArgs(SortKeywords::from_keyword_args(args))
+        }
+    }
+}
+
+/// Sort SEQ, stably, and return the sorted sequence.
+/// SEQ should be a list or vector.  The remaining arguments are
+/// keyword-value pairs:
+///
+///  :key FUNC    -- FUNC is called once on each element, and the results
+///                  are compared instead of the elements themselves.
+///  :lessp FUNC  -- FUNC is called with two values to compare, and should
+///                  return non-nil if the first sorts before the second.
+///                  The default is `value<', which is much faster than
+///                  calling a function.
+///  :reverse BOOL -- if non-nil, sort in descending order.  Elements that
+///                  compare equal keep their original order.
+///  :in-place BOOL -- if non-nil, SEQ is sorted in place and returned.
+///                  Otherwise SEQ is left alone and a sorted copy is
+///                  returned.
+///
+/// The old calling convention (sort SEQ LESSP) is also accepted, and
+/// is equivalent to (sort SEQ :lessp LESSP :in-place t).
+///
+/// Lists are sorted by rearranging their elements, so after an in-place
+/// sort SEQ is still the first cons of the sorted list.
+///
+/// usage: (sort SEQ &key KEY LESSP REVERSE IN-PLACE)
+#[lisp_fn(min = "1", keys = "options")]
+pub fn sort(seq: LispObject, options: SortArgs) -> LispObject {
+    let SortKeywords {
+        key,
+        lessp,
+        reverse,
+        in_place,
+    } = options.0;
+
+    if seq.is_nil() {
+        return seq;
+    }
+    if !(seq.is_cons() || seq.is_vector()) {
+        wrong_type!(Qsequencep, seq);
+    }
+    let seq = if in_place {
+        seq
+    } else {
+        unsafe { Fcopy_sequence(seq) }
+    };
+
+    let elements: Vec<LispObject> = if seq.is_cons() {
+        seq.iter_cars().collect()
+    } else {
+        seq.as_vector_or_error().as_slice().to_vec()
+    };
+    let len = elements.len();
+    if len < 2 {
+        return seq;
+    }
+
+    // Decorate: compute every key once, and keep the keys in a Lisp
+    // vector so that they are protected from GC while we sort.
+    let keys = if key.is_nil() {
+        None
+    } else {
+        let mut keys =
+            unsafe { Fmake_vector(LispObject::from(len as EmacsInt), Qnil) }.as_vector_or_error();
+        for (i, &elt) in elements.iter().enumerate() {
+            keys.set(i, call!(key, elt));
+        }
+        Some(keys)
+    };
+    let key_at = |i: usize| keys.map_or(elements[i], |keys| keys.get(i));
+
+    // Sorting indices in descending order of position and reversing the
+    // result afterwards gives a descending sort that is still stable.
+    let mut order: Vec<usize> = if reverse {
+        (0..len).rev().collect()
+    } else {
+        (0..len).collect()
+    };
+    {
+        let mut less = |a: usize, b: usize| {
+            if lessp.is_nil() {
+                value_cmp(key_at(a), key_at(b), 0) == Ordering::Less
             } else {
-                Ordering::Less
+                call!(lessp, key_at(a), key_at(b)).is_not_nil()
             }
-        });
-        seq
-    } else if seq.is_nil() {
-        seq
+        };
+        merge_sort(&mut order, &mut less);
+    }
+    if reverse {
+        order.reverse();
+    }
+
+    // Undecorate: store the elements back in their new order.
+    if seq.is_cons() {
+        for (tail, &i) in seq.iter_tails().zip(order.iter()) {
+            tail.set_car(elements[i]);
+        }
     } else {
-        wrong_type!(Qsequencep, seq)
+        let mut vec = seq.as_vector_or_error();
+        for (pos, &i) in order.iter().enumerate() {
+            vec.set(pos, elements[i]);
+        }
+    }
+
+    seq
+}
+
+/// Stable merge sort of ITEMS, using LESS as the only comparison, so
+/// that each comparison costs a single predicate call.
+fn merge_sort<T: Copy>(items: &mut [T], less: &mut dyn FnMut(T, T) -> bool) {
+    let len = items.len();
+    if len <= 8 {
+        // Insertion sort; shift only past elements the new one is less than.
+        for i in 1..len {
+            let mut j = i;
+            while j > 0 && less(items[j], items[j - 1]) {
+                items.swap(j, j - 1);
+                j -= 1;
+            }
+        }
+        return;
+    }
+
+    let mid = len / 2;
+    merge_sort(&mut items[..mid], less);
+    merge_sort(&mut items[mid..], less);
+    if !less(items[mid], items[mid - 1]) {
+        return; // Already in order.
+    }
+
+    let mut merged = Vec::with_capacity(len);
+    let (mut i, mut j) = (0, mid);
+    while i < mid && j < len {
+        // Take from the right half only when it is strictly less, to
+        // keep the sort stable.
+        if less(items[j], items[i]) {
+            merged.push(items[j]);
+            j += 1;
+        } else {
+            merged.push(items[i]);
+            i += 1;
+        }
+    }
+    merged.extend_from_slice(&items[i..mid]);
+    merged.extend_from_slice(&items[j..len]);
+    items.copy_from_slice(&merged);
+}
+
+// Nesting depth at which `value<' gives up on comparing lists and
+// vectors, to avoid overflowing the stack on structure that is circular
+// through its elements.  Circular tails of lists signal `circular-list'.
+const VALUE_CMP_MAX_DEPTH: usize = 200;
+
+/// Return what is left of a list once TAILS has run out of conses,
+/// signaling `circular-list' if it stopped because the list is circular.
+fn tail_end(tails: &TailsIter) -> LispObject {
+    let rest = tails.rest();
+    if rest.is_cons() {
+        circular_list(rest);
+    }
+    rest
+}
+
+fn names_cmp(a: LispObject, b: LispObject) -> Ordering {
+    string_cmp(a.as_string_or_error(), b.as_string_or_error())
+}
+
+fn seq_cmp<I>(a: I, b: I, depth: usize) -> Ordering
+where
+    I: Iterator<Item = LispObject>,
+{
+    let mut a = a.fuse();
+    let mut b = b.fuse();
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => match value_cmp(x, y, depth + 1) {
+                Ordering::Equal => {}
+                order => return order,
+            },
+        }
+    }
+}
+
+/// The ordering used by `value<'.
+pub fn value_cmp(a: LispObject, b: LispObject, depth: usize) -> Ordering {
+    if depth > VALUE_CMP_MAX_DEPTH {
+        error!("Maximum depth exceeded in comparison");
+    }
+
+    if (a.is_number() && (b.is_number() || b.is_marker())) || (a.is_marker() && b.is_number()) {
+        return if arithcompare(a, b, ArithComparison::Less) {
+            Ordering::Less
+        } else if arithcompare(b, a, ArithComparison::Less) {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        };
+    }
+
+    if let (Some(s1), Some(s2)) = (a.as_string(), b.as_string()) {
+        return string_cmp(s1, s2);
     }
+
+    if let (Some(s1), Some(s2)) = (a.as_symbol(), b.as_symbol()) {
+        return names_cmp(s1.symbol_name(), s2.symbol_name());
+    }
+
+    if a.is_list() && b.is_list() {
+        // The tails that remain once either list runs out of conses.
+        let (l1, l2) = {
+            let mut tails1 = a.iter_tails_safe();
+            let mut tails2 = b.iter_tails_safe();
+            loop {
+                unsafe { maybe_quit() };
+                match (tails1.next(), tails2.next()) {
+                    (Some(c1), Some(c2)) => match value_cmp(c1.car(), c2.car(), depth + 1) {
+                        Ordering::Equal => {}
+                        order => return order,
+                    },
+                    (None, None) => break (tail_end(&tails1), tail_end(&tails2)),
+                    (None, Some(c2)) => break (tail_end(&tails1), c2.into()),
+                    (Some(c1), None) => break (c1.into(), tail_end(&tails2)),
+                }
+            }
+        };
+        return match (l1.is_nil(), l2.is_nil()) {
+            // A proper list sorts before a longer one.
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            // Compare the tails of dotted lists.
+            (false, false) => value_cmp(l1, l2, depth + 1),
+        };
+    }
+
+    if let (Some(m1), Some(m2)) = (a.as_marker(), b.as_marker()) {
+        let b1 = m1.buffer().map(|b| b.name());
+        let b2 = m2.buffer().map(|b| b.name());
+        let by_buffer = match (b1, b2) {
+            (Some(n1), Some(n2)) => names_cmp(n1, n2),
+            (x, y) => x.is_some().cmp(&y.is_some()),
+        };
+        return by_buffer.then(m1.charpos.cmp(&m2.charpos));
+    }
+
+    if let (Some(v1), Some(v2)) = (a.as_vector(), b.as_vector()) {
+        return seq_cmp(v1.iter(), v2.iter(), depth);
+    }
+
+    if let (Some(b1), Some(b2)) = (a.as_buffer(), b.as_buffer()) {
+        return names_cmp(b1.name(), b2.name());
+    }
+
+    if let (Some(p1), Some(p2)) = (a.as_process(), b.as_process()) {
+        return names_cmp(process_name(p1), process_name(p2));
+    }
+
+    xsignal!(Qtype_mismatch, a, b);
 }
 
+/// Return non-nil if A precedes B in standard value order.
+/// A and B must have the same basic type.
+/// Numbers are compared with `<'.
+/// Strings and symbols are compared with `string<'.
+/// Lists and vectors are compared lexicographically.
+/// Markers are compared lexicographically by buffer and position.
+/// Buffers and processes are compared by name.
+/// Values of other types, or of different types, cannot be compared, and
+/// signal a `type-mismatch' error.
+#[lisp_fn(name = "value<", c_name = "value_lt")]
+pub fn value_lt(a: LispObject, b: LispObject) -> bool {
+    value_cmp(a, b, 0) == Ordering::Less
+}
+
+def_lisp_sym!(QCkey, ":key");
+def_lisp_sym!(QClessp, ":lessp");
+def_lisp_sym!(QCreverse, ":reverse");
+def_lisp_sym!(QCin_place, ":in-place");
+
 /// Return t if OBJECT is a vector.
 #[lisp_fn]
 pub fn vectorp(object: LispObject) -> bool {
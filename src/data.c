@@ -166,6 +166,11 @@ DEFUN ("fset", Ffset, Sfset, 2, 2, 0,
 
   set_symbol_function (symbol, definition);
 
+  /* The where-is cache follows prefix commands through their function
+     definitions, so it is stale once one of those changes.  */
+  if (KEYMAPP (function) || KEYMAPP (definition))
+    where_is_cache_invalidate ();
+
   return definition;
 }
 
@@ -2095,7 +2100,11 @@ syms_of_data (void)
   DEFSYM (Qerror, "error");
   DEFSYM (Quser_error, "user-error");
   DEFSYM (Qquit, "quit");
+  DEFSYM (Qeval_limit_exceeded, "eval-limit-exceeded");
   DEFSYM (Qwrong_length_argument, "wrong-length-argument");
+  DEFSYM (Qinvalid_keyword_argument, "invalid-keyword-argument");
+  DEFSYM (Qmissing_keyword_value, "missing-keyword-value");
+  DEFSYM (Qtype_mismatch, "type-mismatch");
   DEFSYM (Qwrong_type_argument, "wrong-type-argument");
   DEFSYM (Qargs_out_of_range, "args-out-of-range");
   DEFSYM (Qvoid_function, "void-function");
@@ -2167,10 +2176,18 @@ syms_of_data (void)
   Fput (sym, Qerror_message, build_pure_c_string (msg))
 
   PUT_ERROR (Qquit, Qnil, "Quit");
+  /* Like `quit', not an `error', so that code run by
+     `eval-with-limits' cannot ignore it as it would an error.  */
+  PUT_ERROR (Qeval_limit_exceeded, Qnil, "Evaluation limit exceeded");
 
   PUT_ERROR (Quser_error, error_tail, "");
   PUT_ERROR (Qwrong_length_argument, error_tail, "Wrong length argument");
+  PUT_ERROR (Qinvalid_keyword_argument, error_tail,
+	     "Invalid keyword argument");
+  PUT_ERROR (Qmissing_keyword_value, error_tail,
+	     "Keyword argument missing its value");
   PUT_ERROR (Qwrong_type_argument, error_tail, "Wrong type argument");
+  PUT_ERROR (Qtype_mismatch, error_tail, "Types do not match");
   PUT_ERROR (Qargs_out_of_range, error_tail, "Args out of range");
   PUT_ERROR (Qvoid_function, error_tail,
 	     "Symbol's function definition is void");
@@ -140,6 +140,11 @@ bool inhibit_window_system;
    data on the first attempt to change it inside asynchronous code.  */
 bool running_asynch_code;
 
+/* If non-null, called in place of the editor command loop once Emacs
+   is initialized.  The Rust tests that need a live Lisp runtime run
+   inside this.  */
+void (*lisp_test_runner) (void);
+
 #if defined (HAVE_X_WINDOWS) || defined (HAVE_NS)
 /* If true, -d was specified, meaning we're using some window system.  */
 bool display_arg;
@@ -1510,9 +1515,9 @@ Using an Emacs configured with --with-x-toolkit=lucid does not have this problem
 
       syms_of_gnutls ();
 
-#ifdef HAVE_INOTIFY
+#if defined HAVE_INOTIFY || defined USE_FILE_POLLING
       syms_of_inotify ();
-#endif /* HAVE_INOTIFY */
+#endif /* HAVE_INOTIFY || USE_FILE_POLLING */
 
 #ifdef HAVE_KQUEUE
       syms_of_kqueue ();
@@ -1635,6 +1640,9 @@ Using an Emacs configured with --with-x-toolkit=lucid does not have this problem
 
   initialized = 1;
 
+  if (lisp_test_runner)
+    lisp_test_runner ();
+
   /* Enter editor command loop.  This never returns.  */
   Frecursive_edit ();
   /* NOTREACHED */
@@ -103,7 +103,7 @@ union specbinding *backtrace_top (void) EXTERNALLY_VISIBLE;
 static Lisp_Object apply_lambda (Lisp_Object, Lisp_Object, ptrdiff_t);
 static Lisp_Object lambda_arity (Lisp_Object);
 
-static Lisp_Object
+Lisp_Object
 specpdl_symbol (union specbinding *pdl)
 {
   eassert (pdl->kind >= SPECPDL_LET);
@@ -117,7 +117,7 @@ specpdl_kind (union specbinding *pdl)
   return pdl->let.kind;
 }
 
-static Lisp_Object
+Lisp_Object
 specpdl_old_value (union specbinding *pdl)
 {
   eassert (pdl->kind >= SPECPDL_LET);
@@ -131,14 +131,14 @@ set_specpdl_old_value (union specbinding *pdl, Lisp_Object val)
   pdl->let.old_value = val;
 }
 
-static Lisp_Object
+Lisp_Object
 specpdl_where (union specbinding *pdl)
 {
   eassert (pdl->kind > SPECPDL_LET);
   return pdl->let.where;
 }
 
-static Lisp_Object
+Lisp_Object
 specpdl_saved_value (union specbinding *pdl)
 {
   eassert (pdl->kind >= SPECPDL_LET);
@@ -159,7 +159,7 @@ backtrace_function (union specbinding *pdl)
   return pdl->bt.function;
 }
 
-static ptrdiff_t
+ptrdiff_t
 backtrace_nargs (union specbinding *pdl)
 {
   eassert (pdl->kind == SPECPDL_BACKTRACE);
@@ -221,6 +221,26 @@ backtrace_next (union specbinding *pdl)
   return pdl;
 }
 
+/* Return the kind of the specpdl entry PDL.  */
+
+enum specbind_tag
+specpdl_entry_kind (union specbinding *pdl)
+{
+  return pdl->kind;
+}
+
+/* If unwinding PDL makes a buffer current again, as the entries made
+   by record_unwind_current_buffer do, return that buffer.  Otherwise
+   return nil.  */
+
+Lisp_Object
+specpdl_unwind_buffer (union specbinding *pdl)
+{
+  if (pdl->kind == SPECPDL_UNWIND && pdl->unwind.func == set_buffer_if_live)
+    return pdl->unwind.arg;
+  return Qnil;
+}
+
 void
 init_eval_once (void)
 {
@@ -634,6 +654,7 @@ unwind_to_catch (struct handler *catch, Lisp_Object value)
   /* Restore certain special C variables.  */
   set_poll_suppress_count (catch->poll_suppress_count);
   unblock_input_to (catch->interrupt_input_blocked);
+  byte_code_stack_top = catch->byte_code_stack_top;
 
   do
     {
@@ -944,6 +965,7 @@ push_handler_nosignal (Lisp_Object tag_ch_val, enum handlertype handlertype)
   c->pdlcount = SPECPDL_INDEX ();
   c->poll_suppress_count = poll_suppress_count;
   c->interrupt_input_blocked = interrupt_input_blocked;
+  c->byte_code_stack_top = byte_code_stack_top;
   handlerlist = c;
   return c;
 }
@@ -989,6 +1011,8 @@ maybe_quit (void)
     process_quit_flag ();
   else if (pending_signals)
     process_pending_signals ();
+  if (eval_limits_active)
+    check_eval_limits ();
 }
 
 DEFUN ("signal", Fsignal, Ssignal, 2, 2, 0,
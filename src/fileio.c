@@ -389,7 +389,7 @@ Given a Unix syntax file name, returns a string ending in slash.  */)
       dostounix_filename (SSDATA (tem_fn));
 #ifdef WINDOWSNT
       if (!NILP (Vw32_downcase_file_names))
-	tem_fn = Fdowncase (tem_fn);
+	tem_fn = Fdowncase (tem_fn, Qnil);
 #endif
     }
   else
@@ -536,7 +536,7 @@ is already present.  */)
 
 #ifdef WINDOWSNT
   if (!NILP (Vw32_downcase_file_names))
-    file = Fdowncase (file);
+    file = Fdowncase (file, Qnil);
 #endif
   buf = SAFE_ALLOCA (SBYTES (file) + file_name_as_directory_slop + 1);
   length = file_name_as_directory (buf, SSDATA (file), SBYTES (file),
@@ -618,7 +618,7 @@ In Unix-syntax, this function just removes the final slash.  */)
 
 #ifdef WINDOWSNT
   if (!NILP (Vw32_downcase_file_names))
-    directory = Fdowncase (directory);
+    directory = Fdowncase (directory, Qnil);
 #endif
   buf = SAFE_ALLOCA (SBYTES (directory) + 1);
   length = directory_file_name (buf, SSDATA (directory), SBYTES (directory),
@@ -875,7 +875,7 @@ the root directory.  */)
 
 #ifdef WINDOWSNT
   if (!NILP (Vw32_downcase_file_names))
-    default_directory = Fdowncase (default_directory);
+    default_directory = Fdowncase (default_directory, Qnil);
 #endif
 
   /* Make a local copy of NAME to protect it from GC in DECODE_FILE below.  */
@@ -976,7 +976,7 @@ the root directory.  */)
 	    }
 #ifdef WINDOWSNT
 	  if (!NILP (Vw32_downcase_file_names))
-	    name = Fdowncase (name);
+	    name = Fdowncase (name, Qnil);
 #endif
 #else /* not DOS_NT */
 	  if (strcmp (nm, SSDATA (name)) != 0)
@@ -1373,7 +1373,7 @@ the root directory.  */)
     dostounix_filename (SSDATA (result));
 #ifdef WINDOWSNT
     if (!NILP (Vw32_downcase_file_names))
-      result = Fdowncase (result);
+      result = Fdowncase (result, Qnil);
 #endif
 #else  /* !DOS_NT */
     result = make_specified_string (target, -1, o - target, multibyte);
@@ -1695,7 +1695,7 @@ those `/' is discarded.  */)
     {
 #ifdef WINDOWSNT
       if (!NILP (Vw32_downcase_file_names))
-	filename = Fdowncase (filename);
+	filename = Fdowncase (filename, Qnil);
 #endif
       SAFE_FREE ();
       return filename;
@@ -1716,7 +1716,7 @@ those `/' is discarded.  */)
     {
       Lisp_Object xname = make_specified_string (xnm, -1, x - xnm, multibyte);
 
-      filename = Fdowncase (xname);
+      filename = Fdowncase (xname, Qnil);
     }
   else
 #endif
@@ -2242,8 +2242,8 @@ This is what happens in interactive use with M-x.  */)
   if (!NILP (Ffile_name_case_insensitive_p (file)))
     {
       newname = Fexpand_file_name (newname, Qnil);
-      case_only_rename = !NILP (Fstring_equal (Fdowncase (file),
-					       Fdowncase (newname)));
+      case_only_rename = !NILP (Fstring_equal (Fdowncase (file, Qnil),
+					       Fdowncase (newname, Qnil)));
     }
 #endif
 
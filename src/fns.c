@@ -22,7 +22,6 @@ along with GNU Emacs.  If not, see <https://www.gnu.org/licenses/>.  */
 
 #include <stdlib.h>
 #include <unistd.h>
-#include <filevercmp.h>
 #include <intprops.h>
 #include <vla.h>
 #include <errno.h>
@@ -105,8 +104,8 @@ If string STR1 is greater, the value is a positive number N;
 
       if (! NILP (ignore_case))
 	{
-	  c1 = XINT (Fupcase (make_number (c1)));
-	  c2 = XINT (Fupcase (make_number (c2)));
+	  c1 = XINT (Fupcase (make_number (c1), Qnil));
+	  c2 = XINT (Fupcase (make_number (c2), Qnil));
 	}
 
       if (c1 == c2)
@@ -129,50 +128,6 @@ If string STR1 is greater, the value is a positive number N;
   return Qt;
 }
 
-DEFUN ("string-version-lessp", Fstring_version_lessp,
-       Sstring_version_lessp, 2, 2, 0,
-       doc: /* Return non-nil if S1 is less than S2, as version strings.
-
-This function compares version strings S1 and S2:
-   1) By prefix lexicographically.
-   2) Then by version (similarly to version comparison of Debian's dpkg).
-      Leading zeros in version numbers are ignored.
-   3) If both prefix and version are equal, compare as ordinary strings.
-
-For example, \"foo2.png\" compares less than \"foo12.png\".
-Case is significant.
-Symbols are also allowed; their print names are used instead.  */)
-  (Lisp_Object string1, Lisp_Object string2)
-{
-  if (SYMBOLP (string1))
-    string1 = SYMBOL_NAME (string1);
-  if (SYMBOLP (string2))
-    string2 = SYMBOL_NAME (string2);
-  CHECK_STRING (string1);
-  CHECK_STRING (string2);
-
-  char *p1 = SSDATA (string1);
-  char *p2 = SSDATA (string2);
-  char *lim1 = p1 + SBYTES (string1);
-  char *lim2 = p2 + SBYTES (string2);
-  int cmp;
-
-  while ((cmp = filevercmp (p1, p2)) == 0)
-    {
-      /* If the strings are identical through their first null bytes,
-	 skip past identical prefixes and try again.  */
-      ptrdiff_t size = strlen (p1) + 1;
-      p1 += size;
-      p2 += size;
-      if (lim1 < p1)
-	return lim2 < p2 ? Qnil : Qt;
-      if (lim2 < p2)
-	return Qnil;
-    }
-
-  return cmp < 0 ? Qt : Qnil;
-}
-
 DEFUN ("string-collate-lessp", Fstring_collate_lessp, Sstring_collate_lessp, 2, 4, 0,
        doc: /* Return t if first arg string is less than second in collation order.
 Symbols are also allowed; their print names are used instead.
@@ -1787,7 +1742,7 @@ if `last-nonmenu-event' is nil, and `use-dialog-box' is non-nil.  */)
     {
       ans = Fdowncase (Fread_from_minibuffer (prompt, Qnil, Qnil, Qnil,
 					      Qyes_or_no_p_history, Qnil,
-					      Qnil));
+					      Qnil), Qnil);
       if (SCHARS (ans) == 3 && !strcmp (SSDATA (ans), "yes"))
 	return Qt;
       if (SCHARS (ans) == 2 && !strcmp (SSDATA (ans), "no"))
@@ -3487,7 +3442,6 @@ this variable.  */);
   use_file_dialog = 1;
 
   defsubr (&Scompare_strings);
-  defsubr (&Sstring_version_lessp);
   defsubr (&Sstring_collate_lessp);
   defsubr (&Sstring_collate_equalp);
   defsubr (&Svconcat);
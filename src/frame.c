@@ -4676,7 +4676,7 @@ x_get_arg (Display_Info *dpyinfo, Lisp_Object alist, Lisp_Object param,
 	      return make_float (atof (SSDATA (tem)));
 
 	    case RES_TYPE_BOOLEAN:
-	      tem = Fdowncase (tem);
+	      tem = Fdowncase (tem, Qnil);
 	      if (!strcmp (SSDATA (tem), "on")
 #ifdef HAVE_NS
                   || !strcmp (SSDATA (tem), "yes")
@@ -4694,7 +4694,7 @@ x_get_arg (Display_Info *dpyinfo, Lisp_Object alist, Lisp_Object param,
 		 to Qt, and `false' and `off' to Qnil.  */
 	      {
 		Lisp_Object lower;
-		lower = Fdowncase (tem);
+		lower = Fdowncase (tem, Qnil);
 		if (!strcmp (SSDATA (lower), "on")
 #ifdef HAVE_NS
                     || !strcmp (SSDATA (lower), "yes")
@@ -297,7 +297,7 @@ ftfont_resolve_generic_family (Lisp_Object family, FcPattern *pattern)
   FcResult result;
   FcLangSet *langset;
 
-  family = Fintern (Fdowncase (SYMBOL_NAME (family)), Qnil);
+  family = Fintern (Fdowncase (SYMBOL_NAME (family), Qnil), Qnil);
   if (EQ (family, Qmono))
     family = Qmonospace;
   else if (EQ (family, Qsans) || EQ (family, Qsans__serif))
@@ -2494,7 +2494,7 @@ ftfont_shape_by_flt (Lisp_Object lgstring, struct font *font,
       flt_font_ft.flt_font.family = Mnil;
     else
       flt_font_ft.flt_font.family
-	= msymbol (SSDATA (Fdowncase (SYMBOL_NAME (family))));
+	= msymbol (SSDATA (Fdowncase (SYMBOL_NAME (family), Qnil)));
   }
   flt_font_ft.flt_font.x_ppem = ft_face->size->metrics.x_ppem;
   flt_font_ft.flt_font.y_ppem = ft_face->size->metrics.y_ppem;
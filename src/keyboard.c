@@ -3637,6 +3637,24 @@ kbd_buffer_store_help_event (Lisp_Object frame, Lisp_Object help)
 }
 
 
+/* Store a FILE_NOTIFY_EVENT whose argument is ARG in the input queue.
+   This lets the Rust file notification code queue events without
+   depending on whether USE_FILE_NOTIFY is defined.  */
+
+void
+kbd_buffer_store_file_notify_event (Lisp_Object arg)
+{
+#ifdef USE_FILE_NOTIFY
+  struct input_event event;
+
+  EVENT_INIT (event);
+  event.kind = FILE_NOTIFY_EVENT;
+  event.arg = arg;
+  kbd_buffer_store_event (&event);
+#endif
+}
+
+
 /* Discard any mouse events in the event buffer by setting them to
    NO_EVENT.  */
 void
@@ -6024,12 +6042,13 @@ make_lispy_event (struct input_event *event)
       }
 #endif
 
-#if defined HAVE_INOTIFY || defined HAVE_KQUEUE || defined HAVE_GFILENOTIFY
+#if defined HAVE_INOTIFY || defined HAVE_KQUEUE || defined HAVE_GFILENOTIFY \
+  || defined USE_FILE_POLLING
     case FILE_NOTIFY_EVENT:
       {
         return Fcons (Qfile_notify, event->arg);
       }
-#endif /* HAVE_INOTIFY || HAVE_KQUEUE || HAVE_GFILENOTIFY */
+#endif /* HAVE_INOTIFY || HAVE_KQUEUE || HAVE_GFILENOTIFY || USE_FILE_POLLING */
 
     case CONFIG_CHANGED_EVENT:
 	return list3 (Qconfig_changed_event,
@@ -8240,7 +8259,7 @@ parse_tool_bar_item (Lisp_Object key, Lisp_Object item)
       else
 	label = "";
 
-      new_lbl = Fupcase_initials (build_string (label));
+      new_lbl = Fupcase_initials (build_string (label), Qnil);
       if (SCHARS (new_lbl) <= tool_bar_max_label_size)
         set_prop (TOOL_BAR_ITEM_LABEL, new_lbl);
       else
@@ -8495,8 +8514,8 @@ read_char_minibuf_menu_prompt (int commandflag,
 		  Lisp_Object s
 		    = AREF (item_properties, ITEM_PROPERTY_NAME);
 
-		  upcased_event = Fupcase (event);
-		  downcased_event = Fdowncase (event);
+		  upcased_event = Fupcase (event, Qnil);
+		  downcased_event = Fdowncase (event, Qnil);
 		  char_matches = (XINT (upcased_event) == SREF (s, 0)
 				  || XINT (downcased_event) == SREF (s, 0));
 		  if (! char_matches)
@@ -462,6 +462,7 @@ extern void clear_waiting_for_input (void);
 extern void swallow_events (bool);
 extern bool lucid_event_type_list_p (Lisp_Object);
 extern void kbd_buffer_store_event (struct input_event *);
+extern void kbd_buffer_store_file_notify_event (Lisp_Object);
 extern void kbd_buffer_store_buffered_event (union buffered_input_event *,
 					     struct input_event *);
 INLINE void
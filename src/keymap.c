@@ -92,7 +92,6 @@ static void describe_vector (Lisp_Object, Lisp_Object, Lisp_Object,
                              void (*) (Lisp_Object, Lisp_Object), bool,
                              Lisp_Object, Lisp_Object, bool, bool);
 static void silly_event_symbol_error (Lisp_Object);
-static Lisp_Object get_keyelt (Lisp_Object, bool);
 
 void map_keymap_item (map_keymap_function_t, Lisp_Object, Lisp_Object, Lisp_Object, void *);
 void map_keymap_char_table_item (Lisp_Object, Lisp_Object, Lisp_Object);
@@ -368,7 +367,7 @@ map_keymap_canonical (Lisp_Object map, map_keymap_function_t fun, Lisp_Object ar
 
    This can GC because menu_item_eval_property calls Feval.  */
 
-static Lisp_Object
+Lisp_Object
 get_keyelt (Lisp_Object object, bool autoload)
 {
   while (1)
@@ -424,8 +423,7 @@ static Lisp_Object
 store_in_keymap (Lisp_Object keymap, register Lisp_Object idx, Lisp_Object def)
 {
   /* Flush any reverse-map cache.  */
-  set_where_is_cache(Qnil);
-  set_where_is_cache_keymaps(Qt);
+  where_is_cache_invalidate ();
 
   if (EQ (idx, Qkeymap))
     error ("`keymap' is reserved for embedded parent maps");
@@ -1835,598 +1833,6 @@ See Info node `(elisp)Describing Characters' for examples.  */)
   return build_string (str);
 }
 
-static int where_is_preferred_modifier;
-
-/* Return 0 if SEQ uses non-preferred modifiers or non-char events.
-   Else, return 2 if SEQ uses the where_is_preferred_modifier,
-   and 1 otherwise.  */
-static int
-preferred_sequence_p (Lisp_Object seq)
-{
-  EMACS_INT i;
-  EMACS_INT len = XFASTINT (Flength (seq));
-  int result = 1;
-
-  for (i = 0; i < len; i++)
-    {
-      Lisp_Object ii, elt;
-
-      XSETFASTINT (ii, i);
-      elt = Faref (seq, ii);
-
-      if (!INTEGERP (elt))
-	return 0;
-      else
-	{
-	  int modifiers = XINT (elt) & (CHAR_MODIFIER_MASK & ~CHAR_META);
-	  if (modifiers == where_is_preferred_modifier)
-	    result = 2;
-	  else if (modifiers)
-	    return 0;
-	}
-    }
-
-  return result;
-}
-
-
-/* where-is - finding a command in a set of keymaps.			*/
-
-static void where_is_internal_1 (Lisp_Object key, Lisp_Object binding,
-                                 Lisp_Object args, void *data);
-
-/* Like Flookup_key, but uses a list of keymaps SHADOW instead of a single map.
-   Returns the first non-nil binding found in any of those maps.
-   If REMAP is true, pass the result of the lookup through command
-   remapping before returning it.  */
-
-static Lisp_Object
-shadow_lookup (Lisp_Object shadow, Lisp_Object key, Lisp_Object flag,
-	       bool remap)
-{
-  Lisp_Object tail, value;
-
-  for (tail = shadow; CONSP (tail); tail = XCDR (tail))
-    {
-      value = Flookup_key (XCAR (tail), key, flag);
-      if (NATNUMP (value))
-	{
-	  value = Flookup_key (XCAR (tail),
-			       Fsubstring (key, make_number (0), value), flag);
-	  if (!NILP (value))
-	    return Qnil;
-	}
-      else if (!NILP (value))
-	{
-	  Lisp_Object remapping;
-	  if (remap && SYMBOLP (value)
-	      && (remapping = Fcommand_remapping (value, Qnil, shadow),
-		  !NILP (remapping)))
-	    return remapping;
-	  else
-	    return value;
-	}
-    }
-  return Qnil;
-}
-
-static Lisp_Object Vmouse_events;
-
-struct where_is_internal_data {
-  Lisp_Object definition, this, last;
-  bool last_is_meta, noindirect;
-  Lisp_Object sequences;
-};
-
-/* This function can't GC, AFAIK.  */
-/* Return the list of bindings found.  This list is ordered "longest
-   to shortest".  It may include bindings that are actually shadowed
-   by others, as well as duplicate bindings and remapping bindings.
-   The list returned is potentially shared with where_is_cache, so
-   be careful not to modify it via side-effects.  */
-
-static Lisp_Object
-where_is_internal (Lisp_Object definition, Lisp_Object keymaps,
-		   bool noindirect, bool nomenus)
-{
-  Lisp_Object maps = Qnil;
-  Lisp_Object found;
-  struct where_is_internal_data data;
-
-  /* Only important use of caching is for the menubar
-     (i.e. where-is-internal called with (def nil t nil nil)).  */
-  if (nomenus && !noindirect)
-    {
-      /* Check heuristic-consistency of the cache.  */
-      if (NILP (Fequal (keymaps, get_where_is_cache_keymaps())))
-	set_where_is_cache(Qnil);
-
-      if (NILP (get_where_is_cache()))
-	{
-	  /* We need to create the cache.  */
-	  set_where_is_cache(Fmake_hash_table (0, NULL));
-	  set_where_is_cache_keymaps(Qt);
-	}
-      else
-	/* We can reuse the cache.  */
-	return Fgethash (definition, get_where_is_cache(), Qnil);
-    }
-  else
-    /* Kill the cache so that where_is_internal_1 doesn't think
-       we're filling it up.  */
-    set_where_is_cache(Qnil);
-
-  found = keymaps;
-  while (CONSP (found))
-    {
-      maps =
-	nconc2 (maps,
-		Faccessible_keymaps (get_keymap (XCAR (found), 1, 0), Qnil));
-      found = XCDR (found);
-    }
-
-  data.sequences = Qnil;
-  for (; CONSP (maps); maps = XCDR (maps))
-    {
-      /* Key sequence to reach map, and the map that it reaches */
-      register Lisp_Object this, map, tem;
-
-      /* In order to fold [META-PREFIX-CHAR CHAR] sequences into
-	 [M-CHAR] sequences, check if last character of the sequence
-	 is the meta-prefix char.  */
-      Lisp_Object last;
-      bool last_is_meta;
-
-      this = Fcar (XCAR (maps));
-      map  = Fcdr (XCAR (maps));
-      last = make_number (XINT (Flength (this)) - 1);
-      last_is_meta = (XINT (last) >= 0
-		      && EQ (Faref (this, last), meta_prefix_char));
-
-      /* if (nomenus && !preferred_sequence_p (this)) */
-      if (nomenus && XINT (last) >= 0
-	  && SYMBOLP (tem = Faref (this, make_number (0)))
-	  && !NILP (Fmemq (XCAR (parse_modifiers (tem)), Vmouse_events)))
-	/* If no menu entries should be returned, skip over the
-	   keymaps bound to `menu-bar' and `tool-bar' and other
-	   non-ascii prefixes like `C-down-mouse-2'.  */
-	continue;
-
-      maybe_quit ();
-
-      data.definition = definition;
-      data.noindirect = noindirect;
-      data.this = this;
-      data.last = last;
-      data.last_is_meta = last_is_meta;
-
-      if (CONSP (map))
-	map_keymap (map, where_is_internal_1, Qnil, &data, 0);
-    }
-
-  if (nomenus && !noindirect)
-    { /* Remember for which keymaps this cache was built.
-	 We do it here (late) because we want to keep where_is_cache_keymaps
-	 set to t while the cache isn't fully filled.  */
-      set_where_is_cache_keymaps(keymaps);
-      /* During cache-filling, data.sequences is not filled by
-	 where_is_internal_1.  */
-      return Fgethash (definition, get_where_is_cache(), Qnil);
-    }
-  else
-    return data.sequences;
-}
-
-/* This function can GC if Flookup_key autoloads any keymaps.  */
-
-DEFUN ("where-is-internal", Fwhere_is_internal, Swhere_is_internal, 1, 5, 0,
-       doc: /* Return list of keys that invoke DEFINITION.
-If KEYMAP is a keymap, search only KEYMAP and the global keymap.
-If KEYMAP is nil, search all the currently active keymaps, except
- for `overriding-local-map' (which is ignored).
-If KEYMAP is a list of keymaps, search only those keymaps.
-
-If optional 3rd arg FIRSTONLY is non-nil, return the first key sequence found,
-rather than a list of all possible key sequences.
-If FIRSTONLY is the symbol `non-ascii', return the first binding found,
-no matter what it is.
-If FIRSTONLY has another non-nil value, prefer bindings
-that use the modifier key specified in `where-is-preferred-modifier'
-\(or their meta variants) and entirely reject menu bindings.
-
-If optional 4th arg NOINDIRECT is non-nil, don't extract the commands inside
-menu-items.  This makes it possible to search for a menu-item itself.
-
-The optional 5th arg NO-REMAP alters how command remapping is handled:
-
-- If another command OTHER-COMMAND is remapped to DEFINITION, normally
-  search for the bindings of OTHER-COMMAND and include them in the
-  returned list.  But if NO-REMAP is non-nil, include the vector
-  [remap OTHER-COMMAND] in the returned list instead, without
-  searching for those other bindings.
-
-- If DEFINITION is remapped to OTHER-COMMAND, normally return the
-  bindings for OTHER-COMMAND.  But if NO-REMAP is non-nil, return the
-  bindings for DEFINITION instead, ignoring its remapping.  */)
-  (Lisp_Object definition, Lisp_Object keymap, Lisp_Object firstonly, Lisp_Object noindirect, Lisp_Object no_remap)
-{
-  /* The keymaps in which to search.  */
-  Lisp_Object keymaps;
-  /* Potentially relevant bindings in "shortest to longest" order.  */
-  Lisp_Object sequences = Qnil;
-    /* Actually relevant bindings.  */
-  Lisp_Object found = Qnil;
-  /* 1 means ignore all menu bindings entirely.  */
-  bool nomenus = !NILP (firstonly) && !EQ (firstonly, Qnon_ascii);
-  /* List of sequences found via remapping.  Keep them in a separate
-     variable, so as to push them later, since we prefer
-     non-remapped binding.  */
-  Lisp_Object remapped_sequences = Qnil;
-  /* Whether or not we're handling remapped sequences.  This is needed
-     because remapping is not done recursively by Fcommand_remapping: you
-     can't remap a remapped command.  */
-  bool remapped = 0;
-  Lisp_Object tem = Qnil;
-
-  /* Refresh the C version of the modifier preference.  */
-  where_is_preferred_modifier
-    = parse_solitary_modifier (Vwhere_is_preferred_modifier);
-
-  /* Find the relevant keymaps.  */
-  if (CONSP (keymap) && KEYMAPP (XCAR (keymap)))
-    keymaps = keymap;
-  else if (!NILP (keymap))
-    keymaps = list2 (keymap, current_global_map);
-  else
-    keymaps = Fcurrent_active_maps (Qnil, Qnil);
-
-  tem = Fcommand_remapping (definition, Qnil, keymaps);
-  /* If `definition' is remapped to tem', then OT1H no key will run
-     that command (since they will run `tem' instead), so we should
-     return nil; but OTOH all keys bound to `definition' (or to `tem')
-     will run the same command.
-     So for menu-shortcut purposes, we want to find all the keys bound (maybe
-     via remapping) to `tem'.  But for the purpose of finding the keys that
-     run `definition', then we'd want to just return nil.
-     We choose to make it work right for menu-shortcuts, since it's the most
-     common use.
-     Known bugs: if you remap switch-to-buffer to toto, C-h f switch-to-buffer
-     will tell you that switch-to-buffer is bound to C-x b even though C-x b
-     will run toto instead.  And if `toto' is itself remapped to forward-char,
-     then C-h f toto will tell you that it's bound to C-f even though C-f does
-     not run toto and it won't tell you that C-x b does run toto.  */
-  if (NILP (no_remap) && !NILP (tem))
-    definition = tem;
-
-  if (SYMBOLP (definition)
-      && !NILP (firstonly)
-      && !NILP (tem = Fget (definition, QCadvertised_binding)))
-    {
-      /* We have a list of advertised bindings.  */
-      while (CONSP (tem))
-	if (EQ (shadow_lookup (keymaps, XCAR (tem), Qnil, 0), definition))
-	  return XCAR (tem);
-	else
-	  tem = XCDR (tem);
-      if (EQ (shadow_lookup (keymaps, tem, Qnil, 0), definition))
-	return tem;
-    }
-
-  sequences = Freverse (where_is_internal (definition, keymaps,
-					   !NILP (noindirect), nomenus));
-
-  while (CONSP (sequences)
-	 /* If we're at the end of the `sequences' list and we haven't
-	    considered remapped sequences yet, copy them over and
-	    process them.  */
-	 || (!remapped && (sequences = remapped_sequences,
-			   remapped = 1,
-			   CONSP (sequences))))
-    {
-      Lisp_Object sequence, function;
-
-      sequence = XCAR (sequences);
-      sequences = XCDR (sequences);
-
-      /* Verify that this key binding is not shadowed by another
-	 binding for the same key, before we say it exists.
-
-	 Mechanism: look for local definition of this key and if
-	 it is defined and does not match what we found then
-	 ignore this key.
-
-	 Either nil or number as value from Flookup_key
-	 means undefined.  */
-      if (NILP (Fequal (shadow_lookup (keymaps, sequence, Qnil, remapped),
-			definition)))
-	continue;
-
-      /* If the current sequence is a command remapping with
-	 format [remap COMMAND], find the key sequences
-	 which run COMMAND, and use those sequences instead.  */
-      if (NILP (no_remap) && !remapped
-	  && VECTORP (sequence) && ASIZE (sequence) == 2
-	  && EQ (AREF (sequence, 0), Qremap)
-	  && (function = AREF (sequence, 1), SYMBOLP (function)))
-	{
-	  Lisp_Object seqs = where_is_internal (function, keymaps,
-						!NILP (noindirect), nomenus);
-	  remapped_sequences = nconc2 (Freverse (seqs), remapped_sequences);
-	  continue;
-	}
-
-      /* Don't annoy user with strings from a menu such as the
-	 entries from the "Edit => Paste from Kill Menu".
-	 Change them all to "(any string)", so that there
-	 seems to be only one menu item to report.  */
-      if (! NILP (sequence))
-	{
-	  Lisp_Object tem1;
-	  tem1 = Faref (sequence, make_number (ASIZE (sequence) - 1));
-	  if (STRINGP (tem1))
-	    Faset (sequence, make_number (ASIZE (sequence) - 1),
-		   build_string ("(any string)"));
-	}
-
-      /* It is a true unshadowed match.  Record it, unless it's already
-	 been seen (as could happen when inheriting keymaps).  */
-      if (NILP (Fmember (sequence, found)))
-	found = Fcons (sequence, found);
-
-      /* If firstonly is Qnon_ascii, then we can return the first
-	 binding we find.  If firstonly is not Qnon_ascii but not
-	 nil, then we should return the first ascii-only binding
-	 we find.  */
-      if (EQ (firstonly, Qnon_ascii))
-	return sequence;
-      else if (!NILP (firstonly)
-	       && 2 == preferred_sequence_p (sequence))
-	return sequence;
-    }
-
-  found = Fnreverse (found);
-
-  /* firstonly may have been t, but we may have gone all the way through
-     the keymaps without finding an all-ASCII key sequence.  So just
-     return the best we could find.  */
-  if (NILP (firstonly))
-    return found;
-  else if (where_is_preferred_modifier == 0)
-    return Fcar (found);
-  else
-    { /* Maybe we did not find a preferred_modifier binding, but we did find
-	 some ASCII binding.  */
-      Lisp_Object bindings = found;
-      while (CONSP (bindings))
-	if (preferred_sequence_p (XCAR (bindings)))
-	  return XCAR (bindings);
-	else
-	  bindings = XCDR (bindings);
-      return Fcar (found);
-    }
-}
-
-/* This function can GC because get_keyelt can.  */
-
-static void
-where_is_internal_1 (Lisp_Object key, Lisp_Object binding, Lisp_Object args, void *data)
-{
-  struct where_is_internal_data *d = data; /* Cast! */
-  Lisp_Object definition = d->definition;
-  bool noindirect = d->noindirect;
-  Lisp_Object this = d->this;
-  Lisp_Object last = d->last;
-  bool last_is_meta = d->last_is_meta;
-  Lisp_Object sequence;
-
-  /* Search through indirections unless that's not wanted.  */
-  if (!noindirect)
-    binding = get_keyelt (binding, 0);
-
-  /* End this iteration if this element does not match
-     the target.  */
-
-  if (!(!NILP (get_where_is_cache())	/* everything "matches" during cache-fill.  */
-	|| EQ (binding, definition)
-	|| (CONSP (definition) && !NILP (Fequal (binding, definition)))))
-    /* Doesn't match.  */
-    return;
-
-  /* We have found a match.  Construct the key sequence where we found it.  */
-  if (INTEGERP (key) && last_is_meta)
-    {
-      sequence = Fcopy_sequence (this);
-      Faset (sequence, last, make_number (XINT (key) | meta_modifier));
-    }
-  else
-    {
-      if (CONSP (key))
-	key = Fcons (XCAR (key), XCDR (key));
-      sequence = append_key (this, key);
-    }
-
-  if (!NILP (get_where_is_cache()))
-    {
-      Lisp_Object sequences = Fgethash (binding, get_where_is_cache(), Qnil);
-      Fputhash (binding, Fcons (sequence, sequences), get_where_is_cache());
-    }
-  else
-    d->sequences = Fcons (sequence, d->sequences);
-}
-
-/* describe-bindings - summarizing all the bindings in a set of keymaps.  */
-
-DEFUN ("describe-buffer-bindings", Fdescribe_buffer_bindings, Sdescribe_buffer_bindings, 1, 3, 0,
-       doc: /* Insert the list of all defined keys and their definitions.
-The list is inserted in the current buffer, while the bindings are
-looked up in BUFFER.
-The optional argument PREFIX, if non-nil, should be a key sequence;
-then we display only bindings that start with that prefix.
-The optional argument MENUS, if non-nil, says to mention menu bindings.
-\(Ordinarily these are omitted from the output.)  */)
-  (Lisp_Object buffer, Lisp_Object prefix, Lisp_Object menus)
-{
-  Lisp_Object outbuf, shadow;
-  bool nomenu = NILP (menus);
-  Lisp_Object start1;
-
-  const char *alternate_heading
-    = "\
-Keyboard translations:\n\n\
-You type        Translation\n\
---------        -----------\n";
-
-  CHECK_BUFFER (buffer);
-
-  shadow = Qnil;
-  outbuf = Fcurrent_buffer ();
-
-  /* Report on alternates for keys.  */
-  if (STRINGP (KVAR (current_kboard, Vkeyboard_translate_table)) && !NILP (prefix))
-    {
-      int c;
-      const unsigned char *translate = SDATA (KVAR (current_kboard, Vkeyboard_translate_table));
-      int translate_len = SCHARS (KVAR (current_kboard, Vkeyboard_translate_table));
-
-      for (c = 0; c < translate_len; c++)
-	if (translate[c] != c)
-	  {
-	    char buf[KEY_DESCRIPTION_SIZE];
-	    char *bufend;
-
-	    if (alternate_heading)
-	      {
-		insert_string (alternate_heading);
-		alternate_heading = 0;
-	      }
-
-	    bufend = push_key_description (translate[c], buf);
-	    insert (buf, bufend - buf);
-	    Findent_to (make_number (16), make_number (1));
-	    bufend = push_key_description (c, buf);
-	    insert (buf, bufend - buf);
-
-	    insert ("\n", 1);
-
-	    /* Insert calls signal_after_change which may GC.  */
-	    translate = SDATA (KVAR (current_kboard, Vkeyboard_translate_table));
-	  }
-
-      insert ("\n", 1);
-    }
-
-  if (!NILP (Vkey_translation_map))
-    describe_map_tree (Vkey_translation_map, 0, Qnil, prefix,
-		       "Key translations", nomenu, 1, 0, 0);
-
-
-  /* Print the (major mode) local map.  */
-  start1 = Qnil;
-  if (!NILP (KVAR (current_kboard, Voverriding_terminal_local_map)))
-    start1 = KVAR (current_kboard, Voverriding_terminal_local_map);
-
-  if (!NILP (start1))
-    {
-      describe_map_tree (start1, 1, shadow, prefix,
-			 "\f\nOverriding Bindings", nomenu, 0, 0, 0);
-      shadow = Fcons (start1, shadow);
-      start1 = Qnil;
-    }
-  else if (!NILP (Voverriding_local_map))
-    start1 = Voverriding_local_map;
-
-  if (!NILP (start1))
-    {
-      describe_map_tree (start1, 1, shadow, prefix,
-			 "\f\nOverriding Bindings", nomenu, 0, 0, 0);
-      shadow = Fcons (start1, shadow);
-    }
-  else
-    {
-      /* Print the minor mode and major mode keymaps.  */
-      int i, nmaps;
-      Lisp_Object *modes, *maps;
-
-      /* Temporarily switch to `buffer', so that we can get that buffer's
-	 minor modes correctly.  */
-      Fset_buffer (buffer);
-
-      nmaps = current_minor_maps (&modes, &maps);
-      Fset_buffer (outbuf);
-
-      start1 = get_local_map (BUF_PT (XBUFFER (buffer)),
-			      XBUFFER (buffer), Qkeymap);
-      if (!NILP (start1))
-	{
-	  describe_map_tree (start1, 1, shadow, prefix,
-			     "\f\n`keymap' Property Bindings", nomenu,
-			     0, 0, 0);
-	  shadow = Fcons (start1, shadow);
-	}
-
-      /* Print the minor mode maps.  */
-      for (i = 0; i < nmaps; i++)
-	{
-	  /* The title for a minor mode keymap
-	     is constructed at run time.
-	     We let describe_map_tree do the actual insertion
-	     because it takes care of other features when doing so.  */
-	  char *title, *p;
-
-	  if (!SYMBOLP (modes[i]))
-	    emacs_abort ();
-
-	  USE_SAFE_ALLOCA;
-	  p = title = SAFE_ALLOCA (42 + SBYTES (SYMBOL_NAME (modes[i])));
-	  *p++ = '\f';
-	  *p++ = '\n';
-	  *p++ = '`';
-	  memcpy (p, SDATA (SYMBOL_NAME (modes[i])),
-		  SBYTES (SYMBOL_NAME (modes[i])));
-	  p += SBYTES (SYMBOL_NAME (modes[i]));
-	  *p++ = '\'';
-	  memcpy (p, " Minor Mode Bindings", strlen (" Minor Mode Bindings"));
-	  p += strlen (" Minor Mode Bindings");
-	  *p = 0;
-
-	  describe_map_tree (maps[i], 1, shadow, prefix,
-			     title, nomenu, 0, 0, 0);
-	  shadow = Fcons (maps[i], shadow);
-	  SAFE_FREE ();
-	}
-
-      start1 = get_local_map (BUF_PT (XBUFFER (buffer)),
-			      XBUFFER (buffer), Qlocal_map);
-      if (!NILP (start1))
-	{
-	  if (EQ (start1, BVAR (XBUFFER (buffer), keymap)))
-	    describe_map_tree (start1, 1, shadow, prefix,
-			       "\f\nMajor Mode Bindings", nomenu, 0, 0, 0);
-	  else
-	    describe_map_tree (start1, 1, shadow, prefix,
-			       "\f\n`local-map' Property Bindings",
-			       nomenu, 0, 0, 0);
-
-	  shadow = Fcons (start1, shadow);
-	}
-    }
-
-  describe_map_tree (current_global_map, 1, shadow, prefix,
-		     "\f\nGlobal Bindings", nomenu, 0, 1, 0);
-
-  /* Print the function-key-map translations under this prefix.  */
-  if (!NILP (KVAR (current_kboard, Vlocal_function_key_map)))
-    describe_map_tree (KVAR (current_kboard, Vlocal_function_key_map), 0, Qnil, prefix,
-		       "\f\nFunction key map translations", nomenu, 1, 0, 0);
-
-  /* Print the input-decode-map translations under this prefix.  */
-  if (!NILP (KVAR (current_kboard, Vinput_decode_map)))
-    describe_map_tree (KVAR (current_kboard, Vinput_decode_map), 0, Qnil, prefix,
-		       "\f\nInput decoding map translations", nomenu, 1, 0, 0);
-
-  return Qnil;
-}
-
 /* Insert a description of the key bindings in STARTMAP,
     followed by those of all maps reachable through STARTMAP.
    If PARTIAL, omit certain "uninteresting" commands
@@ -3115,7 +2521,7 @@ Return list of symbols found.  */)
   apropos_predicate = predicate;
   apropos_accumulate = Qnil;
   map_obarray (Vobarray, apropos_accum, regexp);
-  tem = Fsort (apropos_accumulate, Qstring_lessp);
+  tem = CALLN (Fsort, apropos_accumulate, Qstring_lessp);
   apropos_accumulate = Qnil;
   apropos_predicate = Qnil;
   return tem;
@@ -3210,30 +2616,15 @@ uses this modifier key if possible.  If nil, or if no such binding
 exists, bindings using keys without modifiers (or only with meta) will
 be preferred.  */);
   Vwhere_is_preferred_modifier = Qnil;
-  where_is_preferred_modifier = 0;
 
   DEFSYM (Qmenu_bar, "menu-bar");
   DEFSYM (Qmode_line, "mode-line");
 
-  staticpro (&Vmouse_events);
-  Vmouse_events = listn (CONSTYPE_PURE, 9,
-			 Qmenu_bar,
-			 Qtool_bar,
-			 Qheader_line,
-			 Qmode_line,
-			 intern_c_string ("mouse-1"),
-			 intern_c_string ("mouse-2"),
-			 intern_c_string ("mouse-3"),
-			 intern_c_string ("mouse-4"),
-			 intern_c_string ("mouse-5"));
-
   /* Keymap used for minibuffers when doing completion.  */
   /* Keymap used for minibuffers when doing completion and require a match.  */
   DEFSYM (Qkeymapp, "keymapp");
-  DEFSYM (Qnon_ascii, "non-ascii");
   DEFSYM (Qmenu_item, "menu-item");
   DEFSYM (Qremap, "remap");
-  DEFSYM (QCadvertised_binding, ":advertised-binding");
 
   command_remapping_vector = Fmake_vector (make_number (2), Qremap);
   staticpro (&command_remapping_vector);
@@ -3250,8 +2641,6 @@ be preferred.  */);
   defsubr (&Sdescribe_vector);
   defsubr (&Ssingle_key_description);
   defsubr (&Stext_char_description);
-  defsubr (&Swhere_is_internal);
-  defsubr (&Sdescribe_buffer_bindings);
   defsubr (&Sapropos_internal);
 }
 
@@ -3139,11 +3139,13 @@ struct handler
   ptrdiff_t pdlcount;
   int poll_suppress_count;
   int interrupt_input_blocked;
+  ptrdiff_t byte_code_stack_top;
 };
 
 extern Lisp_Object memory_signal_data;
 
 extern void maybe_quit (void);
+extern void check_eval_limits (void);
 
 /* True if ought to quit now.  */
 
@@ -3813,6 +3815,13 @@ extern Lisp_Object intern_sym (Lisp_Object sym, Lisp_Object obarray, Lisp_Object
 extern void init_symbol (Lisp_Object, Lisp_Object);
 extern Lisp_Object oblookup (Lisp_Object, const char *, ptrdiff_t, ptrdiff_t);
 extern Lisp_Object read_internal_start (Lisp_Object, Lisp_Object, Lisp_Object);
+extern int readchar (Lisp_Object, bool *);
+extern void unreadchar (Lisp_Object, int);
+extern void skip_dyn_bytes (Lisp_Object, ptrdiff_t);
+extern void skip_dyn_eof (Lisp_Object);
+extern void skip_doc_string (Lisp_Object, ptrdiff_t);
+extern Lisp_Object get_lazy_string (Lisp_Object);
+extern void read_from_string_setup (Lisp_Object, Lisp_Object, Lisp_Object);
 extern void loadhist_attach(Lisp_Object x);
 INLINE void
 LOADHIST_ATTACH (Lisp_Object x)
@@ -4108,12 +4117,9 @@ extern void syms_of_minibuf (void);
 
 extern void syms_of_callint (void);
 
-/* Defined in casefiddle.c.  */
+/* Defined in casefiddle.rs.  */
 
 enum case_action {CASE_UP, CASE_DOWN, CASE_CAPITALIZE, CASE_CAPITALIZE_UP};
-Lisp_Object casify_object (enum case_action flag, Lisp_Object obj);
-ptrdiff_t casify_region (enum case_action flag, Lisp_Object b, Lisp_Object e);
-Lisp_Object casify_region_nil (enum case_action flag, Lisp_Object b, Lisp_Object e);
 extern void syms_of_casefiddle (void);
 extern void keys_of_casefiddle (void);
 
@@ -4189,6 +4195,7 @@ INLINE void synchronize_system_time_locale (void) {}
 #endif
 extern char *emacs_strerror (int);
 extern void shut_down_emacs (int, Lisp_Object);
+extern void (*lisp_test_runner) (void);
 
 /* True means don't do interactive redisplay and don't change tty modes.  */
 extern bool noninteractive;
@@ -4275,10 +4282,12 @@ extern void syms_of_doc (void);
 extern int read_bytecode_char (bool);
 
 /* Defined in bytecode.c.  */
+extern Lisp_Object get_byte_code_arity (Lisp_Object);
+
+/* Defined in Rust.  */
 extern void syms_of_bytecode (void);
 extern Lisp_Object exec_byte_code (Lisp_Object, Lisp_Object, Lisp_Object,
 				   Lisp_Object, ptrdiff_t, Lisp_Object *);
-extern Lisp_Object get_byte_code_arity (Lisp_Object);
 
 /* Defined in macros.c.  */
 extern void init_macros (void);
@@ -4388,7 +4397,7 @@ extern void syms_of_fontset (void);
 #endif
 
 /* Defined in inotify.c */
-#ifdef HAVE_INOTIFY
+#if defined HAVE_INOTIFY || defined USE_FILE_POLLING
 extern void syms_of_inotify (void);
 #endif
 
@@ -4762,6 +4771,15 @@ maybe_gc (void)
 Lisp_Object funcall_lambda (Lisp_Object, ptrdiff_t, Lisp_Object *);
 
 bool backtrace_debug_on_exit (union specbinding *pdl);
+extern Lisp_Object *backtrace_args (union specbinding *);
+extern Lisp_Object backtrace_function (union specbinding *);
+extern ptrdiff_t backtrace_nargs (union specbinding *);
+extern enum specbind_tag specpdl_entry_kind (union specbinding *);
+extern Lisp_Object specpdl_symbol (union specbinding *);
+extern Lisp_Object specpdl_old_value (union specbinding *);
+extern Lisp_Object specpdl_where (union specbinding *);
+extern Lisp_Object specpdl_saved_value (union specbinding *);
+extern Lisp_Object specpdl_unwind_buffer (union specbinding *);
 
 void do_debug_on_call (Lisp_Object code, ptrdiff_t count);
 
@@ -64,46 +64,11 @@ along with GNU Emacs.  If not, see <https://www.gnu.org/licenses/>.  */
 #define file_tell ftell
 #endif
 
-/* The objects or placeholders read with the #n=object form.
-
-   A hash table maps a number to either a placeholder (while the
-   object is still being parsed, in case it's referenced within its
-   own definition) or to the completed object.  With small integers
-   for keys, it's effectively little more than a vector, but it'll
-   manage any needed resizing for us.
-
-   The variable must be reset to an empty hash table before all
-   top-level calls to read0.  In between calls, it may be an empty
-   hash table left unused from the previous call (to reduce
-   allocations), or nil.  */
-static Lisp_Object read_objects_map;
-
-/* The recursive objects read with the #n=object form.
-
-   Objects that might have circular references are stored here, so
-   that recursive substitution knows not to keep processing them
-   multiple times.
-
-   Only objects that are completely processed, including substituting
-   references to themselves (but not necessarily replacing
-   placeholders for other objects still being read), are stored.
-
-   A hash table is used for efficient lookups of keys.  We don't care
-   what the value slots hold.  The variable must be set to an empty
-   hash table before all top-level calls to read0.  In between calls,
-   it may be an empty hash table left unused from the previous call
-   (to reduce allocations), or nil.  */
-static Lisp_Object read_objects_completed;
-
 /* For use within read-from-string (this reader is non-reentrant!!)  */
 static ptrdiff_t read_from_string_index;
 static ptrdiff_t read_from_string_index_byte;
 static ptrdiff_t read_from_string_limit;
 
-/* Number of characters read in the current call to Fread or
-   Fread_from_string.  */
-static EMACS_INT readchar_count;
-
 /* This contains the last string skipped with #@.  */
 static char *saved_doc_string;
 /* Length of buffer allocated in saved_doc_string.  */
@@ -124,12 +89,6 @@ static ptrdiff_t prev_saved_doc_string_length;
 /* This is the file position that string came from.  */
 static file_offset prev_saved_doc_string_position;
 
-/* True means inside a new-style backquote
-   with no surrounding parentheses.
-   Fread initializes this to false, so we need not specbind it
-   or worry about what happens to it when there is an error.  */
-static bool new_backquote_flag;
-
 /* A list of file names for files being loaded in Fload.  Used to
    check for recursive loads.  */
 
@@ -167,7 +126,12 @@ static int readbyte_from_string (int, Lisp_Object);
    means that there's no unread character.  */
 static int unread_char;
 
-static int
+/* Read a character from READCHARFUN, or return -1 at the end of the
+   input.  If MULTIBYTE is non-null, set *MULTIBYTE to the
+   multibyteness of the source.  The reader proper lives in
+   rust_src/src/lread.rs.  */
+
+int
 readchar (Lisp_Object readcharfun, bool *multibyte)
 {
   Lisp_Object tem;
@@ -180,8 +144,6 @@ readchar (Lisp_Object readcharfun, bool *multibyte)
   if (multibyte)
     *multibyte = 0;
 
-  readchar_count++;
-
   if (BUFFERP (readcharfun))
     {
       register struct buffer *inbuffer = XBUFFER (readcharfun);
@@ -341,7 +303,7 @@ readchar (Lisp_Object readcharfun, bool *multibyte)
   (EQ (readcharfun, Qget_file_char)			\
    || EQ (readcharfun, Qget_emacs_mule_file_char))
 
-static void
+void
 skip_dyn_bytes (Lisp_Object readcharfun, ptrdiff_t n)
 {
   if (FROM_FILE_P (readcharfun))
@@ -365,7 +327,7 @@ skip_dyn_bytes (Lisp_Object readcharfun, ptrdiff_t n)
     }
 }
 
-static void
+void
 skip_dyn_eof (Lisp_Object readcharfun)
 {
   if (FROM_FILE_P (readcharfun))
@@ -382,10 +344,9 @@ skip_dyn_eof (Lisp_Object readcharfun)
 /* Unread the character C in the way appropriate for the stream READCHARFUN.
    If the stream is a user function, call it with the char as argument.  */
 
-static void
+void
 unreadchar (Lisp_Object readcharfun, int c)
 {
-  readchar_count--;
   if (c == -1)
     /* Don't back up the pointer if we're unreading the end-of-input mark,
        since readchar didn't advance it when we read it.  */
@@ -569,30 +530,6 @@ read_emacs_mule_char (int c, int (*readbyte) (int, Lisp_Object), Lisp_Object rea
 }
 
 
-/* An in-progress substitution of OBJECT for PLACEHOLDER.  */
-struct subst
-{
-  Lisp_Object object;
-  Lisp_Object placeholder;
-
-  /* Hash table of subobjects of OBJECT that might be circular.  If
-     Qt, all such objects might be circular.  */
-  Lisp_Object completed;
-
-  /* List of subobjects of OBJECT that have already been visited.  */
-  Lisp_Object seen;
-};
-
-static Lisp_Object read0 (Lisp_Object);
-static Lisp_Object read1 (Lisp_Object, int *, bool);
-
-static Lisp_Object read_list (bool, Lisp_Object);
-static Lisp_Object read_vector (Lisp_Object, bool);
-
-static Lisp_Object substitute_object_recurse (struct subst *, Lisp_Object);
-static void substitute_in_interval (INTERVAL, void *);
-
-
 /* Get a character from the tty.  */
 
 /* Read input events until we get one that's acceptable for our purposes.
@@ -818,7 +755,7 @@ lisp_file_lexically_bound_p (Lisp_Object readcharfun)
         ch = READCHAR;
       if (ch == '\n') ch = READCHAR;
       /* It is OK to leave the position after a #! line, since
-         that is what read1 does.  */
+         that is what the reader does.  */
     }
 
   if (ch != ';')
@@ -998,7 +935,7 @@ load_warn_unescaped_character_literals (Lisp_Object file)
          format, file,
          Fmapconcat (list3 (Qlambda, list1 (Qchar),
                             list3 (Qformat, inner_format, Qchar)),
-                     Fsort (Vlread_unescaped_character_literals, Qlss),
+                     CALLN (Fsort, Vlread_unescaped_character_literals, Qlss),
                      separator));
 }
 
@@ -1789,18 +1726,6 @@ readevalloop_1 (int old)
   load_convert_to_unibyte = old;
 }
 
-/* Signal an `end-of-file' error, if possible with file name
-   information.  */
-
-static _Noreturn void
-end_of_file_error (void)
-{
-  if (STRINGP (Vload_file_name))
-    xsignal1 (Qend_of_file, Vload_file_name);
-
-  xsignal0 (Qend_of_file);
-}
-
 static Lisp_Object
 readevalloop_eager_expand_eval (Lisp_Object val, Lisp_Object macroexpand)
 {
@@ -1951,51 +1876,25 @@ readevalloop (Lisp_Object readcharfun,
 	  || c == NO_BREAK_SPACE)
 	goto read_next;
 
-      if (! HASH_TABLE_P (read_objects_map)
-	  || XHASH_TABLE (read_objects_map)->count)
-	read_objects_map
-	  = make_hash_table (hashtest_eq, DEFAULT_HASH_SIZE,
-			     DEFAULT_REHASH_SIZE, DEFAULT_REHASH_THRESHOLD,
-			     Qnil, false);
-      if (! HASH_TABLE_P (read_objects_completed)
-	  || XHASH_TABLE (read_objects_completed)->count)
-	read_objects_completed
-	  = make_hash_table (hashtest_eq, DEFAULT_HASH_SIZE,
-			     DEFAULT_REHASH_SIZE, DEFAULT_REHASH_THRESHOLD,
-			     Qnil, false);
-      if (!NILP (Vpurify_flag) && c == '(')
-	{
-	  val = read_list (0, readcharfun);
-	}
-      else
+      UNREAD (c);
+      if (!NILP (readfun))
 	{
-	  UNREAD (c);
-	  if (!NILP (readfun))
-	    {
-	      val = call1 (readfun, readcharfun);
+	  val = call1 (readfun, readcharfun);
 
-	      /* If READCHARFUN has set point to ZV, we should
-	         stop reading, even if the form read sets point
-		 to a different value when evaluated.  */
-	      if (BUFFERP (readcharfun))
-		{
-		  struct buffer *buf = XBUFFER (readcharfun);
-		  if (BUF_PT (buf) == BUF_ZV (buf))
-		    continue_reading_p = 0;
-		}
+	  /* If READCHARFUN has set point to ZV, we should
+	     stop reading, even if the form read sets point
+	     to a different value when evaluated.  */
+	  if (BUFFERP (readcharfun))
+	    {
+	      struct buffer *buf = XBUFFER (readcharfun);
+	      if (BUF_PT (buf) == BUF_ZV (buf))
+		continue_reading_p = 0;
 	    }
-	  else if (! NILP (Vload_read_function))
-	    val = call1 (Vload_read_function, readcharfun);
-	  else
-	    val = read_internal_start (readcharfun, Qnil, Qnil);
 	}
-      /* Empty hashes can be reused; otherwise, reset on next call.  */
-      if (HASH_TABLE_P (read_objects_map)
-	  && XHASH_TABLE (read_objects_map)->count > 0)
-	read_objects_map = Qnil;
-      if (HASH_TABLE_P (read_objects_completed)
-	  && XHASH_TABLE (read_objects_completed)->count > 0)
-	read_objects_completed = Qnil;
+      else if (! NILP (Vload_read_function))
+	val = call1 (Vload_read_function, readcharfun);
+      else
+	val = read_internal_start (readcharfun, Qnil, Qnil);
 
       if (!NILP (start) && continue_reading_p)
 	start = Fpoint_marker ();
@@ -2097,390 +1996,94 @@ the end of STRING.  */)
   return Fcons (ret, make_number (read_from_string_index));
 }
 
-/* Function to set up the global context we need in toplevel read
-   calls.  START and END only used when STREAM is a string.  */
-Lisp_Object
-read_internal_start (Lisp_Object stream, Lisp_Object start, Lisp_Object end)
-{
-  Lisp_Object retval;
-
-  readchar_count = 0;
-  new_backquote_flag = 0;
-  /* We can get called from readevalloop which may have set these
-     already.  */
-  if (! HASH_TABLE_P (read_objects_map)
-      || XHASH_TABLE (read_objects_map)->count)
-    read_objects_map
-      = make_hash_table (hashtest_eq, DEFAULT_HASH_SIZE, DEFAULT_REHASH_SIZE,
-			 DEFAULT_REHASH_THRESHOLD, Qnil, false);
-  if (! HASH_TABLE_P (read_objects_completed)
-      || XHASH_TABLE (read_objects_completed)->count)
-    read_objects_completed
-      = make_hash_table (hashtest_eq, DEFAULT_HASH_SIZE, DEFAULT_REHASH_SIZE,
-			 DEFAULT_REHASH_THRESHOLD, Qnil, false);
-  if (EQ (Vread_with_symbol_positions, Qt)
-      || EQ (Vread_with_symbol_positions, stream))
-    Vread_symbol_positions_list = Qnil;
-
-  if (STRINGP (stream)
-      || ((CONSP (stream) && STRINGP (XCAR (stream)))))
-    {
-      ptrdiff_t startval, endval;
-      Lisp_Object string;
-
-      if (STRINGP (stream))
-	string = stream;
-      else
-	string = XCAR (stream);
+/* Prepare to read from STREAM, a string or a cons whose car is a
+   string, between START and END.  */
 
-      validate_subarray (string, start, end, SCHARS (string),
-			 &startval, &endval);
-
-      read_from_string_index = startval;
-      read_from_string_index_byte = string_char_to_byte (string, startval);
-      read_from_string_limit = endval;
-    }
-
-  retval = read0 (stream);
-  if (EQ (Vread_with_symbol_positions, Qt)
-      || EQ (Vread_with_symbol_positions, stream))
-    Vread_symbol_positions_list = Fnreverse (Vread_symbol_positions_list);
-  /* Empty hashes can be reused; otherwise, reset on next call.  */
-  if (HASH_TABLE_P (read_objects_map)
-      && XHASH_TABLE (read_objects_map)->count > 0)
-    read_objects_map = Qnil;
-  if (HASH_TABLE_P (read_objects_completed)
-      && XHASH_TABLE (read_objects_completed)->count > 0)
-    read_objects_completed = Qnil;
-  return retval;
-}
-
-
-/* Signal Qinvalid_read_syntax error.
-   S is error string of length N (if > 0)  */
-
-static _Noreturn void
-invalid_syntax (const char *s)
-{
-  xsignal1 (Qinvalid_read_syntax, build_string (s));
-}
-
-
-/* Use this for recursive reads, in contexts where internal tokens
-   are not allowed.  */
-
-static Lisp_Object
-read0 (Lisp_Object readcharfun)
-{
-  register Lisp_Object val;
-  int c;
-
-  val = read1 (readcharfun, &c, 0);
-  if (!c)
-    return val;
-
-  xsignal1 (Qinvalid_read_syntax,
-	    Fmake_string (make_number (1), make_number (c), Qnil));
-}
-
-/* Grow a read buffer BUF that contains OFFSET useful bytes of data,
-   by at least MAX_MULTIBYTE_LENGTH bytes.  Update *BUF_ADDR and
-   *BUF_SIZE accordingly; 0 <= OFFSET <= *BUF_SIZE.  If *BUF_ADDR is
-   initially null, BUF is on the stack: copy its data to the new heap
-   buffer.  Otherwise, BUF must equal *BUF_ADDR and can simply be
-   reallocated.  Either way, remember the heap allocation (which is at
-   pdl slot COUNT) so that it can be freed when unwinding the stack.*/
-
-static char *
-grow_read_buffer (char *buf, ptrdiff_t offset,
-		  char **buf_addr, ptrdiff_t *buf_size, ptrdiff_t count)
+void
+read_from_string_setup (Lisp_Object stream, Lisp_Object start,
+			Lisp_Object end)
 {
-  char *p = xpalloc (*buf_addr, buf_size, MAX_MULTIBYTE_LENGTH, -1, 1);
-  if (!*buf_addr)
-    {
-      memcpy (p, buf, offset);
-      record_unwind_protect_ptr (xfree, p);
-    }
-  else
-    set_unwind_protect_ptr (count, xfree, p);
-  *buf_addr = p;
-  return p;
-}
+  ptrdiff_t startval, endval;
+  Lisp_Object string = STRINGP (stream) ? stream : XCAR (stream);
 
-/* Return the scalar value that has the Unicode character name NAME.
-   Raise 'invalid-read-syntax' if there is no such character.  */
-static int
-character_name_to_code (char const *name, ptrdiff_t name_len)
-{
-  /* For "U+XXXX", pass the leading '+' to string_to_number to reject
-     monstrosities like "U+-0000".  */
-  Lisp_Object code
-    = (name[0] == 'U' && name[1] == '+'
-       ? string_to_number (name + 1, 16, false)
-       : call2 (Qchar_from_name, make_unibyte_string (name, name_len), Qt));
-
-  if (! RANGED_INTEGERP (0, code, MAX_UNICODE_CHAR)
-      || char_surrogate_p (XINT (code)))
-    {
-      AUTO_STRING (format, "\\N{%s}");
-      AUTO_STRING_WITH_LEN (namestr, name, name_len);
-      xsignal1 (Qinvalid_read_syntax, CALLN (Fformat, format, namestr));
-    }
+  validate_subarray (string, start, end, SCHARS (string),
+		     &startval, &endval);
 
-  return XINT (code);
+  read_from_string_index = startval;
+  read_from_string_index_byte = string_char_to_byte (string, startval);
+  read_from_string_limit = endval;
 }
 
-/* Bound on the length of a Unicode character name.  As of
-   Unicode 9.0.0 the maximum is 83, so this should be safe.  */
-enum { UNICODE_CHARACTER_NAME_LENGTH_BOUND = 200 };
-
-/* Read a \-escape sequence, assuming we already read the `\'.
-   If the escape sequence forces unibyte, return eight-bit char.  */
+/* Skip the NSKIP bytes that follow #@NSKIP in READCHARFUN.  That's
+   used in .elc files to skip over doc strings and function
+   definitions.  */
 
-static int
-read_escape (Lisp_Object readcharfun, bool stringp)
+void
+skip_doc_string (Lisp_Object readcharfun, ptrdiff_t nskip)
 {
-  int c = READCHAR;
-  /* \u allows up to four hex digits, \U up to eight.  Default to the
-     behavior for \u, and change this value in the case that \U is seen.  */
-  int unicode_hex_count = 4;
+  enum { extra = 100 };
+  ptrdiff_t i;
+  int c = 0;
 
-  switch (c)
-    {
-    case -1:
-      end_of_file_error ();
-
-    case 'a':
-      return '\007';
-    case 'b':
-      return '\b';
-    case 'd':
-      return 0177;
-    case 'e':
-      return 033;
-    case 'f':
-      return '\f';
-    case 'n':
-      return '\n';
-    case 'r':
-      return '\r';
-    case 't':
-      return '\t';
-    case 'v':
-      return '\v';
-    case '\n':
-      return -1;
-    case ' ':
-      if (stringp)
-	return -1;
-      return ' ';
+  if (STRING_BYTES_BOUND - extra < nskip)
+    string_overflow ();
 
-    case 'M':
-      c = READCHAR;
-      if (c != '-')
-	error ("Invalid escape character syntax");
-      c = READCHAR;
-      if (c == '\\')
-	c = read_escape (readcharfun, 0);
-      return c | meta_modifier;
-
-    case 'S':
-      c = READCHAR;
-      if (c != '-')
-	error ("Invalid escape character syntax");
-      c = READCHAR;
-      if (c == '\\')
-	c = read_escape (readcharfun, 0);
-      return c | shift_modifier;
-
-    case 'H':
-      c = READCHAR;
-      if (c != '-')
-	error ("Invalid escape character syntax");
-      c = READCHAR;
-      if (c == '\\')
-	c = read_escape (readcharfun, 0);
-      return c | hyper_modifier;
-
-    case 'A':
-      c = READCHAR;
-      if (c != '-')
-	error ("Invalid escape character syntax");
-      c = READCHAR;
-      if (c == '\\')
-	c = read_escape (readcharfun, 0);
-      return c | alt_modifier;
-
-    case 's':
-      c = READCHAR;
-      if (stringp || c != '-')
-	{
-	  UNREAD (c);
-	  return ' ';
-	}
-      c = READCHAR;
-      if (c == '\\')
-	c = read_escape (readcharfun, 0);
-      return c | super_modifier;
-
-    case 'C':
-      c = READCHAR;
-      if (c != '-')
-	error ("Invalid escape character syntax");
-      FALLTHROUGH;
-    case '^':
-      c = READCHAR;
-      if (c == '\\')
-	c = read_escape (readcharfun, 0);
-      if ((c & ~CHAR_MODIFIER_MASK) == '?')
-	return 0177 | (c & CHAR_MODIFIER_MASK);
-      else if (! SINGLE_BYTE_CHAR_P ((c & ~CHAR_MODIFIER_MASK)))
-	return c | ctrl_modifier;
-      /* ASCII control chars are made from letters (both cases),
-	 as well as the non-letters within 0100...0137.  */
-      else if ((c & 0137) >= 0101 && (c & 0137) <= 0132)
-	return (c & (037 | ~0177));
-      else if ((c & 0177) >= 0100 && (c & 0177) <= 0137)
-	return (c & (037 | ~0177));
-      else
-	return c | ctrl_modifier;
-
-    case '0':
-    case '1':
-    case '2':
-    case '3':
-    case '4':
-    case '5':
-    case '6':
-    case '7':
-      /* An octal escape, as in ANSI C.  */
-      {
-	register int i = c - '0';
-	register int count = 0;
-	while (++count < 3)
-	  {
-	    if ((c = READCHAR) >= '0' && c <= '7')
-	      {
-		i *= 8;
-		i += c - '0';
-	      }
-	    else
-	      {
-		UNREAD (c);
-		break;
-	      }
-	  }
-
-	if (i >= 0x80 && i < 0x100)
-	  i = BYTE8_TO_CHAR (i);
-	return i;
-      }
+  if (load_force_doc_strings
+      && (FROM_FILE_P (readcharfun)))
+    {
+      /* If we are supposed to force doc strings into core right now,
+	 record the last string that we skipped,
+	 and record where in the file it comes from.  */
 
-    case 'x':
-      /* A hex escape, as in ANSI C.  */
+      /* But first exchange saved_doc_string
+	 with prev_saved_doc_string, so we save two strings.  */
       {
-	unsigned int i = 0;
-	int count = 0;
-	while (1)
-	  {
-	    c = READCHAR;
-	    int digit = char_hexdigit (c);
-	    if (digit < 0)
-	      {
-		UNREAD (c);
-		break;
-	      }
-	    i = (i << 4) + digit;
-	    /* Allow hex escapes as large as ?\xfffffff, because some
-	       packages use them to denote characters with modifiers.  */
-	    if ((CHAR_META | (CHAR_META - 1)) < i)
-	      error ("Hex character out of range: \\x%x...", i);
-	    count += count < 3;
-	  }
-
-	if (count < 3 && i >= 0x80)
-	  return BYTE8_TO_CHAR (i);
-	return i;
+	char *temp = saved_doc_string;
+	ptrdiff_t temp_size = saved_doc_string_size;
+	file_offset temp_pos = saved_doc_string_position;
+	ptrdiff_t temp_len = saved_doc_string_length;
+
+	saved_doc_string = prev_saved_doc_string;
+	saved_doc_string_size = prev_saved_doc_string_size;
+	saved_doc_string_position = prev_saved_doc_string_position;
+	saved_doc_string_length = prev_saved_doc_string_length;
+
+	prev_saved_doc_string = temp;
+	prev_saved_doc_string_size = temp_size;
+	prev_saved_doc_string_position = temp_pos;
+	prev_saved_doc_string_length = temp_len;
       }
 
-    case 'U':
-      /* Post-Unicode-2.0: Up to eight hex chars.  */
-      unicode_hex_count = 8;
-      FALLTHROUGH;
-    case 'u':
+      if (saved_doc_string_size == 0)
+	{
+	  saved_doc_string = xmalloc (nskip + extra);
+	  saved_doc_string_size = nskip + extra;
+	}
+      if (nskip > saved_doc_string_size)
+	{
+	  saved_doc_string = xrealloc (saved_doc_string, nskip + extra);
+	  saved_doc_string_size = nskip + extra;
+	}
 
-      /* A Unicode escape.  We only permit them in strings and characters,
-	 not arbitrarily in the source code, as in some other languages.  */
-      {
-	unsigned int i = 0;
-	int count = 0;
-
-	while (++count <= unicode_hex_count)
-	  {
-	    c = READCHAR;
-	    /* `isdigit' and `isalpha' may be locale-specific, which we don't
-	       want.  */
-	    int digit = char_hexdigit (c);
-	    if (digit < 0)
-	      error ("Non-hex digit used for Unicode escape");
-	    i = (i << 4) + digit;
-	  }
-	if (i > 0x10FFFF)
-	  error ("Non-Unicode character: 0x%x", i);
-	return i;
-      }
+      FILE *instream = infile->stream;
+      saved_doc_string_position = (file_tell (instream)
+				   - infile->lookahead);
 
-    case 'N':
-      /* Named character.  */
-      {
-        c = READCHAR;
-        if (c != '{')
-          invalid_syntax ("Expected opening brace after \\N");
-        char name[UNICODE_CHARACTER_NAME_LENGTH_BOUND + 1];
-        bool whitespace = false;
-        ptrdiff_t length = 0;
-        while (true)
-          {
-            c = READCHAR;
-            if (c < 0)
-              end_of_file_error ();
-            if (c == '}')
-              break;
-            if (! (0 < c && c < 0x80))
-              {
-                AUTO_STRING (format,
-                             "Invalid character U+%04X in character name");
-                xsignal1 (Qinvalid_read_syntax,
-                          CALLN (Fformat, format, make_natnum (c)));
-              }
-            /* Treat multiple adjacent whitespace characters as a
-               single space character.  This makes it easier to use
-               character names in e.g. multi-line strings.  */
-            if (c_isspace (c))
-              {
-                if (whitespace)
-                  continue;
-                c = ' ';
-                whitespace = true;
-              }
-            else
-              whitespace = false;
-            name[length++] = c;
-            if (length >= sizeof name)
-              invalid_syntax ("Character name too long");
-          }
-        if (length == 0)
-          invalid_syntax ("Empty character name");
-	name[length] = '\0';
-
-	/* character_name_to_code can invoke read1, recursively.
-	   This is why read1's buffer is not static.  */
-	return character_name_to_code (name, length);
-      }
+      /* Copy that many bytes into saved_doc_string.  */
+      i = 0;
+      for (int n = min (nskip, infile->lookahead); 0 < n; n--)
+	saved_doc_string[i++]
+	  = c = infile->buf[--infile->lookahead];
+      block_input ();
+      for (; i < nskip && 0 <= c; i++)
+	saved_doc_string[i] = c = getc_unlocked (instream);
+      unblock_input ();
 
-    default:
-      return c;
+      saved_doc_string_length = i;
     }
+  else
+    /* Skip that many bytes.  */
+    skip_dyn_bytes (readcharfun, nskip);
 }
 
 /* Return the digit that CHARACTER stands for in the given BASE.
@@ -2503,1065 +2106,6 @@ digit_to_number (int character, int base)
   return digit < base ? digit : -1;
 }
 
-/* Read an integer in radix RADIX using READCHARFUN to read
-   characters.  RADIX must be in the interval [2..36]; if it isn't, a
-   read error is signaled .  Value is the integer read.  Signals an
-   error if encountering invalid read syntax or if RADIX is out of
-   range.  */
-
-static Lisp_Object
-read_integer (Lisp_Object readcharfun, EMACS_INT radix)
-{
-  /* Room for sign, leading 0, other digits, trailing null byte.
-     Also, room for invalid syntax diagnostic.  */
-  char buf[max (1 + 1 + UINTMAX_WIDTH + 1,
-		sizeof "integer, radix " + INT_STRLEN_BOUND (EMACS_INT))];
-
-  int valid = -1; /* 1 if valid, 0 if not, -1 if incomplete.  */
-
-  if (radix < 2 || radix > 36)
-    valid = 0;
-  else
-    {
-      char *p = buf;
-      int c, digit;
-
-      c = READCHAR;
-      if (c == '-' || c == '+')
-	{
-	  *p++ = c;
-	  c = READCHAR;
-	}
-
-      if (c == '0')
-	{
-	  *p++ = c;
-	  valid = 1;
-
-	  /* Ignore redundant leading zeros, so the buffer doesn't
-	     fill up with them.  */
-	  do
-	    c = READCHAR;
-	  while (c == '0');
-	}
-
-      while ((digit = digit_to_number (c, radix)) >= -1)
-	{
-	  if (digit == -1)
-	    valid = 0;
-	  if (valid < 0)
-	    valid = 1;
-
-	  if (p < buf + sizeof buf - 1)
-	    *p++ = c;
-	  else
-	    valid = 0;
-
-	  c = READCHAR;
-	}
-
-      UNREAD (c);
-      *p = '\0';
-    }
-
-  if (valid != 1)
-    {
-      sprintf (buf, "integer, radix %"pI"d", radix);
-      invalid_syntax (buf);
-    }
-
-  return string_to_number (buf, radix, 0);
-}
-
-
-/* If the next token is ')' or ']' or '.', we store that character
-   in *PCH and the return value is not interesting.  Else, we store
-   zero in *PCH and we read and return one lisp object.
-
-   FIRST_IN_LIST is true if this is the first element of a list.  */
-
-static Lisp_Object
-read1 (Lisp_Object readcharfun, int *pch, bool first_in_list)
-{
-  int c;
-  bool uninterned_symbol = false;
-  bool multibyte;
-  char stackbuf[MAX_ALLOCA];
-  current_thread->stack_top = stackbuf;
-
-  *pch = 0;
-
- retry:
-
-  c = READCHAR_REPORT_MULTIBYTE (&multibyte);
-  if (c < 0)
-    end_of_file_error ();
-
-  switch (c)
-    {
-    case '(':
-      return read_list (0, readcharfun);
-
-    case '[':
-      return read_vector (readcharfun, 0);
-
-    case ')':
-    case ']':
-      {
-	*pch = c;
-	return Qnil;
-      }
-
-    case '#':
-      c = READCHAR;
-      if (c == 's')
-	{
-	  c = READCHAR;
-	  if (c == '(')
-	    {
-	      /* Accept extended format for hash tables (extensible to
-		 other types), e.g.
-		 #s(hash-table size 2 test equal data (k1 v1 k2 v2))  */
-	      Lisp_Object tmp = read_list (0, readcharfun);
-	      Lisp_Object head = CAR_SAFE (tmp);
-	      Lisp_Object data = Qnil;
-	      Lisp_Object val = Qnil;
-	      /* The size is 2 * number of allowed keywords to
-		 make-hash-table.  */
-	      Lisp_Object params[12];
-	      Lisp_Object ht;
-	      Lisp_Object key = Qnil;
-	      int param_count = 0;
-
-	      if (!EQ (head, Qhash_table))
-		{
-		  ptrdiff_t size = XINT (Flength (tmp));
-		  Lisp_Object record = Fmake_record (CAR_SAFE (tmp),
-						     make_number (size - 1),
-						     Qnil);
-		  for (int i = 1; i < size; i++)
-		    {
-		      tmp = Fcdr (tmp);
-		      ASET (record, i, Fcar (tmp));
-		    }
-		  return record;
-		}
-
-	      tmp = CDR_SAFE (tmp);
-
-	      /* This is repetitive but fast and simple.  */
-	      params[param_count] = QCsize;
-	      params[param_count + 1] = Fplist_get (tmp, Qsize);
-	      if (!NILP (params[param_count + 1]))
-		param_count += 2;
-
-	      params[param_count] = QCtest;
-	      params[param_count + 1] = Fplist_get (tmp, Qtest);
-	      if (!NILP (params[param_count + 1]))
-		param_count += 2;
-
-	      params[param_count] = QCweakness;
-	      params[param_count + 1] = Fplist_get (tmp, Qweakness);
-	      if (!NILP (params[param_count + 1]))
-		param_count += 2;
-
-	      params[param_count] = QCrehash_size;
-	      params[param_count + 1] = Fplist_get (tmp, Qrehash_size);
-	      if (!NILP (params[param_count + 1]))
-		param_count += 2;
-
-	      params[param_count] = QCrehash_threshold;
-	      params[param_count + 1] = Fplist_get (tmp, Qrehash_threshold);
-	      if (!NILP (params[param_count + 1]))
-		param_count += 2;
-
-              params[param_count] = QCpurecopy;
-              params[param_count + 1] = Fplist_get (tmp, Qpurecopy);
-              if (!NILP (params[param_count + 1]))
-                param_count += 2;
-
-	      /* This is the hash table data.  */
-	      data = Fplist_get (tmp, Qdata);
-
-	      /* Now use params to make a new hash table and fill it.  */
-	      ht = Fmake_hash_table (param_count, params);
-
-	      while (CONSP (data))
-	      	{
-	      	  key = XCAR (data);
-	      	  data = XCDR (data);
-	      	  if (!CONSP (data))
-		    error ("Odd number of elements in hash table data");
-	      	  val = XCAR (data);
-	      	  data = XCDR (data);
-	      	  Fputhash (key, val, ht);
-	      	}
-
-	      return ht;
-	    }
-	  UNREAD (c);
-	  invalid_syntax ("#");
-	}
-      if (c == '^')
-	{
-	  c = READCHAR;
-	  if (c == '[')
-	    {
-	      Lisp_Object tmp;
-	      tmp = read_vector (readcharfun, 0);
-	      if (ASIZE (tmp) < CHAR_TABLE_STANDARD_SLOTS)
-		error ("Invalid size char-table");
-	      XSETPVECTYPE (XVECTOR (tmp), PVEC_CHAR_TABLE);
-	      return tmp;
-	    }
-	  else if (c == '^')
-	    {
-	      c = READCHAR;
-	      if (c == '[')
-		{
-		  /* Sub char-table can't be read as a regular
-		     vector because of a two C integer fields.  */
-		  Lisp_Object tbl, tmp = read_list (1, readcharfun);
-		  ptrdiff_t size = XINT (Flength (tmp));
-		  int i, depth, min_char;
-		  struct Lisp_Cons *cell;
-
-		  if (size == 0)
-		    error ("Zero-sized sub char-table");
-
-		  if (! RANGED_INTEGERP (1, XCAR (tmp), 3))
-		    error ("Invalid depth in sub char-table");
-		  depth = XINT (XCAR (tmp));
-		  if (chartab_size[depth] != size - 2)
-		    error ("Invalid size in sub char-table");
-		  cell = XCONS (tmp), tmp = XCDR (tmp), size--;
-		  free_cons (cell);
-
-		  if (! RANGED_INTEGERP (0, XCAR (tmp), MAX_CHAR))
-		    error ("Invalid minimum character in sub-char-table");
-		  min_char = XINT (XCAR (tmp));
-		  cell = XCONS (tmp), tmp = XCDR (tmp), size--;
-		  free_cons (cell);
-
-		  tbl = make_uninit_sub_char_table (depth, min_char);
-		  for (i = 0; i < size; i++)
-		    {
-		      XSUB_CHAR_TABLE (tbl)->contents[i] = XCAR (tmp);
-		      cell = XCONS (tmp), tmp = XCDR (tmp);
-		      free_cons (cell);
-		    }
-		  return tbl;
-		}
-	      invalid_syntax ("#^^");
-	    }
-	  invalid_syntax ("#^");
-	}
-      if (c == '&')
-	{
-	  Lisp_Object length;
-	  length = read1 (readcharfun, pch, first_in_list);
-	  c = READCHAR;
-	  if (c == '"')
-	    {
-	      Lisp_Object tmp, val;
-	      EMACS_INT size_in_chars = bool_vector_bytes (XFASTINT (length));
-	      unsigned char *data;
-
-	      UNREAD (c);
-	      tmp = read1 (readcharfun, pch, first_in_list);
-	      if (STRING_MULTIBYTE (tmp)
-		  || (size_in_chars != SCHARS (tmp)
-		      /* We used to print 1 char too many
-			 when the number of bits was a multiple of 8.
-			 Accept such input in case it came from an old
-			 version.  */
-		      && ! (XFASTINT (length)
-			    == (SCHARS (tmp) - 1) * BOOL_VECTOR_BITS_PER_CHAR)))
-		invalid_syntax ("#&...");
-
-	      val = make_uninit_bool_vector (XFASTINT (length));
-	      data = bool_vector_uchar_data (val);
-	      memcpy (data, SDATA (tmp), size_in_chars);
-	      /* Clear the extraneous bits in the last byte.  */
-	      if (XINT (length) != size_in_chars * BOOL_VECTOR_BITS_PER_CHAR)
-		data[size_in_chars - 1]
-		  &= (1 << (XINT (length) % BOOL_VECTOR_BITS_PER_CHAR)) - 1;
-	      return val;
-	    }
-	  invalid_syntax ("#&...");
-	}
-      if (c == '[')
-	{
-	  /* Accept compiled functions at read-time so that we don't have to
-	     build them using function calls.  */
-	  Lisp_Object tmp;
-	  struct Lisp_Vector *vec;
-	  tmp = read_vector (readcharfun, 1);
-	  vec = XVECTOR (tmp);
-	  if (vec->header.size == 0)
-	    invalid_syntax ("Empty byte-code object");
-	  make_byte_code (vec);
-	  return tmp;
-	}
-      if (c == '(')
-	{
-	  Lisp_Object tmp;
-	  int ch;
-
-	  /* Read the string itself.  */
-	  tmp = read1 (readcharfun, &ch, 0);
-	  if (ch != 0 || !STRINGP (tmp))
-	    invalid_syntax ("#");
-	  /* Read the intervals and their properties.  */
-	  while (1)
-	    {
-	      Lisp_Object beg, end, plist;
-
-	      beg = read1 (readcharfun, &ch, 0);
-	      end = plist = Qnil;
-	      if (ch == ')')
-		break;
-	      if (ch == 0)
-		end = read1 (readcharfun, &ch, 0);
-	      if (ch == 0)
-		plist = read1 (readcharfun, &ch, 0);
-	      if (ch)
-		invalid_syntax ("Invalid string property list");
-	      Fset_text_properties (beg, end, plist, tmp);
-	    }
-
-	  return tmp;
-	}
-
-      /* #@NUMBER is used to skip NUMBER following bytes.
-	 That's used in .elc files to skip over doc strings
-	 and function definitions.  */
-      if (c == '@')
-	{
-	  enum { extra = 100 };
-	  ptrdiff_t i, nskip = 0, digits = 0;
-
-	  /* Read a decimal integer.  */
-	  while ((c = READCHAR) >= 0
-		 && c >= '0' && c <= '9')
-	    {
-	      if ((STRING_BYTES_BOUND - extra) / 10 <= nskip)
-		string_overflow ();
-	      digits++;
-	      nskip *= 10;
-	      nskip += c - '0';
-	      if (digits == 2 && nskip == 0)
-		{ /* We've just seen #@00, which means "skip to end".  */
-		  skip_dyn_eof (readcharfun);
-		  return Qnil;
-		}
-	    }
-	  if (nskip > 0)
-	    /* We can't use UNREAD here, because in the code below we side-step
-               READCHAR.  Instead, assume the first char after #@NNN occupies
-               a single byte, which is the case normally since it's just
-               a space.  */
-	    nskip--;
-	  else
-	    UNREAD (c);
-
-	  if (load_force_doc_strings
-	      && (FROM_FILE_P (readcharfun)))
-	    {
-	      /* If we are supposed to force doc strings into core right now,
-		 record the last string that we skipped,
-		 and record where in the file it comes from.  */
-
-	      /* But first exchange saved_doc_string
-		 with prev_saved_doc_string, so we save two strings.  */
-	      {
-		char *temp = saved_doc_string;
-		ptrdiff_t temp_size = saved_doc_string_size;
-		file_offset temp_pos = saved_doc_string_position;
-		ptrdiff_t temp_len = saved_doc_string_length;
-
-		saved_doc_string = prev_saved_doc_string;
-		saved_doc_string_size = prev_saved_doc_string_size;
-		saved_doc_string_position = prev_saved_doc_string_position;
-		saved_doc_string_length = prev_saved_doc_string_length;
-
-		prev_saved_doc_string = temp;
-		prev_saved_doc_string_size = temp_size;
-		prev_saved_doc_string_position = temp_pos;
-		prev_saved_doc_string_length = temp_len;
-	      }
-
-	      if (saved_doc_string_size == 0)
-		{
-		  saved_doc_string = xmalloc (nskip + extra);
-		  saved_doc_string_size = nskip + extra;
-		}
-	      if (nskip > saved_doc_string_size)
-		{
-		  saved_doc_string = xrealloc (saved_doc_string, nskip + extra);
-		  saved_doc_string_size = nskip + extra;
-		}
-
-	      FILE *instream = infile->stream;
-	      saved_doc_string_position = (file_tell (instream)
-					   - infile->lookahead);
-
-	      /* Copy that many bytes into saved_doc_string.  */
-	      i = 0;
-	      for (int n = min (nskip, infile->lookahead); 0 < n; n--)
-		saved_doc_string[i++]
-		  = c = infile->buf[--infile->lookahead];
-	      block_input ();
-	      for (; i < nskip && 0 <= c; i++)
-		saved_doc_string[i] = c = getc_unlocked (instream);
-	      unblock_input ();
-
-	      saved_doc_string_length = i;
-	    }
-	  else
-	    /* Skip that many bytes.  */
-	    skip_dyn_bytes (readcharfun, nskip);
-
-	  goto retry;
-	}
-      if (c == '!')
-	{
-	  /* #! appears at the beginning of an executable file.
-	     Skip the first line.  */
-	  while (c != '\n' && c >= 0)
-	    c = READCHAR;
-	  goto retry;
-	}
-      if (c == '$')
-	return Vload_file_name;
-      if (c == '\'')
-	return list2 (Qfunction, read0 (readcharfun));
-      /* #:foo is the uninterned symbol named foo.  */
-      if (c == ':')
-	{
-	  uninterned_symbol = true;
-	  c = READCHAR;
-	  if (!(c > 040
-		&& c != NO_BREAK_SPACE
-		&& (c >= 0200
-		    || strchr ("\"';()[]#`,", c) == NULL)))
-	    {
-	      /* No symbol character follows, this is the empty
-		 symbol.  */
-	      UNREAD (c);
-	      return Fmake_symbol (empty_unibyte_string);
-	    }
-	  goto read_symbol;
-	}
-      /* ## is the empty symbol.  */
-      if (c == '#')
-	return Fintern (empty_unibyte_string, Qnil);
-      /* Reader forms that can reuse previously read objects.  */
-      if (c >= '0' && c <= '9')
-	{
-	  EMACS_INT n = 0;
-	  Lisp_Object tem;
-	  bool overflow = false;
-
-	  /* Read a non-negative integer.  */
-	  while (c >= '0' && c <= '9')
-	    {
-	      overflow |= INT_MULTIPLY_WRAPV (n, 10, &n);
-	      overflow |= INT_ADD_WRAPV (n, c - '0', &n);
-	      c = READCHAR;
-	    }
-
-	  if (!overflow && n <= MOST_POSITIVE_FIXNUM)
-	    {
-	      if (c == 'r' || c == 'R')
-		return read_integer (readcharfun, n);
-
-	      if (! NILP (Vread_circle))
-		{
-		  /* #n=object returns object, but associates it with
-                      n for #n#.  */
-		  if (c == '=')
-		    {
-		      /* Make a placeholder for #n# to use temporarily.  */
-		      /* Note: We used to use AUTO_CONS to allocate
-			 placeholder, but that is a bad idea, since it
-			 will place a stack-allocated cons cell into
-			 the list in read_objects_map, which is a
-			 staticpro'd global variable, and thus each of
-			 its elements is marked during each GC.  A
-			 stack-allocated object will become garbled
-			 when its stack slot goes out of scope, and
-			 some other function reuses it for entirely
-			 different purposes, which will cause crashes
-			 in GC.  */
-		      Lisp_Object placeholder = Fcons (Qnil, Qnil);
-		      struct Lisp_Hash_Table *h
-			= XHASH_TABLE (read_objects_map);
-		      EMACS_UINT hash;
-		      Lisp_Object number = make_number (n);
-
-		      ptrdiff_t i = hash_lookup (h, number, &hash);
-		      if (i >= 0)
-			/* Not normal, but input could be malformed.  */
-			set_hash_value_slot (h, i, placeholder);
-		      else
-			hash_put (h, number, placeholder, hash);
-
-		      /* Read the object itself.  */
-		      tem = read0 (readcharfun);
-
-		      /* If it can be recursive, remember it for
-			 future substitutions.  */
-		      if (! SYMBOLP (tem)
-			  && ! NUMBERP (tem)
-			  && ! (STRINGP (tem) && !string_intervals (tem)))
-			{
-			  struct Lisp_Hash_Table *h2
-			    = XHASH_TABLE (read_objects_completed);
-			  i = hash_lookup (h2, tem, &hash);
-			  eassert (i < 0);
-			  hash_put (h2, tem, Qnil, hash);
-			}
-
-		      /* Now put it everywhere the placeholder was...  */
-                      if (CONSP (tem))
-                        {
-                          Fsetcar (placeholder, XCAR (tem));
-                          Fsetcdr (placeholder, XCDR (tem));
-                          return placeholder;
-                        }
-                      else
-                        {
-		          Flread__substitute_object_in_subtree
-			    (tem, placeholder, read_objects_completed);
-
-		          /* ...and #n# will use the real value from now on.  */
-			  i = hash_lookup (h, number, &hash);
-			  eassert (i >= 0);
-			  set_hash_value_slot (h, i, tem);
-
-		          return tem;
-                        }
-		    }
-
-		  /* #n# returns a previously read object.  */
-		  if (c == '#')
-		    {
-		      struct Lisp_Hash_Table *h
-			= XHASH_TABLE (read_objects_map);
-		      ptrdiff_t i = hash_lookup (h, make_number (n), NULL);
-		      if (i >= 0)
-			return HASH_VALUE (h, i);
-		    }
-		}
-	    }
-	  /* Fall through to error message.  */
-	}
-      else if (c == 'x' || c == 'X')
-	return read_integer (readcharfun, 16);
-      else if (c == 'o' || c == 'O')
-	return read_integer (readcharfun, 8);
-      else if (c == 'b' || c == 'B')
-	return read_integer (readcharfun, 2);
-
-      UNREAD (c);
-      invalid_syntax ("#");
-
-    case ';':
-      while ((c = READCHAR) >= 0 && c != '\n');
-      goto retry;
-
-    case '\'':
-      return list2 (Qquote, read0 (readcharfun));
-
-    case '`':
-      {
-	int next_char = READCHAR;
-	UNREAD (next_char);
-	/* Transition from old-style to new-style:
-	   If we see "(`" it used to mean old-style, which usually works
-	   fine because ` should almost never appear in such a position
-	   for new-style.  But occasionally we need "(`" to mean new
-	   style, so we try to distinguish the two by the fact that we
-	   can either write "( `foo" or "(` foo", where the first
-	   intends to use new-style whereas the second intends to use
-	   old-style.  For Emacs-25, we should completely remove this
-	   first_in_list exception (old-style can still be obtained via
-	   "(\`" anyway).  */
-	if (!new_backquote_flag && first_in_list && next_char == ' ')
-	  {
-	    Vlread_old_style_backquotes = Qt;
-	    goto default_label;
-	  }
-	else
-	  {
-	    Lisp_Object value;
-	    bool saved_new_backquote_flag = new_backquote_flag;
-
-	    new_backquote_flag = 1;
-	    value = read0 (readcharfun);
-	    new_backquote_flag = saved_new_backquote_flag;
-
-	    return list2 (Qbackquote, value);
-	  }
-      }
-    case ',':
-      {
-	int next_char = READCHAR;
-	UNREAD (next_char);
-	/* Transition from old-style to new-style:
-           It used to be impossible to have a new-style , other than within
-	   a new-style `.  This is sufficient when ` and , are used in the
-	   normal way, but ` and , can also appear in args to macros that
-	   will not interpret them in the usual way, in which case , may be
-	   used without any ` anywhere near.
-	   So we now use the same heuristic as for backquote: old-style
-	   unquotes are only recognized when first on a list, and when
-	   followed by a space.
-	   Because it's more difficult to peek 2 chars ahead, a new-style
-	   ,@ can still not be used outside of a `, unless it's in the middle
-	   of a list.  */
-	if (new_backquote_flag
-	    || !first_in_list
-	    || (next_char != ' ' && next_char != '@'))
-	  {
-	    Lisp_Object comma_type = Qnil;
-	    Lisp_Object value;
-	    int ch = READCHAR;
-
-	    if (ch == '@')
-	      comma_type = Qcomma_at;
-	    else if (ch == '.')
-	      comma_type = Qcomma_dot;
-	    else
-	      {
-		if (ch >= 0) UNREAD (ch);
-		comma_type = Qcomma;
-	      }
-
-	    value = read0 (readcharfun);
-	    return list2 (comma_type, value);
-	  }
-	else
-	  {
-	    Vlread_old_style_backquotes = Qt;
-	    goto default_label;
-	  }
-      }
-    case '?':
-      {
-	int modifiers;
-	int next_char;
-	bool ok;
-
-	c = READCHAR;
-	if (c < 0)
-	  end_of_file_error ();
-
-	/* Accept `single space' syntax like (list ? x) where the
-	   whitespace character is SPC or TAB.
-	   Other literal whitespace like NL, CR, and FF are not accepted,
-	   as there are well-established escape sequences for these.  */
-	if (c == ' ' || c == '\t')
-	  return make_number (c);
-
-	if (c == '(' || c == ')' || c == '[' || c == ']'
-            || c == '"' || c == ';')
-	  {
-            CHECK_LIST (Vlread_unescaped_character_literals);
-            Lisp_Object char_obj = make_natnum (c);
-            if (NILP (Fmemq (char_obj, Vlread_unescaped_character_literals)))
-              Vlread_unescaped_character_literals =
-                Fcons (char_obj, Vlread_unescaped_character_literals);
-	  }
-
-	if (c == '\\')
-	  c = read_escape (readcharfun, 0);
-	modifiers = c & CHAR_MODIFIER_MASK;
-	c &= ~CHAR_MODIFIER_MASK;
-	if (CHAR_BYTE8_P (c))
-	  c = CHAR_TO_BYTE8 (c);
-	c |= modifiers;
-
-	next_char = READCHAR;
-	ok = (next_char <= 040
-	      || (next_char < 0200
-		  && strchr ("\"';()[]#?`,.", next_char) != NULL));
-	UNREAD (next_char);
-	if (ok)
-	  return make_number (c);
-
-	invalid_syntax ("?");
-      }
-
-    case '"':
-      {
-	ptrdiff_t count = SPECPDL_INDEX ();
-	char *read_buffer = stackbuf;
-	ptrdiff_t read_buffer_size = sizeof stackbuf;
-	char *heapbuf = NULL;
-	char *p = read_buffer;
-	char *end = read_buffer + read_buffer_size;
-	int ch;
-	/* True if we saw an escape sequence specifying
-	   a multibyte character.  */
-	bool force_multibyte = false;
-	/* True if we saw an escape sequence specifying
-	   a single-byte character.  */
-	bool force_singlebyte = false;
-	bool cancel = false;
-	ptrdiff_t nchars = 0;
-
-	while ((ch = READCHAR) >= 0
-	       && ch != '\"')
-	  {
-	    if (end - p < MAX_MULTIBYTE_LENGTH)
-	      {
-		ptrdiff_t offset = p - read_buffer;
-		read_buffer = grow_read_buffer (read_buffer, offset,
-						&heapbuf, &read_buffer_size,
-						count);
-		p = read_buffer + offset;
-		end = read_buffer + read_buffer_size;
-	      }
-
-	    if (ch == '\\')
-	      {
-		int modifiers;
-
-		ch = read_escape (readcharfun, 1);
-
-		/* CH is -1 if \ newline or \ space has just been seen.  */
-		if (ch == -1)
-		  {
-		    if (p == read_buffer)
-		      cancel = true;
-		    continue;
-		  }
-
-		modifiers = ch & CHAR_MODIFIER_MASK;
-		ch = ch & ~CHAR_MODIFIER_MASK;
-
-		if (CHAR_BYTE8_P (ch))
-		  force_singlebyte = true;
-		else if (! ASCII_CHAR_P (ch))
-		  force_multibyte = true;
-		else		/* I.e. ASCII_CHAR_P (ch).  */
-		  {
-		    /* Allow `\C- ' and `\C-?'.  */
-		    if (modifiers == CHAR_CTL)
-		      {
-			if (ch == ' ')
-			  ch = 0, modifiers = 0;
-			else if (ch == '?')
-			  ch = 127, modifiers = 0;
-		      }
-		    if (modifiers & CHAR_SHIFT)
-		      {
-			/* Shift modifier is valid only with [A-Za-z].  */
-			if (ch >= 'A' && ch <= 'Z')
-			  modifiers &= ~CHAR_SHIFT;
-			else if (ch >= 'a' && ch <= 'z')
-			  ch -= ('a' - 'A'), modifiers &= ~CHAR_SHIFT;
-		      }
-
-		    if (modifiers & CHAR_META)
-		      {
-			/* Move the meta bit to the right place for a
-			   string.  */
-			modifiers &= ~CHAR_META;
-			ch = BYTE8_TO_CHAR (ch | 0x80);
-			force_singlebyte = true;
-		      }
-		  }
-
-		/* Any modifiers remaining are invalid.  */
-		if (modifiers)
-		  error ("Invalid modifier in string");
-		p += CHAR_STRING (ch, (unsigned char *) p);
-	      }
-	    else
-	      {
-		p += CHAR_STRING (ch, (unsigned char *) p);
-		if (CHAR_BYTE8_P (ch))
-		  force_singlebyte = true;
-		else if (! ASCII_CHAR_P (ch))
-		  force_multibyte = true;
-	      }
-	    nchars++;
-	  }
-
-	if (ch < 0)
-	  end_of_file_error ();
-
-	/* If purifying, and string starts with \ newline,
-	   return zero instead.  This is for doc strings
-	   that we are really going to find in etc/DOC.nn.nn.  */
-	if (!NILP (Vpurify_flag) && NILP (Vdoc_file_name) && cancel)
-	  return unbind_to (count, make_number (0));
-
-	if (! force_multibyte && force_singlebyte)
-	  {
-	    /* READ_BUFFER contains raw 8-bit bytes and no multibyte
-	       forms.  Convert it to unibyte.  */
-	    nchars = str_as_unibyte ((unsigned char *) read_buffer,
-				     p - read_buffer);
-	    p = read_buffer + nchars;
-	  }
-
-	Lisp_Object result
-	  = make_specified_string (read_buffer, nchars, p - read_buffer,
-				   (force_multibyte
-				    || (p - read_buffer != nchars)));
-	return unbind_to (count, result);
-      }
-
-    case '.':
-      {
-	int next_char = READCHAR;
-	UNREAD (next_char);
-
-	if (next_char <= 040
-	    || (next_char < 0200
-		&& strchr ("\"';([#?`,", next_char) != NULL))
-	  {
-	    *pch = c;
-	    return Qnil;
-	  }
-      }
-      /* The atom-reading loop below will now loop at least once,
-	 assuring that we will not try to UNREAD two characters in a
-	 row.  */
-      FALLTHROUGH;
-    default:
-    default_label:
-      if (c <= 040) goto retry;
-      if (c == NO_BREAK_SPACE)
-	goto retry;
-
-    read_symbol:
-      {
-	ptrdiff_t count = SPECPDL_INDEX ();
-	char *read_buffer = stackbuf;
-	ptrdiff_t read_buffer_size = sizeof stackbuf;
-	char *heapbuf = NULL;
-	char *p = read_buffer;
-	char *end = read_buffer + read_buffer_size;
-	bool quoted = false;
-	EMACS_INT start_position = readchar_count - 1;
-
-	do
-	  {
-	    if (end - p < MAX_MULTIBYTE_LENGTH + 1)
-	      {
-		ptrdiff_t offset = p - read_buffer;
-		read_buffer = grow_read_buffer (read_buffer, offset,
-						&heapbuf, &read_buffer_size,
-						count);
-		p = read_buffer + offset;
-		end = read_buffer + read_buffer_size;
-	      }
-
-	    if (c == '\\')
-	      {
-		c = READCHAR;
-		if (c == -1)
-		  end_of_file_error ();
-		quoted = true;
-	      }
-
-	    if (multibyte)
-	      p += CHAR_STRING (c, (unsigned char *) p);
-	    else
-	      *p++ = c;
-	    c = READCHAR;
-	  }
-	while (c > 040
-	       && c != NO_BREAK_SPACE
-	       && (c >= 0200
-		   || strchr ("\"';()[]#`,", c) == NULL));
-
-	*p = 0;
-	UNREAD (c);
-
-	if (!quoted && !uninterned_symbol)
-	  {
-	    Lisp_Object result = string_to_number (read_buffer, 10, 0);
-	    if (! NILP (result))
-	      return unbind_to (count, result);
-	  }
-        if (!quoted && multibyte)
-          {
-            int ch = STRING_CHAR ((unsigned char *) read_buffer);
-            switch (ch)
-              {
-              case 0x2018: /* LEFT SINGLE QUOTATION MARK */
-              case 0x2019: /* RIGHT SINGLE QUOTATION MARK */
-              case 0x201B: /* SINGLE HIGH-REVERSED-9 QUOTATION MARK */
-              case 0x201C: /* LEFT DOUBLE QUOTATION MARK */
-              case 0x201D: /* RIGHT DOUBLE QUOTATION MARK */
-              case 0x201F: /* DOUBLE HIGH-REVERSED-9 QUOTATION MARK */
-              case 0x301E: /* DOUBLE PRIME QUOTATION MARK */
-              case 0xFF02: /* FULLWIDTH QUOTATION MARK */
-              case 0xFF07: /* FULLWIDTH APOSTROPHE */
-                xsignal2 (Qinvalid_read_syntax, build_string ("strange quote"),
-                          CALLN (Fstring, make_number (ch)));
-              }
-          }
-	{
-	  Lisp_Object result;
-	  ptrdiff_t nbytes = p - read_buffer;
-	  ptrdiff_t nchars
-	    = (multibyte
-	       ? multibyte_chars_in_text ((unsigned char *) read_buffer,
-					  nbytes)
-	       : nbytes);
-
-	  if (uninterned_symbol)
-	    {
-	      Lisp_Object name
-		= ((! NILP (Vpurify_flag)
-		    ? make_pure_string : make_specified_string)
-		   (read_buffer, nchars, nbytes, multibyte));
-	      result = Fmake_symbol (name);
-	    }
-	  else
-	    {
-	      /* Don't create the string object for the name unless
-		 we're going to retain it in a new symbol.
-
-		 Like intern_1 but supports multibyte names.  */
-	      Lisp_Object obarray = check_obarray (Vobarray);
-	      Lisp_Object tem = oblookup (obarray, read_buffer,
-					  nchars, nbytes);
-
-	      if (SYMBOLP (tem))
-		result = tem;
-	      else
-		{
-		  Lisp_Object name
-		    = make_specified_string (read_buffer, nchars, nbytes,
-					     multibyte);
-		  result = intern_driver (name, obarray, tem);
-		}
-	    }
-
-	  if (EQ (Vread_with_symbol_positions, Qt)
-	      || EQ (Vread_with_symbol_positions, readcharfun))
-	    Vread_symbol_positions_list
-	      = Fcons (Fcons (result, make_number (start_position)),
-		       Vread_symbol_positions_list);
-	  return unbind_to (count, result);
-	}
-      }
-    }
-}
-
-DEFUN ("lread--substitute-object-in-subtree",
-       Flread__substitute_object_in_subtree,
-       Slread__substitute_object_in_subtree, 3, 3, 0,
-       doc: /* In OBJECT, replace every occurrence of PLACEHOLDER with OBJECT.
-COMPLETED is a hash table of objects that might be circular, or is t
-if any object might be circular.  */)
-  (Lisp_Object object, Lisp_Object placeholder, Lisp_Object completed)
-{
-  struct subst subst = { object, placeholder, completed, Qnil };
-  Lisp_Object check_object = substitute_object_recurse (&subst, object);
-
-  /* The returned object here is expected to always eq the
-     original.  */
-  if (!EQ (check_object, object))
-    error ("Unexpected mutation error in reader");
-  return Qnil;
-}
-
-static Lisp_Object
-substitute_object_recurse (struct subst *subst, Lisp_Object subtree)
-{
-  /* If we find the placeholder, return the target object.  */
-  if (EQ (subst->placeholder, subtree))
-    return subst->object;
-
-  /* For common object types that can't contain other objects, don't
-     bother looking them up; we're done.  */
-  if (SYMBOLP (subtree)
-      || (STRINGP (subtree) && !string_intervals (subtree))
-      || NUMBERP (subtree))
-    return subtree;
-
-  /* If we've been to this node before, don't explore it again.  */
-  if (!EQ (Qnil, Fmemq (subtree, subst->seen)))
-    return subtree;
-
-  /* If this node can be the entry point to a cycle, remember that
-     we've seen it.  It can only be such an entry point if it was made
-     by #n=, which means that we can find it as a value in
-     COMPLETED.  */
-  if (EQ (subst->completed, Qt)
-      || hash_lookup (XHASH_TABLE (subst->completed), subtree, NULL) >= 0)
-    subst->seen = Fcons (subtree, subst->seen);
-
-  /* Recurse according to subtree's type.
-     Every branch must return a Lisp_Object.  */
-  switch (XTYPE (subtree))
-    {
-    case Lisp_Vectorlike:
-      {
-	ptrdiff_t i = 0, length = 0;
-	if (BOOL_VECTOR_P (subtree))
-	  return subtree;		/* No sub-objects anyway.  */
-	else if (CHAR_TABLE_P (subtree) || SUB_CHAR_TABLE_P (subtree)
-		 || COMPILEDP (subtree) || HASH_TABLE_P (subtree)
-		 || RECORDP (subtree))
-	  length = PVSIZE (subtree);
-	else if (VECTORP (subtree))
-	  length = ASIZE (subtree);
-	else
-	  /* An unknown pseudovector may contain non-Lisp fields, so we
-	     can't just blindly traverse all its fields.  We used to call
-	     `Flength' which signaled `sequencep', so I just preserved this
-	     behavior.  */
-	  wrong_type_argument (Qsequencep, subtree);
-
-	if (SUB_CHAR_TABLE_P (subtree))
-	  i = 2;
-	for ( ; i < length; i++)
-	  ASET (subtree, i,
-		substitute_object_recurse (subst, AREF (subtree, i)));
-	return subtree;
-      }
-
-    case Lisp_Cons:
-      XSETCAR (subtree, substitute_object_recurse (subst, XCAR (subtree)));
-      XSETCDR (subtree, substitute_object_recurse (subst, XCDR (subtree)));
-      return subtree;
-
-    case Lisp_String:
-      {
-	/* Check for text properties in each interval.
-	   substitute_in_interval contains part of the logic.  */
-
-	INTERVAL root_interval = string_intervals (subtree);
-	traverse_intervals_noorder (root_interval,
-				    substitute_in_interval, subst);
-	return subtree;
-      }
-
-      /* Other types don't recurse any further.  */
-    default:
-      return subtree;
-    }
-}
-
-/*  Helper function for substitute_object_recurse.  */
-static void
-substitute_in_interval (INTERVAL interval, void *arg)
-{
-  set_interval_plist (interval,
-		      substitute_object_recurse (arg, interval->plist));
-}
-
-
 /* Convert STRING to a number, assuming base BASE.  Return a fixnum if
    STRING has integer syntax and fits in a fixnum, else return the
    nearest float if STRING has either floating point or integer syntax
@@ -3693,235 +2237,69 @@ string_to_number (char const *string, int base, bool ignore_trailing)
 }
 
 
-static Lisp_Object
-read_vector (Lisp_Object readcharfun, bool bytecodeflag)
-{
-  ptrdiff_t i, size;
-  Lisp_Object *ptr;
-  Lisp_Object tem, item, vector;
-  struct Lisp_Cons *otem;
-  Lisp_Object len;
-
-  tem = read_list (1, readcharfun);
-  len = Flength (tem);
-  vector = Fmake_vector (len, Qnil);
-
-  size = ASIZE (vector);
-  ptr = XVECTOR (vector)->contents;
-  for (i = 0; i < size; i++)
-    {
-      item = Fcar (tem);
-      /* If `load-force-doc-strings' is t when reading a lazily-loaded
-	 bytecode object, the docstring containing the bytecode and
-	 constants values must be treated as unibyte and passed to
-	 Fread, to get the actual bytecode string and constants vector.  */
-      if (bytecodeflag && load_force_doc_strings)
-	{
-	  if (i == COMPILED_BYTECODE)
-	    {
-	      if (!STRINGP (item))
-		error ("Invalid byte code");
-
-	      /* Delay handling the bytecode slot until we know whether
-		 it is lazily-loaded (we can tell by whether the
-		 constants slot is nil).  */
-	      ASET (vector, COMPILED_CONSTANTS, item);
-	      item = Qnil;
-	    }
-	  else if (i == COMPILED_CONSTANTS)
-	    {
-	      Lisp_Object bytestr = ptr[COMPILED_CONSTANTS];
-
-	      if (NILP (item))
-		{
-		  /* Coerce string to unibyte (like string-as-unibyte,
-		     but without generating extra garbage and
-		     guaranteeing no change in the contents).  */
-		  STRING_SET_CHARS (bytestr, SBYTES (bytestr));
-		  STRING_SET_UNIBYTE (bytestr);
-
-		  item = Fread (Fcons (bytestr, readcharfun));
-		  if (!CONSP (item))
-		    error ("Invalid byte code");
-
-		  otem = XCONS (item);
-		  bytestr = XCAR (item);
-		  item = XCDR (item);
-		  free_cons (otem);
-		}
-
-	      /* Now handle the bytecode slot.  */
-	      ASET (vector, COMPILED_BYTECODE, bytestr);
-	    }
-	  else if (i == COMPILED_DOC_STRING
-		   && STRINGP (item)
-		   && ! STRING_MULTIBYTE (item))
-	    {
-	      if (EQ (readcharfun, Qget_emacs_mule_file_char))
-		item = Fdecode_coding_string (item, Qemacs_mule, Qnil, Qnil);
-	      else
-		item = Fstring_as_multibyte (item);
-	    }
-	}
-      ASET (vector, i, item);
-      otem = XCONS (tem);
-      tem = Fcdr (tem);
-      free_cons (otem);
-    }
-  return vector;
-}
+/* Return the string that VAL, a (FILE . POSITION) doc reference read
+   while `load-force-doc-strings' is non-nil, refers to.  If it's in
+   one of the strings recently skipped with #@, get it from there.
 
-/* FLAG means check for ']' to terminate rather than ')' and '.'.  */
+   Here, we don't know if the string is a bytecode string or a doc
+   string.  As a bytecode string must be unibyte, we always return a
+   unibyte string.  If it is actually a doc string, caller must make
+   it multibyte.  */
 
-static Lisp_Object
-read_list (bool flag, Lisp_Object readcharfun)
+Lisp_Object
+get_lazy_string (Lisp_Object val)
 {
-  Lisp_Object val, tail;
-  Lisp_Object elt, tem;
-  /* 0 is the normal case.
-     1 means this list is a doc reference; replace it with the number 0.
-     2 means this list is a doc reference; replace it with the doc string.  */
-  int doc_reference = 0;
+  char *saved = NULL;
+  file_offset saved_position;
 
-  /* Initialize this to 1 if we are reading a list.  */
-  bool first_in_list = flag <= 0;
-
-  val = Qnil;
-  tail = Qnil;
-
-  while (1)
+  /* Position is negative for user variables.  */
+  EMACS_INT pos = eabs (XINT (XCDR (val)));
+  if (pos >= saved_doc_string_position
+      && pos < (saved_doc_string_position
+		+ saved_doc_string_length))
     {
-      int ch;
-      elt = read1 (readcharfun, &ch, first_in_list);
-
-      first_in_list = 0;
+      saved = saved_doc_string;
+      saved_position = saved_doc_string_position;
+    }
+  /* Look in prev_saved_doc_string the same way.  */
+  else if (pos >= prev_saved_doc_string_position
+	   && pos < (prev_saved_doc_string_position
+		     + prev_saved_doc_string_length))
+    {
+      saved = prev_saved_doc_string;
+      saved_position = prev_saved_doc_string_position;
+    }
+  if (saved)
+    {
+      ptrdiff_t start = pos - saved_position;
+      ptrdiff_t from, to;
 
-      /* While building, if the list starts with #$, treat it specially.  */
-      if (EQ (elt, Vload_file_name)
-	  && ! NILP (elt)
-	  && !NILP (Vpurify_flag))
+      /* Process quoting with ^A,
+	 and find the end of the string,
+	 which is marked with ^_ (037).  */
+      for (from = start, to = start;
+	   saved[from] != 037;)
 	{
-	  if (NILP (Vdoc_file_name))
-	    /* We have not yet called Snarf-documentation, so assume
-	       this file is described in the DOC file
-	       and Snarf-documentation will fill in the right value later.
-	       For now, replace the whole list with 0.  */
-	    doc_reference = 1;
-	  else
-	    /* We have already called Snarf-documentation, so make a relative
-	       file name for this file, so it can be found properly
-	       in the installed Lisp directory.
-	       We don't use Fexpand_file_name because that would make
-	       the directory absolute now.  */
+	  int c = saved[from++];
+	  if (c == 1)
 	    {
-	      AUTO_STRING (dot_dot_lisp, "../lisp/");
-	      elt = concat2 (dot_dot_lisp, Ffile_name_nondirectory (elt));
+	      c = saved[from++];
+	      saved[to++] = (c == 1 ? c
+			     : c == '0' ? 0
+			     : c == '_' ? 037
+			     : c);
 	    }
+	  else
+	    saved[to++] = c;
 	}
-      else if (EQ (elt, Vload_file_name)
-	       && ! NILP (elt)
-	       && load_force_doc_strings)
-	doc_reference = 2;
-
-      if (ch)
-	{
-	  if (flag > 0)
-	    {
-	      if (ch == ']')
-		return val;
-	      invalid_syntax (") or . in a vector");
-	    }
-	  if (ch == ')')
-	    return val;
-	  if (ch == '.')
-	    {
-	      if (!NILP (tail))
-		XSETCDR (tail, read0 (readcharfun));
-	      else
-		val = read0 (readcharfun);
-	      read1 (readcharfun, &ch, 0);
 
-	      if (ch == ')')
-		{
-		  if (doc_reference == 1)
-		    return make_number (0);
-		  if (doc_reference == 2 && INTEGERP (XCDR (val)))
-		    {
-		      char *saved = NULL;
-		      file_offset saved_position;
-		      /* Get a doc string from the file we are loading.
-			 If it's in saved_doc_string, get it from there.
-
-			 Here, we don't know if the string is a
-			 bytecode string or a doc string.  As a
-			 bytecode string must be unibyte, we always
-			 return a unibyte string.  If it is actually a
-			 doc string, caller must make it
-			 multibyte.  */
-
-		      /* Position is negative for user variables.  */
-		      EMACS_INT pos = eabs (XINT (XCDR (val)));
-		      if (pos >= saved_doc_string_position
-			  && pos < (saved_doc_string_position
-				    + saved_doc_string_length))
-			{
-			  saved = saved_doc_string;
-			  saved_position = saved_doc_string_position;
-			}
-		      /* Look in prev_saved_doc_string the same way.  */
-		      else if (pos >= prev_saved_doc_string_position
-			       && pos < (prev_saved_doc_string_position
-					 + prev_saved_doc_string_length))
-			{
-			  saved = prev_saved_doc_string;
-			  saved_position = prev_saved_doc_string_position;
-			}
-		      if (saved)
-			{
-			  ptrdiff_t start = pos - saved_position;
-			  ptrdiff_t from, to;
-
-			  /* Process quoting with ^A,
-			     and find the end of the string,
-			     which is marked with ^_ (037).  */
-			  for (from = start, to = start;
-			       saved[from] != 037;)
-			    {
-			      int c = saved[from++];
-			      if (c == 1)
-				{
-				  c = saved[from++];
-				  saved[to++] = (c == 1 ? c
-						 : c == '0' ? 0
-						 : c == '_' ? 037
-						 : c);
-				}
-			      else
-				saved[to++] = c;
-			    }
-
-			  return make_unibyte_string (saved + start,
-						      to - start);
-			}
-		      else
-			return get_doc_string (val, 1, 0);
-		    }
-
-		  return val;
-		}
-	      invalid_syntax (". in wrong context");
-	    }
-	  invalid_syntax ("] in a list");
-	}
-      tem = list1 (elt);
-      if (!NILP (tail))
-	XSETCDR (tail, tem);
-      else
-	val = tem;
-      tail = tem;
+      return make_unibyte_string (saved + start,
+				  to - start);
     }
+  else
+    return get_doc_string (val, 1, 0);
 }
-
+
 Lisp_Object initial_obarray;
 
 /* `oblookup' stores the bucket number here, for the sake of Funintern.  */
@@ -4439,7 +2817,6 @@ void
 syms_of_lread (void)
 {
   defsubr (&Sread_from_string);
-  defsubr (&Slread__substitute_object_in_subtree);
   defsubr (&Sunintern);
   defsubr (&Sget_load_suffixes);
   defsubr (&Sload);
@@ -4743,11 +3120,6 @@ that are loaded before your customizations are read!  */);
   DEFSYM (Qdir_ok, "dir-ok");
   DEFSYM (Qdo_after_load_evaluation, "do-after-load-evaluation");
 
-  staticpro (&read_objects_map);
-  read_objects_map = Qnil;
-  staticpro (&read_objects_completed);
-  read_objects_completed = Qnil;
-
   Vloads_in_progress = Qnil;
   staticpro (&Vloads_in_progress);
 
@@ -2390,9 +2390,9 @@ since only regular expressions have distinguished subexpressions.  */)
 
       /* Do case substitution in NEWTEXT if desired.  */
       if (case_action == all_caps)
-	newtext = Fupcase (newtext);
+	newtext = Fupcase (newtext, Qnil);
       else if (case_action == cap_initial)
-	newtext = Fupcase_initials (newtext);
+	newtext = Fupcase_initials (newtext, Qnil);
 
       return concat3 (before, newtext, after);
     }
@@ -2553,10 +2553,10 @@ since only regular expressions have distinguished subexpressions.  */)
   if (case_action == all_caps)
     Fupcase_region (make_number (search_regs.start[sub]),
 		    make_number (newpoint),
-		    Qnil);
+		    Qnil, Qnil);
   else if (case_action == cap_initial)
     Fupcase_initials_region (make_number (search_regs.start[sub]),
-			     make_number (newpoint));
+			     make_number (newpoint), Qnil);
 
   if (search_regs.start[sub] != sub_start
       || search_regs.end[sub] != sub_end
@@ -792,6 +792,7 @@ If NAME is given, it must be a string; it names the new thread.  */)
   new_thread->error_symbol = Qnil;
   new_thread->error_data = Qnil;
   new_thread->event_object = Qnil;
+  new_thread->m_byte_code_stack = Qnil;
 
   new_thread->m_specpdl_size = 50;
   new_thread->m_specpdl = xmalloc ((1 + new_thread->m_specpdl_size)
@@ -963,6 +964,7 @@ init_main_thread (void)
   main_thread.error_symbol = Qnil;
   main_thread.error_data = Qnil;
   main_thread.event_object = Qnil;
+  main_thread.m_byte_code_stack = Qnil;
 }
 
 bool
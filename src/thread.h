@@ -60,6 +60,11 @@ struct thread_state
      waiting on.  */
   Lisp_Object event_object;
 
+  /* The stack of the byte-code interpreter, a vector that is replaced
+     by a bigger one as needed.  */
+  Lisp_Object m_byte_code_stack;
+#define byte_code_stack (current_thread->m_byte_code_stack)
+
   /* m_stack_bottom must be the first non-Lisp field.  */
   /* An address near the bottom of the stack.
      Tells GC how to save a copy of the stack.  */
@@ -105,6 +110,27 @@ struct thread_state
   EMACS_INT m_lisp_eval_depth;
 #define lisp_eval_depth (current_thread->m_lisp_eval_depth)
 
+  /* Number of slots of m_byte_code_stack used by active calls.  */
+  ptrdiff_t m_byte_code_stack_top;
+#define byte_code_stack_top (current_thread->m_byte_code_stack_top)
+
+  /* The limits of `eval-with-limits' in effect, managed by eval.rs, or
+     NULL if there are none.  */
+  void *m_eval_limits;
+
+  /* The number of evaluation steps taken while limits were in effect.  */
+  EMACS_INT m_eval_steps;
+
+  /* Once a limit has been reached, the limits of the `eval-with-limits'
+     being thrown to, else NULL.  No limit is checked until that
+     throw is done.  */
+  void *m_eval_limit_exceeded;
+
+  /* True while limits are in effect and have not been signaled, so that
+     maybe_quit must check them.  */
+  bool m_eval_limits_active;
+#define eval_limits_active (current_thread->m_eval_limits_active)
+
   /* This points to the current buffer.  */
   struct buffer *m_current_buffer;
 #define current_buffer (current_thread->m_current_buffer)
@@ -4969,7 +4969,7 @@ be found.  Value is ALIST.  */)
       entry = Fcopy_sequence (entry);
       XSETCAR (tail, entry);
       for (tail2 = entry; CONSP (tail2); tail2 = XCDR (tail2))
-	XSETCAR (tail2, Fdowncase (XCAR (tail2)));
+	XSETCAR (tail2, Fdowncase (XCAR (tail2), Qnil));
     }
   Vface_alternative_font_registry_alist = alist;
   free_all_realized_faces (Qnil);
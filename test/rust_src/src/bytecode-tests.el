@@ -0,0 +1,246 @@
+;;; bytecode-tests.el --- Tests for bytecode.rs  -*- lexical-binding: t; -*-
+
+;;; Commentary:
+
+;; Each form in `bytecode-tests--forms' is evaluated by the
+;; interpreter and run as a compiled function, and the results are
+;; compared.
+
+;;; Code:
+
+(require 'ert)
+
+(defvar bytecode-tests--dynamic 'outer)
+
+(defun bytecode-tests--read-dynamic ()
+  bytecode-tests--dynamic)
+
+(defconst bytecode-tests--forms
+  '(;; Stack, constants, variables and calls.
+    (let ((a 1) (b 2) (c 3)) (list a b c (+ a b c)))
+    (let ((x 0)) (dotimes (i 10) (setq x (+ x i))) x)
+    (let ((bytecode-tests--dynamic 'inner)) (bytecode-tests--read-dynamic))
+    (list (bytecode-tests--read-dynamic)
+          (let ((bytecode-tests--dynamic 'inner))
+            (setq bytecode-tests--dynamic 'changed)
+            (bytecode-tests--read-dynamic))
+          (bytecode-tests--read-dynamic))
+    (let ((f (let ((n 10)) (lambda (m) (setq n (+ n m))))))
+      (list (funcall f 1) (funcall f 2)))
+    (apply #'+ 1 2 '(3 4))
+    (funcall (lambda (a &optional b &rest c) (list a b c)) 1)
+    (funcall (lambda (a &optional b &rest c) (list a b c)) 1 2 3 4)
+    ;; Lists and sequences.
+    (let ((l (list 1 2 3 4)))
+      (list (car l) (cdr l) (nth 2 l) (nthcdr 2 l) (elt l 1) (length l)
+            (car-safe 'x) (cdr-safe l) (memq 3 l) (member "b" '("a" "b"))
+            (assq 'b '((a . 1) (b . 2))) (cons 0 l) (nreverse (copy-sequence l))))
+    (let ((v (vector 1 2 3)))
+      (aset v 0 'x)
+      (list (aref v 0) (elt v 2) (length v)))
+    (let ((c (cons 1 2))) (setcar c 'a) (setcdr c 'b) c)
+    (nconc (list 1 2) (list 3))
+    (list (list 1) (list 1 2) (list 1 2 3) (list 1 2 3 4) (list 1 2 3 4 5 6 7))
+    (list (concat "a" "b") (concat "a" "b" "c") (concat "a" "b" "c" "d")
+          (concat "a" "b" "c" "d" "e") (substring "abcdef" 1 3))
+    ;; Predicates.
+    (mapcar (lambda (x)
+              (list (symbolp x) (consp x) (stringp x) (listp x) (not x)
+                    (numberp x) (integerp x)))
+            '(nil a (1) "s" 1 1.5))
+    (list (eq 'a 'a) (equal "a" "a") (string= "a" "a") (string< "a" "b"))
+    ;; Arithmetic.
+    (let ((a 7) (b 2))
+      (list (1+ a) (1- a) (- a) (+ a b) (- a b) (* a b) (/ a b) (% a b)
+            (max a b) (min a b) (= a b) (> a b) (< a b) (>= a b) (<= a b)))
+    (let ((a 7.5) (b 2)) (list (+ a b) (/ a b) (= a 7.5) (< b a)))
+    ;; Symbols.
+    (progn (put 'bytecode-tests--sym 'prop 'val)
+           (list (get 'bytecode-tests--sym 'prop)
+                 (symbol-value 'bytecode-tests--dynamic)
+                 (symbol-function 'car)))
+    ;; Control flow.
+    (let (acc) (dolist (x '(1 2 3 4)) (if (> x 2) (push x acc) (push (- x) acc))) acc)
+    (let ((x 3)) (cond ((eq x 1) 'one) ((eq x 2) 'two) ((eq x 3) 'three) (t 'other)))
+    (mapcar (lambda (x)
+              (pcase x (`a 1) (`b 2) (`c 3) (`d 4) (`e 5) (`f 6) (_ 0)))
+            '(a c f z))
+    (let ((x 0)) (while (< x 100) (setq x (1+ x))) x)
+    (and 1 2 nil 3)
+    (or nil nil 4)
+    ;; Non-local exits.
+    (catch 'tag (dotimes (i 10) (when (= i 4) (throw 'tag i))) 'none)
+    (catch 'outer (catch 'inner (throw 'outer 1)) 2)
+    (condition-case err (car 1) (wrong-type-argument (list 'caught (car err))))
+    (condition-case nil (/ 1 0) (arith-error 'div))
+    (condition-case nil 'no-error (error 'error))
+    (let ((log nil))
+      (catch 'done
+        (unwind-protect (throw 'done nil) (push 'unwound log)))
+      log)
+    (let ((x 0))
+      (dotimes (i 5)
+        (condition-case nil
+            (if (= (% i 2) 1) (error "odd") (setq x (+ x i)))
+          (error (setq x (+ x 100)))))
+      x)
+    (let ((bytecode-tests--dynamic 'before))
+      (catch 'tag
+        (let ((bytecode-tests--dynamic 'bound))
+          (throw 'tag nil)))
+      bytecode-tests--dynamic)
+    ;; Buffers.
+    (with-temp-buffer
+      (insert "hello" " " "world\nsecond line")
+      (goto-char (point-min))
+      (list (point) (point-max) (point-min) (char-after) (following-char)
+            (progn (forward-char 2) (preceding-char)) (current-column)
+            (progn (end-of-line) (eolp)) (progn (forward-line 1) (bolp))
+            (progn (forward-word 1) (point)) (eobp) (bobp)
+            (buffer-substring 1 6) (char-syntax ?a)
+            (progn (skip-chars-backward "a-z") (point))
+            (progn (skip-chars-forward "a-z ") (point))))
+    (with-temp-buffer
+      (insert "abcdef")
+      (save-excursion (goto-char 2) (delete-region 2 4))
+      (save-restriction
+        (narrow-to-region 2 3)
+        (list (point-min) (point-max) (buffer-string)
+              (progn (widen) (buffer-string)))))
+    (with-temp-buffer
+      (let ((buf (current-buffer)))
+        (with-temp-buffer (set-buffer buf) (eq (current-buffer) buf))))
+    (with-temp-buffer
+      (insert "foo")
+      (let ((m (make-marker)))
+        (set-marker m 2)
+        (list (marker-position m) (upcase "abc") (downcase "ABC"))))
+    (with-temp-buffer
+      (insert "abc 123")
+      (goto-char 1)
+      (when (re-search-forward "\\([0-9]+\\)" nil t)
+        (list (match-beginning 1) (match-end 1))))
+    (with-temp-buffer (indent-to 4) (current-column)))
+  "Forms whose compiled and interpreted results should be `equal'.")
+
+(ert-deftest bytecode-tests--compiled-matches-interpreted ()
+  (dolist (form bytecode-tests--forms)
+    (let ((interpreted (eval form t))
+          (compiled (funcall (byte-compile (eval `(lambda () ,form) t)))))
+      (should (equal (list form compiled) (list form interpreted))))))
+
+(ert-deftest bytecode-tests--byte-code ()
+  ;; constant 0, return
+  (should (eq (byte-code "\300\207" [ok] 1) 'ok))
+  ;; constant 0, constant 1, plus, return
+  (should (= (byte-code "\300\301\134\207" [1 2] 2) 3)))
+
+(ert-deftest bytecode-tests--maxdepth-too-small ()
+  (should-error (byte-code "\300\207" [ok] 0))
+  (should-error (byte-code "\300\301\134\207" [1 2] 1)))
+
+(ert-deftest bytecode-tests--malformed ()
+  ;; Falls off the end of the code.
+  (should-error (byte-code "\300" [ok] 1))
+  ;; Truncated two-byte operand.
+  (should-error (byte-code "\202" [] 1))
+  ;; Constant index out of range.
+  (should-error (byte-code "\301\207" [ok] 1))
+  ;; Stack underflow.
+  (should-error (byte-code "\210\207" [] 1))
+  ;; Invalid opcode.
+  (should-error (byte-code "\000\207" [] 1))
+  ;; Jump out of range.
+  (should-error (byte-code "\202\377\000" [] 1))
+  ;; Pophandler without a handler.
+  (should-error (byte-code "\060\207" [] 1)))
+
+(ert-deftest bytecode-tests--wrong-number-of-arguments ()
+  (let ((f (byte-compile (lambda (a b) (list a b)))))
+    (should (equal (funcall f 1 2) '(1 2)))
+    (should-error (funcall f 1) :type 'wrong-number-of-arguments)
+    (should-error (funcall f 1 2 3) :type 'wrong-number-of-arguments)))
+
+(defun bytecode-tests--count-down (n)
+  (if (= n 0) 0 (1+ (bytecode-tests--count-down (1- n)))))
+
+(defun bytecode-tests--throw-from (n)
+  (if (= n 0)
+      (throw 'done 'thrown)
+    (list (bytecode-tests--throw-from (1- n)))))
+
+(ert-deftest bytecode-tests--stack-growth ()
+  (byte-compile 'bytecode-tests--count-down)
+  (byte-compile 'bytecode-tests--throw-from)
+  (let ((max-lisp-eval-depth 2000))
+    ;; Enough nested calls to outgrow the thread's first stack vector.
+    (should (= (bytecode-tests--count-down 600) 600))
+    ;; Calls unwound by a throw give their stack slots back.
+    (dotimes (_ 1000)
+      (should (eq (catch 'done (bytecode-tests--throw-from 50)) 'thrown)))
+    (should (= (bytecode-tests--count-down 600) 600))))
+
+(ert-deftest bytecode-tests--decode ()
+  (should (equal (byte-code-decode "\300\301\134\207")
+                 '((0 byte-constant 0) (1 byte-constant 1) (2 byte-plus nil)
+                   (3 byte-return nil))))
+  ;; goto 3, return
+  (should (equal (byte-code-decode "\202\003\000\207")
+                 '((0 byte-goto 3) (3 byte-return nil))))
+  (let ((f (byte-compile (lambda (x) (car x)))))
+    (should (equal (car (last (byte-code-decode f))) '(2 byte-return nil))))
+  (should-error (byte-code-decode "\000"))
+  (should-error (byte-code-decode "\202\003")))
+
+(ert-deftest bytecode-tests--verify ()
+  (should-not (byte-code-verify "\300\207" [ok] 1))
+  (should-not (byte-code-verify "\300\301\134\207" [1 2] 2))
+  (dolist (form bytecode-tests--forms)
+    (should-not (byte-code-verify (byte-compile (eval `(lambda () ,form) t)))))
+  (should-not (byte-code-verify (byte-compile (lambda (a &optional b &rest c)
+                                                (list a b c))))))
+
+(ert-deftest bytecode-tests--verify-errors ()
+  ;; Stack depth exceeds MAXDEPTH.
+  (should (stringp (byte-code-verify "\300\207" [ok] 0)))
+  ;; Constant index out of range.
+  (should (stringp (byte-code-verify "\301\207" [ok] 1)))
+  ;; Falls off the end of the code.
+  (should (stringp (byte-code-verify "\300" [ok] 1)))
+  ;; Stack underflow.
+  (should (stringp (byte-code-verify "\210\207" [] 1)))
+  ;; Jump out of range.
+  (should (stringp (byte-code-verify "\202\377\000" [] 1)))
+  ;; Inconsistent depth at a join: goto-if-nil 5 with one value
+  ;; pushed on one path only.
+  (should (stringp (byte-code-verify "\300\203\005\000\300\207" [t] 2)))
+  (should-error (byte-code-verify "\300\207")))
+
+(defalias 'bytecode-tests--metered
+  (byte-compile (lambda (n) (let ((s 0)) (dotimes (i n) (setq s (+ s i))) s))))
+
+(ert-deftest bytecode-tests--meter ()
+  (byte-code-meter-reset)
+  (should (equal (byte-code-meter-report) '((t 0))))
+  (bytecode-tests--metered 10)
+  (should (equal (byte-code-meter-report) '((t 0))))
+  (let ((byte-metering-on t))
+    (bytecode-tests--metered 10)
+    (bytecode-tests--metered 10))
+  (let* ((report (byte-code-meter-report))
+         (global (car report))
+         (entry (assq 'bytecode-tests--metered report)))
+    (should (eq (car global) t))
+    (should entry)
+    (should (> (nth 1 entry) 0))
+    (should (<= (nth 1 entry) (nth 1 global)))
+    (should (= (cdr (assq 'byte-return (nthcdr 2 entry))) 2))
+    (should (= (nth 1 entry) (apply #'+ (mapcar #'cdr (nthcdr 2 entry)))))
+    (should (vectorp byte-code-meter))
+    (should (> (aref (aref byte-code-meter 0) 135) 0)))
+  (byte-code-meter-reset)
+  (should (equal (byte-code-meter-report) '((t 0))))
+  (should-not byte-code-meter))
+
+(provide 'bytecode-tests)
+;;; bytecode-tests.el ends here
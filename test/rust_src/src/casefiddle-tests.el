@@ -0,0 +1,107 @@
+;;; casefiddle-tests.el --- -*- lexical-binding: t; -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest casefiddle-one-to-many ()
+  (should (equal (upcase "straße") "STRASSE"))
+  (should (equal (upcase "ﬁsh") "FISH"))
+  (should (equal (capitalize "ﬁsh") "Fish"))
+  ;; Characters can't grow, so they are left alone.
+  (should (eq (upcase ?ß) ?ß)))
+
+(ert-deftest casefiddle-final-sigma ()
+  (should (equal (downcase "ΟΔΟΣ ΟΔΟΣ") "οδος οδος"))
+  (should (equal (downcase "ΣΑ") "σα"))
+  (should (equal (capitalize "ΟΔΟΣ") "Οδος")))
+
+(ert-deftest casefiddle-turkic-locale ()
+  (should (equal (upcase "istanbul" "tr") "İSTANBUL"))
+  (should (equal (upcase "istanbul") "ISTANBUL"))
+  (should (equal (downcase "DİYARBAKIR" "tr") "diyarbakır"))
+  (should (equal (downcase "DİYARBAKIR" 'az_AZ) "diyarbakır"))
+  ;; I followed by a combining dot above is a dotted i.
+  (should (equal (downcase "I\u0307" "tr") "i"))
+  (should (equal (capitalize "izmir" "tr_TR.UTF-8") "İzmir"))
+  (should (eq (upcase ?i "tr") ?İ))
+  (should (eq (downcase ?I "tr") ?ı)))
+
+(ert-deftest casefiddle-lithuanian-locale ()
+  ;; The dot of i is kept when lower casing under an accent...
+  (should (equal (downcase "\u00CC" "lt") "i\u0307\u0300"))
+  (should (equal (downcase "I\u0300" "lt") "i\u0307\u0300"))
+  (should (equal (downcase "I" "lt") "i"))
+  ;; ...and dropped again when upper casing.
+  (should (equal (upcase "i\u0307\u0300" "lt") "I\u0300"))
+  (should (equal (upcase "i\u0307\u0300") "I\u0307\u0300")))
+
+(ert-deftest casefiddle-locale-region ()
+  (with-temp-buffer
+    (insert "kırmızı istiridye")
+    (upcase-region (point-min) (point-max) nil "tr")
+    (should (equal (buffer-string) "KIRMIZI İSTİRİDYE"))
+    (downcase-region (point-min) (point-max) nil "tr")
+    (should (equal (buffer-string) "kırmızı istiridye"))
+    (capitalize-region (point-min) (point-max) "tr")
+    (should (equal (buffer-string) "Kırmızı İstiridye"))))
+
+(ert-deftest casefiddle-region-context ()
+  (with-temp-buffer
+    ;; The characters after the region decide whether a sigma is final...
+    (insert "ΟΔΟΣΑ")
+    (downcase-region 1 5)
+    (should (equal (buffer-string) "οδοσΑ"))
+    ;; ...and whether a Turkish I is dotted.
+    (erase-buffer)
+    (insert "I\u0307x")
+    (downcase-region 1 2 nil "tr")
+    (should (equal (buffer-string) "i\u0307x"))))
+
+(ert-deftest casefiddle-byte-compiled-locale ()
+  (should (equal (funcall (byte-compile (lambda (s) (upcase s "tr"))) "i") "İ"))
+  (should (equal (funcall (byte-compile (lambda (s) (downcase s))) "A") "a")))
+
+(ert-deftest casefiddle-region-grows ()
+  (with-temp-buffer
+    (insert "straße x")
+    (goto-char (point-max))
+    (upcase-region (point-min) (point-max))
+    (should (equal (buffer-string) "STRASSE X"))
+    (should (= (point) (point-max)))))
+
+(ert-deftest casefiddle-bad-locale ()
+  (should-error (upcase "a" 42) :type 'wrong-type-argument))
+
+(ert-deftest casefiddle-string-convert-case ()
+  (let ((identifiers '("parseHTTPResponse2" "parse_http_response_2"
+                       "ParseHttpResponse2" "parse-http-response-2")))
+    (dolist (identifier identifiers)
+      (should (equal (string-convert-case identifier 'snake)
+                     "parse_http_response_2"))
+      (should (equal (string-convert-case identifier 'screaming)
+                     "PARSE_HTTP_RESPONSE_2"))
+      (should (equal (string-convert-case identifier 'kebab)
+                     "parse-http-response-2"))
+      (should (equal (string-convert-case identifier 'camel)
+                     "parseHttpResponse2"))
+      (should (equal (string-convert-case identifier 'pascal)
+                     "ParseHttpResponse2")))))
+
+(ert-deftest casefiddle-string-convert-case-unicode ()
+  (should (equal (string-convert-case "ÉcoleNormale" 'snake) "école_normale"))
+  (should (equal (string-convert-case "straße_name" 'screaming) "STRASSE_NAME"))
+  (should (equal (string-convert-case "über cool" 'camel) "überCool"))
+  (should (equal (string-convert-case "__" 'snake) ""))
+  (should-error (string-convert-case "foo" 'no-such-style)))
+
+(ert-deftest casefiddle-convert-case-region ()
+  (with-temp-buffer
+    (insert "(setq someVariableName 1)")
+    (convert-case-region 7 23 'kebab)
+    (should (equal (buffer-string) "(setq some-variable-name 1)"))
+    (convert-case-region 7 25 'screaming)
+    (should (equal (buffer-string) "(setq SOME_VARIABLE_NAME 1)"))))
+
+(provide 'casefiddle-tests)
+;;; casefiddle-tests.el ends here
@@ -50,5 +50,33 @@
   (let ((last-command-event ?a))
     (should-error (self-insert-command -1))))
 
+(ert-deftest forward-char-grapheme-cluster-motion ()
+  (with-temp-buffer
+    (insert "ae\u0301\U0001F44B\U0001F3FDz")
+    (goto-char (point-min))
+    (let ((grapheme-cluster-motion t))
+      (forward-char 2)
+      (should (= (point) 4))
+      (forward-char)
+      (should (= (point) 6))
+      (backward-char 2)
+      (should (= (point) 2))
+      (should-error (forward-char 5) :type 'end-of-buffer)
+      (should (= (point) (point-max))))
+    (goto-char 2)
+    (forward-char)
+    (should (= (point) 3))))
+
+(ert-deftest delete-char-grapheme-cluster-motion ()
+  (with-temp-buffer
+    (insert "ae\u0301\U0001F44B\U0001F3FDz")
+    (goto-char 2)
+    (let ((grapheme-cluster-motion t))
+      (delete-char 1)
+      (should (equal (buffer-string) "a\U0001F44B\U0001F3FDz"))
+      (goto-char (point-max))
+      (delete-char -2)
+      (should (equal (buffer-string) "a")))))
+
 (provide 'cmds-tests)
 ;;; cmds-tests.el ends here
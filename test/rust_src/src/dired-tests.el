@@ -25,6 +25,127 @@
     (should (file-attributes-lessp '(rstr t) '(wstr t)))
     (should (not (file-attributes-lessp '(wstr t) '(rstr t))))))
 
+;; Create FILES, a list of relative names, under DIR.  Names ending
+;; in a slash are created as directories.
+(defun dired-tests--make-tree (dir files)
+  (dolist (file files)
+    (let ((name (expand-file-name file dir)))
+      (if (directory-name-p file)
+          (make-directory name t)
+        (make-directory (file-name-directory name) t)
+        (write-region "" nil name nil 'silent)))))
+
+(defmacro dired-tests--with-tree (var files &rest body)
+  (declare (indent 2))
+  `(let ((,var (make-temp-file "dired-tests" t)))
+     (unwind-protect
+         (progn (dired-tests--make-tree ,var ,files)
+                ,@body)
+       (delete-directory ,var t))))
+
+(ert-deftest test-directory-walk ()
+  (dired-tests--with-tree dir '("a.el" "b.txt" "sub/c.el" "sub/deep/d.el")
+    (let ((case-fold-search nil))
+      (should (equal (directory-walk dir "\\.el\\'")
+                     (mapcar (lambda (f) (expand-file-name f dir))
+                             '("sub/deep/d.el" "sub/c.el" "a.el"))))
+      (should (equal (directory-walk dir "\\`[a-z]+\\'" t)
+                     (mapcar (lambda (f) (expand-file-name f dir))
+                             '("sub/deep" "sub"))))
+      (should (equal (directory-walk dir "\\.el\\'" nil 1)
+                     (mapcar (lambda (f) (expand-file-name f dir))
+                             '("sub/c.el" "a.el"))))
+      (should (equal (directory-walk dir "\\.el\\'" nil 0)
+                     (list (expand-file-name "a.el" dir))))
+      (should (equal (directory-walk dir nil nil nil nil nil
+                                     (lambda (d) (not (string-suffix-p "deep" d))))
+                     (mapcar (lambda (f) (expand-file-name f dir))
+                             '("sub/c.el" "a.el" "b.txt"))))
+      (should (equal (directory-files-recursively dir "\\.el\\'")
+                     (directory-walk dir "\\.el\\'")))
+      (let ((walk (directory-walk dir "\\`a" nil nil nil nil nil t)))
+        (should (equal (caar walk) (expand-file-name "a.el" dir)))
+        (should (null (car (cdar walk))))))))
+
+(ert-deftest test-directory-walk-non-utf-8 ()
+  ;; In Latin-1 "é" is encoded as a byte that is not valid UTF-8.
+  (let ((file-name-coding-system 'latin-1)
+        (case-fold-search nil))
+    (dired-tests--with-tree dir '("\u00e9/\u00e0.el" "\u00e9.el")
+      (should (equal (directory-walk dir "\\.el\\'")
+                     (mapcar (lambda (f) (expand-file-name f dir))
+                             '("\u00e9/\u00e0.el" "\u00e9.el"))))
+      (should (equal (directory-walk dir "\\`\u00e9\\'" t)
+                     (list (expand-file-name "\u00e9" dir)))))))
+
+(ert-deftest test-directory-walk-ignore-file ()
+  (dired-tests--with-tree dir '("keep.el" "drop.log" "build/out.el"
+                                "sub/keep.log" "sub/x.el")
+    (write-region "*.log\nbuild/\n" nil (expand-file-name ".ignore" dir)
+                  nil 'silent)
+    (write-region "!keep.log\n" nil (expand-file-name "sub/.ignore" dir)
+                  nil 'silent)
+    (should (equal (directory-walk dir "\\`[^.]" nil nil nil ".ignore")
+                   (mapcar (lambda (f) (expand-file-name f dir))
+                           '("sub/keep.log" "sub/x.el" "keep.el"))))))
+
+(ert-deftest test-directory-walk-symlink ()
+  (dired-tests--with-tree dir '("a.el" "real/x.el")
+    (make-symbolic-link "real" (expand-file-name "link" dir))
+    (let ((case-fold-search nil))
+      ;; A link to a directory is not descended into, but it is still
+      ;; sorted and listed as a directory.
+      (should (equal (directory-walk dir "\\.el\\'")
+                     (mapcar (lambda (f) (expand-file-name f dir))
+                             '("real/x.el" "a.el"))))
+      (should (equal (directory-walk dir "\\`[a-z]+\\'")
+                     nil))
+      (should (equal (directory-walk dir "\\`[a-z]+\\'" t)
+                     (mapcar (lambda (f) (expand-file-name f dir))
+                             '("link" "real")))))))
+
+(defvar dired-tests--handler-calls nil)
+
+(defun dired-tests--walk-handler (operation &rest args)
+  (if (eq operation 'directory-walk)
+      (progn (push args dired-tests--handler-calls)
+             '("/dired-tests:/x"))
+    (let ((inhibit-file-name-handlers
+           (cons 'dired-tests--walk-handler inhibit-file-name-handlers))
+          (inhibit-file-name-operation operation))
+      (apply operation args))))
+
+(ert-deftest test-directory-walk-handler ()
+  (let ((dired-tests--handler-calls nil)
+        (file-name-handler-alist
+         (cons '("\\`/dired-tests:" . dired-tests--walk-handler)
+               file-name-handler-alist)))
+    (should (equal (directory-walk "/dired-tests:/" "x" t)
+                   '("/dired-tests:/x")))
+    (should (equal dired-tests--handler-calls
+                   '(("/dired-tests:/" "x" t nil nil nil nil nil))))))
+
+(ert-deftest test-directory-walk-missing ()
+  (should-error (directory-walk (make-temp-name "/nonexistent-dir"))
+                :type 'file-missing))
+
+(ert-deftest test-file-attributes-extended ()
+  (let ((file (make-temp-file "dired-tests")))
+    (unwind-protect
+        (let ((attrs (file-attributes-extended file)))
+          (should (natnump (plist-get attrs :block-size)))
+          (should (natnump (plist-get attrs :blocks)))
+          (should (natnump (plist-get attrs :device-major)))
+          (should (natnump (plist-get attrs :device-minor)))
+          (should (eql (plist-get attrs :rdev-major) 0))
+          (should (listp (plist-get attrs :xattrs)))
+          (should (memq (plist-get attrs :acl) '(nil t)))
+          (let ((birth (plist-get attrs :birth-time)))
+            (should (or (null birth)
+                        (time-less-p birth (time-add (current-time) 1))))))
+      (delete-file file)))
+  (should-not (file-attributes-extended (make-temp-name "/nonexistent-file"))))
+
 (ert-deftest test-system-users ()
   (should-error (eval '(system-users 'rms)) :type 'wrong-number-of-arguments)
   ;; The result should be a list of >= 1 user name(s) on all Unix and GNU systems.
@@ -35,3 +156,13 @@
         (should (= (length (system-users)) 1)))
     (progn
       (should (>= (length (system-users)) 1)))))
+
+(ert-deftest test-directory-files-version-sort ()
+  (dired-tests--with-tree dir '("file10" "file2" "file1" "file02")
+    (should (equal (directory-files dir nil "\\`file")
+                   '("file02" "file1" "file10" "file2")))
+    (should (equal (directory-files dir nil "\\`file" 'version)
+                   '("file1" "file02" "file2" "file10")))
+    (should (equal (mapcar #'car (directory-files-and-attributes
+                                  dir nil "\\`file" 'version))
+                   '("file1" "file02" "file2" "file10")))))
@@ -39,3 +39,6 @@
       (insert payload)
       (should (equal (buffer-string) payload)))))
 
+(ert-deftest test-message-box-arity ()
+  (should-error (message-box) :type 'wrong-number-of-arguments))
+
@@ -247,6 +247,23 @@
   (makunbound 'function-hook)
   (makunbound 'list-of-functions))
 
+(defvar eval-tests--hook-calls nil)
+(defvar eval-tests--hook-a nil)
+(defvar eval-tests--hook-b nil)
+
+(ert-deftest eval-tests--run-hooks-rest ()
+  (let ((eval-tests--hook-calls nil)
+        (eval-tests--hook-a (list (lambda () (push 'a eval-tests--hook-calls))))
+        (eval-tests--hook-b (lambda () (push 'b eval-tests--hook-calls))))
+    (should-not (run-hooks))
+    (run-hooks 'eval-tests--hook-a 'eval-tests--hook-b 'eval-tests--hook-a)
+    (should (equal eval-tests--hook-calls '(a b a)))
+    (should-error (run-hooks 'eval-tests--hook-a 1) :type 'wrong-type-argument)))
+
+(ert-deftest eval-tests--run-hook-with-args-arity ()
+  (should-error (run-hook-with-args) :type 'wrong-number-of-arguments)
+  (should-not (run-hook-with-args 'eval-tests--unbound-hook 1 2)))
+
 (ert-deftest eval-tests--funcall()
   (let ((f (lambda () 1)))
     (should (eq (funcall f) 1)))
@@ -256,6 +273,8 @@
 (ert-deftest eval-tests--funcall-error()
   "Checks all error cases for funcall."
 
+  (should-error (funcall) :type 'wrong-number-of-arguments)
+
   (should-error (funcall nil) :type 'void-function)
 
   (let ((f nil))
@@ -271,6 +290,149 @@
 
   (should-error (funcall (lambda)) :type 'invalid-function))
 
+;; `backtrace-frames-structured'.
+
+(defvar eval-tests--dynamic 'outer)
+
+(defun eval-tests--structured-frames (_x)
+  (let ((eval-tests--dynamic 'inner))
+    (let ((eval-tests--dynamic 'innermost))
+      (with-temp-buffer
+        (list (current-buffer) (backtrace-frames-structured))))))
+
+(defun eval-tests--find-frame (function frames)
+  (catch 'found
+    (dolist (frame frames)
+      (when (eq (plist-get frame :function) function)
+        (throw 'found frame)))))
+
+(ert-deftest eval-tests--backtrace-frames-structured ()
+  (let* ((result (eval-tests--structured-frames 1))
+         (temp-buffer (car result))
+         (frames (cadr result))
+         (frame (eval-tests--find-frame 'eval-tests--structured-frames frames)))
+    (should frame)
+    (should (equal (plist-get frame :args) '(1)))
+    (should-not (plist-get frame :special-form))
+    (should (eq (plist-get frame :buffer) (current-buffer)))
+    (should (eq (plist-get (car frames) :buffer) temp-buffer))
+    (should (eval-tests--find-frame 'eval-tests--backtrace-frames-structured
+                                    frames))
+    (let (values)
+      (dolist (frame (reverse frames))
+        (dolist (binding (plist-get frame :bindings))
+          (when (eq (car-safe binding) 'eval-tests--dynamic)
+            (push (cdr binding) values))))
+      (should (equal (nreverse values) '(inner innermost))))))
+
+(ert-deftest eval-tests--backtrace-frames-structured-special-form ()
+  (let ((frame (car (eval '(if t (backtrace-frames-structured)) t))))
+    (should (eq (plist-get frame :function) 'if))
+    (should (plist-get frame :special-form))
+    (should (equal (plist-get frame :args) '(t (backtrace-frames-structured))))))
+
+(ert-deftest eval-tests--backtrace-frames-structured-dead-thread ()
+  (skip-unless (fboundp 'make-thread))
+  (let ((thread (make-thread #'ignore)))
+    (thread-join thread)
+    (should-not (backtrace-frames-structured thread))))
+
+;; A thread blocked on a mutex has its bindings swapped out, so their
+;; values have to be found where they were saved.
+(defun eval-tests--blocked-thread (mutex _x)
+  (let ((eval-tests--dynamic 'thread))
+    (mutex-lock mutex)
+    (mutex-unlock mutex)))
+
+(ert-deftest eval-tests--backtrace-frames-structured-blocked-thread ()
+  (skip-unless (fboundp 'make-thread))
+  (let ((mutex (make-mutex))
+        thread)
+    (mutex-lock mutex)
+    (unwind-protect
+        (progn
+          (setq thread (make-thread
+                        (lambda () (eval-tests--blocked-thread mutex 1))))
+          (while (not (thread--blocker thread))
+            (thread-yield))
+          (let* ((frames (backtrace-frames-structured thread))
+                 (frame (eval-tests--find-frame 'eval-tests--blocked-thread
+                                                frames))
+                 values)
+            (should frame)
+            (should (equal (plist-get frame :args) (list mutex 1)))
+            (dolist (frame frames)
+              (dolist (binding (plist-get frame :bindings))
+                (when (eq (car-safe binding) 'eval-tests--dynamic)
+                  (push (cdr binding) values))))
+            (should (equal values '(thread)))
+            (should (eq eval-tests--dynamic 'outer))))
+      (mutex-unlock mutex)
+      (when thread
+        (thread-join thread)))))
+
+;; `eval-with-limits'.
+
+(defun eval-tests--limit-reached (form &rest limits)
+  (condition-case err
+      (progn (apply #'eval-with-limits form limits) nil)
+    (eval-limit-exceeded (cdr err))))
+
+(ert-deftest eval-tests--eval-with-limits ()
+  (should (= (eval-with-limits '(+ 1 2) 1000 100000 10) 3))
+  (should (= (eval-with-limits 'x nil nil nil '((x . 4))) 4))
+  (should (equal (eval-tests--limit-reached '(while t) 1000) '(steps 1000)))
+  (should (equal (eval-tests--limit-reached '(let (l) (while t (push 1 l)))
+                                            nil 100000)
+                 '(consing 100000)))
+  (should (equal (eval-tests--limit-reached '(while t) nil nil 0.1)
+                 '(timeout 0.1)))
+  (should-error (eval-with-limits 1 nil nil -1) :type 'args-out-of-range))
+
+(ert-deftest eval-tests--eval-with-limits-not-an-error ()
+  (should (equal (eval-tests--limit-reached
+                  '(while t (ignore-errors (while t)))
+                  1000)
+                 '(steps 1000))))
+
+(ert-deftest eval-tests--eval-with-limits-uncatchable ()
+  ;; FORM cannot catch the limit and carry on without one.
+  (should (equal (eval-tests--limit-reached
+                  '(while t (condition-case nil (while t) (t nil)))
+                  1000)
+                 '(steps 1000)))
+  (should (equal (eval-tests--limit-reached
+                  '(condition-case nil
+                       (while t)
+                     (eval-limit-exceeded (while t)))
+                  1000)
+                 '(steps 1000))))
+
+(ert-deftest eval-tests--eval-with-limits-nested ()
+  (should (equal (eval-tests--limit-reached
+                  '(eval-with-limits '(while t) 1000000)
+                  100)
+                 '(steps 100)))
+  (should (equal (eval-tests--limit-reached
+                  '(eval-with-limits '(while t) 100)
+                  1000000)
+                 '(steps 100)))
+  ;; The limits are gone once `eval-with-limits' returns.
+  (should (eq (eval-with-limits ''done 100) 'done))
+  (dotimes (_ 1000) (ignore)))
+
+(ert-deftest eval-tests--eval-with-limits-unwind ()
+  ;; No limit is checked while unwinding, so `with-temp-buffer' gets
+  ;; to kill its buffer.
+  (let ((buffers (buffer-list)))
+    (should (equal (eval-tests--limit-reached '(with-temp-buffer (while t)) 1000)
+                   '(steps 1000)))
+    (should (equal (buffer-list) buffers)))
+  (should (equal (eval-tests--limit-reached
+                  '(unwind-protect (while t) (dotimes (_ 10000) (ignore)))
+                  1000)
+                 '(steps 1000))))
+
 ;; Local Variables:
 ;; byte-compile-warnings: (not lexical free-vars unresolved)
 ;; End:
@@ -27,3 +27,26 @@
       (should (directory-name-p "./"))
       (should-not (directory-name-p ""))
       (should-not (directory-name-p ".\\")))))
+
+(ert-deftest test-write-region-atomically ()
+  (let* ((dir (make-temp-file "fileio-atomic" t))
+         (file (expand-file-name "config" dir)))
+    (unwind-protect
+        (progn
+          (write-region-atomically "old\n" nil file)
+          (set-file-modes file #o640)
+          (with-temp-buffer
+            (insert "new\ncontents\n")
+            (write-region-atomically 1 5 file))
+          (should (equal (with-temp-buffer
+                           (insert-file-contents-literally file)
+                           (buffer-string))
+                         "new\n"))
+          (should (= (file-modes file) #o640))
+          (with-temp-buffer
+            (insert "λ")
+            (write-region-atomically nil nil file 'utf-8))
+          (should (= (file-attribute-size (file-attributes file)) 2))
+          (should (equal (directory-files dir nil "\\`[^.]\\|\\`\\.[^.]")
+                         '("config"))))
+      (delete-directory dir t))))
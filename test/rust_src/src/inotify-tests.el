@@ -0,0 +1,53 @@
+;;; inotify-tests.el --- -*- lexical-binding: t; -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest inotify-add-rm-watch ()
+  (skip-unless (featurep 'inotify))
+  (let* ((dir (make-temp-file "inotify-dir" t))
+         (wd1 (inotify-add-watch dir '(create delete) #'ignore))
+         (wd2 (inotify-add-watch dir 'modify #'ignore)))
+    (unwind-protect
+        (progn
+          (should (inotify-valid-p wd1))
+          (should (inotify-valid-p wd2))
+          (should-not (equal wd1 wd2))
+          (should (inotify-rm-watch wd1))
+          (should-not (inotify-valid-p wd1))
+          (should (inotify-valid-p wd2))
+          (should (inotify-rm-watch wd2))
+          (should-not (inotify-valid-p wd2)))
+      (delete-directory dir t))))
+
+(ert-deftest inotify-unknown-aspect ()
+  (skip-unless (featurep 'inotify))
+  (let ((dir (make-temp-file "inotify-dir" t)))
+    (unwind-protect
+        (should-error (inotify-add-watch dir '(create no-such-aspect) #'ignore)
+                      :type 'file-notify-error)
+      (delete-directory dir t))))
+
+(ert-deftest inotify-recursive-watch ()
+  (skip-unless (featurep 'inotify))
+  (let* ((dir (make-temp-file "inotify-dir" t))
+         (sub (expand-file-name "sub" dir))
+         events wd)
+    (make-directory sub)
+    (setq wd (inotify-add-watch dir '(create recursive)
+                                (lambda (event) (push event events))))
+    (unwind-protect
+        (progn
+          (write-region "" nil (expand-file-name "file" sub) nil 'silent)
+          (with-timeout (5 nil)
+            (while (not events)
+              (read-event nil nil 0.1)))
+          (should (equal (nth 0 (car events)) wd))
+          (should (memq 'create (nth 1 (car events))))
+          (should (equal (nth 2 (car events)) "sub/file")))
+      (inotify-rm-watch wd)
+      (delete-directory dir t))))
+
+(provide 'inotify-tests)
+;;; inotify-tests.el ends here
@@ -217,3 +217,136 @@
     (use-global-map sample-keymap)
     (should (equal (current-global-map) '(keymap (3 keymap (26 . emacs-version)))))
     (use-global-map backup-keymap)))
+
+(ert-deftest keymap-tests--where-is-internal ()
+  (let ((map (make-sparse-keymap)))
+    (define-key map "\C-c\C-z" 'keymap-tests--command)
+    (define-key map [f5] 'keymap-tests--command)
+    (should (equal (where-is-internal 'keymap-tests--command (list map))
+                   '([3 26] [f5])))
+    (should (equal (where-is-internal 'keymap-tests--command (list map) t)
+                   [3 26]))
+    (should-not (where-is-internal 'keymap-tests--other (list map)))))
+
+(ert-deftest keymap-tests--where-is-internal-cache ()
+  (let ((map (make-sparse-keymap)))
+    (define-key map "a" 'keymap-tests--command)
+    (should (equal (where-is-internal 'keymap-tests--command (list map) t)
+                   [?a]))
+    ;; Changing the keymap must flush the cached reverse index.
+    (define-key map "b" 'keymap-tests--command)
+    (define-key map "a" nil)
+    (should (equal (where-is-internal 'keymap-tests--command (list map) t)
+                   [?b]))))
+
+(ert-deftest keymap-tests--where-is-internal-uncached ()
+  ;; Lookups that can see menus are never served from the cache, so
+  ;; they see changes that do not flush it.
+  (let ((map (make-keymap))
+        (bound t))
+    (define-key map "a" 'keymap-tests--command)
+    (should (equal (where-is-internal 'keymap-tests--command (list map))
+                   '([?a])))
+    (aset (nth 1 map) ?a nil)
+    (aset (nth 1 map) ?b 'keymap-tests--command)
+    (should (equal (where-is-internal 'keymap-tests--command (list map))
+                   '([?b])))
+    (setcdr (last map) (list (cons ?c 'keymap-tests--command)))
+    (should (member [?c] (where-is-internal 'keymap-tests--command (list map))))
+    (define-key map [menu-bar frob]
+      '(menu-item "Frob" keymap-tests--other
+                  :filter (lambda (cmd) (and bound cmd))))
+    (should (equal (where-is-internal 'keymap-tests--other (list map))
+                   '([menu-bar frob])))
+    (setq bound nil)
+    (should-not (where-is-internal 'keymap-tests--other (list map)))))
+
+(ert-deftest keymap-tests--where-is-internal-prefix-command ()
+  (let ((map (make-sparse-keymap))
+        (prefix-1 (make-sparse-keymap))
+        (prefix-2 (make-sparse-keymap)))
+    (define-key prefix-1 "a" 'keymap-tests--command)
+    (define-key prefix-2 "b" 'keymap-tests--command)
+    (unwind-protect
+        (progn
+          (fset 'keymap-tests--prefix prefix-1)
+          (define-key map "\C-c" 'keymap-tests--prefix)
+          (should (equal (where-is-internal 'keymap-tests--command (list map) t)
+                         [3 ?a]))
+          ;; Redefining the prefix command must flush the cache too.
+          (defalias 'keymap-tests--prefix prefix-2)
+          (should (equal (where-is-internal 'keymap-tests--command (list map) t)
+                         [3 ?b])))
+      (fmakunbound 'keymap-tests--prefix))))
+
+(ert-deftest keymap-tests--where-is-internal-remap ()
+  (let ((map (make-sparse-keymap)))
+    (define-key map "a" 'keymap-tests--command)
+    (define-key map [remap keymap-tests--command] 'keymap-tests--other)
+    (should (equal (where-is-internal 'keymap-tests--other (list map))
+                   '([?a])))
+    (should (equal (where-is-internal 'keymap-tests--other (list map) nil nil t)
+                   '([remap keymap-tests--command])))))
+
+(ert-deftest keymap-tests--describe-buffer-bindings ()
+  (let ((buffer (generate-new-buffer "keymap-tests")))
+    (unwind-protect
+        (with-temp-buffer
+          (with-current-buffer buffer
+            (use-local-map (make-sparse-keymap))
+            (local-set-key "\C-c\C-z" 'keymap-tests--command))
+          (describe-buffer-bindings buffer)
+          (goto-char (point-min))
+          (should (search-forward "Major Mode Bindings" nil t))
+          (should (re-search-forward "C-c C-z +keymap-tests--command" nil t)))
+      (kill-buffer buffer))))
+
+(ert-deftest keymap-tests--key-parse ()
+  (should (equal (key-parse "C-x C-f") [24 6]))
+  (should (equal (key-parse "M-<f1> RET") [M-f1 13]))
+  (should (equal (key-parse "C-M-<return>") [C-M-return]))
+  (should (equal (key-parse "C--") [67108909]))
+  (should (equal (key-description (key-parse "C-x 4 s-a")) "C-x 4 s-a"))
+  (should-error (key-parse "C-x  C-f"))
+  (should-error (key-parse "C-C-a"))
+  (should-error (key-parse "f1"))
+  (should-error (key-parse 'C-x)))
+
+(ert-deftest keymap-tests--key-valid-p ()
+  (should (key-valid-p "C-x C-f"))
+  (should (key-valid-p "<mouse-1>"))
+  (should (key-valid-p "SPC"))
+  (should-not (key-valid-p ""))
+  (should-not (key-valid-p "C-"))
+  (should-not (key-valid-p "<f 1>"))
+  (should-not (key-valid-p "C-x "))
+  (should-not (key-valid-p [24 6])))
+
+(ert-deftest keymap-tests--keymap-shadowed-bindings ()
+  (let ((high (make-sparse-keymap))
+        (low (make-sparse-keymap)))
+    (define-key high "a" 'keymap-tests--high)
+    (define-key high "\C-c" 'keymap-tests--prefix-command)
+    (define-key high "b" 'keymap-tests--same)
+    (define-key low "a" 'keymap-tests--low)
+    (define-key low "\C-c\C-z" 'keymap-tests--low)
+    (define-key low "b" 'keymap-tests--same)
+    (define-key low "c" 'keymap-tests--unshadowed)
+    (let ((shadowed (keymap-shadowed-bindings (list high low))))
+      (should (= (length shadowed) 2))
+      (should (member '([?a] keymap-tests--low 1 [?a] keymap-tests--high 0)
+                      shadowed))
+      (should (member '([?\C-c ?\C-z] keymap-tests--low 1
+                        [?\C-c] keymap-tests--prefix-command 0)
+                      shadowed)))
+    (should-not (keymap-shadowed-bindings (list low (make-sparse-keymap))))))
+
+(ert-deftest keymap-tests--keymap-shadowed-bindings-prefix ()
+  (let ((high (make-sparse-keymap))
+        (low (make-sparse-keymap)))
+    (define-key high "\C-c\C-z" 'keymap-tests--high)
+    (define-key low "\C-c" 'keymap-tests--low)
+    (let ((shadowed (keymap-shadowed-bindings (list high low))))
+      (should (= (length shadowed) 1))
+      (should (equal (nth 0 (car shadowed)) [?\C-c]))
+      (should (keymapp (nth 4 (car shadowed)))))))
@@ -198,4 +198,75 @@ literals (Bug#20852)."
       (should (functionp 'test-eval-region-func))
       (should (boundp 'test-eval-region-var)))))
 
+;; `read-with-positions' entries are (DATUM LINE COLUMN OFFSET).
+
+(ert-deftest lread-tests--read-with-positions ()
+  (let* ((result (read-with-positions "(a\n  \"b\" ?c)"))
+         (object (car result)))
+    (should (equal object '(a "b" ?c)))
+    (should (equal (cdr result)
+                   `((,object 1 0 0) (a 1 1 1) ("b" 2 2 5) (?c 2 6 9))))
+    (should (eq (car (cadr result)) object))))
+
+(ert-deftest lread-tests--read-with-positions-multibyte ()
+  (should (equal (cdr (read-with-positions "(λ b)"))
+                 '(((λ b) 1 0 0) (λ 1 1 1) (b 1 3 4)))))
+
+(ert-deftest lread-tests--read-with-positions-syntax ()
+  (should (equal (cdr (read-with-positions "(a . b)"))
+                 '(((a . b) 1 0 0) (a 1 1 1) (b 1 5 5))))
+  (should (equal (cdr (read-with-positions "[a #s(r 1)]"))
+                 '(([a #s(r 1)] 1 0 0) (a 1 1 1) (#s(r 1) 1 3 3) (r 1 6 6)
+                   (1 1 8 8))))
+  (should (equal (cdr (read-with-positions "'a"))
+                 '(('a 1 0 0) (a 1 1 1)))))
+
+(ert-deftest lread-tests--read-with-positions-labels ()
+  (let* ((result (read-with-positions "(#1=(x) #1#)"))
+         (object (car result)))
+    (should (equal object '((x) (x))))
+    (should (eq (car object) (cadr object)))
+    (should (equal (cdr result)
+                   '((((x) (x)) 1 0 0) ((x) 1 1 1) (x 1 5 5) ((x) 1 8 8))))
+    (should (eq (car (nth 2 result)) (car object)))))
+
+(ert-deftest lread-tests--read-with-positions-buffer ()
+  (with-temp-buffer
+    (insert "foo (bar)\n baz")
+    (goto-char 4)
+    (should (equal (read-with-positions (current-buffer))
+                   '((bar) ((bar) 1 1 1) (bar 1 2 2))))
+    (should (= (point) 10))
+    (should (equal (read-with-positions (current-buffer))
+                   '(baz (baz 2 1 2))))))
+
+;; `read-tolerant' returns (FORMS . DIAGNOSTICS).
+
+(ert-deftest lread-tests--read-tolerant ()
+  (should (equal (read-tolerant "(a) ; comment\n b") '(((a) b))))
+  (should (equal (read-tolerant "") '(nil)))
+  (let ((result (read-tolerant "(a))\n(b")))
+    (should (equal (car result) '((a))))
+    (should (equal (mapcar #'car (cdr result)) '(3 5)))
+    (should (equal (cadr (nth 0 (cdr result))) "Invalid read syntax: )"))
+    (should (equal (cadr (nth 1 (cdr result))) "End of file during parsing"))))
+
+(ert-deftest lread-tests--read-tolerant-resync ()
+  (let ((result (read-tolerant
+                 "(defun f ()\n  (let ((x 1))\n(defun g () 2)\n(defvar v #<x>)\nv")))
+    (should (equal (car result) '((defun g () 2))))
+    ;; The trailing v can't be read, as there is no toplevel form to
+    ;; resume at after the error at 42.
+    (should (equal (mapcar #'car (cdr result)) '(0 42 58)))
+    (should (equal (cadr (nth 2 (cdr result))) "Text after a read error not read"))))
+
+(ert-deftest lread-tests--read-tolerant-buffer ()
+  (with-temp-buffer
+    (insert "skipped (a) (b\n(c)")
+    (goto-char 8)
+    (let ((result (read-tolerant (current-buffer))))
+      (should (equal (car result) '((a) (c))))
+      (should (equal (mapcar #'car (cdr result)) '(13)))
+      (should (= (point) (point-max))))))
+
 ;;; lread-tests.el ends here
@@ -398,6 +398,43 @@
   (minibuf-tests--test-completion-regexp
    #'minibuf-tests--strings-to-symbol-hashtable))
 
+;; Cases handled by the native comparison fast path.
+
+(ert-deftest try-completion-ignore-case ()
+  (let ((completion-ignore-case t))
+    (should (equal (try-completion "ab" '("ABC" "abd")) "ab"))
+    (should (equal (try-completion "abc" '("ABC" "abcd")) "ABC"))
+    (should (equal (try-completion "fo" '("Foo")) "Foo")))
+  (let ((completion-ignore-case nil))
+    (should-not (try-completion "ab" '("ABC")))))
+
+(ert-deftest try-completion-exact-match ()
+  (should (eq (try-completion "foo" '("foo")) t))
+  (should (equal (try-completion "foo" '("foo" "foobar")) "foo"))
+  (should (equal (try-completion "f" '("foo" "foobar")) "foo")))
+
+(ert-deftest try-completion-mixed-representation ()
+  (let ((unibyte (string-to-unibyte "abc"))
+        (multibyte (string-to-multibyte "abd")))
+    (should (equal (try-completion "a" (list unibyte multibyte)) "ab"))
+    (should (equal (all-completions multibyte (list unibyte multibyte))
+                   '("abd")))))
+
+(ert-deftest all-completions-hide-spaces ()
+  (let ((strings '(" hidden" "shown")))
+    (should (equal (all-completions "" strings nil t) '("shown")))
+    (should (equal (all-completions " " strings nil t) '(" hidden")))
+    (should (equal (all-completions "" strings) strings))))
+
+(ert-deftest test-completion-ignore-case ()
+  (let ((completion-ignore-case t))
+    (should (test-completion "ABC" '("abc")))
+    (should (test-completion "ABC" (minibuf-tests--strings-to-obarray '("abc"))))
+    (should (test-completion "ABC" (minibuf-tests--strings-to-string-hashtable
+                                    '("abc")))))
+  (let ((completion-ignore-case nil))
+    (should-not (test-completion "ABC" '("abc")))))
+
 (ert-deftest test-minibuffer-contents ()
   (let ((buf (window-buffer (select-window (minibuffer-window)))))
     (with-current-buffer buf
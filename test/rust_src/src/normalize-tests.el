@@ -0,0 +1,59 @@
+;;; normalize-tests.el --- -*- lexical-binding: t; -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest string-normalize-nfd ()
+  (should (equal (string-normalize "café" 'nfd) "café"))
+  (should (equal (string-normalize "ế" 'nfd) "ế"))
+  ;; Combining marks are put into canonical order.
+  (should (equal (string-normalize "ẹ́" 'nfd) "ẹ́"))
+  (should (equal (string-normalize "한" 'nfd) "한"))
+  ;; Compatibility characters are kept.
+  (should (equal (string-normalize "ﬁ" 'nfd) "ﬁ")))
+
+(ert-deftest string-normalize-nfc ()
+  (should (equal (string-normalize "café" 'nfc) "café"))
+  (should (equal (string-normalize "ẹ́" 'nfc) "ẹ́"))
+  (should (equal (string-normalize "한" 'nfc) "한"))
+  ;; Singletons and excluded characters are not recomposed.
+  (should (equal (string-normalize "Ω" 'nfc) "Ω"))
+  (should (equal (string-normalize "क़" 'nfc) "क़")))
+
+(ert-deftest string-normalize-compatibility ()
+  (should (equal (string-normalize "ﬁle" 'nfkd) "file"))
+  (should (equal (string-normalize "Ａ²" 'nfkc) "A2"))
+  (should (equal (string-normalize "ẛ̣" 'nfkc) "ṩ"))
+  (should (equal (string-normalize "ẛ̣" 'nfc) "ẛ̣")))
+
+(ert-deftest string-normalize-unchanged ()
+  (let ((ascii "plain text")
+        (nfc "café"))
+    (should (eq (string-normalize ascii 'nfd) ascii))
+    (should (eq (string-normalize nfc 'nfc) nfc)))
+  (let ((unibyte (encode-coding-string "café" 'utf-8)))
+    (should (eq (string-normalize unibyte 'nfd) unibyte)))
+  (should-error (string-normalize "abc" 'nfx)))
+
+(ert-deftest string-normalized-p ()
+  (should (string-normalized-p "café" 'nfc))
+  (should-not (string-normalized-p "café" 'nfd))
+  (should (string-normalized-p "café" 'nfd))
+  (should-not (string-normalized-p "ﬁ" 'nfkc))
+  (should (string-normalized-p "ﬁ" 'nfc))
+  (should (string-normalized-p "" 'nfkd)))
+
+(ert-deftest normalize-region ()
+  (with-temp-buffer
+    (insert "Café crème")
+    (normalize-region (point-min) (point-max) 'nfc)
+    (should (equal (buffer-string) "Café crème"))
+    (set-buffer-modified-p nil)
+    (normalize-region (point-min) (point-max) 'nfc)
+    (should-not (buffer-modified-p))
+    (normalize-region 1 5 'nfd)
+    (should (equal (buffer-string) "Café crème"))))
+
+(provide 'normalize-tests)
+;;; normalize-tests.el ends here
@@ -0,0 +1,60 @@
+;;; segmentation-tests.el --- -*- lexical-binding: t; -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest string-graphemes ()
+  (should (equal (string-graphemes "") nil))
+  (should (equal (string-graphemes "abc") '("a" "b" "c")))
+  (should (equal (string-graphemes "e\u0301x") '("e\u0301" "x")))
+  (should (equal (string-graphemes "\r\na") '("\r\n" "a")))
+  ;; Conjoining jamo form a single syllable.
+  (should (equal (string-graphemes "\u1112\u1161\u11AB!") '("\u1112\u1161\u11AB" "!")))
+  ;; Emoji with a skin tone modifier, and a family joined with ZWJs.
+  (should (equal (string-graphemes "\U0001F44B\U0001F3FDx")
+                 '("\U0001F44B\U0001F3FD" "x")))
+  (should (equal (length (string-graphemes
+                          "\U0001F469\u200D\U0001F469\u200D\U0001F467"))
+                 1))
+  ;; Flags are pairs of regional indicators.
+  (should (equal (string-graphemes "\U0001F1EB\U0001F1F7\U0001F1E9\U0001F1EA")
+                 '("\U0001F1EB\U0001F1F7" "\U0001F1E9\U0001F1EA")))
+  ;; Text properties are kept.
+  (should (equal (get-text-property
+                  0 'face (car (string-graphemes (propertize "ab" 'face 'bold))))
+                 'bold)))
+
+(ert-deftest grapheme-boundary-p ()
+  (let ((s "e\u0301x"))
+    (should (grapheme-boundary-p 0 s))
+    (should-not (grapheme-boundary-p 1 s))
+    (should (grapheme-boundary-p 2 s))
+    (should (grapheme-boundary-p 3 s))
+    (should-error (grapheme-boundary-p 4 s) :type 'args-out-of-range))
+  (with-temp-buffer
+    (insert "a\U0001F44B\U0001F3FDb")
+    (should (grapheme-boundary-p 2))
+    (should-not (grapheme-boundary-p 3))
+    (should (grapheme-boundary-p 4))
+    (should (grapheme-boundary-p (point-max)))))
+
+(ert-deftest string-word-segments ()
+  (should (equal (string-word-segments "can't stop, 3.14 x_y")
+                 '("can't" " " "stop" "," " " "3.14" " " "x_y")))
+  (should (equal (string-word-segments "cafe\u0301  ok")
+                 '("cafe\u0301" "  " "ok")))
+  ;; Ideographs are single segments.
+  (should (equal (string-word-segments "日本") '("日" "本"))))
+
+(ert-deftest word-boundary-p ()
+  (should (word-boundary-p 0 "don't go"))
+  (should-not (word-boundary-p 3 "don't go"))
+  (should (word-boundary-p 5 "don't go"))
+  (with-temp-buffer
+    (insert "1,000 items")
+    (should-not (word-boundary-p 3))
+    (should (word-boundary-p 6))))
+
+(provide 'segmentation-tests)
+;;; segmentation-tests.el ends here
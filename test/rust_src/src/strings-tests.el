@@ -0,0 +1,81 @@
+;;; strings-tests.el --- -*- lexical-binding: t; -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest string-distance ()
+  (should (equal 3 (string-distance "kitten" "sitting")))
+  (should (equal 0 (string-distance "" "")))
+  (should (equal 3 (string-distance "" "abc")))
+  (should (equal 1 (string-distance "ab" "aB")))
+  ;; Multibyte characters count once unless comparing bytes.
+  (should (equal 1 (string-distance "löwe" "lowe")))
+  (should (equal 2 (string-distance "löwe" "lowe" t)))
+  ;; Text properties are ignored.
+  (should (equal 0 (string-distance (propertize "abc" 'face 'bold) "abc"))))
+
+(ert-deftest string-distance-max-distance ()
+  (should (equal 3 (string-distance "kitten" "sitting" nil 3)))
+  (should-not (string-distance "kitten" "sitting" nil 2))
+  (should-not (string-distance "a" "abcdef" nil 4))
+  (should-error (string-distance "a" "b" nil -1) :type 'wrong-type-argument))
+
+(ert-deftest string-damerau-distance ()
+  (should (equal 1 (string-damerau-distance "ab" "ba")))
+  (should (equal 2 (string-distance "ab" "ba")))
+  (should (equal 2 (string-damerau-distance "ca" "abc")))
+  (should (equal 1 (string-damerau-distance "fröm" "förm")))
+  (should (equal 1 (string-damerau-distance "ab" "ba" 1)))
+  (should-not (string-damerau-distance "kitten" "sitting" 2)))
+
+(ert-deftest string-jaro-winkler ()
+  (should (= 1.0 (string-jaro-winkler "" "")))
+  (should (= 1.0 (string-jaro-winkler "same" "same")))
+  (should (= 0.0 (string-jaro-winkler "abc" "xyz")))
+  (should (< (abs (- (string-jaro-winkler "MARTHA" "MARHTA") 0.9611)) 0.001))
+  (should (< (abs (- (string-jaro-winkler "DIXON" "DICKSONX") 0.8133)) 0.001))
+  ;; A shared prefix ranks the intended command first.
+  (should (> (string-jaro-winkler "buffer-lst" "buffer-list")
+             (string-jaro-winkler "buffer-lst" "list-buffers"))))
+
+(ert-deftest string-lcs-length ()
+  (should (equal 4 (string-lcs-length "ABCBDAB" "BDCABA")))
+  (should (equal 0 (string-lcs-length "" "abc")))
+  (should (equal 3 (string-lcs-length "äöü" "xäyöüz"))))
+
+;; Natural and version ordering
+
+(ert-deftest string-version-lessp ()
+  (should (string-version-lessp "file2" "file10"))
+  (should-not (string-version-lessp "file10" "file2"))
+  (should (string-version-lessp "1.9" "1.10"))
+  (should (string-version-lessp "emacs-26.1" "emacs-26.1.90"))
+  (should (string-version-lessp 'foo2 'foo10))
+  ;; Leading zeros are ignored, but the order stays total.
+  (should (string-version-lessp "a007" "a7"))
+  (should-not (string-version-lessp "a7" "a007"))
+  (should-not (string-version-lessp "abc" "abc"))
+  ;; The order is that of gnulib's filevercmp.
+  (should (string-version-lessp "1.0~rc1" "1.0"))
+  (should (string-version-lessp ".emacs" "a"))
+  (should (string-version-lessp "foo-1.2.tar.gz" "foo-1.10.tar"))
+  (should (string-version-lessp "a\0b9" "a\0b10"))
+  (should (equal (sort (list "v10" "v9" "v1.2" "v1.10") #'string-version-lessp)
+                 '("v1.2" "v1.10" "v9" "v10"))))
+
+(ert-deftest string-collate-natural ()
+  (should (string-collate-natural "file2" "file10"))
+  (should (string-collate-natural "B" "a"))
+  (should-not (string-collate-natural "B" "a" t))
+  (should (string-collate-natural "File9" "file10" t))
+  ;; Without IGNORE-LEADING-ZEROS, a leading zero starts a fraction.
+  (should (string-collate-natural "1.050" "1.5"))
+  (should-not (string-collate-natural "1.5" "1.050"))
+  (should (string-collate-natural "1.5" "1.050" nil t))
+  (should (equal (sort (list "x10" "X2" "x1")
+                       (lambda (a b) (string-collate-natural a b t)))
+                 '("x1" "X2" "x10"))))
+
+(provide 'strings-tests)
+;;; strings-tests.el ends here
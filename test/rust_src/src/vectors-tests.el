@@ -1,3 +1,91 @@
 (ert-deftest vector-tests-make ()
   (let ((v (make-vector 10 "asdfghjklqwertyuiopzxcvbnm")))
     (should (= 10 (length v)))))
+
+(ert-deftest vector-tests-sort-old-convention ()
+  (let ((l (list 3 1 2)))
+    (should (equal (sort l #'<) '(1 2 3)))
+    (should (equal l '(1 2 3))))
+  (let ((v (vector 3 1 2)))
+    (should (eq (sort v #'<) v))
+    (should (equal v [1 2 3]))))
+
+(ert-deftest vector-tests-sort-keywords ()
+  (let ((l (list "bb" "a" "ccc")))
+    (should (equal (sort l) '("a" "bb" "ccc")))
+    (should (equal l '("bb" "a" "ccc")))
+    (should (equal (sort l :key #'length :reverse t) '("ccc" "bb" "a")))
+    (should (equal (sort l :lessp #'string> :in-place t) '("ccc" "bb" "a")))
+    (should (equal l '("ccc" "bb" "a"))))
+  (should (equal (sort [3 1 2] :reverse t) [3 2 1]))
+  ;; The first occurrence of a repeated keyword wins.
+  (should (equal (sort [3 1 2] :reverse t :reverse nil) [3 2 1])))
+
+(ert-deftest vector-tests-sort-invalid-keywords ()
+  (should (equal (should-error (sort '(1 2) :no-such-keyword t))
+                 '(invalid-keyword-argument :no-such-keyword)))
+  (should (equal (should-error (sort '(1 2) :reverse t :key))
+                 '(missing-keyword-value :key)))
+  (should (equal (should-error (sort '(1 2) :reverse t 'foo))
+                 '(invalid-keyword-argument foo)))
+  ;; A predicate cannot be combined with keywords.
+  (should (equal (should-error (sort '(1 2) #'< :reverse t))
+                 '(invalid-keyword-argument <)))
+  (should (equal (should-error (sort '(1 2) #'< #'>))
+                 '(invalid-keyword-argument <))))
+
+(ert-deftest vector-tests-sort-keyword-missing-value ()
+  ;; A lone keyword is not taken for the predicate of the old
+  ;; convention.
+  (dolist (keyword '(:key :lessp :reverse :in-place))
+    (let* ((l (list 2 1))
+           (err (should-error (sort l keyword))))
+      (should (equal err (list 'missing-keyword-value keyword)))
+      (should (equal l '(2 1))))))
+
+(ert-deftest vector-tests-sort-stable ()
+  (let ((pairs '((1 . a) (0 . b) (1 . c) (0 . d))))
+    (should (equal (sort pairs :key #'car)
+                   '((0 . b) (0 . d) (1 . a) (1 . c))))
+    (should (equal (sort pairs :key #'car :reverse t)
+                   '((1 . a) (1 . c) (0 . b) (0 . d))))))
+
+(ert-deftest vector-tests-sort-key-called-once ()
+  (let ((calls 0))
+    (sort (number-sequence 1 50)
+          :key (lambda (x) (setq calls (1+ calls)) (- x)))
+    (should (= calls 50))))
+
+(ert-deftest vector-tests-value< ()
+  (should (value< 1 2.5))
+  (should-not (value< 2 2))
+  (should (value< "abc" "abd"))
+  (should (value< 'a 'b))
+  (should (value< '(1 2) '(1 3)))
+  (should (value< '(1) '(1 2)))
+  (should (value< [1 "a"] [1 "b"]))
+  (with-temp-buffer
+    (insert "xyz")
+    (should (value< (copy-marker 1) (copy-marker 2))))
+  (should-error (value< 1 "a") :type 'type-mismatch))
+
+(ert-deftest vector-tests-value<-dotted-and-circular ()
+  (should (value< '(1 . 2) '(1 . 3)))
+  (should (value< '(1) '(1 . 2)))
+  (let ((l (list 1)))
+    (setcdr l l)
+    (should-error (value< l l) :type 'circular-list)
+    (should-error (value< l '(1 1 1 2)) :type 'circular-list)
+    (should-error (sort (list l (list 1 2)) :in-place t) :type 'circular-list)))
+
+(ert-deftest vector-tests-value<-mixed-strings ()
+  ;; Unibyte and multibyte strings compare by character, like `string<'.
+  (let ((unibyte "\xe9")
+        (multibyte "\u00e9")
+        (ascii "z"))
+    (should-not (multibyte-string-p unibyte))
+    (should-not (value< unibyte multibyte))
+    (should-not (value< multibyte unibyte))
+    (dolist (a (list unibyte multibyte ascii))
+      (dolist (b (list unibyte multibyte ascii))
+        (should (eq (value< a b) (string< a b)))))))